@@ -0,0 +1,46 @@
+//! `Config::find_hotkey` 的查找耗时
+//!
+//! 运行：`cargo bench --bench find_hotkey`
+//!
+//! 生成一份包含大量热键的配置，分别统计命中与未命中（普通按键）时的平均耗时
+
+use rust_keymacro::Config;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// 配置中的热键数量
+const HOTKEY_COUNT: usize = 200;
+/// 每个键名的查找次数
+const ROUNDS: u32 = 100_000;
+
+/// 生成 `HOTKEY_COUNT` 个绑定键盘按键组（如 `["K0", "F1"]`）的热键配置
+fn build_config() -> Config {
+    let mut yaml = String::from("hotkeys:\n");
+    for i in 0..HOTKEY_COUNT {
+        yaml.push_str(&format!(
+            "  - type: keyboard\n    key: [\"K{i}\", \"Ctrl+K{i}\"]\n    action: \"type_text\"\n    params:\n      text: \"{i}\"\n"
+        ));
+    }
+    Config::from_str(&yaml).unwrap_or_else(|e| {
+        eprintln!("生成配置失败: {}", e);
+        std::process::exit(1);
+    })
+}
+
+fn measure(config: &Config, name: &str, key: &str) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(config.find_hotkey(black_box(key)));
+    }
+    let average = start.elapsed() / ROUNDS;
+    println!("{:<8} {:<12} 平均 {:?}", name, key, average);
+    average
+}
+
+fn main() {
+    let config = build_config();
+    println!("热键数量: {}", config.hotkeys.len());
+    measure(&config, "首个", "K0");
+    measure(&config, "最后", &format!("ctrl+k{}", HOTKEY_COUNT - 1));
+    measure(&config, "未命中", "A");
+}
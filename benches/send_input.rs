@@ -0,0 +1,56 @@
+//! SendInput 批量发送与逐键发送的耗时对比
+//!
+//! 运行：`cargo bench --bench send_input`
+//!
+//! 会向当前焦点窗口发送真实按键（F24，一般不会触发任何操作），运行期间不要操作键盘
+
+use rust_keymacro::winapi::keyboard::{self, KeyEventType};
+use std::time::{Duration, Instant};
+
+/// 模拟的文本长度（每个字符一次按下和一次释放）
+const CHAR_COUNT: usize = 1000;
+/// 每种方式的重复次数，取平均值
+const ROUNDS: u32 = 5;
+/// 发送的按键 VK_F24
+const BENCH_VK: u16 = 0x87;
+
+fn per_key() -> Result<(), windows::core::Error> {
+    for _ in 0..CHAR_COUNT {
+        keyboard::simulate_key(BENCH_VK, KeyEventType::Press)?;
+        keyboard::simulate_key(BENCH_VK, KeyEventType::Release)?;
+    }
+    Ok(())
+}
+
+fn batched() -> Result<(), windows::core::Error> {
+    let inputs: Vec<_> = (0..CHAR_COUNT)
+        .flat_map(|_| {
+            [
+                keyboard::key_input(BENCH_VK, KeyEventType::Press),
+                keyboard::key_input(BENCH_VK, KeyEventType::Release),
+            ]
+        })
+        .collect();
+    keyboard::simulate_inputs(&inputs)
+}
+
+fn measure(name: &str, f: fn() -> Result<(), windows::core::Error>) -> Duration {
+    let mut total = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        if let Err(e) = f() {
+            eprintln!("{} 发送失败: {}", name, e);
+            std::process::exit(1);
+        }
+        total += start.elapsed();
+    }
+    let average = total / ROUNDS;
+    println!("{:<8} {} 字符: 平均 {:?}", name, CHAR_COUNT, average);
+    average
+}
+
+fn main() {
+    let per_key = measure("逐键", per_key);
+    let batched = measure("批量", batched);
+    println!("批量发送加速比: {:.1}x", per_key.as_secs_f64() / batched.as_secs_f64().max(f64::EPSILON));
+}
@@ -2,25 +2,84 @@
 //!
 //! 管理系统托盘应用的主逻辑、事件处理和生命周期
 
-use crate::macros::{init_keyboard_macro_system, set_macro_enabled, cleanup_keyboard_hook};
-use crate::config::Config;
+use crate::macros::{
+    set_macro_enabled, toggle_macro_pause, abort_macro, emergency_stop, macro_stats, hook_stats, set_delay_scale, set_config,
+    debounce_passed, AppNotice, MacroSystem,
+};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use crate::config::{Config, Rgb};
+use crate::state::{AppState, MacroState};
 use tray_icon::{
-    menu::{Menu, MenuItem, MenuId},
+    menu::{CheckMenuItem, Menu, MenuItem, MenuId, PredefinedMenuItem, Submenu},
     TrayIcon, TrayIconBuilder,
 };
 use winit::{
     application::ApplicationHandler,
     event_loop::{ActiveEventLoop, ControlFlow},
 };
-use global_hotkey::{GlobalHotKeyEvent, HotKeyState};
-use windows::Win32::UI::WindowsAndMessaging::HHOOK;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+
+/// 已注册的全局热键
+pub struct GlobalHotkeys {
+    /// 热键管理器（保持活动以监听热键）
+    pub manager: GlobalHotKeyManager,
+    /// 宏开关热键 ID
+    pub toggle_id: u32,
+    /// 实际注册的宏开关热键（如 "Ctrl+`"，被占用时为备选热键），显示在托盘提示中
+    pub toggle_label: String,
+    /// 暂停/继续序列热键 ID（未注册时为 None）
+    pub pause_id: Option<u32>,
+    /// 中止序列热键 ID（未注册时为 None）
+    pub abort_id: Option<u32>,
+    /// 紧急停止热键 ID（未注册时为 None）
+    pub stop_id: Option<u32>,
+    /// 切换到下一个配置方案的热键 ID（未注册或没有其他方案时为 None）
+    pub next_profile_id: Option<u32>,
+}
+
+/// 托盘菜单中可选的延迟倍率
+const DELAY_SCALE_CHOICES: [f64; 4] = [0.5, 1.0, 2.0, 4.0];
+
+/// 托盘菜单项 ID
+pub struct TrayMenuIds {
+    /// "延迟倍率"子菜单的选项及对应倍率（保留菜单项以更新勾选状态）
+    pub delay_scales: Vec<(CheckMenuItem, f64)>,
+    /// "配置方案"子菜单的选项，顺序与 `Config::profile_names` 相同（没有其他方案时为空）
+    pub profiles: Vec<CheckMenuItem>,
+    /// "统计"菜单项 ID
+    pub stats: MenuId,
+    /// "钩子统计"菜单项 ID
+    pub hook_stats: MenuId,
+    /// "导出配置"菜单项 ID
+    pub export_config: MenuId,
+    /// 导出配置写入的文件（配置文件旁的 `<文件名>.exported.yaml`）
+    pub export_path: PathBuf,
+    /// "退出"菜单项 ID
+    pub quit: MenuId,
+}
+
+impl TrayMenuIds {
+    /// 查找菜单项对应的延迟倍率，不是延迟倍率选项时返回 None
+    fn delay_scale_for(&self, id: &MenuId) -> Option<f64> {
+        self.delay_scales
+            .iter()
+            .find(|(item, _)| item.id() == id)
+            .map(|(_, scale)| *scale)
+    }
+
+    /// 查找菜单项对应的配置方案序号，不是配置方案选项时返回 None
+    fn profile_for(&self, id: &MenuId) -> Option<usize> {
+        self.profiles.iter().position(|item| item.id() == id)
+    }
+}
 
 /// 托盘应用程序主结构体
 ///
 /// 负责处理系统托盘图标、菜单、热键和键盘宏
 pub struct TrayApp {
-    /// 退出菜单项 ID
-    quit_item_id: MenuId,
+    /// 托盘菜单项 ID
+    menu_ids: TrayMenuIds,
     
     /// 菜单事件接收器
     menu_event_receiver: tray_icon::menu::MenuEventReceiver,
@@ -28,26 +87,38 @@ pub struct TrayApp {
     /// 托盘图标事件接收器
     tray_event_receiver: tray_icon::TrayIconEventReceiver,
     
-    /// 热键管理器（保持活动以监听热键）
-    _hotkey_manager: global_hotkey::GlobalHotKeyManager,
+    /// 全局热键
+    hotkeys: GlobalHotkeys,
     
-    /// 当前状态（0 或 1）
-    toggle_state: bool,
+    /// 当前状态（关闭，或开启及当前配置方案）
+    state: MacroState,
+
+    /// 状态文件路径（保存开关状态，无法确定程序目录时为 None，不保存）
+    state_path: Option<PathBuf>,
     
     /// 托盘图标
     tray_icon: Option<tray_icon::TrayIcon>,
     
-    /// 状态 0 的图标（红色）
-    icon_state_0: tray_icon::Icon,
+    /// 关闭状态的图标（红色）
+    icon_disabled: tray_icon::Icon,
     
-    /// 状态 1 的图标（绿色）
-    icon_state_1: tray_icon::Icon,
+    /// 各配置方案开启状态的图标（与 `Config::profile_names` 对应，默认方案为绿色）
+    icons_enabled: Vec<tray_icon::Icon>,
     
-    /// 键盘钩子句柄（程序退出时清理）
-    keyboard_hook: Option<HHOOK>,
+    /// 键盘宏系统（持有键盘钩子，程序退出时释放以卸载钩子）
+    macro_system: Option<MacroSystem>,
+
+    /// 安全模式：不启动键盘宏系统（见 `bootstrap::run_safe_mode`）
+    safe_mode: bool,
     
-    /// 键盘宏配置
+    /// 当前配置方案序号（0 为顶层 `hotkeys` 构成的默认方案）
+    active_profile: usize,
+
+    /// 键盘宏配置（包含所有配置方案，切换方案时从中生成生效的配置）
     config: Config,
+
+    /// 开关热键上一次生效的时间（用于 `toggle_debounce_ms` 防抖）
+    last_toggle: Option<Instant>,
 }
 
 impl TrayApp {
@@ -55,44 +126,195 @@ impl TrayApp {
     ///
     /// # 参数
     ///
-    /// * `quit_item_id` - 退出菜单项 ID
+    /// * `menu_ids` - 托盘菜单项 ID
     /// * `menu_event_receiver` - 菜单事件接收器
     /// * `tray_event_receiver` - 托盘事件接收器
-    /// * `hotkey_manager` - 热键管理器
+    /// * `hotkeys` - 已注册的全局热键
     /// * `tray_icon` - 托盘图标
-    /// * `icon_state_0` - 状态 0 图标
-    /// * `icon_state_1` - 状态 1 图标
+    /// * `icon_disabled` - 关闭状态图标
+    /// * `icons_enabled` - 各配置方案开启状态的图标
     /// * `config` - 键盘宏配置
     pub fn new(
-        quit_item_id: MenuId,
+        menu_ids: TrayMenuIds,
         menu_event_receiver: tray_icon::menu::MenuEventReceiver,
         tray_event_receiver: tray_icon::TrayIconEventReceiver,
-        hotkey_manager: global_hotkey::GlobalHotKeyManager,
+        hotkeys: GlobalHotkeys,
         tray_icon: tray_icon::TrayIcon,
-        icon_state_0: tray_icon::Icon,
-        icon_state_1: tray_icon::Icon,
+        icon_disabled: tray_icon::Icon,
+        icons_enabled: Vec<tray_icon::Icon>,
         config: Config,
     ) -> Self {
-        Self {
-            quit_item_id,
+        // 恢复上次退出前的开关状态（状态文件不存在或无效时默认开启）
+        let state_path = crate::state::default_state_path();
+        let enabled = state_path.as_deref().map(AppState::load).unwrap_or_default().enabled;
+        if !enabled {
+            log::info!("上次退出前宏已关闭，保持关闭状态");
+        }
+        set_macro_enabled(enabled);
+        crate::overlay::set_overlay_enabled(config.overlay_enabled);
+        crate::overlay::set_overlay_monitor(config.overlay_monitor);
+        // 只在配置开启 `overlay_progress` 时发送进度，重新加载配置后开启也能生效
+        crate::macros::set_progress_sender(crate::overlay::start_progress_overlay());
+
+        let app = Self {
+            menu_ids,
             menu_event_receiver,
             tray_event_receiver,
-            _hotkey_manager: hotkey_manager,
-            toggle_state: true, // 默认开启
+            hotkeys,
+            state: MacroState::new(enabled, 0),
+            state_path,
             tray_icon: Some(tray_icon),
-            icon_state_0,
-            icon_state_1,
-            keyboard_hook: None,
+            icon_disabled,
+            icons_enabled,
+            macro_system: None,
+            safe_mode: false,
+            active_profile: 0,
             config,
+            last_toggle: None,
+        };
+        // 托盘图标创建时还不知道实际注册的开关热键和恢复的开关状态，这里按当前状态刷新提示和图标
+        app.update_tray_state();
+        app
+    }
+
+    /// 以安全模式运行：不安装键盘钩子，热键都不会触发
+    pub fn without_macro_system(mut self) -> Self {
+        self.safe_mode = true;
+        self
+    }
+
+    /// 切换宏启用状态并更新托盘和屏幕提示
+    fn toggle_enabled(&mut self) {
+        self.apply_enabled(!self.state.is_enabled());
+    }
+
+    /// 设置宏启用状态并更新托盘、保存状态和显示屏幕提示
+    ///
+    /// # 参数
+    ///
+    /// * `enabled` - 新的启用状态（绑定了 `toggle_system` 的热键切换后通知的状态已经生效，再次设置不影响结果）
+    fn apply_enabled(&mut self, enabled: bool) {
+        self.state = MacroState::new(enabled, self.active_profile);
+
+        // 更新宏状态和托盘
+        set_macro_enabled(self.state.is_enabled());
+        self.update_tray_state();
+        self.save_state();
+
+        // 显示屏幕提示
+        self.show_overlay(&self.state.label(), self.state.color());
+    }
+
+    /// 设置全局延迟倍率，并只勾选对应的菜单项
+    fn select_delay_scale(&self, scale: f64) {
+        set_delay_scale(scale);
+        for (item, item_scale) in &self.menu_ids.delay_scales {
+            item.set_checked(*item_scale == scale);
+        }
+        log::info!("延迟倍率已设为 {}x", scale);
+    }
+
+    /// 切换到指定的配置方案，并只勾选对应的菜单项
+    ///
+    /// # 参数
+    ///
+    /// * `index` - 方案序号（与 `Config::profile_names` 对应）
+    fn select_profile(&mut self, index: usize) {
+        let Some(config) = self.config.with_profile(index) else {
+            return;
+        };
+        set_config(config);
+        self.active_profile = index;
+        self.state = MacroState::new(self.state.is_enabled(), index);
+        for (i, item) in self.menu_ids.profiles.iter().enumerate() {
+            item.set_checked(i == index);
+        }
+        self.update_tray_state();
+
+        let name = self.config.profile_names()[index];
+        log::info!("已切换到配置方案: {}", name);
+        // 关闭状态下切换方案仍以方案颜色提示
+        self.show_overlay(name, MacroState::new(true, index).color());
+    }
+
+    /// 把当前配置（包括所有配置方案和运行时修改的延迟倍率）写入 `export_path`，并提示结果
+    fn export_config(&self) {
+        let mut config = self.config.clone();
+        config.delay_scale = crate::macros::delay_scale();
+        let path = &self.menu_ids.export_path;
+        match config.to_file(path) {
+            Ok(()) => {
+                log::info!("配置已导出到 {}", path.display());
+                show_info_dialog("导出配置", format!("配置已导出到\n{}", path.display()));
+            }
+            Err(e) => {
+                log::warn!("导出配置到 {} 失败: {}", path.display(), e);
+                show_info_dialog("导出配置", format!("导出配置到 {} 失败: {}", path.display(), e));
+            }
+        }
+    }
+
+    /// 紧急停止：禁用宏、中止序列并释放所有按键
+    fn emergency_stop(&mut self) {
+        log::warn!("紧急停止");
+        emergency_stop();
+
+        self.state = MacroState::Disabled;
+        self.update_tray_state();
+        self.save_state();
+        self.show_overlay("STOPPED", crate::state::DISABLED_COLOR);
+    }
+
+    /// 以指定颜色显示屏幕提示（配置中 `overlay_enabled: false` 时不显示，只通过托盘提示和图标反馈）
+    fn show_overlay(&self, text: &str, color: Rgb) {
+        if self.config.overlay_enabled {
+            crate::overlay::show_overlay_colored(text, color);
+        }
+    }
+
+    /// 保存开关状态，下次启动时恢复
+    fn save_state(&self) {
+        let Some(path) = &self.state_path else {
+            return;
+        };
+        if let Err(e) = (AppState { enabled: self.state.is_enabled() }).save(path) {
+            log::warn!("保存状态文件失败 ({}): {}", path.display(), e);
+        }
+    }
+
+    /// 按当前状态更新托盘提示和图标
+    fn update_tray_state(&self) {
+        if let Some(tray_icon) = &self.tray_icon {
+            // 只统计当前配置方案中的热键
+            let active = self.config.with_profile(self.active_profile).map_or(0, |c| c.active_hotkey_count());
+            let mut tooltip = if active == 0 {
+                "无已启用的热键".to_string()
+            } else if self.config.profiles.is_empty() {
+                format!("状态: {} (开关: {})", self.state.label(), self.hotkeys.toggle_label)
+            } else {
+                let profile = self.config.profile_names()[self.active_profile];
+                format!("状态: {} - {} (开关: {})", self.state.label(), profile, self.hotkeys.toggle_label)
+            };
+            // 每个已连接的手柄一行，如 "手柄 0: 已连接 (电量 低)"
+            for line in crate::macros::gamepad_status().summary() {
+                tooltip.push('\n');
+                tooltip.push_str(&line);
+            }
+            let _ = tray_icon.set_tooltip(Some(&tooltip));
+            let new_icon = match self.state {
+                MacroState::Disabled => &self.icon_disabled,
+                MacroState::Enabled { profile } => &self.icons_enabled[profile],
+            };
+            let _ = tray_icon.set_icon(Some(new_icon.clone()));
         }
     }
 }
 
-impl ApplicationHandler for TrayApp {
+impl ApplicationHandler<AppNotice> for TrayApp {
     fn resumed(&mut self, _event_loop: &ActiveEventLoop) {
         // 初始化键盘宏系统（传递配置）
-        if self.keyboard_hook.is_none() {
-            self.keyboard_hook = init_keyboard_macro_system(self.config.clone());
+        if self.macro_system.is_none() && !self.safe_mode {
+            self.macro_system = MacroSystem::start(self.config.clone()).ok();
         }
     }
 
@@ -105,21 +327,40 @@ impl ApplicationHandler for TrayApp {
         // 本应用无窗口，忽略窗口事件
     }
 
-    fn user_event(&mut self, _event_loop: &ActiveEventLoop, _event: ()) {
-        // 无自定义用户事件
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: AppNotice) {
+        // 宏处理线程发来的通知（见 `set_app_notifier`）
+        match event {
+            AppNotice::EnabledChanged(enabled) => self.apply_enabled(enabled),
+        }
     }
 
     fn new_events(&mut self, event_loop: &ActiveEventLoop, _cause: winit::event::StartCause) {
-        // 等待模式，减少 CPU 占用
-        event_loop.set_control_flow(ControlFlow::Wait);
+        // 等待模式，减少 CPU 占用；启用手柄时定期醒来刷新托盘提示中的手柄电量
+        if self.config.gamepad.enabled {
+            let next_refresh = std::time::Instant::now() + crate::gamepad::battery::BATTERY_POLL_INTERVAL;
+            event_loop.set_control_flow(ControlFlow::WaitUntil(next_refresh));
+        } else {
+            event_loop.set_control_flow(ControlFlow::Wait);
+        }
+        if crate::macros::gamepad_status().take_changed() {
+            self.update_tray_state();
+        }
 
-        // 处理菜单事件（退出）
+        // 处理菜单事件（配置方案、延迟倍率、导出配置、统计、退出）
         if let Ok(menu_event) = self.menu_event_receiver.try_recv() {
-            if menu_event.id == self.quit_item_id {
+            if let Some(index) = self.menu_ids.profile_for(&menu_event.id) {
+                self.select_profile(index);
+            } else if let Some(scale) = self.menu_ids.delay_scale_for(&menu_event.id) {
+                self.select_delay_scale(scale);
+            } else if menu_event.id == self.menu_ids.stats {
+                show_stats_dialog();
+            } else if menu_event.id == self.menu_ids.hook_stats {
+                show_hook_stats_dialog();
+            } else if menu_event.id == self.menu_ids.export_config {
+                self.export_config();
+            } else if menu_event.id == self.menu_ids.quit {
                 // 清理钩子并退出
-                if let Some(hook) = self.keyboard_hook.take() {
-                    cleanup_keyboard_hook(hook);
-                }
+                drop(self.macro_system.take());
                 event_loop.exit();
             }
         }
@@ -127,25 +368,37 @@ impl ApplicationHandler for TrayApp {
         // 忽略托盘图标事件（右键自动显示菜单）
         let _ = self.tray_event_receiver.try_recv();
 
-        // 处理热键事件（Ctrl+`）
+        // 处理全局热键事件（在主线程直接处理，宏执行中也能立即生效）
         while let Ok(hotkey_event) = GlobalHotKeyEvent::receiver().try_recv() {
-            if hotkey_event.state() == HotKeyState::Pressed {
-                // 切换状态
-                self.toggle_state = !self.toggle_state;
-                
-                // 更新宏状态和托盘
-                set_macro_enabled(self.toggle_state);
-                
-                let state_text = if self.toggle_state { "1" } else { "0" };
-                if let Some(tray_icon) = &self.tray_icon {
-                    let _ = tray_icon.set_tooltip(Some(&format!("状态: {}", state_text)));
-                    let new_icon = if self.toggle_state { &self.icon_state_1 } else { &self.icon_state_0 };
-                    let _ = tray_icon.set_icon(Some(new_icon.clone()));
+            if hotkey_event.state() != HotKeyState::Pressed {
+                continue;
+            }
+
+            let id = hotkey_event.id();
+            if Some(id) == self.hotkeys.stop_id {
+                self.emergency_stop();
+            } else if id == self.hotkeys.toggle_id {
+                // 开关热键（默认 Ctrl+`，被占用时为备选热键）；部分键盘按一次会连续报告两次，防抖后再切换
+                let now = Instant::now();
+                let window = Duration::from_millis(self.config.toggle_debounce_ms);
+                if !debounce_passed(self.last_toggle, now, window) {
+                    log::debug!("忽略 {} 毫秒内重复的开关热键", self.config.toggle_debounce_ms);
+                    continue;
                 }
-                
-                // 显示屏幕提示
-                crate::overlay::show_overlay(state_text);
+                self.last_toggle = Some(now);
+                self.toggle_enabled();
                 break;
+            } else if Some(id) == self.hotkeys.pause_id {
+                if toggle_macro_pause() {
+                    log::info!("序列已暂停");
+                } else {
+                    log::info!("序列已继续");
+                }
+            } else if Some(id) == self.hotkeys.abort_id {
+                log::info!("中止正在执行的序列");
+                abort_macro();
+            } else if Some(id) == self.hotkeys.next_profile_id {
+                self.select_profile(self.config.next_profile_index(self.active_profile));
             }
         }
     }
@@ -153,66 +406,248 @@ impl ApplicationHandler for TrayApp {
 
 /// 初始化并创建托盘图标
 /// 
+/// # 参数
+/// 
+/// * `config` - 键盘宏配置（读取自定义图标路径）
+/// * `config_path` - 配置文件路径（以不可点击的菜单项显示，便于确认加载的是哪一份配置）
+/// 
 /// # 返回
 /// 
-/// 返回一个元组，包含：
+/// 成功返回一个元组，包含：
 /// - 托盘图标对象（需要保持活动状态）
-/// - 退出菜单项的ID（用于后续事件处理）
-/// - 状态0的图标（红色）
-/// - 状态1的图标（绿色）
+/// - 菜单项的ID（用于后续事件处理）
+/// - 关闭状态的图标（红色）
+/// - 各配置方案开启状态的图标（没有其他配置方案时只有一个，为绿色）
+/// 
+/// 在没有系统托盘的环境（如部分远程桌面会话）中创建失败时返回错误信息
 /// 
 /// # 注意
 /// 
 /// 托盘图标对象必须保持活动状态，否则托盘图标会消失
-pub fn init_tray_icon() -> (TrayIcon, MenuId, tray_icon::Icon, tray_icon::Icon) {
-    // 创建托盘右键菜单和"退出"菜单项
+pub fn init_tray_icon(config: &Config, config_path: &Path) -> Result<(TrayIcon, TrayMenuIds, tray_icon::Icon, Vec<tray_icon::Icon>), String> {
+    // 创建托盘右键菜单：配置文件路径（不可点击）、"热键绑定"、"配置方案"（有其他方案时）和"延迟倍率"子菜单、
+    // "导出配置"、"统计"、"钩子统计"和"退出"菜单项
     let tray_menu = Menu::new();
+    let config_item = MenuItem::new(format!("配置: {}", config_path.display()), false, None);
+    let bindings_menu = build_bindings_menu(config)?;
+    let (profile_menu, profiles) = build_profile_menu(config)?;
+    let (delay_scale_menu, delay_scales) = build_delay_scale_menu(config.delay_scale)?;
+    let export_item = MenuItem::new("导出配置", true, None);
+    let stats_item = MenuItem::new("统计", true, None);
+    let hook_stats_item = MenuItem::new("钩子统计", true, None);
     let quit_item = MenuItem::new("退出", true, None);
-    let quit_item_id = quit_item.id().clone();
+    let menu_ids = TrayMenuIds {
+        delay_scales,
+        profiles,
+        stats: stats_item.id().clone(),
+        hook_stats: hook_stats_item.id().clone(),
+        export_config: export_item.id().clone(),
+        export_path: exported_config_path(config_path),
+        quit: quit_item.id().clone(),
+    };
     
     // 将菜单项添加到菜单中
-    tray_menu.append(&quit_item).unwrap();
+    tray_menu.append_items(&[&config_item, &PredefinedMenuItem::separator(), &bindings_menu])
+        .map_err(|e| format!("创建托盘菜单失败: {}", e))?;
+    if let Some(profile_menu) = &profile_menu {
+        tray_menu.append(profile_menu)
+            .map_err(|e| format!("创建托盘菜单失败: {}", e))?;
+    }
+    tray_menu.append_items(&[&delay_scale_menu, &export_item, &stats_item, &hook_stats_item, &quit_item])
+        .map_err(|e| format!("创建托盘菜单失败: {}", e))?;
 
-    // 创建两种状态的图标
-    let icon_state_0 = load_icon(false); // 状态0 - 红色
-    let icon_state_1 = load_icon(true);  // 状态1 - 绿色
+    // 创建关闭状态和各配置方案开启状态的图标（关闭状态和默认方案优先使用配置中的图标文件）
+    let icon_disabled = load_state_icon(config.icon_disabled.as_deref(), MacroState::Disabled)?;
+    let icons_enabled = (0..config.profile_names().len())
+        .map(|profile| {
+            let path = if profile == 0 { config.icon_enabled.as_deref() } else { None };
+            load_state_icon(path, MacroState::Enabled { profile })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
     // 创建托盘图标（菜单所有权已转移，无需返回）
-    // 默认使用状态1的图标（绿色）
+    // 默认使用默认方案开启状态的图标（绿色）
     let tray_icon = TrayIconBuilder::new()
         .with_menu(Box::new(tray_menu))
         .with_tooltip("状态: 1") // 默认状态为开 (1)
-        .with_icon(icon_state_1.clone())
+        .with_icon(icons_enabled[0].clone())
         .build()
-        .expect("Failed to create tray icon");
+        .map_err(|e| format!("创建托盘图标失败: {}", e))?;
+
+    Ok((tray_icon, menu_ids, icon_disabled, icons_enabled))
+}
+
+/// 创建"热键绑定"子菜单，每个热键一项（只用于查看，不可点击）
+fn build_bindings_menu(config: &Config) -> Result<Submenu, String> {
+    let submenu = Submenu::new("热键绑定", true);
+    let items: Vec<MenuItem> = if config.hotkeys.is_empty() {
+        vec![MenuItem::new("（未绑定任何热键）", false, None)]
+    } else {
+        config.menu_labels().into_iter().map(|label| MenuItem::new(label, false, None)).collect()
+    };
+
+    for item in &items {
+        submenu.append(item)
+            .map_err(|e| format!("创建托盘菜单失败: {}", e))?;
+    }
+    Ok(submenu)
+}
+
+/// 创建"配置方案"子菜单，默认方案为勾选状态
+///
+/// # 返回
+///
+/// 没有其他配置方案时返回 None 和空列表，否则返回子菜单及各方案选项
+fn build_profile_menu(config: &Config) -> Result<(Option<Submenu>, Vec<CheckMenuItem>), String> {
+    if config.profiles.is_empty() {
+        return Ok((None, Vec::new()));
+    }
+
+    let submenu = Submenu::new("配置方案", true);
+    let mut items = Vec::with_capacity(config.profiles.len() + 1);
+    for (i, name) in config.profile_names().into_iter().enumerate() {
+        let item = CheckMenuItem::new(name, true, i == 0, None);
+        submenu.append(&item)
+            .map_err(|e| format!("创建托盘菜单失败: {}", e))?;
+        items.push(item);
+    }
+    Ok((Some(submenu), items))
+}
+
+/// 导出配置写入的文件：与配置文件同目录，文件名为 `<配置文件名>.exported.yaml`（如 `config.exported.yaml`）
+fn exported_config_path(config_path: &Path) -> PathBuf {
+    let stem = config_path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| "config".to_string());
+    config_path.with_file_name(format!("{}.exported.yaml", stem))
+}
+
+/// 创建"延迟倍率"子菜单，勾选与配置中 `delay_scale` 相同的选项
+///
+/// # 返回
+///
+/// 子菜单，以及各选项和对应的倍率
+fn build_delay_scale_menu(current: f64) -> Result<(Submenu, Vec<(CheckMenuItem, f64)>), String> {
+    let submenu = Submenu::new("延迟倍率", true);
+    let mut items = Vec::with_capacity(DELAY_SCALE_CHOICES.len());
+
+    for scale in DELAY_SCALE_CHOICES {
+        let item = CheckMenuItem::new(format!("{}x", scale), true, scale == current, None);
+        submenu.append(&item)
+            .map_err(|e| format!("创建托盘菜单失败: {}", e))?;
+        items.push((item, scale));
+    }
+    Ok((submenu, items))
+}
+
+/// 显示各热键的触发次数
+fn show_stats_dialog() {
+    let stats = macro_stats();
+    let text = if stats.is_empty() {
+        "本次运行中还没有触发过宏".to_string()
+    } else {
+        stats
+            .iter()
+            .map(|(name, count)| format!("{}: {} 次", name, count))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    show_info_dialog("宏触发统计", text);
+}
+
+/// 显示键盘钩子处理事件的计数，用于排查按键被拦截或丢失的问题
+fn show_hook_stats_dialog() {
+    let stats = hook_stats();
+    let text = format!(
+        "收到的事件: {}\n拦截: {}\n放行: {}\n忽略的自动重复: {}\n未派发的触发: {}",
+        stats.seen, stats.suppressed, stats.passed_through, stats.repeat_ignored, stats.dropped
+    );
+    show_info_dialog("键盘钩子统计", text);
+}
+
+/// 显示信息对话框
+///
+/// 对话框在单独的线程中显示，打开期间主线程仍能处理开关和紧急停止热键
+fn show_info_dialog(title: &'static str, text: String) {
+    use windows::Win32::UI::WindowsAndMessaging::{MB_ICONINFORMATION, MB_OK};
 
-    (tray_icon, quit_item_id, icon_state_0, icon_state_1)
+    std::thread::spawn(move || {
+        crate::winapi::window::message_box(&text, title, MB_ICONINFORMATION | MB_OK);
+    });
+}
+
+/// 加载指定状态的托盘图标
+/// 
+/// # 参数
+/// 
+/// * `path` - 自定义图标文件路径（.ico 或 .png），为 None 时使用生成的方块图标
+/// * `state` - 图标表示的状态（决定生成的方块图标的颜色）
+/// 
+/// # 说明
+/// 
+/// 图标文件不存在或无法加载时记录警告并回退到生成的方块图标
+fn load_state_icon(path: Option<&str>, state: MacroState) -> Result<tray_icon::Icon, String> {
+    if let Some(path) = path {
+        match load_icon_file(Path::new(path)) {
+            Ok(icon) => return Ok(icon),
+            Err(e) => log::warn!("加载托盘图标失败 ({}): {}，使用默认图标", path, e),
+        }
+    }
+    load_icon(state.color())
+}
+
+/// 从文件加载托盘图标，扩展名为 .png 时解码为 RGBA 像素，否则按 .ico 加载
+fn load_icon_file(path: &Path) -> Result<tray_icon::Icon, String> {
+    let is_png = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+    if !is_png {
+        return tray_icon::Icon::from_path(path, None).map_err(|e| e.to_string());
+    }
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let (rgba, width, height) = decode_png_rgba(std::io::BufReader::new(file))?;
+    tray_icon::Icon::from_rgba(rgba, width, height).map_err(|e| e.to_string())
+}
+
+/// 解码 PNG 图片
+///
+/// # 返回
+///
+/// 成功返回 RGBA 像素数据及宽高，灰度和不带透明通道的图片转换为不透明的 RGBA
+fn decode_png_rgba(input: impl std::io::Read) -> Result<(Vec<u8>, u32, u32), String> {
+    let mut decoder = png::Decoder::new(input);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| format!("解码 PNG 失败: {}", e))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(|e| format!("解码 PNG 失败: {}", e))?;
+    buffer.truncate(info.buffer_size());
+
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha => buffer.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+        png::ColorType::Indexed => return Err("解码 PNG 失败: 不支持的调色板格式".to_string()),
+    };
+    Ok((rgba, info.width, info.height))
 }
 
 /// 加载并创建托盘图标
 /// 
 /// # 参数
 /// 
-/// * `is_state_1` - 是否为状态1（true=绿色，false=红色）
+/// * `color` - 方块颜色（见 `MacroState::color`）
 /// 
 /// # 返回
 /// 
-/// 返回一个托盘图标对象，用于在系统托盘中显示
+/// 成功返回一个托盘图标对象，用于在系统托盘中显示；创建失败返回错误信息
 /// 
 /// # 说明
 /// 
 /// 创建一个 16x16 像素的方块图标。在实际应用中，
 /// 可以加载自定义的图标文件（如 .ico 格式）。
 /// 每个像素包含 4 个字节（R, G, B, A）。
-fn load_icon(is_state_1: bool) -> tray_icon::Icon {
+fn load_icon(color: Rgb) -> Result<tray_icon::Icon, String> {
     const SIZE: usize = 16;
     
-    // 根据状态选择颜色：状态1为绿色，状态0为红色
-    let pixel_color = if is_state_1 {
-        [0, 255, 0, 255] // 绿色像素: R=0, G=255, B=0, A=255
-    } else {
-        [255, 0, 0, 255] // 红色像素: R=255, G=0, B=0, A=255
-    };
+    // 注意这里是 RGBA 字节顺序，与屏幕提示使用的 COLORREF（0x00BBGGRR）不同
+    let pixel_color = [color.r, color.g, color.b, 255];
     
     // 创建 16x16 的图标数据（256 个像素）
     let rgba: Vec<u8> = std::iter::repeat(&pixel_color)
@@ -222,5 +657,34 @@ fn load_icon(is_state_1: bool) -> tray_icon::Icon {
         .collect();
     
     tray_icon::Icon::from_rgba(rgba, SIZE as u32, SIZE as u32)
-        .expect("Failed to create icon from RGBA data")
+        .map_err(|e| format!("创建托盘图标数据失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 编码一张 2x1 的 PNG 图片
+    fn encode_png(color_type: png::ColorType, pixels: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+        encoder.set_color(color_type);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header().unwrap().write_image_data(pixels).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_decode_png_rgba() {
+        let rgba = [255, 0, 0, 255, 0, 255, 0, 128];
+        assert_eq!(decode_png_rgba(&encode_png(png::ColorType::Rgba, &rgba)[..]).unwrap(), (rgba.to_vec(), 2, 1));
+
+        // 不带透明通道的图片转换为不透明
+        let rgb = encode_png(png::ColorType::Rgb, &[255, 0, 0, 0, 0, 255]);
+        assert_eq!(decode_png_rgba(&rgb[..]).unwrap().0, vec![255, 0, 0, 255, 0, 0, 255, 255]);
+        let gray = encode_png(png::ColorType::Grayscale, &[0, 200]);
+        assert_eq!(decode_png_rgba(&gray[..]).unwrap().0, vec![0, 0, 0, 255, 200, 200, 200, 255]);
+
+        assert!(decode_png_rgba(&b"not a png"[..]).is_err());
+    }
 }
@@ -2,56 +2,327 @@
 //!
 //! 负责加载配置、初始化应用和错误处理
 
-use crate::app::TrayApp;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::app::{GlobalHotkeys, TrayApp};
 use crate::config::Config;
+use crate::macros::{AppNotice, MacroSystem};
+use crate::state::{AppState, MacroState};
 use winit::{
     event_loop::EventLoop,
     platform::windows::EventLoopBuilderExtWindows,
 };
-use global_hotkey::GlobalHotKeyManager;
+use global_hotkey::{hotkey::HotKey, GlobalHotKeyManager};
+
+/// 启用 DPI 感知
+///
+/// 需要在创建任何窗口（托盘、屏幕提示、对话框）之前调用，使屏幕提示在高缩放比例下清晰且尺寸正确
+pub fn enable_dpi_awareness() {
+    if let Err(e) = crate::winapi::window::set_process_dpi_aware() {
+        log::warn!("设置 DPI 感知失败: {}", e);
+    }
+}
+
+/// 指定配置文件路径的环境变量
+pub const CONFIG_ENV: &str = "KEYMACRO_CONFIG";
+
+/// 不弹出需要回答的对话框的环境变量（值为 `1` 或 `true` 时与 `--non-interactive` 相同）
+pub const NON_INTERACTIVE_ENV: &str = "KEYMACRO_NON_INTERACTIVE";
+
+/// 指定安全模式按键的环境变量（设为 `none` 时不检查）
+pub const SAFE_MODE_KEY_ENV: &str = "KEYMACRO_SAFE_MODE_KEY";
+
+/// 默认的安全模式按键：启动时按住该键则不加载配置
+const DEFAULT_SAFE_MODE_KEY: &str = "Shift";
+
+/// 安全模式使用的空配置
+const SAFE_MODE_CONFIG: &str = "hotkeys: []";
+
+/// 宏开关热键的候选列表，前面的热键被其他程序占用时依次尝试后面的
+const TOGGLE_HOTKEY_CANDIDATES: [&str; 3] = ["Ctrl+`", "Ctrl+Alt+M", "Ctrl+Alt+F12"];
+
+/// 单实例检查使用的命名互斥量名称（仅在当前登录会话内唯一）
+const INSTANCE_MUTEX_NAME: &str = "Local\\rust_keymacro_single_instance";
+
+/// 命令行参数
+#[derive(Debug, Default, PartialEq)]
+pub struct CliArgs {
+    /// `--config <path>` 指定的配置文件路径
+    pub config: Option<PathBuf>,
+    /// `--allow-multiple-instances`：跳过单实例检查，允许同时运行多个实例
+    pub allow_multiple_instances: bool,
+    /// `--headless`：不创建托盘图标和事件循环，只运行钩子和宏（见 `run_headless`）
+    pub headless: bool,
+    /// `--non-interactive`：启动时不弹出需要回答的对话框（如询问是否生成示例配置）
+    pub non_interactive: bool,
+}
+
+impl CliArgs {
+    /// 解析命令行参数（不含程序路径）
+    ///
+    /// # 参数
+    ///
+    /// * `args` - 命令行参数，通常为 `std::env::args().skip(1)`
+    ///
+    /// # 返回值
+    ///
+    /// 成功返回解析结果，参数缺少值或无法识别时返回错误信息
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "--config" {
+                let path = args.next().ok_or("--config 缺少配置文件路径".to_string())?;
+                parsed.config = Some(PathBuf::from(path));
+            } else if let Some(path) = arg.strip_prefix("--config=") {
+                parsed.config = Some(PathBuf::from(path));
+            } else if arg == "--allow-multiple-instances" {
+                parsed.allow_multiple_instances = true;
+            } else if arg == "--headless" {
+                parsed.headless = true;
+            } else if arg == "--non-interactive" {
+                parsed.non_interactive = true;
+            } else {
+                return Err(format!(
+                    "无法识别的命令行参数: {}\n\n用法: rust_keymacro [--config <配置文件路径>] [--allow-multiple-instances] [--headless] [--non-interactive]",
+                    arg
+                ));
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// 显式指定的配置文件路径（命令行参数优先于 `KEYMACRO_CONFIG` 环境变量）
+    ///
+    /// # 返回值
+    ///
+    /// 都未指定时返回 None，由 `load_config` 按目录查找
+    pub fn config_path(&self) -> Option<PathBuf> {
+        resolve_config_path(self.config.clone(), std::env::var_os(CONFIG_ENV))
+    }
+
+    /// 启动时能否弹出需要回答的对话框（传给 `load_config`）
+    ///
+    /// # 返回值
+    ///
+    /// 使用了 `--headless` 或 `--non-interactive`，或 `KEYMACRO_NON_INTERACTIVE` 为 `1` / `true` 时返回 false
+    pub fn interactive(&self) -> bool {
+        resolve_interactive(self, std::env::var(NON_INTERACTIVE_ENV).ok().as_deref())
+    }
+}
+
+/// 按命令行参数和 `KEYMACRO_NON_INTERACTIVE` 环境变量的值判断能否弹出需要回答的对话框
+fn resolve_interactive(args: &CliArgs, env: Option<&str>) -> bool {
+    !(args.headless || args.non_interactive || crate::config::env_flag_enabled(env))
+}
+
+/// 按优先级选择显式指定的配置文件路径：命令行参数 > 环境变量
+fn resolve_config_path(cli: Option<PathBuf>, env: Option<OsString>) -> Option<PathBuf> {
+    cli.or_else(|| env.filter(|v| !v.is_empty()).map(PathBuf::from))
+}
+
+/// 启动时是否按住了安全模式按键（默认为 Shift，可通过 `KEYMACRO_SAFE_MODE_KEY` 环境变量修改）
+///
+/// # 说明
+///
+/// 配置写错导致键盘无法正常使用（如拦截了所有按键）时，按住该键启动程序即可跳过配置，见 `run_safe_mode`
+pub fn safe_mode_requested() -> bool {
+    let key = std::env::var(SAFE_MODE_KEY_ENV).ok();
+    should_start_in_safe_mode(key.as_deref(), crate::winapi::keyboard::is_key_held)
+}
+
+/// 判断是否进入安全模式
+///
+/// # 参数
+///
+/// * `key` - 环境变量指定的按键名称，为 None 或空时使用 Shift，为 `none` 时不进入安全模式
+/// * `is_held` - 查询按键（虚拟键码）当前是否被按住
+///
+/// # 说明
+///
+/// 无法识别的按键名称记录警告并使用 Shift，避免写错环境变量导致无法进入安全模式
+fn should_start_in_safe_mode(key: Option<&str>, is_held: impl Fn(u16) -> bool) -> bool {
+    let name = key.map(str::trim).filter(|k| !k.is_empty()).unwrap_or(DEFAULT_SAFE_MODE_KEY);
+    if name.eq_ignore_ascii_case("none") {
+        return false;
+    }
+    let vk = crate::keys::named_key_vk(name).or_else(|| {
+        log::warn!("{} 指定的按键无法识别: {}，使用 {}", SAFE_MODE_KEY_ENV, name, DEFAULT_SAFE_MODE_KEY);
+        crate::keys::named_key_vk(DEFAULT_SAFE_MODE_KEY)
+    });
+    vk.is_some_and(is_held)
+}
+
+/// 单实例检查
+///
+/// # 返回值
+///
+/// 成功返回实例守卫（需在程序运行期间一直持有），已有实例在运行时返回错误信息
+///
+/// # 说明
+///
+/// 两个实例会各自安装键盘钩子，同时拦截和触发同一个热键，因此默认只允许运行一个实例。
+/// 创建互斥量本身失败时只记录警告，不阻止程序启动
+pub fn acquire_single_instance() -> Result<Option<crate::winapi::process::NamedMutex>, String> {
+    match crate::winapi::process::create_named_mutex(INSTANCE_MUTEX_NAME) {
+        Ok((_, true)) => Err(
+            "程序已经在运行（请查看系统托盘）。\n\n如确需同时运行多个实例，请使用 --allow-multiple-instances 参数启动".to_string()
+        ),
+        Ok((guard, false)) => Ok(Some(guard)),
+        Err(e) => {
+            log::warn!("单实例检查失败: {}", e);
+            Ok(None)
+        }
+    }
+}
 
 /// 加载配置文件
 ///
-/// 优先从当前工作目录加载，找不到则从可执行文件所在目录加载
+/// # 参数
+///
+/// * `path` - 显式指定的配置文件路径（来自 `--config` 或 `KEYMACRO_CONFIG`），为 None 时按目录查找
 ///
 /// # 返回值
 ///
-/// 成功返回配置对象，失败返回错误信息
-pub fn load_config() -> Result<Config, String> {
+/// 成功返回配置对象和实际加载的配置文件路径，失败返回错误信息
+///
+/// # 说明
+///
+/// - 显式指定的文件不存在时直接返回错误，不会回退到目录查找
+/// - 未指定时优先从当前工作目录加载，找不到则从可执行文件所在目录加载
+/// - 两处都没有配置文件时询问是否在程序目录生成示例配置，同意则生成后直接加载；
+///   `interactive` 为 false（如无界面模式）时不询问，直接返回找不到配置文件的错误
+pub fn load_config(path: Option<&Path>, interactive: bool) -> Result<(Config, PathBuf), String> {
+    let (config, path) = match path {
+        Some(path) => load_explicit_config(path)?,
+        None => search_config(interactive)?,
+    };
+
+    log::info!("已加载配置文件: {}", path.display());
+    Ok((config, path))
+}
+
+/// 加载显式指定的配置文件（相对路径按工作目录解析为绝对路径）
+fn load_explicit_config(path: &Path) -> Result<(Config, PathBuf), String> {
+    if !path.exists() {
+        return Err(format!("指定的配置文件不存在: {}", path.display()));
+    }
+
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let config = Config::from_file(&path)
+        .map_err(|e| format!("加载配置文件失败: {}\n\n配置文件路径: {}", e, path.display()))?;
+    Ok((config, path))
+}
+
+/// 依次在工作目录和程序目录中查找并加载 config.yaml，`interactive` 为 true 时才询问是否生成示例配置
+fn search_config(interactive: bool) -> Result<(Config, PathBuf), String> {
     // 获取当前工作目录
     let current_dir = std::env::current_dir()
-        .map_err(|_| "获取当前工作目录失败".to_string())?;
+        .map_err(|e| format!("获取当前工作目录失败: {}", e))?;
     
     let current_dir_config = current_dir.join("config.yaml");
     
     // 首先尝试从工作目录加载
     if current_dir_config.exists() {
-        return Config::from_file(current_dir_config.to_str().unwrap())
+        let config = Config::from_file(current_dir_config.to_str().unwrap())
             .map_err(|e| format!(
                 "加载配置文件失败: {}\n\n配置文件路径: {}\n\n当前工作目录: {}",
                 e,
                 current_dir_config.display(),
                 current_dir.display()
-            ));
+            ))?;
+        return Ok((config, current_dir_config));
     }
     
     // 如果工作目录没有，则从exe所在目录加载
     let exe_path = std::env::current_exe()
-        .map_err(|_| "获取可执行文件路径失败".to_string())?;
+        .map_err(|e| format!("获取可执行文件路径失败: {}", e))?;
     
     let exe_dir = exe_path.parent()
         .ok_or("获取可执行文件目录失败".to_string())?;
     
     let exe_dir_config = exe_dir.join("config.yaml");
-    
-    Config::from_file(exe_dir_config.to_str().unwrap())
+
+    if !exe_dir_config.exists() && interactive && offer_example_config(&exe_dir_config) {
+        Config::write_example(&exe_dir_config)
+            .map_err(|e| format!("生成示例配置失败: {}\n\n配置文件路径: {}", e, exe_dir_config.display()))?;
+        log::info!("已生成示例配置: {}", exe_dir_config.display());
+    }
+
+    let config = Config::from_file(exe_dir_config.to_str().unwrap())
         .map_err(|e| format!(
             "加载配置文件失败: {}\n\n请确保 config.yaml 文件存在于以下任一目录:\n1. 工作目录: {}\n2. 程序目录: {}\n\n当前工作目录: {}",
             e,
             current_dir_config.display(),
             exe_dir_config.display(),
             current_dir.display()
-        ))
+        ))?;
+    Ok((config, exe_dir_config))
+}
+
+/// 询问是否生成示例配置
+///
+/// # 参数
+///
+/// * `path` - 将要生成的配置文件路径
+///
+/// # 返回值
+///
+/// 用户同意时返回 true
+fn offer_example_config(path: &Path) -> bool {
+    show_confirm_dialog(&format!(
+        "未找到配置文件 config.yaml。\n\n是否在以下位置生成示例配置并继续运行？\n{}",
+        path.display()
+    ))
+}
+
+/// 配置中没有能够触发的热键时询问是否继续运行
+///
+/// # 参数
+///
+/// * `config` - 已加载的配置
+/// * `config_path` - 配置文件路径（显示在对话框中）
+///
+/// # 返回值
+///
+/// 有能够触发的热键或用户选择继续时返回 true
+pub fn confirm_active_hotkeys(config: &Config, config_path: &Path) -> bool {
+    let Some(warning) = config.no_active_hotkeys_warning() else {
+        return true;
+    };
+    show_confirm_dialog(&format!(
+        "{}，程序运行后不会执行任何宏。\n\n配置文件: {}\n\n是否仍然继续运行？",
+        warning,
+        config_path.display()
+    ))
+}
+
+/// 检查是否有热键绑定到危险按键（如单独的 Ctrl）
+///
+/// # 参数
+///
+/// * `config` - 已加载的配置
+/// * `interactive` - 能否弹出对话框（见 `CliArgs::interactive`）
+///
+/// # 说明
+///
+/// 只提示不阻止启动：总是记录警告日志，`interactive` 为 true 且 `dangerous_key_dialog` 开启时再弹出提示框
+pub fn warn_dangerous_hotkeys(config: &Config, interactive: bool) {
+    let Some(warning) = config.dangerous_hotkeys_warning() else {
+        return;
+    };
+    log::warn!("{}", warning);
+    if interactive && config.dangerous_key_dialog {
+        show_warning_dialog(&format!(
+            "{}。\n\n如果键盘无法正常使用，可以通过托盘菜单关闭宏或退出程序。\n设置 dangerous_key_dialog: false 可以不再显示此提示。",
+            warning
+        ));
+    }
 }
 
 /// 运行应用程序
@@ -61,50 +332,361 @@ pub fn load_config() -> Result<Config, String> {
 /// # 参数
 ///
 /// * `config` - 键盘宏配置
+/// * `config_path` - 配置文件路径（显示在托盘菜单中）
 ///
 /// # 返回值
 ///
 /// 运行成功返回 Ok，失败返回错误信息
-pub fn run_application(config: Config) -> Result<(), String> {
-    // 创建事件循环
-    let event_loop = EventLoop::builder()
+pub fn run_application(config: Config, config_path: &Path) -> Result<(), String> {
+    run_tray_application(config, config_path, false)
+}
+
+/// 以安全模式运行应用程序
+///
+/// # 参数
+///
+/// * `config_path` - 将要加载的配置文件路径（只显示在托盘菜单中，不会读取）
+///
+/// # 返回值
+///
+/// 运行成功返回 Ok，失败返回错误信息
+///
+/// # 说明
+///
+/// 弹出说明安全模式的对话框后以空配置运行托盘，不安装键盘钩子，任何按键都不会被拦截，
+/// 可以在修改配置文件后退出程序并正常启动
+pub fn run_safe_mode(config_path: &Path) -> Result<(), String> {
+    let config = Config::from_str(SAFE_MODE_CONFIG).map_err(|e| format!("创建空配置失败: {}", e))?;
+    show_warning_dialog(&format!(
+        "已进入安全模式（启动时按住了安全模式按键）。\n\n本次运行没有加载配置文件，也没有安装键盘钩子，不会拦截任何按键。\n修改配置文件后请通过托盘菜单退出程序，再正常启动。\n\n配置文件: {}",
+        config_path.display()
+    ));
+    run_tray_application(config, config_path, true)
+}
+
+/// 创建托盘和事件循环并运行，`safe_mode` 为 true 时不启动键盘宏系统
+fn run_tray_application(config: Config, config_path: &Path, safe_mode: bool) -> Result<(), String> {
+    // 创建事件循环（用户事件为宏处理线程发来的通知，如 toggle_system 热键切换了开关）
+    let event_loop = EventLoop::<AppNotice>::with_user_event()
         .with_any_thread(true)
         .build()
-        .map_err(|_| "创建事件循环失败".to_string())?;
+        .map_err(|e| format!("创建事件循环失败: {}", e))?;
+    let proxy = event_loop.create_proxy();
+    crate::macros::set_app_notifier(move |notice| {
+        if let Err(e) = proxy.send_event(notice) {
+            log::warn!("发送通知到托盘失败: {}", e);
+        }
+    });
 
     // 初始化托盘图标
-    let (tray_icon, quit_item_id, icon_state_0, icon_state_1) = crate::app::init_tray_icon();
+    let (tray_icon, menu_ids, icon_disabled, icons_enabled) = crate::app::init_tray_icon(&config, config_path)?;
 
     // 注册全局热键
-    let hotkey_manager = GlobalHotKeyManager::new()
-        .map_err(|_| "创建热键管理器失败".to_string())?;
-    
-    let hotkey = global_hotkey::hotkey::HotKey::new(
-        Some(global_hotkey::hotkey::Modifiers::CONTROL),
-        global_hotkey::hotkey::Code::Backquote
-    );
-    
-    hotkey_manager.register(hotkey)
-        .map_err(|_| "注册热键失败".to_string())?;
+    let hotkeys = register_global_hotkeys(&config)?;
 
     // 创建应用实例并运行
     let mut app = TrayApp::new(
-        quit_item_id,
+        menu_ids,
         tray_icon::menu::MenuEvent::receiver().clone(),
         tray_icon::TrayIconEvent::receiver().clone(),
-        hotkey_manager,
+        hotkeys,
         tray_icon,
-        icon_state_0,
-        icon_state_1,
+        icon_disabled,
+        icons_enabled,
         config,
     );
+    if safe_mode {
+        app = app.without_macro_system();
+    }
 
     event_loop.run_app(&mut app)
-        .map_err(|_| "运行事件循环失败".to_string())?;
+        .map_err(|e| format!("运行事件循环失败: {}", e))?;
     
     Ok(())
 }
 
+/// 无界面模式运行消息循环的线程 ID（为 0 时没有在运行）
+static HEADLESS_THREAD: AtomicU32 = AtomicU32::new(0);
+
+/// 是否已请求结束无界面模式
+static HEADLESS_STOP: AtomicBool = AtomicBool::new(false);
+
+/// 以无界面模式运行（不创建托盘图标和 winit 事件循环，只运行钩子和宏）
+///
+/// # 参数
+///
+/// * `config` - 键盘宏配置
+///
+/// # 返回值
+///
+/// 收到 Ctrl+C（或关闭控制台、调用 `stop_headless`）后卸载钩子并返回 Ok；
+/// 注册开关热键或设置键盘钩子失败时返回错误信息
+///
+/// # 说明
+///
+/// - 在调用线程运行 `GetMessageW` 消息循环，低级键盘钩子和全局热键都依赖它，返回前不要在其他地方阻塞该线程
+/// - 开关、暂停/中止、紧急停止和切换配置方案热键与托盘模式相同；开关状态同样从 `state.yaml` 恢复并在切换后保存
+/// - 不弹出任何对话框，警告只记录在日志中
+pub fn run_headless(config: Config) -> Result<(), String> {
+    HEADLESS_STOP.store(false, Ordering::SeqCst);
+    // 先创建消息队列再公开线程 ID，之后 stop_headless 投递的退出消息不会丢失
+    crate::winapi::window::ensure_message_queue();
+    HEADLESS_THREAD.store(crate::winapi::process::current_thread_id(), Ordering::SeqCst);
+    let result = run_headless_loop(config);
+    // 通知回调引用了本次运行的状态，返回后不再接收通知
+    crate::macros::clear_app_notifier();
+    HEADLESS_THREAD.store(0, Ordering::SeqCst);
+    result
+}
+
+/// 请求结束无界面模式，可以在任意线程调用（Ctrl+C 时由控制台事件处理函数调用）
+///
+/// # 说明
+///
+/// `run_headless` 卸载钩子后返回；无界面模式没有在运行时只记录请求，下次 `run_headless` 开始时清除
+pub fn stop_headless() {
+    HEADLESS_STOP.store(true, Ordering::SeqCst);
+    let thread_id = HEADLESS_THREAD.load(Ordering::SeqCst);
+    if thread_id != 0 {
+        if let Err(e) = crate::winapi::window::post_thread_quit(thread_id) {
+            log::warn!("通知无界面模式退出失败: {}", e);
+        }
+    }
+}
+
+/// 附加到启动程序的控制台（无界面模式下用于接收 Ctrl+C）
+///
+/// # 返回值
+///
+/// 从资源管理器或计划任务等没有控制台的环境启动时返回 false
+pub fn attach_console() -> bool {
+    crate::winapi::console::attach_parent_console()
+}
+
+/// 无界面模式是否正在运行（`run_headless` 已开始且尚未返回）
+pub fn headless_running() -> bool {
+    HEADLESS_THREAD.load(Ordering::SeqCst) != 0
+}
+
+/// 无界面模式的主体：注册热键、启动宏系统并运行消息循环
+fn run_headless_loop(config: Config) -> Result<(), String> {
+    if let Err(e) = crate::winapi::console::set_ctrl_handler(stop_headless) {
+        log::warn!("设置 Ctrl+C 处理函数失败: {}", e);
+    }
+    if let Some(warning) = config.dangerous_hotkeys_warning() {
+        log::warn!("{}", warning);
+    }
+
+    let hotkeys = register_global_hotkeys(&config)?;
+    crate::overlay::set_overlay_enabled(config.overlay_enabled);
+    crate::overlay::set_overlay_monitor(config.overlay_monitor);
+    // 只在配置开启 `overlay_progress` 时发送进度，重新加载配置后开启也能生效
+    crate::macros::set_progress_sender(crate::overlay::start_progress_overlay());
+
+    let state_path = crate::state::default_state_path();
+    let enabled = state_path.as_deref().map(AppState::load).unwrap_or_default().enabled;
+    let active_profile = Arc::new(AtomicUsize::new(0));
+    let system = MacroSystem::start(config.clone()).map_err(|e| format!("启动键盘宏系统失败: {}", e))?;
+    system.set_enabled(enabled);
+
+    // 开关热键和绑定了 toggle_system 的热键都经过通知保存状态并显示提示
+    let notice_profile = Arc::clone(&active_profile);
+    let notice_state_path = state_path.clone();
+    crate::macros::set_app_notifier(move |notice| match notice {
+        AppNotice::EnabledChanged(enabled) => {
+            let state = MacroState::new(enabled, notice_profile.load(Ordering::Relaxed));
+            save_headless_state(notice_state_path.as_deref(), enabled);
+            crate::overlay::show_overlay_colored(&state.label(), state.color());
+        }
+    });
+
+    log::info!("无界面模式已启动（开关热键 {}），按 Ctrl+C 退出", hotkeys.toggle_label);
+    let mut msg = Default::default();
+    let mut last_toggle = None;
+    while !HEADLESS_STOP.load(Ordering::SeqCst) && crate::winapi::window::get_message(&mut msg) {
+        crate::winapi::window::dispatch_message(&msg);
+        handle_headless_hotkeys(&hotkeys, &config, &active_profile, state_path.as_deref(), &mut last_toggle);
+    }
+
+    drop(system);
+    log::info!("无界面模式已退出");
+    Ok(())
+}
+
+/// 处理无界面模式下的全局热键事件（与托盘模式的处理相同，只是没有托盘需要更新）
+///
+/// `last_toggle` 记录开关热键上一次生效的时间，用于 `toggle_debounce_ms` 防抖
+fn handle_headless_hotkeys(
+    hotkeys: &GlobalHotkeys,
+    config: &Config,
+    active_profile: &AtomicUsize,
+    state_path: Option<&Path>,
+    last_toggle: &mut Option<Instant>,
+) {
+    use global_hotkey::{GlobalHotKeyEvent, HotKeyState};
+
+    while let Ok(hotkey_event) = GlobalHotKeyEvent::receiver().try_recv() {
+        if hotkey_event.state() != HotKeyState::Pressed {
+            continue;
+        }
+
+        let id = hotkey_event.id();
+        if Some(id) == hotkeys.stop_id {
+            log::warn!("紧急停止");
+            crate::macros::emergency_stop();
+            save_headless_state(state_path, false);
+            crate::overlay::show_overlay_colored("STOPPED", crate::state::DISABLED_COLOR);
+        } else if id == hotkeys.toggle_id {
+            let now = Instant::now();
+            if crate::macros::debounce_passed(*last_toggle, now, Duration::from_millis(config.toggle_debounce_ms)) {
+                *last_toggle = Some(now);
+                crate::macros::toggle_macro_enabled();
+            } else {
+                log::debug!("忽略 {} 毫秒内重复的开关热键", config.toggle_debounce_ms);
+            }
+        } else if Some(id) == hotkeys.pause_id {
+            if crate::macros::toggle_macro_pause() {
+                log::info!("序列已暂停");
+            } else {
+                log::info!("序列已继续");
+            }
+        } else if Some(id) == hotkeys.abort_id {
+            log::info!("中止正在执行的序列");
+            crate::macros::abort_macro();
+        } else if Some(id) == hotkeys.next_profile_id {
+            let index = config.next_profile_index(active_profile.load(Ordering::Relaxed));
+            if let Some(profile_config) = config.with_profile(index) {
+                crate::macros::set_config(profile_config);
+                active_profile.store(index, Ordering::Relaxed);
+                let name = config.profile_names()[index];
+                log::info!("已切换到配置方案: {}", name);
+                crate::overlay::show_overlay_colored(name, MacroState::new(true, index).color());
+            }
+        }
+    }
+}
+
+/// 保存无界面模式的开关状态
+fn save_headless_state(path: Option<&Path>, enabled: bool) {
+    let Some(path) = path else {
+        return;
+    };
+    if let Err(e) = (AppState { enabled }).save(path) {
+        log::warn!("保存状态文件失败 ({}): {}", path.display(), e);
+    }
+}
+
+/// 注册全局热键
+///
+/// # 参数
+///
+/// * `config` - 键盘宏配置（读取暂停/中止/紧急停止和切换配置方案热键）
+///
+/// # 返回值
+///
+/// 成功返回已注册的热键，开关热键注册失败或热键格式无效时返回错误信息
+///
+/// # 说明
+///
+/// 暂停/中止/紧急停止和切换配置方案热键注册失败（如已被其他程序占用）时只记录警告，不影响程序启动
+fn register_global_hotkeys(config: &Config) -> Result<GlobalHotkeys, String> {
+    let manager = GlobalHotKeyManager::new()
+        .map_err(|e| format!("创建热键管理器失败: {}", e))?;
+
+    let (toggle_label, toggle_id) = register_first_available(&TOGGLE_HOTKEY_CANDIDATES, |candidate| {
+        let hotkey: HotKey = candidate.parse().map_err(|e| format!("热键格式无效: {}", e))?;
+        manager.register(hotkey).map_err(|e| e.to_string())?;
+        Ok(hotkey.id())
+    })
+    .map_err(|e| format!("注册开关热键失败: {}", e))?;
+
+    if toggle_label != TOGGLE_HOTKEY_CANDIDATES[0] {
+        log::warn!("开关热键 {} 已被占用，改用 {}", TOGGLE_HOTKEY_CANDIDATES[0], toggle_label);
+    }
+    if let Some(warning) = config.global_hotkey_conflict_warning("开关热键", toggle_label) {
+        log::warn!("{}", warning);
+    }
+
+    let pause_id = register_optional_hotkey(&manager, "pause_hotkey", config.pause_hotkey.as_deref())?;
+    let abort_id = register_optional_hotkey(&manager, "abort_hotkey", config.abort_hotkey.as_deref())?;
+    let stop_id = register_optional_hotkey(&manager, "stop_hotkey", config.stop_hotkey.as_deref())?;
+    let next_profile_id = if config.profiles.is_empty() {
+        if config.next_profile_hotkey.is_some() {
+            log::warn!("配置了 next_profile_hotkey 但没有其他配置方案，不注册该热键");
+        }
+        None
+    } else {
+        register_optional_hotkey(&manager, "next_profile_hotkey", config.next_profile_hotkey.as_deref())?
+    };
+
+    Ok(GlobalHotkeys {
+        manager,
+        toggle_id,
+        toggle_label: toggle_label.to_string(),
+        pause_id,
+        abort_id,
+        stop_id,
+        next_profile_id,
+    })
+}
+
+/// 依次尝试注册候选热键，直到有一个注册成功
+///
+/// # 参数
+///
+/// * `candidates` - 按优先级排列的候选热键
+/// * `register` - 注册单个候选热键，成功返回热键 ID，失败返回原因
+///
+/// # 返回值
+///
+/// 成功返回注册成功的候选热键及其 ID，全部失败时返回每个候选热键的失败原因
+fn register_first_available<'a, F>(candidates: &[&'a str], mut register: F) -> Result<(&'a str, u32), String>
+where
+    F: FnMut(&str) -> Result<u32, String>,
+{
+    let mut failures = Vec::new();
+    for candidate in candidates {
+        match register(candidate) {
+            Ok(id) => return Ok((candidate, id)),
+            Err(e) => failures.push(format!("{}: {}", candidate, e)),
+        }
+    }
+    Err(failures.join("; "))
+}
+
+/// 注册配置中的可选热键
+///
+/// # 参数
+///
+/// * `manager` - 热键管理器
+/// * `field` - 配置字段名（用于错误信息）
+/// * `value` - 热键字符串，如 "Ctrl+Shift+P"，为 None 时不注册
+///
+/// # 返回值
+///
+/// 注册成功返回热键 ID，未配置或注册失败返回 None，格式无效时返回错误信息
+fn register_optional_hotkey(
+    manager: &GlobalHotKeyManager,
+    field: &str,
+    value: Option<&str>,
+) -> Result<Option<u32>, String> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+
+    let hotkey: HotKey = value
+        .parse()
+        .map_err(|e| format!("热键格式无效 ({}: {}): {}", field, value, e))?;
+
+    match manager.register(hotkey) {
+        Ok(()) => Ok(Some(hotkey.id())),
+        Err(e) => {
+            log::warn!("注册热键失败 ({}: {}): {}", field, value, e);
+            Ok(None)
+        }
+    }
+}
+
 /// 显示错误对话框
 ///
 /// 使用 Windows MessageBox 显示错误信息
@@ -113,18 +695,166 @@ pub fn run_application(config: Config) -> Result<(), String> {
 ///
 /// * `message` - 错误消息
 pub fn show_error_dialog(message: &str) {
-    use windows::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_ICONERROR, MB_OK};
-    use windows::Win32::Foundation::HWND;
-
-    let message_wide: Vec<u16> = message.encode_utf16().chain(std::iter::once(0)).collect();
-    let title_wide: Vec<u16> = "错误".encode_utf16().chain(std::iter::once(0)).collect();
-
-    unsafe {
-        MessageBoxW(
-            HWND(std::ptr::null_mut()),
-            windows::core::PCWSTR(message_wide.as_ptr()),
-            windows::core::PCWSTR(title_wide.as_ptr()),
-            MB_ICONERROR | MB_OK
+    use windows::Win32::UI::WindowsAndMessaging::{MB_ICONERROR, MB_OK};
+
+    crate::winapi::window::message_box(message, "错误", MB_ICONERROR | MB_OK);
+}
+
+/// 显示警告对话框
+///
+/// # 参数
+///
+/// * `message` - 警告内容
+pub fn show_warning_dialog(message: &str) {
+    use windows::Win32::UI::WindowsAndMessaging::{MB_ICONWARNING, MB_OK};
+
+    crate::winapi::window::message_box(message, "警告", MB_ICONWARNING | MB_OK);
+}
+
+/// 显示确认对话框
+///
+/// # 参数
+///
+/// * `message` - 询问内容
+///
+/// # 返回值
+///
+/// 用户点击“是”时返回 true
+pub fn show_confirm_dialog(message: &str) -> bool {
+    use windows::Win32::UI::WindowsAndMessaging::{IDYES, MB_ICONQUESTION, MB_YESNO};
+
+    crate::winapi::window::message_box(message, "提示", MB_ICONQUESTION | MB_YESNO) == IDYES
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_register_first_available() {
+        let candidates = ["Ctrl+`", "Ctrl+Alt+M", "Ctrl+Alt+F12"];
+        let mut attempts = Vec::new();
+
+        // 第一个被占用时使用第二个，之后的候选不再尝试
+        let result = register_first_available(&candidates, |c| {
+            attempts.push(c.to_string());
+            if c == "Ctrl+`" { Err("already registered".to_string()) } else { Ok(7) }
+        });
+        assert_eq!(result, Ok(("Ctrl+Alt+M", 7)));
+        assert_eq!(attempts, ["Ctrl+`", "Ctrl+Alt+M"]);
+
+        assert_eq!(register_first_available(&candidates, |_| Ok(1)), Ok(("Ctrl+`", 1)));
+
+        let err = register_first_available(&candidates, |_| Err("busy".to_string())).unwrap_err();
+        assert_eq!(err, "Ctrl+`: busy; Ctrl+Alt+M: busy; Ctrl+Alt+F12: busy");
+    }
+
+    #[test]
+    fn test_toggle_candidates_parse() {
+        for candidate in TOGGLE_HOTKEY_CANDIDATES {
+            assert!(candidate.parse::<HotKey>().is_ok(), "{}", candidate);
+        }
+    }
+
+    #[test]
+    fn test_parse_config_argument() {
+        assert_eq!(CliArgs::parse(args(&[])).unwrap().config, None);
+        assert_eq!(
+            CliArgs::parse(args(&["--config", "D:/macros/work.yaml"])).unwrap().config,
+            Some(PathBuf::from("D:/macros/work.yaml"))
+        );
+        assert_eq!(
+            CliArgs::parse(args(&["--config=work.yaml"])).unwrap().config,
+            Some(PathBuf::from("work.yaml"))
         );
+        assert!(CliArgs::parse(args(&["--config"])).is_err());
+        assert!(CliArgs::parse(args(&["--verbose"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_allow_multiple_instances() {
+        assert!(!CliArgs::parse(args(&[])).unwrap().allow_multiple_instances);
+
+        let parsed = CliArgs::parse(args(&["--allow-multiple-instances", "--config", "a.yaml"])).unwrap();
+        assert!(parsed.allow_multiple_instances);
+        assert_eq!(parsed.config, Some(PathBuf::from("a.yaml")));
+    }
+
+    #[test]
+    fn test_parse_headless() {
+        assert!(!CliArgs::parse(args(&[])).unwrap().headless);
+
+        let parsed = CliArgs::parse(args(&["--headless", "--config=a.yaml"])).unwrap();
+        assert!(parsed.headless);
+        assert_eq!(parsed.config, Some(PathBuf::from("a.yaml")));
+    }
+
+    #[test]
+    fn test_parse_non_interactive() {
+        let parsed = CliArgs::parse(args(&["--non-interactive"])).unwrap();
+        assert!(parsed.non_interactive);
+        assert!(!parsed.headless);
+
+        let default = CliArgs::default();
+        assert!(resolve_interactive(&default, None));
+        assert!(resolve_interactive(&default, Some("0")));
+        assert!(!resolve_interactive(&default, Some("true")));
+        assert!(!resolve_interactive(&parsed, None));
+        assert!(!resolve_interactive(&CliArgs { headless: true, ..CliArgs::default() }, None));
+    }
+
+    #[test]
+    fn test_config_path_precedence() {
+        let cli = Some(PathBuf::from("cli.yaml"));
+        let env = Some(OsString::from("env.yaml"));
+
+        assert_eq!(resolve_config_path(cli.clone(), env.clone()), cli);
+        assert_eq!(resolve_config_path(None, env), Some(PathBuf::from("env.yaml")));
+        assert_eq!(resolve_config_path(None, Some(OsString::new())), None);
+        assert_eq!(resolve_config_path(None, None), None);
+    }
+
+    #[test]
+    fn test_safe_mode_decision() {
+        const VK_SHIFT: u16 = 0x10;
+        const VK_CONTROL: u16 = 0x11;
+        let held = |keys: &'static [u16]| move |vk: u16| keys.contains(&vk);
+
+        // 默认检查 Shift，空值和无法识别的名称同样使用 Shift
+        assert!(should_start_in_safe_mode(None, held(&[VK_SHIFT])));
+        assert!(!should_start_in_safe_mode(None, held(&[])));
+        assert!(should_start_in_safe_mode(Some(" "), held(&[VK_SHIFT])));
+        assert!(should_start_in_safe_mode(Some("NoSuchKey"), held(&[VK_SHIFT])));
+
+        // 指定其他按键时只检查该键，none 时不检查
+        assert!(should_start_in_safe_mode(Some("ctrl"), held(&[VK_CONTROL])));
+        assert!(!should_start_in_safe_mode(Some("Ctrl"), held(&[VK_SHIFT])));
+        assert!(!should_start_in_safe_mode(Some("none"), |_: u16| -> bool { panic!("不应查询按键状态") }));
+
+        assert!(Config::from_str(SAFE_MODE_CONFIG).unwrap().hotkeys.is_empty());
+    }
+
+    #[test]
+    fn test_explicit_config_reports_loaded_path() {
+        let path = std::env::temp_dir().join(format!("keymacro_explicit_{}.yaml", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        Config::write_example(&path).unwrap();
+
+        let loaded = load_config(Some(&path), false);
+        let _ = std::fs::remove_file(&path);
+
+        let (_, loaded_path) = loaded.unwrap();
+        assert_eq!(loaded_path, path);
+    }
+
+    #[test]
+    fn test_missing_explicit_config_is_an_error() {
+        let path = std::env::temp_dir().join("keymacro_missing_config.yaml");
+        let err = load_config(Some(&path), false).unwrap_err();
+        assert!(err.contains("不存在"), "{}", err);
     }
 }
@@ -2,74 +2,635 @@
 //!
 //! 支持从 YAML 文件加载键盘宏配置
 
-use rand::Rng;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
+
+/// 时长配置值
+///
+/// 纯数字按毫秒计（兼容旧配置），字符串可以带单位：`"500us"`、`"2ms"`、`"1.5s"`。
+/// 精度为微秒，但 Windows 上 `thread::sleep` 的实际精度通常只有 1~15 毫秒，
+/// 更短的等待会被延长
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DurationSpec(pub Duration);
+
+impl DurationSpec {
+    /// 零时长
+    pub const ZERO: Self = Self(Duration::ZERO);
+
+    /// 以毫秒创建
+    pub const fn from_millis(ms: u64) -> Self {
+        Self(Duration::from_millis(ms))
+    }
+
+    /// 对应的时长
+    pub fn duration(&self) -> Duration {
+        self.0
+    }
+
+    /// 是否为 0
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    /// 微秒数（超出 u64 范围时取最大值）
+    fn micros(&self) -> u64 {
+        u64::try_from(self.0.as_micros()).unwrap_or(u64::MAX)
+    }
+
+    /// 解析带单位的时长字符串
+    ///
+    /// # 参数
+    ///
+    /// * `value` - 如 `"500us"`、`"2ms"`、`"1.5s"`，不带单位时按毫秒计（`"µs"` 与 `"us"` 相同）
+    ///
+    /// # 返回
+    ///
+    /// 格式无效或为负数时返回 None
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (number, micros_per_unit) = [("us", 1.0), ("µs", 1.0), ("ms", 1_000.0), ("s", 1_000_000.0)]
+            .into_iter()
+            .find_map(|(suffix, scale)| value.strip_suffix(suffix).map(|n| (n, scale)))
+            .unwrap_or((value, 1_000.0));
+
+        let number: f64 = number.trim().parse().ok()?;
+        let micros = number * micros_per_unit;
+        if !micros.is_finite() || micros < 0.0 || micros > u64::MAX as f64 {
+            return None;
+        }
+        Some(Self(Duration::from_micros(micros.round() as u64)))
+    }
+}
+
+impl std::fmt::Display for DurationSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl Serialize for DurationSpec {
+    /// 整毫秒写为数字（与旧配置相同），否则写为带 `us` 单位的字符串
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let micros = self.micros();
+        let (millis, sub_millis) = (micros / 1_000, micros % 1_000);
+        if sub_millis == 0 {
+            serializer.serialize_u64(millis)
+        } else {
+            serializer.serialize_str(&format!("{}us", micros))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DurationSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DurationVisitor;
+
+        impl serde::de::Visitor<'_> for DurationVisitor {
+            type Value = DurationSpec;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("毫秒数，或带单位的时长字符串（如 \"500us\"、\"2ms\"、\"1s\"）")
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, ms: u64) -> Result<DurationSpec, E> {
+                Ok(DurationSpec::from_millis(ms))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, ms: i64) -> Result<DurationSpec, E> {
+                u64::try_from(ms)
+                    .map(DurationSpec::from_millis)
+                    .map_err(|_| E::custom(format!("时长不能为负数: {}", ms)))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<DurationSpec, E> {
+                DurationSpec::parse(value).ok_or_else(|| E::custom(format!("无效的时长: {}", value)))
+            }
+        }
+
+        deserializer.deserialize_any(DurationVisitor)
+    }
+}
 
 /// 延迟配置，支持固定值或随机范围
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(untagged)]
+///
+/// 通过 `RawDelayConfig` 解析，随机范围的 `min` 大于 `max` 时在加载配置时报错
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged, try_from = "RawDelayConfig")]
 pub enum DelayConfig {
-    /// 固定延迟值（毫秒）
-    Fixed(u64),
-    /// 随机延迟范围（毫秒）
-    Range { min: u64, max: u64 },
+    /// 固定延迟值
+    Fixed(DurationSpec),
+    /// 随机延迟范围
+    Range { min: DurationSpec, max: DurationSpec },
+}
+
+/// 延迟配置的原始结构，写法与 `DelayConfig` 相同
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawDelayConfig {
+    Fixed(DurationSpec),
+    Range { min: DurationSpec, max: DurationSpec },
+}
+
+impl TryFrom<RawDelayConfig> for DelayConfig {
+    type Error = String;
+
+    fn try_from(raw: RawDelayConfig) -> Result<Self, Self::Error> {
+        match raw {
+            RawDelayConfig::Fixed(delay) => Ok(DelayConfig::Fixed(delay)),
+            RawDelayConfig::Range { min, max } if min.micros() > max.micros() => {
+                Err(format!("随机延迟的 min ({}) 不能大于 max ({})", min, max))
+            }
+            RawDelayConfig::Range { min, max } => Ok(DelayConfig::Range { min, max }),
+        }
+    }
 }
 
 impl DelayConfig {
-    /// 获取实际延迟值（如果是随机范围则生成随机值）
-    pub fn get_delay(&self) -> u64 {
+    /// 获取实际延迟值（如果是随机范围则生成随机值，精确到微秒）
+    pub fn get_delay(&self) -> Duration {
         match self {
-            DelayConfig::Fixed(ms) => *ms,
+            DelayConfig::Fixed(delay) => delay.duration(),
             DelayConfig::Range { min, max } => {
-                rand::thread_rng().gen_range(*min..=*max)
+                Duration::from_micros(crate::random::gen_range(min.micros()..=max.micros()))
             }
         }
     }
+
+    /// 是否始终为 0
+    pub fn is_zero(&self) -> bool {
+        match self {
+            DelayConfig::Fixed(delay) => delay.is_zero(),
+            DelayConfig::Range { min, max } => min.is_zero() && max.is_zero(),
+        }
+    }
+}
+
+/// 默认配置方案（顶层 `hotkeys`）的名称
+pub const DEFAULT_PROFILE_NAME: &str = "默认";
+
+/// 当前程序支持的配置文件版本
+pub const CONFIG_VERSION: u32 = 1;
+
+/// 开启演练模式的环境变量，设为 `1` 或 `true` 时与配置中的 `dry_run: true` 效果相同
+pub const DRY_RUN_ENV: &str = "KEYMACRO_DRY_RUN";
+
+fn default_version() -> u32 {
+    1
+}
+
+fn default_stop_hotkey() -> Option<String> {
+    Some("Ctrl+Alt+Escape".to_string())
+}
+
+fn default_toggle_debounce_ms() -> u64 {
+    200
+}
+
+fn default_suppress_triggers() -> bool {
+    true
+}
+
+fn default_gamepad_enabled() -> bool {
+    true
+}
+
+fn default_overlay_enabled() -> bool {
+    true
+}
+
+fn default_left_deadzone() -> u16 {
+    crate::gamepad::stick::DEFAULT_LEFT_DEADZONE
+}
+
+fn default_right_deadzone() -> u16 {
+    crate::gamepad::stick::DEFAULT_RIGHT_DEADZONE
+}
+
+fn default_delay_scale() -> f64 {
+    1.0
+}
+
+fn default_pixel_timeout() -> u64 {
+    5000
+}
+
+fn default_release_timeout() -> u64 {
+    10000
+}
+
+fn default_chord_timeout() -> u64 {
+    1000
+}
+
+fn default_dangerous_key_dialog() -> bool {
+    true
+}
+
+/// 单独作为触发键时会使系统难以操作的修饰键：每次按下都会触发宏，拦截时还会使所有快捷键失效
+static DANGEROUS_MODIFIERS: &[&str] = &["Ctrl", "Alt", "Shift", "LWin", "RWin"];
+
+/// 拦截触发键时会妨碍正常输入的常用按键
+static DANGEROUS_SUPPRESSED_KEYS: &[&str] = &["Enter", "Escape", "Tab", "Space", "Backspace"];
+
+fn default_chat_key() -> String {
+    "Enter".to_string()
+}
+
+fn default_chat_pre_delay() -> DurationSpec {
+    DurationSpec::from_millis(100)
+}
+
+fn default_chat_post_delay() -> DurationSpec {
+    DurationSpec::from_millis(50)
 }
 
 /// 配置文件根结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
+    /// 配置文件版本，缺省为 1
+    #[serde(default = "default_version")]
+    pub version: u32,
+    #[serde(deserialize_with = "deserialize_hotkeys")]
+    pub hotkeys: Vec<HotkeyConfig>,
+    /// 宏开启状态的托盘图标文件路径（.ico 或 .png），未设置时使用绿色方块
+    #[serde(default)]
+    pub icon_enabled: Option<String>,
+    /// 宏关闭状态的托盘图标文件路径（.ico 或 .png），未设置时使用红色方块
+    #[serde(default)]
+    pub icon_disabled: Option<String>,
+    /// 暂停/继续正在执行的序列的全局热键，默认不注册（全局热键会占用该按键，其他程序收不到）
+    #[serde(default)]
+    pub pause_hotkey: Option<String>,
+    /// 中止正在执行的序列的全局热键，默认不注册
+    #[serde(default)]
+    pub abort_hotkey: Option<String>,
+    /// 紧急停止热键（禁用宏、中止序列并释放所有按键），默认为 Ctrl+Alt+Escape，设为 null 时不注册
+    #[serde(default = "default_stop_hotkey")]
+    pub stop_hotkey: Option<String>,
+    /// 开关热键的防抖时间（毫秒），距上一次切换不足该时间的再次按下被忽略，默认为 200；设为 0 时不防抖
+    #[serde(default = "default_toggle_debounce_ms")]
+    pub toggle_debounce_ms: u64,
+    /// 同一触发键两次按下的最短间隔（毫秒），间隔不足时后一次按下视为自动重复被忽略，默认为 0（只按系统的自动重复标记判断）；
+    /// 用于长按时不设置重复标记、而是连续发送按下事件的键盘
+    #[serde(default)]
+    pub repeat_guard_ms: u64,
+    /// 是否拦截触发键的原始按键事件，默认为 true；设为 false 时触发键在执行宏的同时照常生效
+    #[serde(default = "default_suppress_triggers")]
+    pub suppress_triggers: bool,
+    /// 演练模式：只在日志中记录将要发送的按键和等待，不发送真实输入，默认为 false
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 无法以 Unicode 方式输入的字符是否改用 Alt+小键盘输入字符编码，默认为 false；
+    /// 效果取决于键盘布局和目标程序
+    #[serde(default)]
+    pub alt_numpad_fallback: bool,
+    /// 输入文本（`type_text`、`type_file`、序列和聊天）前是否临时关闭前台窗口的输入法，输入结束后恢复，默认为 false；
+    /// 用于中文、日文等输入法开启时注入的按键被输入法组合截获的情况
+    #[serde(default)]
+    pub ime_safe: bool,
+    /// 是否在切换开关、紧急停止等操作时显示屏幕中央的提示，默认为 true；
+    /// 录屏或直播时可以设为 false，只通过托盘图标和提示反馈状态
+    #[serde(default = "default_overlay_enabled")]
+    pub overlay_enabled: bool,
+    /// 屏幕提示显示在哪个显示器的工作区中央：主显示器（默认）、鼠标所在的显示器或按序号指定
+    #[serde(default)]
+    pub overlay_monitor: OverlayMonitor,
+    /// 序列和聊天执行期间是否一直显示屏幕提示（热键名称和当前步骤，如 `F5 3/12`），执行结束后隐藏，默认为 false；
+    /// 关闭 `overlay_enabled` 时不显示
+    #[serde(default)]
+    pub overlay_progress: bool,
+    /// 手柄设置（是否启用、摇杆死区和垂直方向反转）
+    #[serde(default)]
+    pub gamepad: GamepadConfig,
+    /// 全局延迟倍率，所有宏的等待时间都乘以该值（如 4.0 为放慢 4 倍），默认为 1.0；
+    /// 运行时可以在托盘菜单中修改
+    #[serde(default = "default_delay_scale")]
+    pub delay_scale: f64,
+    /// 其他配置方案，每个方案是一组替换顶层 `hotkeys` 的热键，可在托盘菜单中切换
+    #[serde(default)]
+    pub profiles: Vec<ProfileConfig>,
+    /// 切换到下一个配置方案的全局热键（如 "Ctrl+Alt+P"），默认不注册
+    #[serde(default)]
+    pub next_profile_hotkey: Option<String>,
+    /// 组合序列（如 `key: "` 1"`）中两次按键之间的最长间隔毫秒数，超过后重新开始匹配，默认 1000
+    #[serde(default = "default_chord_timeout")]
+    pub chord_timeout_ms: u64,
+    /// 组合序列不匹配时是否重新发送已拦截的按键，默认 false（直接丢弃）
+    #[serde(default)]
+    pub chord_replay: bool,
+    /// 序列中按键步骤（`action: complete`）按下与释放之间的默认间隔毫秒数，默认 0（立即释放）；
+    /// 部分游戏会忽略过短的按键，可以适当调大。序列的 `key_hold` 和步骤的 `delay` 优先
+    #[serde(default)]
+    pub default_key_hold_ms: u64,
+    /// 有热键绑定到单独的修饰键等危险按键时，是否在启动时弹出提示，默认为 true（日志中总是记录警告）
+    #[serde(default = "default_dangerous_key_dialog")]
+    pub dangerous_key_dialog: bool,
+    /// 随机延迟、抖动和随机等待使用的随机数种子，设置后同样的操作每次得到相同的等待时间（便于测试），默认使用系统熵
+    #[serde(default)]
+    pub rng_seed: Option<u64>,
+    /// 是否以略高于普通的优先级运行宏处理线程，默认为 false；系统繁忙时对时间敏感的宏不容易被推迟，只在启动时生效
+    #[serde(default)]
+    pub high_priority_worker: bool,
+    /// 键盘钩子始终放行的按键（如 `["Escape"]`），即使绑定了热键也不拦截、不触发宏；默认为空
+    #[serde(default)]
+    pub never_suppress: Vec<String>,
+    /// 加在每段输入文本（`type_text`、`type_file` 和序列中的 `text` 步骤）前面的文本，默认为空
+    #[serde(default)]
+    pub type_text_prefix: String,
+    /// 加在每段输入文本后面的文本，默认为空
+    #[serde(default)]
+    pub type_text_suffix: String,
+    /// 触发键索引（小写规范键名 -> 绑定该键的配置信息），
+    /// 由 `from_str` 和 `with_profile` 建立；为 `None` 时 `find_hotkey` 逐个比较
+    #[serde(skip)]
+    hotkey_index: Option<HashMap<String, HotkeyIndexEntry>>,
+}
+
+/// 触发键索引中单个按键的信息
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HotkeyIndexEntry {
+    /// `hotkeys` 中第一个绑定该键的序号
+    first: usize,
+    /// 是否有绑定该键且限定了窗口（`when_window`）的配置
+    has_window_variants: bool,
+}
+
+/// 显示屏幕提示的显示器，配置中写为 `"primary"`、`"cursor"` 或从 1 开始的显示器序号
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverlayMonitor {
+    /// 主显示器
+    #[default]
+    Primary,
+    /// 显示提示时鼠标光标所在的显示器
+    Cursor,
+    /// 按系统枚举顺序的第 n 个显示器（从 1 开始）
+    Index(usize),
+}
+
+impl Serialize for OverlayMonitor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            OverlayMonitor::Primary => serializer.serialize_str("primary"),
+            OverlayMonitor::Cursor => serializer.serialize_str("cursor"),
+            OverlayMonitor::Index(index) => serializer.serialize_u64(*index as u64),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for OverlayMonitor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MonitorVisitor;
+
+        impl serde::de::Visitor<'_> for MonitorVisitor {
+            type Value = OverlayMonitor;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("\"primary\"、\"cursor\" 或从 1 开始的显示器序号")
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, index: u64) -> Result<OverlayMonitor, E> {
+                match usize::try_from(index) {
+                    Ok(index) if index >= 1 => Ok(OverlayMonitor::Index(index)),
+                    _ => Err(E::custom(format!("显示器序号从 1 开始: {}", index))),
+                }
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, index: i64) -> Result<OverlayMonitor, E> {
+                match u64::try_from(index) {
+                    Ok(index) => self.visit_u64(index),
+                    Err(_) => Err(E::custom(format!("显示器序号从 1 开始: {}", index))),
+                }
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<OverlayMonitor, E> {
+                match value.to_ascii_lowercase().as_str() {
+                    "primary" => Ok(OverlayMonitor::Primary),
+                    "cursor" => Ok(OverlayMonitor::Cursor),
+                    _ => Err(E::custom(format!("未知的 overlay_monitor '{}'，可选值: primary, cursor 或显示器序号", value))),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(MonitorVisitor)
+    }
+}
+
+/// 配置方案
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileConfig {
+    /// 方案名称（显示在托盘菜单和切换时的屏幕提示中）
+    pub name: String,
+    /// 该方案的热键，切换后替换顶层 `hotkeys`
+    #[serde(deserialize_with = "deserialize_hotkeys")]
     pub hotkeys: Vec<HotkeyConfig>,
 }
 
+/// 手柄设置，摇杆推动超过死区时视为按下对应的方向键（如 `LSUp`）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GamepadConfig {
+    /// 是否启用手柄支持，默认为 true；没有手柄时可以设为 false，不再启动手柄轮询线程
+    #[serde(default = "default_gamepad_enabled")]
+    pub enabled: bool,
+    /// 左摇杆死区（0–32767），默认为 XInput 建议值 7849
+    #[serde(default = "default_left_deadzone")]
+    pub left_deadzone: u16,
+    /// 右摇杆死区（0–32767），默认为 XInput 建议值 8689
+    #[serde(default = "default_right_deadzone")]
+    pub right_deadzone: u16,
+    /// 是否反转左摇杆的垂直方向，默认 false
+    #[serde(default)]
+    pub invert_left_y: bool,
+    /// 是否反转右摇杆的垂直方向，默认 false
+    #[serde(default)]
+    pub invert_right_y: bool,
+    /// 是否在日志中记录按钮表中没有的 `wButtons` 按钮位变化（用于了解手柄实际发送的按钮），默认 false
+    #[serde(default)]
+    pub log_unknown: bool,
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_gamepad_enabled(),
+            left_deadzone: default_left_deadzone(),
+            right_deadzone: default_right_deadzone(),
+            invert_left_y: false,
+            invert_right_y: false,
+            log_unknown: false,
+        }
+    }
+}
+
+impl GamepadConfig {
+    /// 检查死区是否在摇杆偏移范围内
+    fn validate(&self) -> Result<(), String> {
+        let max = crate::gamepad::stick::MAX_DEADZONE;
+        for (name, value) in [("left_deadzone", self.left_deadzone), ("right_deadzone", self.right_deadzone)] {
+            if value > max {
+                return Err(format!("gamepad.{} 超出范围 0–{}: {}", name, max, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 单个字符串或字符串列表，如 `key: "F1"` 或 `key: ["F1", "GP:A"]`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum StringOrVec {
+    /// 单个值
+    Single(String),
+    /// 多个值
+    Multiple(Vec<String>),
+}
+
+impl StringOrVec {
+    /// 以切片形式获取所有值
+    pub fn as_slice(&self) -> &[String] {
+        match self {
+            StringOrVec::Single(value) => std::slice::from_ref(value),
+            StringOrVec::Multiple(values) => values,
+        }
+    }
+}
+
 /// 触发源类型
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum TriggerSource {
-    /// 键盘按键，如 "F2", "'"
-    Keyboard { key: String },
-    /// 手柄按键，如 "A", "LT", "DUp"
-    Gamepad { key: String },
+    /// 键盘按键，如 "F2", "'"，或按键组 ["F1", "GP:A"]
+    Keyboard { key: StringOrVec },
+    /// 手柄按键，如 "A", "LB", "DUp"，或按键组 ["A", "B"]
+    Gamepad { key: StringOrVec },
 }
 
 impl TriggerSource {
-    /// 获取触发键名称（用于查找）
-    pub fn key_name(&self) -> String {
+    /// 获取所有触发键名称（按键组中每个元素一个）
+    ///
+    /// 手柄按键会加上 "GP:" 前缀；键盘按键组中也可以直接写 "GP:A" 来混合手柄按键
+    pub fn key_names(&self) -> Vec<String> {
         match self {
-            TriggerSource::Keyboard { key } => key.clone(),
-            TriggerSource::Gamepad { key } => format!("GP:{}", key),
+            TriggerSource::Keyboard { key } => key.as_slice().to_vec(),
+            TriggerSource::Gamepad { key } => key
+                .as_slice()
+                .iter()
+                .map(|k| {
+                    if is_gamepad_key(k) {
+                        k.clone()
+                    } else {
+                        format!("GP:{}", k)
+                    }
+                })
+                .collect(),
         }
     }
 
-    /// 检查是否匹配给定的键名
+    /// 获取触发键名称（用于显示，按键组以 "|" 连接）
+    pub fn key_name(&self) -> String {
+        self.key_names().join("|")
+    }
+
+    /// 检查是否匹配给定的键名（按键组中任意一个匹配即可）
+    ///
+    /// 具名按键按规范名称比较，如配置中的 "Return" 与钩子上报的 "Enter" 视为同一按键
     pub fn matches(&self, name: &str) -> bool {
-        self.key_name().eq_ignore_ascii_case(name)
+        let name = crate::keys::canonicalize(name);
+        let same = |k: &str| crate::keys::canonicalize(k).eq_ignore_ascii_case(name);
+        match self {
+            TriggerSource::Keyboard { key } => key.as_slice().iter().any(|k| same(k)),
+            // 与 `key_names` 相同，没有 "GP:" 前缀的手柄按键按加上前缀后的名称比较
+            TriggerSource::Gamepad { key } => key.as_slice().iter().any(|k| {
+                if is_gamepad_key(k) {
+                    same(k)
+                } else {
+                    is_gamepad_key(name) && name[3..].eq_ignore_ascii_case(k)
+                }
+            }),
+        }
     }
 }
 
 /// 单个热键配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// 通过 `RawHotkeyConfig` 解析，按 `HotkeyConfigRef` 的写法序列化，两者互为逆操作
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "RawHotkeyConfig")]
 pub struct HotkeyConfig {
     /// 触发源配置（新格式）
-    #[serde(flatten)]
     pub trigger: TriggerSource,
-    /// 操作类型："type_text" 或 "sequence"
+    /// 操作类型：见 `ACTION_NAMES`；设置了 `tap_action` / `hold_action` 时为 "none"
     pub action: String,
-    /// 操作参数
+    /// 操作参数，按 `action` 解析
     pub params: ActionParams,
+    /// 轻按（在 `hold_threshold_ms` 内松开）时执行的操作，与 `hold_action` 同时设置
+    pub tap_action: Option<ActionParams>,
+    /// 按住达到 `hold_threshold_ms` 时执行的操作，与 `tap_action` 同时设置
+    pub hold_action: Option<ActionParams>,
+    /// 区分轻按和长按的按住时长（毫秒），默认 300
+    pub hold_threshold_ms: u64,
+    /// 按住触发键时是否随系统自动重复再次触发宏（默认 false，忽略自动重复）
+    pub repeat_on_hold: bool,
+    /// 执行后的冷却时间（毫秒），冷却期间再次按下触发键不执行宏；默认 0，不限制
+    pub cooldown_ms: u64,
+    /// 宏的名称（只用于显示，如托盘菜单）
+    pub name: Option<String>,
+    /// 宏的说明（只用于记录用途，不影响执行）
+    pub description: Option<String>,
+    /// 只在指定窗口处于前台时生效：与进程文件名相同（如 `Photoshop.exe`）或窗口标题包含该文本，
+    /// 均不区分大小写；未设置时作为同一触发键的默认配置
+    pub when_window: Option<String>,
+}
+
+/// 热键配置序列化时的结构：轻按/长按热键省略 `action` 和 `params`，普通热键省略 `hold_threshold_ms`，
+/// 不需要参数的操作省略 `params`，使序列化结果能被 `RawHotkeyConfig` 重新解析
+#[derive(Serialize)]
+struct HotkeyConfigRef<'a> {
+    #[serde(flatten)]
+    trigger: &'a TriggerSource,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    params: Option<&'a ActionParams>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "serialize_nested_action")]
+    tap_action: Option<&'a ActionParams>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "serialize_nested_action")]
+    hold_action: Option<&'a ActionParams>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hold_threshold_ms: Option<u64>,
+    repeat_on_hold: bool,
+    cooldown_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    when_window: Option<&'a str>,
+}
+
+impl Serialize for HotkeyConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let dual = self.tap_or_hold().is_some();
+        HotkeyConfigRef {
+            trigger: &self.trigger,
+            action: (!dual).then_some(self.action.as_str()),
+            params: (!dual).then_some(&self.params).filter(|params| params.has_params()),
+            tap_action: self.tap_action.as_ref(),
+            hold_action: self.hold_action.as_ref(),
+            hold_threshold_ms: dual.then_some(self.hold_threshold_ms),
+            repeat_on_hold: self.repeat_on_hold,
+            cooldown_ms: self.cooldown_ms,
+            name: self.name.as_deref(),
+            description: self.description.as_deref(),
+            when_window: self.when_window.as_deref(),
+        }
+        .serialize(serializer)
+    }
 }
 
 impl HotkeyConfig {
@@ -77,137 +638,2684 @@ impl HotkeyConfig {
     pub fn key(&self) -> String {
         self.trigger.key_name()
     }
+
+    /// 是否为切换宏开关的热键（`action: "toggle_system"`）
+    pub fn is_system_toggle(&self) -> bool {
+        matches!(self.params, ActionParams::ToggleSystem)
+    }
+
+    /// 轻按和长按时执行的操作，未设置 `tap_action` / `hold_action` 时返回 None
+    pub fn tap_or_hold(&self) -> Option<(&ActionParams, &ActionParams)> {
+        self.tap_action.as_ref().zip(self.hold_action.as_ref())
+    }
+
+    /// 热键的所有操作参数：`params`，以及设置了轻按/长按时的 `tap_action` 和 `hold_action`
+    pub fn actions(&self) -> impl Iterator<Item = &ActionParams> {
+        std::iter::once(&self.params).chain(&self.tap_action).chain(&self.hold_action)
+    }
+
+    /// 同 `actions`，返回可变引用（用于迁移和填充默认值）
+    fn actions_mut(&mut self) -> impl Iterator<Item = &mut ActionParams> {
+        std::iter::once(&mut self.params).chain(&mut self.tap_action).chain(&mut self.hold_action)
+    }
+
+    /// 托盘菜单中显示的标签，格式为 `触发键 — 名称`，未设置名称时显示操作类型
+    /// （轻按/长按热键显示为 `轻按操作 / 长按操作`）
+    ///
+    /// 限定了窗口的热键在末尾加上 `[窗口]`
+    pub fn menu_label(&self) -> String {
+        let action = match self.tap_or_hold() {
+            Some((tap, hold)) => format!("{} / {}", tap.action_name(), hold.action_name()),
+            None => self.action.clone(),
+        };
+        let label = format!("{} — {}", self.key(), self.name.as_deref().unwrap_or(&action));
+        match &self.when_window {
+            Some(window) => format!("{} [{}]", label, window),
+            None => label,
+        }
+    }
+
+    /// 检查热键是否在给定的前台窗口中生效
+    ///
+    /// # 参数
+    ///
+    /// * `title` - 前台窗口标题
+    /// * `process_name` - 前台窗口所属进程的可执行文件名
+    ///
+    /// # 返回
+    ///
+    /// 未设置 `when_window` 时始终返回 true
+    pub fn matches_window(&self, title: &str, process_name: &str) -> bool {
+        let Some(pattern) = &self.when_window else {
+            return true;
+        };
+        process_name.eq_ignore_ascii_case(pattern)
+            || title.to_lowercase().contains(&pattern.to_lowercase())
+    }
 }
 
-/// 操作参数
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum ActionParams {
-    TypeText(TypeTextParams),
-    Sequence(SequenceParams),
+/// 触发键索引使用的键：小写的规范键名，与 `TriggerSource::matches` 的比较方式一致
+fn hotkey_index_key(name: &str) -> String {
+    crate::keys::canonicalize(name).to_ascii_lowercase()
 }
 
-/// 输入文本参数
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TypeTextParams {
-    pub text: String,
+/// 以 `hotkey_index_key` 的形式调用 `f`
+///
+/// 不超过 32 字节的键名在栈上转换为小写，钩子中每次按键查找索引都不分配内存
+fn with_index_key<R>(name: &str, f: impl FnOnce(&str) -> R) -> R {
+    let name = crate::keys::canonicalize(name);
+    let mut buf = [0u8; 32];
+    match buf.get_mut(..name.len()) {
+        Some(lower) => {
+            lower.copy_from_slice(name.as_bytes());
+            lower.make_ascii_lowercase();
+            f(std::str::from_utf8(lower).expect("ASCII 小写转换不改变 UTF-8 编码"))
+        }
+        None => f(&name.to_ascii_lowercase()),
+    }
+}
+
+/// 触发源（`TriggerSource`）使用的字段名
+const TRIGGER_FIELDS: [&str; 2] = ["type", "key"];
+
+/// 热键配置的原始结构，`params` 先保留为 YAML 值，再按 `action` 解析
+#[derive(Deserialize)]
+struct RawHotkeyConfig {
+    #[serde(flatten)]
+    trigger: TriggerSource,
+    /// 设置了 `tap_action` / `hold_action` 时省略
     #[serde(default)]
-    pub delay: Option<DelayConfig>,
+    action: Option<String>,
+    /// `action: "none"` 时可以省略
+    #[serde(default)]
+    params: serde_yaml::Value,
+    /// 轻按/长按操作，先保留为 YAML 值，再按其中的 `action` 解析
+    #[serde(default)]
+    tap_action: Option<serde_yaml::Value>,
+    #[serde(default)]
+    hold_action: Option<serde_yaml::Value>,
+    #[serde(default)]
+    hold_threshold_ms: Option<u64>,
+    #[serde(default)]
+    repeat_on_hold: bool,
+    #[serde(default)]
+    cooldown_ms: u64,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    when_window: Option<String>,
+    /// 同一个触发键按窗口区分的变体，只能写在 `hotkeys` 列表中（见 `HotkeyEntry`）
+    #[serde(default)]
+    variants: Option<Vec<RawHotkeyVariant>>,
+    /// 剩余字段（flatten 结构无法使用 deny_unknown_fields，在转换时拒绝未知字段）
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_yaml::Value>,
 }
 
-/// 序列参数
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SequenceParams {
-    pub steps: Vec<Step>,
+impl TryFrom<RawHotkeyConfig> for HotkeyConfig {
+    type Error = String;
+
+    fn try_from(raw: RawHotkeyConfig) -> Result<Self, Self::Error> {
+        let key_name = raw.trigger.key_name();
+
+        // 触发源字段同样会出现在 flatten 的剩余字段中，需要排除
+        let unknown: Vec<&str> = raw
+            .extra
+            .keys()
+            .map(String::as_str)
+            .filter(|k| !TRIGGER_FIELDS.contains(k))
+            .collect();
+        if !unknown.is_empty() {
+            return Err(format!("热键 {} 包含未知字段: {}", key_name, unknown.join(", ")));
+        }
+        if raw.variants.is_some() {
+            return Err(format!("热键 {}: variants 只能用于 hotkeys 列表中的热键", key_name));
+        }
+
+        let (action, params, tap_action, hold_action) = match (raw.tap_action, raw.hold_action) {
+            (None, None) => {
+                if raw.hold_threshold_ms.is_some() {
+                    return Err(format!("热键 {}: hold_threshold_ms 只能与 tap_action / hold_action 一起使用", key_name));
+                }
+                let action = raw.action.ok_or_else(|| format!("热键 {} 缺少字段 action", key_name))?;
+                let params = ActionParams::parse(&action, raw.params)
+                    .map_err(|e| format!("热键 {}: {}", key_name, e))?;
+                (action, params, None, None)
+            }
+            (Some(tap), Some(hold)) => {
+                if raw.action.is_some() || !raw.params.is_null() {
+                    return Err(format!("热键 {}: 设置了 tap_action / hold_action 时不能再设置 action 和 params", key_name));
+                }
+                if raw.repeat_on_hold {
+                    return Err(format!("热键 {}: tap_action / hold_action 不支持 repeat_on_hold", key_name));
+                }
+                let tap = parse_nested_action("tap_action", tap).map_err(|e| format!("热键 {}: {}", key_name, e))?;
+                let hold = parse_nested_action("hold_action", hold).map_err(|e| format!("热键 {}: {}", key_name, e))?;
+                ("none".to_string(), ActionParams::None, Some(tap), Some(hold))
+            }
+            _ => return Err(format!("热键 {}: tap_action 和 hold_action 需要同时设置", key_name)),
+        };
+        let hold_threshold_ms = raw.hold_threshold_ms.unwrap_or_else(default_hold_threshold_ms);
+        if hold_threshold_ms == 0 {
+            return Err(format!("热键 {}: hold_threshold_ms 必须大于 0", key_name));
+        }
+
+        Ok(HotkeyConfig {
+            trigger: raw.trigger,
+            action,
+            params,
+            tap_action,
+            hold_action,
+            hold_threshold_ms,
+            repeat_on_hold: raw.repeat_on_hold,
+            cooldown_ms: raw.cooldown_ms,
+            name: raw.name,
+            description: raw.description,
+            when_window: raw.when_window,
+        })
+    }
 }
 
-/// 按键动作类型
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "lowercase")]
-pub enum KeyAction {
-    Press,    // 只按下
-    Release,  // 只释放
-    Complete, // 按下+释放（默认）
+fn default_hold_threshold_ms() -> u64 {
+    300
 }
 
-impl Default for KeyAction {
-    fn default() -> Self {
-        KeyAction::Complete
+/// 按窗口区分的变体（`variants` 的一项），写法与热键相同，但不包含触发源
+///
+/// 未设置的 `repeat_on_hold`、`cooldown_ms`、`name` 和 `description` 使用所在热键的值
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawHotkeyVariant {
+    #[serde(default)]
+    when_window: Option<String>,
+    #[serde(default)]
+    action: Option<String>,
+    #[serde(default)]
+    params: serde_yaml::Value,
+    #[serde(default)]
+    tap_action: Option<serde_yaml::Value>,
+    #[serde(default)]
+    hold_action: Option<serde_yaml::Value>,
+    #[serde(default)]
+    hold_threshold_ms: Option<u64>,
+    #[serde(default)]
+    repeat_on_hold: Option<bool>,
+    #[serde(default)]
+    cooldown_ms: Option<u64>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+/// `hotkeys` 列表中的一项：单个热键，或同一个触发键下按窗口区分的多个变体
+///
+/// 变体按书写顺序展开为各自设置了 `when_window` 的热键，与分开写成多条配置等价
+#[derive(Deserialize)]
+#[serde(try_from = "RawHotkeyConfig")]
+struct HotkeyEntry(Vec<HotkeyConfig>);
+
+impl TryFrom<RawHotkeyConfig> for HotkeyEntry {
+    type Error = String;
+
+    fn try_from(mut raw: RawHotkeyConfig) -> Result<Self, Self::Error> {
+        let Some(variants) = raw.variants.take() else {
+            return HotkeyConfig::try_from(raw).map(|hotkey| HotkeyEntry(vec![hotkey]));
+        };
+        let key_name = raw.trigger.key_name();
+        let has_action = raw.action.is_some() || !raw.params.is_null() || raw.tap_action.is_some() || raw.hold_action.is_some();
+        if has_action || raw.hold_threshold_ms.is_some() || raw.when_window.is_some() {
+            return Err(format!(
+                "热键 {}: 设置了 variants 时 action、params、tap_action、hold_action、hold_threshold_ms 和 when_window 需要写在各个变体中",
+                key_name
+            ));
+        }
+        if variants.is_empty() {
+            return Err(format!("热键 {}: variants 不能为空", key_name));
+        }
+        if variants.iter().filter(|v| v.when_window.is_none()).count() > 1 {
+            return Err(format!("热键 {}: variants 中只能有一个未设置 when_window 的变体", key_name));
+        }
+
+        variants
+            .into_iter()
+            .map(|variant| {
+                HotkeyConfig::try_from(RawHotkeyConfig {
+                    trigger: raw.trigger.clone(),
+                    action: variant.action,
+                    params: variant.params,
+                    tap_action: variant.tap_action,
+                    hold_action: variant.hold_action,
+                    hold_threshold_ms: variant.hold_threshold_ms,
+                    repeat_on_hold: variant.repeat_on_hold.unwrap_or(raw.repeat_on_hold),
+                    cooldown_ms: variant.cooldown_ms.unwrap_or(raw.cooldown_ms),
+                    name: variant.name.or_else(|| raw.name.clone()),
+                    description: variant.description.or_else(|| raw.description.clone()),
+                    when_window: variant.when_window,
+                    variants: None,
+                    extra: BTreeMap::new(),
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(HotkeyEntry)
+    }
+}
+
+/// 解析 `hotkeys` 列表，写了 `variants` 的热键展开为多条配置
+fn deserialize_hotkeys<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<HotkeyConfig>, D::Error> {
+    let entries = Vec::<HotkeyEntry>::deserialize(deserializer)?;
+    Ok(entries.into_iter().flat_map(|entry| entry.0).collect())
+}
+
+/// 轻按/长按操作（`tap_action` / `hold_action`）的原始结构，写法与热键的 `action` 和 `params` 相同
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawNestedAction {
+    action: String,
+    #[serde(default)]
+    params: serde_yaml::Value,
+}
+
+/// 解析轻按/长按操作
+///
+/// # 参数
+///
+/// * `field` - 字段名（`tap_action` 或 `hold_action`），用于错误说明
+/// * `value` - 原始 YAML 值
+fn parse_nested_action(field: &str, value: serde_yaml::Value) -> Result<ActionParams, String> {
+    let raw: RawNestedAction = serde_yaml::from_value(value).map_err(|e| format!("{} 无效: {}", field, e))?;
+    match ActionParams::parse(&raw.action, raw.params).map_err(|e| format!("{}: {}", field, e))? {
+        ActionParams::ToggleSystem => Err(format!("{} 不支持 toggle_system", field)),
+        params => Ok(params),
+    }
+}
+
+/// 按 `{ action, params }` 的写法序列化轻按/长按操作
+fn serialize_nested_action<S: Serializer>(params: &Option<&ActionParams>, serializer: S) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct NestedAction<'a> {
+        action: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        params: Option<&'a ActionParams>,
+    }
+    match params {
+        Some(params) => NestedAction { action: params.action_name(), params: Some(*params).filter(|p| p.has_params()) }.serialize(serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// 操作参数
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ActionParams {
+    TypeText(TypeTextParams),
+    Sequence(SequenceParams),
+    Chat(ChatParams),
+    TypeFile(TypeFileParams),
+    TapHold(TapHoldParams),
+    /// 占位操作：触发键照常被拦截，但不执行任何操作
+    None,
+    /// 切换宏开关（与全局开关热键效果相同），宏关闭时仍会响应
+    ToggleSystem,
+}
+
+/// 支持的操作类型
+pub const ACTION_NAMES: [&str; 7] = ["type_text", "sequence", "chat", "type_file", "tap_hold", "none", "toggle_system"];
+
+impl ActionParams {
+    /// 参数对应的操作类型（`ACTION_NAMES` 之一）
+    pub fn action_name(&self) -> &'static str {
+        match self {
+            ActionParams::TypeText(_) => "type_text",
+            ActionParams::Sequence(_) => "sequence",
+            ActionParams::Chat(_) => "chat",
+            ActionParams::TypeFile(_) => "type_file",
+            ActionParams::TapHold(_) => "tap_hold",
+            ActionParams::None => "none",
+            ActionParams::ToggleSystem => "toggle_system",
+        }
+    }
+
+    /// 该操作是否有参数（`none`、`toggle_system` 没有参数，序列化时省略 `params`）
+    fn has_params(&self) -> bool {
+        !matches!(self, ActionParams::None | ActionParams::ToggleSystem)
+    }
+
+    /// 按操作类型解析参数
+    ///
+    /// # 参数
+    ///
+    /// * `action` - 操作类型（`HotkeyConfig::action`）
+    /// * `params` - 原始参数
+    ///
+    /// # 返回
+    ///
+    /// 成功返回对应类型的参数，操作类型未知或参数不符合该类型时返回错误说明
+    pub fn parse(action: &str, params: serde_yaml::Value) -> Result<Self, String> {
+        let result = match action {
+            "type_text" => serde_yaml::from_value(params).map(ActionParams::TypeText),
+            "sequence" => serde_yaml::from_value(params).map(ActionParams::Sequence),
+            "chat" => serde_yaml::from_value(params).map(ActionParams::Chat),
+            "type_file" => serde_yaml::from_value(params).map(ActionParams::TypeFile),
+            "tap_hold" => serde_yaml::from_value(params).map(ActionParams::TapHold),
+            "none" => return expect_no_params(action, params).map(|()| ActionParams::None),
+            "toggle_system" => return expect_no_params(action, params).map(|()| ActionParams::ToggleSystem),
+            _ => {
+                return Err(format!(
+                    "未知的 action '{}'，可选值: {}",
+                    action,
+                    ACTION_NAMES.join(", ")
+                ))
+            }
+        };
+        result.map_err(|e| format!("action '{}' 的参数无效: {}", action, e))
+    }
+}
+
+/// 检查不需要参数的操作（`none`、`toggle_system`）没有写参数：省略 `params` 或写成 `{}` 都可以
+fn expect_no_params(action: &str, params: serde_yaml::Value) -> Result<(), String> {
+    match params {
+        serde_yaml::Value::Null => Ok(()),
+        serde_yaml::Value::Mapping(m) if m.is_empty() => Ok(()),
+        _ => Err(format!("action '{}' 不接受参数", action)),
+    }
+}
+
+/// 输入文本参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TypeTextParams {
+    pub text: String,
+    #[serde(default)]
+    pub delay: Option<DelayConfig>,
+    /// 每次延迟的随机抖动幅度（毫秒），实际延迟为 基础延迟 ± 0~jitter，默认 0 不抖动
+    #[serde(default)]
+    pub jitter: u64,
+    /// 旧版字段：每个字符的延迟毫秒数，加载时由 `Config::migrate` 迁移为 `delay`
+    #[serde(default, skip_serializing)]
+    pub speed: Option<u64>,
+    /// 开始输入前的等待时间，默认 0
+    #[serde(default)]
+    pub start_delay: DurationSpec,
+    /// Caps Lock 开启时是否在输入期间临时关闭（输入结束后恢复），默认 false
+    #[serde(default)]
+    pub normalize_caps_lock: bool,
+    /// 输入方式，默认逐字符输入
+    #[serde(default)]
+    pub method: TypeMethod,
+    /// `auto` 方式下改用粘贴的文本长度（字符数），超过该长度时粘贴
+    #[serde(default = "default_paste_threshold")]
+    pub paste_threshold: usize,
+}
+
+impl TypeTextParams {
+    /// 在文本前后加上全局前缀和后缀（配置中的 `type_text_prefix` / `type_text_suffix`）
+    ///
+    /// 前缀和后缀都为空时原样复制
+    pub fn with_affixes(&self, prefix: &str, suffix: &str) -> TypeTextParams {
+        TypeTextParams { text: wrap_text(&self.text, prefix, suffix), ..self.clone() }
+    }
+
+    /// 是否通过剪贴板粘贴输入文本
+    pub fn uses_paste(&self) -> bool {
+        match self.method {
+            TypeMethod::Type => false,
+            TypeMethod::Paste => true,
+            TypeMethod::Auto => self.text.chars().count() > self.paste_threshold,
+        }
+    }
+}
+
+/// 输入文件内容参数：每次触发时读取文件（UTF-8），修改文件后无需重启即可生效
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TypeFileParams {
+    /// 文件路径，相对路径相对于程序的工作目录
+    pub path: String,
+    #[serde(default)]
+    pub delay: Option<DelayConfig>,
+    /// 每次延迟的随机抖动幅度（毫秒），同 `type_text`
+    #[serde(default)]
+    pub jitter: u64,
+    /// 开始输入前的等待时间，默认 0
+    #[serde(default)]
+    pub start_delay: DurationSpec,
+    /// Caps Lock 开启时是否在输入期间临时关闭，同 `type_text`
+    #[serde(default)]
+    pub normalize_caps_lock: bool,
+    /// 输入方式，默认逐字符输入
+    #[serde(default)]
+    pub method: TypeMethod,
+    /// `auto` 方式下改用粘贴的文本长度（字符数）
+    #[serde(default = "default_paste_threshold")]
+    pub paste_threshold: usize,
+}
+
+/// 按住按键参数：按下 `key`，按住 `hold_ms` 毫秒后释放，与触发键按住多久无关
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TapHoldParams {
+    /// 要按住的按键
+    pub key: String,
+    /// 按住时长（毫秒）
+    pub hold_ms: u64,
+    /// 按住期间每隔多少毫秒重新发送一次按下事件（模拟系统的自动重复），默认 0 不重复
+    #[serde(default)]
+    pub repeat_interval_ms: u64,
+}
+
+impl TypeFileParams {
+    /// 以读取到的文件内容生成等价的输入文本参数
+    pub fn to_type_text(&self, text: String) -> TypeTextParams {
+        TypeTextParams {
+            text,
+            delay: self.delay.clone(),
+            jitter: self.jitter,
+            speed: None,
+            start_delay: self.start_delay,
+            normalize_caps_lock: self.normalize_caps_lock,
+            method: self.method,
+            paste_threshold: self.paste_threshold,
+        }
+    }
+}
+
+/// 文本输入方式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TypeMethod {
+    #[default]
+    Type,  // 逐字符模拟按键（默认）
+    Paste, // 写入剪贴板后按 Ctrl+V
+    Auto,  // 超过 paste_threshold 时粘贴，否则逐字符输入
+}
+
+/// `auto` 输入方式的默认粘贴阈值（字符数）
+pub const DEFAULT_PASTE_THRESHOLD: usize = 200;
+
+fn default_paste_threshold() -> usize {
+    DEFAULT_PASTE_THRESHOLD
+}
+
+/// 序列参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SequenceParams {
+    pub steps: Vec<Step>,
+    /// 执行第一个步骤前的等待时间，默认 0
+    #[serde(default)]
+    pub start_delay: DurationSpec,
+    /// 按键步骤按下与释放之间的间隔（步骤未设置 `delay` 时使用），未设置时使用顶层 `default_key_hold_ms`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_hold: Option<DurationSpec>,
+}
+
+impl SequenceParams {
+    /// 在每个 `text` 步骤的文本前后加上全局前缀和后缀，其他步骤不变
+    pub fn with_text_affixes(&self, prefix: &str, suffix: &str) -> SequenceParams {
+        SequenceParams { steps: wrap_step_texts(&self.steps, prefix, suffix), start_delay: self.start_delay, key_hold: self.key_hold }
+    }
+}
+
+/// 给步骤中的 `text` 文本加上前缀和后缀，包括 `if_var` 分支中的步骤
+fn wrap_step_texts(steps: &[Step], prefix: &str, suffix: &str) -> Vec<Step> {
+    steps
+        .iter()
+        .map(|step| match step {
+            Step::Text { value, delay } => Step::Text { value: wrap_text(value, prefix, suffix), delay: delay.clone() },
+            Step::IfVar { name, equals, then, else_ } => Step::IfVar {
+                name: name.clone(),
+                equals: *equals,
+                then: wrap_step_texts(then, prefix, suffix),
+                else_: wrap_step_texts(else_, prefix, suffix),
+            },
+            other => other.clone(),
+        })
+        .collect()
+}
+
+/// 在文本前后加上前缀和后缀
+fn wrap_text(text: &str, prefix: &str, suffix: &str) -> String {
+    format!("{}{}{}", prefix, text, suffix)
+}
+
+/// 聊天参数：按打开键，等待聊天框出现，输入消息，等待后按关闭键（发送）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChatParams {
+    /// 打开聊天框的按键，默认 Enter
+    #[serde(default = "default_chat_key")]
+    pub open_key: String,
+    /// 要输入的消息
+    pub message: String,
+    /// 发送消息（关闭聊天框）的按键，默认 Enter
+    #[serde(default = "default_chat_key")]
+    pub close_key: String,
+    /// 按下打开键后、开始输入前的等待时间，避免聊天框未打开时丢失开头的字符，默认 100 毫秒
+    #[serde(default = "default_chat_pre_delay")]
+    pub pre_delay: DurationSpec,
+    /// 输入完成后、按下关闭键前的等待时间，默认 50 毫秒
+    #[serde(default = "default_chat_post_delay")]
+    pub post_delay: DurationSpec,
+}
+
+impl ChatParams {
+    /// 转换为等价的按键序列
+    ///
+    /// 等待时间为 0 时省略对应的等待步骤
+    pub fn to_sequence(&self) -> SequenceParams {
+        let key = |value: &str| Step::Key { value: value.to_string(), delay: None, action: None };
+        let wait = |value: DurationSpec| (!value.is_zero()).then_some(Step::Wait { value, random: None });
+
+        let steps = [
+            Some(key(&self.open_key)),
+            wait(self.pre_delay),
+            Some(Step::Text { value: self.message.clone(), delay: None }),
+            wait(self.post_delay),
+            Some(key(&self.close_key)),
+        ];
+
+        SequenceParams {
+            steps: steps.into_iter().flatten().collect(),
+            start_delay: DurationSpec::ZERO,
+            key_hold: None,
+        }
+    }
+}
+
+/// 按键动作类型
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyAction {
+    Press,    // 只按下
+    Release,  // 只释放
+    Complete, // 按下+释放（默认）
+}
+
+impl Default for KeyAction {
+    fn default() -> Self {
+        KeyAction::Complete
+    }
+}
+
+/// 序列中的单个步骤
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Step {
+    Key { 
+        value: String, 
+        #[serde(default)] 
+        delay: Option<DelayConfig>,
+        #[serde(default)]
+        action: Option<KeyAction>,
+    },
+    Wait { 
+        value: DurationSpec,
+        #[serde(default)]
+        random: Option<bool>,
+    },
+    Text { value: String, #[serde(default)] delay: Option<DelayConfig> },
+    /// 组合键：按顺序按下所有按键，等待 `delay` 后按相反顺序释放，如 Ctrl+Shift+Esc
+    Combo {
+        keys: Vec<String>,
+        #[serde(default)]
+        delay: Option<DelayConfig>,
+    },
+    /// 等待屏幕坐标 (`x`, `y`) 处的像素变为 `color`（`#RRGGBB`），每个通道允许相差 `tolerance`；
+    /// 超过 `timeout_ms`（默认 5000）仍不匹配时序列出错结束
+    #[serde(rename = "wait_pixel")]
+    WaitPixel {
+        x: i32,
+        y: i32,
+        color: String,
+        #[serde(default)]
+        tolerance: u8,
+        #[serde(default = "default_pixel_timeout")]
+        timeout_ms: u64,
+    },
+    /// 等待触发当前序列的按键松开；超过 `timeout_ms`（默认 10000）仍按住时不再等待，继续执行后续步骤
+    #[serde(rename = "wait_release")]
+    WaitRelease {
+        #[serde(default = "default_release_timeout")]
+        timeout_ms: u64,
+    },
+    /// 把变量 `name` 设为 `value`；变量在所有热键之间共享，程序运行期间一直保留
+    #[serde(rename = "set_var")]
+    SetVar { name: String, value: i64 },
+    /// 变量 `name` 加上 `by`（默认 1，可以为负数），未设置过的变量视为 0
+    #[serde(rename = "inc_var")]
+    IncVar {
+        name: String,
+        #[serde(default = "default_inc_by")]
+        by: i64,
+    },
+    /// 变量 `name` 等于 `equals` 时执行 `then` 中的步骤，否则执行 `else` 中的步骤
+    #[serde(rename = "if_var")]
+    IfVar {
+        name: String,
+        equals: i64,
+        #[serde(default)]
+        then: Vec<Step>,
+        #[serde(default, rename = "else")]
+        else_: Vec<Step>,
+    },
+    /// 按 strftime 风格的 `format`（如 "%Y-%m-%d %H:%M:%S"）输入执行时的本地时间，支持的格式符见 `datetime::format`
+    #[serde(rename = "datetime")]
+    DateTime { format: String },
+    /// 按与按下相反的顺序释放本次执行中按下但尚未释放的所有按键，用于在序列中途回到已知状态
+    #[serde(rename = "release_all")]
+    ReleaseAll,
+    /// 直接发送扫描码 `code`（不经过虚拟键码），用于只接受扫描码输入或无法用键名表示的按键；
+    /// `extended` 为 true 时带扩展键标记（如右 Ctrl、方向键）
+    #[serde(rename = "scan_code")]
+    ScanCode {
+        code: u16,
+        #[serde(default)]
+        action: Option<KeyAction>,
+        #[serde(default)]
+        extended: bool,
+    },
+}
+
+fn default_inc_by() -> i64 {
+    1
+}
+
+/// 检查步骤（包括 `if_var` 分支）中无法由 serde 校验的字段，返回第一个错误
+fn check_steps(steps: &[Step]) -> Result<(), String> {
+    for step in steps {
+        match step {
+            Step::WaitPixel { color, .. } if Rgb::parse(color).is_none() => {
+                return Err(format!("无效的颜色 '{}'，应为 #RRGGBB 格式", color));
+            }
+            Step::ScanCode { code: 0, .. } => return Err("scan_code 步骤的扫描码不能为 0".to_string()),
+            Step::DateTime { format } => {
+                crate::datetime::check_format(format).map_err(|e| format!("无效的日期时间格式 '{}': {}", format, e))?;
+            }
+            Step::IfVar { then, else_, .. } => {
+                check_steps(then)?;
+                check_steps(else_)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// 检查聊天操作的打开键和关闭键是否为能识别的键名
+fn check_chat_keys(params: &ChatParams) -> Result<(), String> {
+    for (field, key) in [("open_key", &params.open_key), ("close_key", &params.close_key)] {
+        if crate::macros::parse_key_string(key).is_none() {
+            return Err(format!("chat 的 {} 无效: '{}'", field, key));
+        }
+    }
+    Ok(())
+}
+
+/// RGB 颜色
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    /// 解析 `#RRGGBB` 格式的颜色（`#` 可省略，不区分大小写）
+    ///
+    /// # 返回
+    ///
+    /// 格式无效时返回 None
+    pub fn parse(value: &str) -> Option<Self> {
+        let hex = value.strip_prefix('#').unwrap_or(value);
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Self { r: channel(0)?, g: channel(2)?, b: channel(4)? })
+    }
+
+    /// 检查两个颜色是否匹配（每个通道的差值都不超过 `tolerance`）
+    pub fn matches(&self, other: Rgb, tolerance: u8) -> bool {
+        self.r.abs_diff(other.r) <= tolerance
+            && self.g.abs_diff(other.g) <= tolerance
+            && self.b.abs_diff(other.b) <= tolerance
+    }
+}
+
+impl std::fmt::Display for Rgb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+    }
+}
+
+/// 示例配置文件内容，找不到配置文件时可以据此生成
+pub const EXAMPLE_CONFIG: &str = r#"# 键盘宏配置文件（自动生成的示例）
+#
+# 按 Ctrl + ` 开启/关闭所有宏，修改本文件后重新启动程序生效
+# 完整说明请参考 README.md
+
+version: 1
+
+hotkeys:
+  # 按 F2 输入一段文本
+  - type: keyboard
+    key: "F2"
+    action: "type_text"
+    params:
+      text: "Hello, keymacro!"
+      delay: 17          # 每个字符的延迟（毫秒），也可以写成 { min: 10, max: 30 }
+
+  # 按 F3 执行按键序列：全选后复制
+  - type: keyboard
+    key: "F3"
+    action: "sequence"
+    params:
+      steps:
+        - type: "key"
+          value: "Ctrl"
+          action: "press"
+        - type: "key"
+          value: "A"
+        - type: "key"
+          value: "C"
+        - type: "key"
+          value: "Ctrl"
+          action: "release"
+        - type: "wait"
+          value: 100
+"#;
+
+/// 只读取版本号，用于在完整解析前拒绝过新的配置
+#[derive(Deserialize)]
+struct VersionProbe {
+    #[serde(default = "default_version")]
+    version: u32,
+}
+
+impl Config {
+    /// 从文件加载配置
+    ///
+    /// # 说明
+    ///
+    /// YAML 语法或结构有误时，错误信息以 `文件:行:列: 原因` 开头，并附上出错位置附近的几行内容
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+        Self::from_str(&content).map_err(|e| locate_parse_error(path, &content, e))
+    }
+
+    /// 将配置写入文件（已存在时覆盖）
+    ///
+    /// # 参数
+    ///
+    /// * `path` - 目标文件路径
+    ///
+    /// # 说明
+    ///
+    /// 写出所有字段（包括取默认值的字段），用 `from_file` 重新加载后得到相同的配置；不保留原文件中的注释和锚点
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// 将示例配置写入文件
+    ///
+    /// # 参数
+    ///
+    /// * `path` - 目标文件路径
+    ///
+    /// # 说明
+    ///
+    /// 文件已存在时返回错误，不会覆盖已有配置
+    pub fn write_example<P: AsRef<Path>>(path: P) -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        file.write_all(EXAMPLE_CONFIG.as_bytes())?;
+        Ok(())
+    }
+
+    /// 从字符串加载配置
+    pub fn from_str(yaml_str: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        // 新版本的配置结构可能无法按当前格式解析，先检查版本号给出明确的错误
+        let probe: VersionProbe = serde_yaml::from_str(yaml_str)?;
+        check_version(probe.version)?;
+
+        let mut config: Config = serde_yaml::from_str(yaml_str)?;
+        config.migrate()?;
+        config.gamepad.validate()?;
+        config.build_hotkey_index();
+        check_delay_scale(config.delay_scale)?;
+        config.validate_steps()?;
+        check_chords(&config.hotkeys)?;
+        for profile in &config.profiles {
+            check_chords(&profile.hotkeys).map_err(|e| format!("配置方案 {}: {}", profile.name, e))?;
+        }
+        config.validate_gamepad_keys()?;
+
+        if let Some(warning) = config.no_active_hotkeys_warning() {
+            log::warn!("{}", warning);
+        }
+        let bypassed: Vec<String> = config.bound_keys().into_iter().filter(|k| config.is_never_suppressed(k)).collect();
+        if !bypassed.is_empty() {
+            log::warn!("以下触发键在 never_suppress 中，按下时始终放行，不会触发宏: {}", bypassed.join(", "));
+        }
+        if !config.gamepad.enabled {
+            let bindings = config.gamepad_bindings();
+            if !bindings.is_empty() {
+                log::warn!("手柄支持已关闭 (gamepad.enabled: false)，以下手柄按键不会触发: {}", bindings.join(", "));
+            }
+        }
+        Ok(config)
+    }
+
+    /// 将旧版本的配置升级为当前版本
+    ///
+    /// # 说明
+    ///
+    /// - 版本号高于 `CONFIG_VERSION` 时返回错误
+    /// - 旧版 `type_text` 的 `speed` 字段迁移为 `delay`（已设置 `delay` 时忽略 `speed`）
+    pub fn migrate(&mut self) -> Result<(), String> {
+        check_version(self.version)?;
+
+        let profile_hotkeys = self.profiles.iter_mut().flat_map(|p| p.hotkeys.iter_mut());
+        for hotkey in self.hotkeys.iter_mut().chain(profile_hotkeys) {
+            let key_name = hotkey.trigger.key_name();
+            for action in hotkey.actions_mut() {
+                if let ActionParams::TypeText(params) = action {
+                    if let Some(speed) = params.speed.take() {
+                        log::warn!("热键 {} 使用了旧版字段 speed，请改为 delay", key_name);
+                        params.delay.get_or_insert(DelayConfig::Fixed(DurationSpec::from_millis(speed)));
+                    }
+                }
+            }
+        }
+
+        self.version = CONFIG_VERSION;
+        Ok(())
+    }
+
+    /// 检查序列步骤和聊天操作中无法由 serde 校验的字段（如 `wait_pixel` 的颜色格式、`datetime` 的格式串、聊天的打开键和关闭键）
+    fn validate_steps(&self) -> Result<(), String> {
+        for hotkey in self.all_hotkeys() {
+            for action in hotkey.actions() {
+                let result = match action {
+                    ActionParams::Sequence(params) => check_steps(&params.steps),
+                    ActionParams::Chat(params) => check_chat_keys(params),
+                    _ => continue,
+                };
+                result.map_err(|e| format!("热键 {}: {}", hotkey.key(), e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查手柄触发键（"GP:" 开头）是否为已知的 XInput 按键名称
+    fn validate_gamepad_keys(&self) -> Result<(), String> {
+        for hotkey in self.all_hotkeys() {
+            for name in hotkey.trigger.key_names() {
+                if let Some(message) = unknown_gamepad_key(&name) {
+                    return Err(format!("热键 {}: {}", hotkey.key(), message));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 是否处于演练模式（配置中开启 `dry_run` 或设置了 `KEYMACRO_DRY_RUN` 环境变量）
+    pub fn dry_run_enabled(&self) -> bool {
+        self.dry_run || env_flag_enabled(std::env::var(DRY_RUN_ENV).ok().as_deref())
+    }
+
+    /// 未设置 `key_hold` 的序列使用的按住时长（`default_key_hold_ms`），在执行时读取，不写入各个序列
+    pub fn default_key_hold(&self) -> DurationSpec {
+        DurationSpec::from_millis(self.default_key_hold_ms)
+    }
+
+    /// 所有已绑定的触发键（按配置顺序，去除重复）
+    ///
+    /// 手柄按键带 "GP:" 前缀，与钩子和手柄线程上报的名称一致
+    pub fn bound_keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::new();
+        for name in self.hotkeys.iter().flat_map(|h| h.trigger.key_names()) {
+            if !keys.iter().any(|k| k.eq_ignore_ascii_case(&name)) {
+                keys.push(name);
+            }
+        }
+        keys
+    }
+
+    /// 是否有切换宏开关的热键（宏关闭时钩子仍需检查这些键）
+    pub fn has_system_toggle(&self) -> bool {
+        self.hotkeys.iter().any(HotkeyConfig::is_system_toggle)
+    }
+
+    /// 托盘"热键绑定"菜单中各热键的标签（按配置中的声明顺序）
+    ///
+    /// 直接遍历 `hotkeys`，不经过热键查找索引，索引只用于按名称查找
+    pub fn menu_labels(&self) -> Vec<String> {
+        self.hotkeys.iter().map(HotkeyConfig::menu_label).collect()
+    }
+
+    /// 所有手柄触发键（带 "GP:" 前缀，包括键盘按键组中混入的手柄按键和其他配置方案中的热键）
+    pub fn gamepad_bindings(&self) -> Vec<String> {
+        self.all_hotkeys()
+            .flat_map(|h| h.trigger.key_names())
+            .filter(|name| is_gamepad_key(name))
+            .collect()
+    }
+
+    /// 当前方案（顶层 `hotkeys`）中能够触发的热键数量（手柄支持关闭时，只绑定了手柄按键的热键不计入）
+    ///
+    /// 统计其他方案时先用 `with_profile` 生成该方案的配置
+    pub fn active_hotkey_count(&self) -> usize {
+        self.hotkeys.iter().filter(|h| self.is_active(h)).count()
+    }
+
+    /// 热键能否触发（手柄支持关闭时，只绑定了手柄按键的热键不能触发）
+    fn is_active(&self, hotkey: &HotkeyConfig) -> bool {
+        self.gamepad.enabled || !hotkey.trigger.key_names().iter().all(|k| is_gamepad_key(k))
+    }
+
+    /// 所有配置方案中都没有能够触发的热键时的警告信息（这样的配置加载后程序什么也不会做，通常是配置写错了）
+    ///
+    /// # 返回
+    ///
+    /// 任一方案中至少有一个热键能够触发时返回 None
+    pub fn no_active_hotkeys_warning(&self) -> Option<String> {
+        if self.all_hotkeys().any(|h| self.is_active(h)) {
+            return None;
+        }
+        Some(if self.all_hotkeys().next().is_none() {
+            "配置中没有定义任何热键 (hotkeys 为空)".to_string()
+        } else {
+            "配置中的热键都只绑定了手柄按键，但手柄支持已关闭 (gamepad.enabled: false)".to_string()
+        })
+    }
+
+    /// 绑定到危险按键的触发键（包括其他配置方案中的热键）
+    ///
+    /// # 返回
+    ///
+    /// 按出现顺序排列、去重后的规范键名
+    ///
+    /// # 说明
+    ///
+    /// 单独的修饰键（Ctrl、Alt、Shift、Win）总是视为危险；Enter、Escape 等常用键只在拦截触发键
+    /// （`suppress_triggers: true`）时视为危险。组合序列的第一个键总是被拦截，同样参与检查
+    pub fn dangerous_hotkeys(&self) -> Vec<String> {
+        let mut dangerous: Vec<String> = Vec::new();
+        for name in self.all_hotkeys().flat_map(|h| h.trigger.key_names()) {
+            if is_gamepad_key(&name) {
+                continue;
+            }
+            let (key, suppressed) = match chord_keys(&name) {
+                Some(keys) => (keys[0].to_string(), true),
+                None => (name, self.suppress_triggers),
+            };
+            // 始终放行的按键不会被钩子拦截，绑定的热键也不会触发
+            if self.is_never_suppressed(&key) {
+                continue;
+            }
+            let key = crate::keys::canonicalize(&key);
+            let is_dangerous = DANGEROUS_MODIFIERS.contains(&key)
+                || (suppressed && DANGEROUS_SUPPRESSED_KEYS.contains(&key));
+            if is_dangerous && !dangerous.iter().any(|k| k == key) {
+                dangerous.push(key.to_string());
+            }
+        }
+        dangerous
+    }
+
+    /// 有热键绑定到危险按键时的警告信息
+    ///
+    /// # 返回
+    ///
+    /// 没有绑定危险按键时返回 None
+    pub fn dangerous_hotkeys_warning(&self) -> Option<String> {
+        let keys = self.dangerous_hotkeys();
+        if keys.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "以下触发键可能使键盘难以正常使用: {}（单独的修饰键会使快捷键失效，被拦截的常用键无法再正常输入）",
+            keys.join(", ")
+        ))
+    }
+
+    /// 会被键盘钩子拦截、使全局热键失效的触发键（包括其他配置方案中的热键）
+    ///
+    /// # 参数
+    ///
+    /// * `hotkey` - 全局热键，如 "Ctrl+`"
+    ///
+    /// # 返回
+    ///
+    /// 与全局热键中任一按键（包括修饰键）相同的触发键，按出现顺序排列、去重后的规范键名
+    ///
+    /// # 说明
+    ///
+    /// 钩子按键名匹配、不检查修饰键，绑定了 "`" 的热键在按下 Ctrl+` 时同样会拦截 "`"，全局热键收不到这次按键。
+    /// 不拦截触发键（`suppress_triggers: false`）时单键热键不冲突；组合序列的第一个键总是被拦截，同样参与检查
+    pub fn global_hotkey_conflicts(&self, hotkey: &str) -> Vec<String> {
+        let hotkey_keys: Vec<&str> = hotkey.split('+').map(|k| crate::keys::canonicalize(k.trim())).collect();
+        let mut conflicts: Vec<String> = Vec::new();
+        for name in self.all_hotkeys().flat_map(|h| h.trigger.key_names()) {
+            if is_gamepad_key(&name) {
+                continue;
+            }
+            let (key, suppressed) = match chord_keys(&name) {
+                Some(keys) => (keys[0].to_string(), true),
+                None => (name, self.suppress_triggers),
+            };
+            if !suppressed || self.is_never_suppressed(&key) {
+                continue;
+            }
+            let key = crate::keys::canonicalize(&key);
+            let conflicting = hotkey_keys.iter().any(|k| k.eq_ignore_ascii_case(key));
+            if conflicting && !conflicts.iter().any(|k| k.eq_ignore_ascii_case(key)) {
+                conflicts.push(key.to_string());
+            }
+        }
+        conflicts
+    }
+
+    /// 有触发键会使全局热键失效时的警告信息
+    ///
+    /// # 参数
+    ///
+    /// * `field` - 全局热键的用途（用于提示，如 "开关热键"）
+    /// * `hotkey` - 全局热键，如 "Ctrl+`"
+    ///
+    /// # 返回
+    ///
+    /// 没有冲突时返回 None
+    pub fn global_hotkey_conflict_warning(&self, field: &str, hotkey: &str) -> Option<String> {
+        let keys = self.global_hotkey_conflicts(hotkey);
+        if keys.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "以下触发键会被键盘钩子拦截，{} {} 将无法触发: {}（可以改用其他触发键，或把它们加入 never_suppress）",
+            field,
+            hotkey,
+            keys.join(", ")
+        ))
+    }
+
+    /// 键盘钩子是否始终放行该按键（在 `never_suppress` 中，按规范名称比较，不区分大小写）
+    pub fn is_never_suppressed(&self, key: &str) -> bool {
+        let key = crate::keys::canonicalize(key);
+        self.never_suppress
+            .iter()
+            .any(|k| crate::keys::canonicalize(k).eq_ignore_ascii_case(key))
+    }
+
+    /// 是否有热键使用鼠标侧键触发（决定是否需要安装鼠标钩子，包括其他配置方案中的热键）
+    pub fn uses_mouse_buttons(&self) -> bool {
+        self.all_hotkeys()
+            .flat_map(|h| h.trigger.key_names())
+            .any(|name| crate::keys::is_mouse_button(&name))
+    }
+
+    /// 是否有热键限定了窗口（`when_window`）（决定是否需要监听前台窗口切换，包括其他配置方案中的热键）
+    pub fn uses_window_variants(&self) -> bool {
+        self.all_hotkeys().any(|h| h.when_window.is_some())
+    }
+
+    /// 顶层和所有配置方案中的热键
+    fn all_hotkeys(&self) -> impl Iterator<Item = &HotkeyConfig> {
+        self.hotkeys.iter().chain(self.profiles.iter().flat_map(|p| p.hotkeys.iter()))
+    }
+
+    /// 所有配置方案的名称，第一个为顶层 `hotkeys` 构成的默认方案
+    pub fn profile_names(&self) -> Vec<&str> {
+        std::iter::once(DEFAULT_PROFILE_NAME)
+            .chain(self.profiles.iter().map(|p| p.name.as_str()))
+            .collect()
+    }
+
+    /// 生成使用指定配置方案的配置
+    ///
+    /// # 参数
+    ///
+    /// * `index` - 方案序号（与 `profile_names` 对应，0 为默认方案）
+    ///
+    /// # 返回
+    ///
+    /// 顶层 `hotkeys` 替换为该方案热键的配置，序号超出范围时返回 None
+    pub fn with_profile(&self, index: usize) -> Option<Config> {
+        let mut config = self.clone();
+        if index > 0 {
+            config.hotkeys = self.profiles.get(index - 1)?.hotkeys.clone();
+            config.build_hotkey_index();
+        }
+        Some(config)
+    }
+
+    /// 下一个配置方案的序号（最后一个方案之后回到默认方案）
+    pub fn next_profile_index(&self, current: usize) -> usize {
+        (current + 1) % (self.profiles.len() + 1)
+    }
+
+    /// 所有组合序列触发键（如 "` 1"），按配置顺序
+    pub fn chords(&self) -> Vec<String> {
+        self.hotkeys
+            .iter()
+            .flat_map(|h| h.trigger.key_names())
+            .filter(|name| chord_keys(name).is_some())
+            .collect()
+    }
+
+    /// 与当前按下的修饰键完全一致的修饰键组合触发键（如 "LShift+RShift"），有多个时返回配置中的第一个
+    ///
+    /// # 参数
+    ///
+    /// * `active` - 当前按下的修饰键（`keys::modifier_bit` 的组合）
+    pub fn modifier_trigger_for(&self, active: u8) -> Option<String> {
+        self.hotkeys
+            .iter()
+            .flat_map(|h| h.trigger.key_names())
+            .find(|name| crate::keys::ModifierChord::parse(name).is_some_and(|chord| chord.matches(active)))
+    }
+
+    /// 查找指定键的配置（不考虑 `when_window`，返回第一个绑定了该键的配置）
+    ///
+    /// 建立索引后按小写规范键名直接查找；索引中的序号与 `hotkeys` 不符（如之后修改了 `hotkeys`）时逐个比较
+    pub fn find_hotkey(&self, key: &str) -> Option<&HotkeyConfig> {
+        let linear = || self.hotkeys.iter().find(|h| h.trigger.matches(key));
+        match self.index_lookup(key) {
+            None => linear(),
+            Some(None) => None,
+            Some(Some(entry)) => self.hotkeys.get(entry.first).filter(|h| h.trigger.matches(key)).or_else(linear),
+        }
+    }
+
+    /// 在触发键索引中查找按键
+    ///
+    /// # 返回
+    ///
+    /// 没有建立索引时返回 None；建立了索引时返回该键的条目，没有绑定该键时为 `Some(None)`
+    fn index_lookup(&self, key: &str) -> Option<Option<HotkeyIndexEntry>> {
+        let index = self.hotkey_index.as_ref()?;
+        Some(with_index_key(key, |k| index.get(k).copied()))
+    }
+
+    /// 根据当前 `hotkeys` 重新建立触发键索引（同一按键保留第一个配置，与逐个比较的结果一致）
+    fn build_hotkey_index(&mut self) {
+        let mut index = HashMap::new();
+        for (i, hotkey) in self.hotkeys.iter().enumerate() {
+            for name in hotkey.trigger.key_names() {
+                let entry = index
+                    .entry(hotkey_index_key(&name))
+                    .or_insert(HotkeyIndexEntry { first: i, has_window_variants: false });
+                entry.has_window_variants |= hotkey.when_window.is_some();
+            }
+        }
+        self.hotkey_index = Some(index);
+    }
+
+    /// 指定键是否有限定窗口（`when_window`）的配置，没有时无需查询前台窗口
+    ///
+    /// 建立索引后直接读取索引中记录的结果，与 `find_hotkey` 相同，索引与 `hotkeys` 不符时逐个比较
+    pub fn has_window_variants(&self, key: &str) -> bool {
+        let linear = || self.hotkeys.iter().any(|h| h.when_window.is_some() && h.trigger.matches(key));
+        match self.index_lookup(key) {
+            None => linear(),
+            Some(None) => false,
+            Some(Some(entry)) if self.hotkeys.get(entry.first).is_some_and(|h| h.trigger.matches(key)) => {
+                entry.has_window_variants
+            }
+            Some(Some(_)) => linear(),
+        }
+    }
+
+    /// 按前台窗口查找指定键生效的配置
+    ///
+    /// # 参数
+    ///
+    /// * `key` - 触发键名称
+    /// * `title` - 前台窗口标题
+    /// * `process_name` - 前台窗口所属进程的可执行文件名
+    ///
+    /// # 返回
+    ///
+    /// 按配置顺序返回第一个匹配前台窗口的配置；都不匹配时返回该键未设置 `when_window` 的默认配置，
+    /// 没有默认配置时返回 None（触发键照常生效）
+    pub fn resolve_hotkey(&self, key: &str, title: &str, process_name: &str) -> Option<&HotkeyConfig> {
+        let candidates = || self.hotkeys.iter().filter(|h| h.trigger.matches(key));
+        candidates()
+            .find(|h| h.when_window.is_some() && h.matches_window(title, process_name))
+            .or_else(|| candidates().find(|h| h.when_window.is_none()))
+    }
+}
+
+/// 是否为手柄触发键名称（带 "GP:" 前缀，不区分大小写）
+fn is_gamepad_key(name: &str) -> bool {
+    name.get(..3).is_some_and(|p| p.eq_ignore_ascii_case("GP:"))
+}
+
+/// 其他手柄常用的按键名称与对应的 XInput 按键名称，用于提示
+const GAMEPAD_KEY_ALIASES: &[(&str, &str)] = &[
+    ("Cross", "A"),
+    ("Circle", "B"),
+    ("Square", "X"),
+    ("Triangle", "Y"),
+    ("L1", "LB"),
+    ("R1", "RB"),
+    ("L3", "LS"),
+    ("R3", "RS"),
+    ("Options", "Start"),
+    ("Menu", "Start"),
+    ("Share", "Back"),
+    ("Select", "Back"),
+    ("View", "Back"),
+    ("Home", "Guide"),
+    ("PS", "Guide"),
+    ("Up", "DUp"),
+    ("Down", "DDown"),
+    ("Left", "DLeft"),
+    ("Right", "DRight"),
+];
+
+/// 检查手柄触发键名称
+///
+/// # 参数
+///
+/// * `name` - 触发键名称，不带 "GP:" 前缀的键盘按键和组合序列不检查
+///
+/// # 返回
+///
+/// 已知的手柄按键（不区分大小写）返回 None，否则返回错误说明，能找到相近的按键时附带提示
+fn unknown_gamepad_key(name: &str) -> Option<String> {
+    if !is_gamepad_key(name) || chord_keys(name).is_some() {
+        return None;
+    }
+    let button = &name[3..];
+    if crate::gamepad::button_names().any(|known| known.eq_ignore_ascii_case(button)) {
+        return None;
+    }
+    if ["LT", "RT", "L2", "R2"].iter().any(|t| t.eq_ignore_ascii_case(button)) {
+        return Some(format!("扳机 '{}' 暂未支持，不会触发", name));
+    }
+    let suggestion = GAMEPAD_KEY_ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(button))
+        .map(|(_, known)| *known)
+        .or_else(|| {
+            let button = button.to_ascii_lowercase();
+            crate::gamepad::button_names()
+                .map(|known| (edit_distance(&button, &known.to_ascii_lowercase()), known))
+                .filter(|(distance, _)| *distance <= 2)
+                .min_by_key(|(distance, _)| *distance)
+                .map(|(_, known)| known)
+        });
+    Some(match suggestion {
+        Some(known) => format!("未知的手柄按键 '{}'，是否为 GP:{}？", name, known),
+        None => format!(
+            "未知的手柄按键 '{}'，可选值: {}",
+            name,
+            crate::gamepad::button_names().collect::<Vec<_>>().join(", ")
+        ),
+    })
+}
+
+/// 两个字符串间的编辑距离（插入、删除、替换各计 1）
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// 将组合序列触发键拆分为各个按键
+///
+/// # 参数
+///
+/// * `name` - 触发键名称，以空格分隔的多个按键为组合序列，如 "` 1"
+///
+/// # 返回
+///
+/// 至少包含两个按键时返回各按键，单个按键返回 None
+pub fn chord_keys(name: &str) -> Option<Vec<&str>> {
+    let keys: Vec<&str> = name.split_whitespace().collect();
+    (keys.len() >= 2).then_some(keys)
+}
+
+/// 检查组合序列的写法
+///
+/// 组合序列只能由键盘按键组成；序列的第一个键不能同时单独作为触发键，
+/// 否则按下它时无法判断是立即触发还是等待后续按键
+fn check_chords(hotkeys: &[HotkeyConfig]) -> Result<(), String> {
+    let names: Vec<String> = hotkeys.iter().flat_map(|h| h.trigger.key_names()).collect();
+    for name in &names {
+        let Some(keys) = chord_keys(name) else {
+            continue;
+        };
+        if let Some(key) = keys.iter().find(|k| is_gamepad_key(k) || crate::keys::is_mouse_button(k)) {
+            return Err(format!("组合序列 '{}' 只能包含键盘按键: {}", name, key));
+        }
+        let leader = crate::keys::canonicalize(keys[0]);
+        if names.iter().any(|n| crate::keys::canonicalize(n).eq_ignore_ascii_case(leader)) {
+            return Err(format!("按键 {} 既是单独的触发键，又是组合序列 '{}' 的第一个键", keys[0], name));
+        }
+    }
+    Ok(())
+}
+
+/// 环境变量的值是否表示开启（`1` 或 `true`，不区分大小写）
+pub(crate) fn env_flag_enabled(value: Option<&str>) -> bool {
+    value.is_some_and(|v| v.trim() == "1" || v.trim().eq_ignore_ascii_case("true"))
+}
+
+/// 最小延迟倍率，倍率按千分之一的精度保存，更小的值会被舍入为 0
+pub const MIN_DELAY_SCALE: f64 = 0.001;
+
+/// 检查延迟倍率是否为不小于 [`MIN_DELAY_SCALE`] 的有限数
+fn check_delay_scale(scale: f64) -> Result<(), String> {
+    if !(scale.is_finite() && scale >= MIN_DELAY_SCALE) {
+        return Err(format!("delay_scale 不能小于 {}: {}", MIN_DELAY_SCALE, scale));
+    }
+    Ok(())
+}
+
+/// 解析错误附带的出错行前后的行数
+const ERROR_CONTEXT_LINES: usize = 2;
+
+/// 为 YAML 解析错误加上文件位置和出错位置附近的内容
+///
+/// # 返回
+///
+/// 格式为 `文件:行:列: 原因`，后接附近的几行内容；不是 YAML 解析错误或没有位置信息时原样返回
+fn locate_parse_error(path: &Path, content: &str, error: Box<dyn std::error::Error>) -> Box<dyn std::error::Error> {
+    let Some(location) = error.downcast_ref::<serde_yaml::Error>().and_then(|e| e.location()) else {
+        return error;
+    };
+    let (line, column) = (location.line(), location.column());
+    // 原始信息末尾自带 " at line X column Y"，位置已经写在开头，去掉重复的部分
+    let message = error.to_string().replacen(&format!(" at line {} column {}", line, column), "", 1);
+    format!(
+        "{}:{}:{}: {}\n\n{}",
+        path.display(),
+        line,
+        column,
+        message,
+        source_context(content, line)
+    )
+    .into()
+}
+
+/// 出错行及其前后 `ERROR_CONTEXT_LINES` 行，每行带行号，出错行以 `>` 标出
+fn source_context(content: &str, line: usize) -> String {
+    let first = line.saturating_sub(ERROR_CONTEXT_LINES).max(1);
+    let last = line + ERROR_CONTEXT_LINES;
+    let width = last.to_string().len();
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, text)| (i + 1, text))
+        .filter(|(number, _)| (first..=last).contains(number))
+        .map(|(number, text)| {
+            let marker = if number == line { '>' } else { ' ' };
+            format!("{} {:>width$} | {}", marker, number, text)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 检查配置版本是否受支持
+fn check_version(version: u32) -> Result<(), String> {
+    if version > CONFIG_VERSION {
+        return Err(format!(
+            "配置文件版本 {} 高于程序支持的版本 {}，请升级程序",
+            version, CONFIG_VERSION
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(value: u64) -> DurationSpec {
+        DurationSpec::from_millis(value)
+    }
+
+    #[test]
+    fn test_parse_type_text_config() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "F2"
+    action: "type_text"
+    params:
+      text: "hello"
+      delay: 5
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert_eq!(config.hotkeys.len(), 1);
+
+        let hotkey = &config.hotkeys[0];
+        assert_eq!(hotkey.key(), "F2");
+        assert_eq!(hotkey.action, "type_text");
+        assert!(!hotkey.repeat_on_hold);
+
+        if let ActionParams::TypeText(params) = &hotkey.params {
+            assert_eq!(params.text, "hello");
+            assert_eq!(params.delay, Some(DelayConfig::Fixed(ms(5))));
+            assert_eq!(params.jitter, 0);
+        } else {
+            panic!("Expected TypeText params");
+        }
+    }
+
+    #[test]
+    fn test_parse_gamepad_config() {
+        let yaml = r#"
+hotkeys:
+  - type: gamepad
+    key: "A"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "key", value: "Space", action: "press" }
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert_eq!(config.hotkeys.len(), 1);
+
+        let hotkey = &config.hotkeys[0];
+        assert_eq!(hotkey.key(), "GP:A");
+        assert_eq!(hotkey.action, "sequence");
+
+        match &hotkey.trigger {
+            TriggerSource::Gamepad { key } => assert_eq!(key, &StringOrVec::Single("A".to_string())),
+            _ => panic!("Expected Gamepad trigger"),
+        }
+    }
+
+    #[test]
+    fn test_parse_key_group_config() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: ["F1", "GP:A"]
+    action: "type_text"
+    params:
+      text: "hello"
+  - type: gamepad
+    key: ["X", "Y"]
+    action: "type_text"
+    params:
+      text: "world"
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert_eq!(config.hotkeys.len(), 2);
+
+        match &config.hotkeys[0].trigger {
+            TriggerSource::Keyboard { key } => {
+                assert_eq!(key.as_slice(), ["F1".to_string(), "GP:A".to_string()]);
+            }
+            _ => panic!("Expected Keyboard trigger"),
+        }
+        assert_eq!(config.hotkeys[0].key(), "F1|GP:A");
+        assert_eq!(config.hotkeys[1].trigger.key_names(), vec!["GP:X", "GP:Y"]);
+    }
+
+    #[test]
+    fn test_parse_repeat_on_hold_config() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "F4"
+    action: "type_text"
+    repeat_on_hold: true
+    params:
+      text: "x"
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert!(config.hotkeys[0].repeat_on_hold);
+        assert_eq!(config.hotkeys[0].cooldown_ms, 0);
+    }
+
+    #[test]
+    fn test_parse_cooldown_config() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "F4"
+    action: "type_text"
+    cooldown_ms: 500
+    params:
+      text: "x"
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert_eq!(config.hotkeys[0].cooldown_ms, 500);
+    }
+
+    #[test]
+    fn test_type_text_method() {
+        let params = |extra: &str| {
+            let yaml = format!("hotkeys:\n  - type: keyboard\n    key: \"F1\"\n    action: \"type_text\"\n    params:\n      text: \"abc\"\n{}", extra);
+            Config::from_str(&yaml).map(|c| match &c.hotkeys[0].params {
+                ActionParams::TypeText(p) => p.clone(),
+                _ => panic!("Expected TypeText params"),
+            })
+        };
+
+        let default = params("").unwrap();
+        assert_eq!(default.method, TypeMethod::Type);
+        assert_eq!(default.paste_threshold, DEFAULT_PASTE_THRESHOLD);
+        assert!(!default.uses_paste());
+
+        assert!(params("      method: \"paste\"\n").unwrap().uses_paste());
+        assert!(!params("      method: \"auto\"\n").unwrap().uses_paste());
+        assert!(params("      method: \"auto\"\n      paste_threshold: 2\n").unwrap().uses_paste());
+        assert!(params("      method: \"clipboard\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_icon_config() {
+        let yaml = r#"
+icon_enabled: "icons/on.ico"
+hotkeys: []
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert_eq!(config.icon_enabled.as_deref(), Some("icons/on.ico"));
+        assert_eq!(config.icon_disabled, None);
+    }
+
+    #[test]
+    fn test_parse_control_hotkeys_config() {
+        let config = Config::from_str("hotkeys: []").unwrap();
+        assert_eq!(config.pause_hotkey, None);
+        assert_eq!(config.abort_hotkey, None);
+        assert_eq!(config.stop_hotkey.as_deref(), Some("Ctrl+Alt+Escape"));
+
+        let yaml = r#"
+pause_hotkey: "Ctrl+Shift+Pause"
+abort_hotkey: "Ctrl+Shift+Escape"
+stop_hotkey: "Ctrl+Alt+F12"
+hotkeys: []
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert_eq!(config.pause_hotkey.as_deref(), Some("Ctrl+Shift+Pause"));
+        assert_eq!(config.abort_hotkey.as_deref(), Some("Ctrl+Shift+Escape"));
+        assert_eq!(config.stop_hotkey.as_deref(), Some("Ctrl+Alt+F12"));
+    }
+
+    #[test]
+    fn test_parse_suppress_triggers_config() {
+        let config = Config::from_str("hotkeys: []").unwrap();
+        assert!(config.suppress_triggers);
+
+        let config = Config::from_str("suppress_triggers: false\nhotkeys: []").unwrap();
+        assert!(!config.suppress_triggers);
+    }
+
+    #[test]
+    fn test_parse_dry_run_config() {
+        let config = Config::from_str("hotkeys: []").unwrap();
+        assert!(!config.dry_run);
+
+        let config = Config::from_str("dry_run: true\nhotkeys: []").unwrap();
+        assert!(config.dry_run);
+        assert!(config.dry_run_enabled());
+    }
+
+    #[test]
+    fn test_parse_alt_numpad_fallback_config() {
+        assert!(!Config::from_str("hotkeys: []").unwrap().alt_numpad_fallback);
+        assert!(Config::from_str("alt_numpad_fallback: true\nhotkeys: []").unwrap().alt_numpad_fallback);
+    }
+
+    #[test]
+    fn test_parse_ime_safe_config() {
+        assert!(!Config::from_str("hotkeys: []").unwrap().ime_safe);
+        assert!(Config::from_str("ime_safe: true\nhotkeys: []").unwrap().ime_safe);
+    }
+
+    #[test]
+    fn test_env_flag_enabled() {
+        assert!(env_flag_enabled(Some("1")));
+        assert!(env_flag_enabled(Some("TRUE")));
+        assert!(env_flag_enabled(Some(" true ")));
+        assert!(!env_flag_enabled(Some("0")));
+        assert!(!env_flag_enabled(Some("")));
+        assert!(!env_flag_enabled(None));
+    }
+
+    #[test]
+    fn test_example_config_round_trip() {
+        let path = std::env::temp_dir().join(format!("keymacro_example_{}.yaml", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        Config::write_example(&path).unwrap();
+        let config = Config::from_file(&path);
+        // 已存在时不覆盖
+        let rewrite = Config::write_example(&path);
+        let _ = fs::remove_file(&path);
+
+        let config = config.unwrap();
+        assert!(rewrite.is_err());
+        assert!(matches!(config.find_hotkey("F2").unwrap().params, ActionParams::TypeText(_)));
+        assert!(matches!(config.find_hotkey("F3").unwrap().params, ActionParams::Sequence(_)));
+    }
+
+    #[test]
+    fn test_to_file_round_trip() {
+        let yaml = r##"
+delay_scale: 2.0
+overlay_monitor: 2
+never_suppress: ["Escape"]
+profiles:
+  - name: "游戏"
+    hotkeys:
+      - type: gamepad
+        key: ["A", "B"]
+        action: "tap_hold"
+        params: { key: "Shift", hold_ms: 100 }
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    name: "问候"
+    action: "type_text"
+    params: { text: "hello", delay: { min: 5, max: 9 } }
+  - type: keyboard
+    key: "F2"
+    hold_threshold_ms: 400
+    tap_action: { action: "type_text", params: { text: "gg" } }
+    hold_action: { action: "none" }
+  - type: keyboard
+    key: "F3"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "key", value: "A", action: "press" }
+        - { type: "wait_pixel", x: 1, y: 2, color: "#ff0000" }
+        - type: "if_var"
+          name: "t"
+          equals: 0
+          then: [{ type: "set_var", name: "t", value: 1 }]
+        - { type: "release_all" }
+  - type: keyboard
+    key: "F4"
+    action: "toggle_system"
+"##;
+        let mut config = Config::from_str(yaml).unwrap();
+        // 运行时修改的设置同样写出
+        config.delay_scale = 0.5;
+
+        let path = std::env::temp_dir().join(format!("keymacro_export_{}.yaml", std::process::id()));
+        config.to_file(&path).unwrap();
+        let reloaded = Config::from_file(&path);
+        let _ = fs::remove_file(&path);
+
+        let reloaded = reloaded.unwrap();
+        assert_eq!(reloaded, config);
+        assert_eq!(reloaded.delay_scale, 0.5);
+        assert_eq!(reloaded.overlay_monitor, OverlayMonitor::Index(2));
+        assert_eq!(reloaded.hotkeys[1].hold_threshold_ms, 400);
+        assert!(reloaded.hotkeys[1].tap_or_hold().is_some());
+        assert_eq!(reloaded.profiles[0].hotkeys, config.profiles[0].hotkeys);
+    }
+
+    #[test]
+    fn test_syntax_error_reports_file_line_and_context() {
+        let path = std::env::temp_dir().join(format!("keymacro_malformed_{}.yaml", std::process::id()));
+        let yaml = "hotkeys:\n  - type: keyboard\n    key: \"F1\"\n    action: type_text: oops\n    params:\n      text: \"x\"\n";
+        fs::write(&path, yaml).unwrap();
+        let err = Config::from_file(&path).unwrap_err().to_string();
+        fs::remove_file(&path).unwrap();
+
+        let prefix = format!("{}:4:22: mapping values are not allowed", path.display());
+        assert!(err.starts_with(&prefix), "{}", err);
+        // 位置只在开头出现一次
+        assert!(!err.contains("at line 4"), "{}", err);
+        assert!(err.contains("> 4 |     action: type_text: oops"), "{}", err);
+        assert!(err.contains("  2 |   - type: keyboard"), "{}", err);
+        assert!(err.contains("  6 |       text: \"x\""), "{}", err);
+        assert!(!err.contains("1 | hotkeys:"), "{}", err);
+    }
+
+    #[test]
+    fn test_source_context_at_file_edges() {
+        let content = "a\nb\nc";
+        assert_eq!(source_context(content, 1), "> 1 | a\n  2 | b\n  3 | c");
+        assert_eq!(source_context(content, 3), "  1 | a\n  2 | b\n> 3 | c");
+    }
+
+    #[test]
+    fn test_non_yaml_errors_keep_their_message() {
+        let path = std::env::temp_dir().join(format!("keymacro_bad_scale_{}.yaml", std::process::id()));
+        fs::write(&path, "delay_scale: 0\nhotkeys: []\n").unwrap();
+        let err = Config::from_file(&path).unwrap_err().to_string();
+        fs::remove_file(&path).unwrap();
+        assert!(err.starts_with("delay_scale"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_versioned_config() {
+        let yaml = r#"
+version: 1
+hotkeys:
+  - type: keyboard
+    key: "F2"
+    action: "type_text"
+    params:
+      text: "hello"
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert_eq!(config.version, 1);
+
+        let config = Config::from_str("hotkeys: []").unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+    }
+
+    #[test]
+    fn test_reject_future_config_version() {
+        let yaml = r#"
+version: 99
+hotkeys:
+  - type: keyboard
+    key: "F2"
+    action: "some_future_action"
+    params:
+      whatever: true
+"#;
+        let err = Config::from_str(yaml).unwrap_err().to_string();
+        assert!(err.contains("配置文件版本 99"), "{}", err);
+    }
+
+    #[test]
+    fn test_migrate_legacy_speed() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "F2"
+    action: "type_text"
+    params:
+      text: "hello"
+      speed: 30
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        if let ActionParams::TypeText(params) = &config.hotkeys[0].params {
+            assert_eq!(params.delay, Some(DelayConfig::Fixed(ms(30))));
+            assert_eq!(params.speed, None);
+        } else {
+            panic!("Expected TypeText params");
+        }
+    }
+
+    #[test]
+    fn test_find_hotkey_key_group() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "F2"
+    action: "type_text"
+    params:
+      text: "single"
+  - type: keyboard
+    key: ["F1", "GP:A"]
+    action: "type_text"
+    params:
+      text: "group"
+"#;
+        let config = Config::from_str(yaml).unwrap();
+
+        for name in ["F1", "f1", "GP:A", "gp:a"] {
+            let hotkey = config.find_hotkey(name).expect("按键组成员应能匹配");
+            match &hotkey.params {
+                ActionParams::TypeText(params) => assert_eq!(params.text, "group"),
+                _ => panic!("Expected TypeText params"),
+            }
+        }
+        assert!(config.find_hotkey("F2").is_some());
+        assert!(config.find_hotkey("F3").is_none());
+        assert!(config.find_hotkey("A").is_none());
+    }
+
+    #[test]
+    fn test_find_hotkey_by_key_alias() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: ["Return", "Esc"]
+    action: "type_text"
+    params:
+      text: "alias"
+"#;
+        let config = Config::from_str(yaml).unwrap();
+
+        assert!(config.find_hotkey("Enter").is_some());
+        assert!(config.find_hotkey("Escape").is_some());
+        assert!(config.find_hotkey("Tab").is_none());
+    }
+
+    #[test]
+    fn test_find_hotkey_index() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: ["f1", "Return"]
+    action: "type_text"
+    params:
+      text: "first"
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "second"
+profiles:
+  - name: "方案二"
+    hotkeys:
+      - type: gamepad
+        key: ["A", "B"]
+        action: "type_text"
+        params:
+          text: "gamepad"
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        let text = |config: &Config, key: &str| match &config.find_hotkey(key)?.params {
+            ActionParams::TypeText(params) => Some(params.text.clone()),
+            _ => None,
+        };
+
+        // 大小写、别名都能命中，同一按键取第一个配置
+        for key in ["F1", "f1", "ENTER", "enter", "Return"] {
+            assert_eq!(text(&config, key).as_deref(), Some("first"), "{}", key);
+        }
+        assert_eq!(text(&config, "GP:A"), None);
+
+        // 切换方案后索引随热键一起更新
+        let profile = config.with_profile(1).unwrap();
+        assert_eq!(text(&profile, "gp:b").as_deref(), Some("gamepad"));
+        assert_eq!(text(&profile, "F1"), None);
+        assert_eq!(text(&profile, &format!("GP:{}", "A".repeat(40))), None);
+
+        // 修改热键后索引与 `hotkeys` 不符时逐个比较
+        let mut stale = config.clone();
+        stale.hotkeys.swap(0, 1);
+        assert_eq!(text(&stale, "F1").as_deref(), Some("second"));
+        assert!(!stale.has_window_variants("F1"));
+
+        // 直接反序列化（未建立索引）时逐个比较，结果相同
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(text(&config, "ENTER").as_deref(), Some("first"));
+        assert_eq!(text(&config, "GP:A"), None);
+    }
+
+    /// 一个设置了名称和说明的热键和一个未设置的热键
+    const METADATA_YAML: &str = r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    name: "问候"
+    description: "在聊天框中输入问候语"
+    action: "type_text"
+    params:
+      text: "hello"
+  - type: keyboard
+    key: ["F2", "GP:A"]
+    action: "sequence"
+    params:
+      steps: []
+"#;
+
+    #[test]
+    fn test_parse_hotkey_metadata() {
+        let config = Config::from_str(METADATA_YAML).unwrap();
+        assert_eq!(config.hotkeys[0].name.as_deref(), Some("问候"));
+        assert_eq!(config.hotkeys[0].description.as_deref(), Some("在聊天框中输入问候语"));
+        assert_eq!(config.hotkeys[1].name, None);
+        assert_eq!(config.hotkeys[1].description, None);
+    }
+
+    #[test]
+    fn test_hotkey_menu_label() {
+        let config = Config::from_str(METADATA_YAML).unwrap();
+        assert_eq!(config.hotkeys[0].menu_label(), "F1 — 问候");
+        assert_eq!(config.hotkeys[1].menu_label(), "F2|GP:A — sequence");
+    }
+
+    const WINDOW_VARIANTS_YAML: &str = r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    name: "默认"
+    action: "type_text"
+    params:
+      text: "fallback"
+  - type: keyboard
+    key: "F1"
+    name: "PS"
+    when_window: "Photoshop.exe"
+    action: "type_text"
+    params:
+      text: "ps"
+  - type: keyboard
+    key: "F1"
+    name: "浏览器"
+    when_window: "chrome"
+    action: "type_text"
+    params:
+      text: "browser"
+  - type: keyboard
+    key: "F1"
+    name: "标题"
+    when_window: "Google"
+    action: "type_text"
+    params:
+      text: "title"
+  - type: keyboard
+    key: "F2"
+    when_window: "Photoshop.exe"
+    action: "type_text"
+    params:
+      text: "ps only"
+"#;
+
+    fn resolved_name(config: &Config, key: &str, title: &str, process_name: &str) -> Option<String> {
+        config
+            .resolve_hotkey(key, title, process_name)
+            .map(|h| h.name.clone().unwrap_or_default())
+    }
+
+    #[test]
+    fn test_resolve_hotkey_by_window() {
+        let config = Config::from_str(WINDOW_VARIANTS_YAML).unwrap();
+
+        // 进程文件名完全匹配（不区分大小写），排在前面的默认配置不会抢先生效
+        assert_eq!(resolved_name(&config, "F1", "未命名-1", "photoshop.EXE").as_deref(), Some("PS"));
+        // 窗口标题包含匹配
+        assert_eq!(resolved_name(&config, "F1", "新标签页 - Chrome", "chrome.exe").as_deref(), Some("浏览器"));
+        // 同时匹配多个变体时按配置顺序取第一个
+        assert_eq!(resolved_name(&config, "F1", "Google - Chrome", "chrome.exe").as_deref(), Some("浏览器"));
+        assert_eq!(resolved_name(&config, "F1", "Google", "firefox.exe").as_deref(), Some("标题"));
+        // 进程名只做完全匹配
+        assert_eq!(resolved_name(&config, "F2", "", "Photoshop.exe.bak").as_deref(), None);
+    }
+
+    #[test]
+    fn test_resolve_hotkey_fallback() {
+        let config = Config::from_str(WINDOW_VARIANTS_YAML).unwrap();
+
+        assert_eq!(resolved_name(&config, "F1", "记事本", "notepad.exe").as_deref(), Some("默认"));
+        // 没有默认配置时不生效
+        assert!(config.resolve_hotkey("F2", "记事本", "notepad.exe").is_none());
+        assert!(config.resolve_hotkey("F2", "", "Photoshop.exe").is_some());
+
+        assert!(config.has_window_variants("F1"));
+        assert!(!config.has_window_variants("F3"));
+        assert!(config.uses_window_variants());
+        assert!(!Config::from_str("hotkeys: []").unwrap().uses_window_variants());
+        assert_eq!(config.bound_keys(), vec!["F1", "F2"]);
+        assert_eq!(config.hotkeys[1].menu_label(), "F1 — PS [Photoshop.exe]");
+    }
+
+    #[test]
+    fn test_parse_window_variants_under_one_key() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    cooldown_ms: 500
+    variants:
+      - name: "默认"
+        action: "type_text"
+        params:
+          text: "fallback"
+      - name: "PS"
+        when_window: "Photoshop.exe"
+        action: "type_text"
+        params:
+          text: "ps"
+      - name: "浏览器"
+        when_window: "chrome"
+        cooldown_ms: 0
+        tap_action: { action: "type_text", params: { text: "tap" } }
+        hold_action: { action: "type_text", params: { text: "hold" } }
+  - type: keyboard
+    key: "F2"
+    action: "type_text"
+    params:
+      text: "plain"
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        // 变体按书写顺序展开为各自的热键，未设置的选项使用所在热键的值
+        assert_eq!(config.hotkeys.len(), 4);
+        assert_eq!(config.hotkeys[1].when_window.as_deref(), Some("Photoshop.exe"));
+        assert_eq!(config.hotkeys[1].cooldown_ms, 500);
+        assert_eq!(config.hotkeys[2].cooldown_ms, 0);
+        assert!(config.hotkeys[2].tap_or_hold().is_some());
+
+        assert_eq!(resolved_name(&config, "F1", "未命名-1", "Photoshop.exe").as_deref(), Some("PS"));
+        assert_eq!(resolved_name(&config, "F1", "新标签页 - Chrome", "chrome.exe").as_deref(), Some("浏览器"));
+        assert_eq!(resolved_name(&config, "F1", "记事本", "notepad.exe").as_deref(), Some("默认"));
+        assert_eq!(config.bound_keys(), vec!["F1", "F2"]);
+
+        // 展开后的配置序列化为多条热键，可以重新解析
+        let reloaded = Config::from_str(&serde_yaml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(reloaded.hotkeys, config.hotkeys);
+    }
+
+    #[test]
+    fn test_invalid_window_variants() {
+        let variants = |extra: &str, variants: &str| {
+            format!("hotkeys:\n  - type: keyboard\n    key: \"F1\"\n{}    variants:\n{}", extra, variants)
+        };
+        let fallback = "      - action: \"none\"\n";
+        let scoped = "      - when_window: \"chrome\"\n        action: \"none\"\n";
+        let invalid = [
+            // 操作需要写在变体中
+            variants("    action: \"none\"\n", scoped),
+            variants("    when_window: \"chrome\"\n", fallback),
+            // 只能有一个默认变体
+            variants("", &format!("{}{}", fallback, fallback)),
+            // 变体中不能写触发源
+            variants("", "      - key: \"F2\"\n        action: \"none\"\n"),
+            variants("", "      - when_window: \"chrome\"\n"),
+            "hotkeys:\n  - type: keyboard\n    key: \"F1\"\n    variants: []\n".to_string(),
+        ];
+        for yaml in invalid {
+            assert!(Config::from_str(&yaml).is_err(), "{}", yaml);
+        }
+        assert!(Config::from_str(&variants("", &format!("{}{}", scoped, fallback))).is_ok());
+    }
+
+    #[test]
+    fn test_gamepad_stick_settings() {
+        let yaml = r#"
+hotkeys: []
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert_eq!(config.gamepad.left_deadzone, 7849);
+        assert_eq!(config.gamepad.right_deadzone, 8689);
+        assert!(!config.gamepad.invert_left_y);
+        assert!(!config.gamepad.invert_right_y);
+
+        let yaml = r#"
+gamepad:
+  left_deadzone: 0
+  right_deadzone: 32767
+  invert_right_y: true
+hotkeys: []
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert_eq!(config.gamepad.left_deadzone, 0);
+        assert_eq!(config.gamepad.right_deadzone, 32767);
+        assert!(!config.gamepad.invert_left_y);
+        assert!(config.gamepad.invert_right_y);
+
+        let yaml = r#"
+gamepad:
+  left_deadzone: 32768
+hotkeys: []
+"#;
+        let err = Config::from_str(yaml).unwrap_err().to_string();
+        assert!(err.contains("gamepad.left_deadzone"), "{}", err);
+
+        let yaml = r#"
+gamepad:
+  deadzone: 100
+hotkeys: []
+"#;
+        assert!(Config::from_str(yaml).is_err());
+    }
+
+    #[test]
+    fn test_delay_scale() {
+        let config = Config::from_str("hotkeys: []").unwrap();
+        assert_eq!(config.delay_scale, 1.0);
+
+        let config = Config::from_str("delay_scale: 4\nhotkeys: []").unwrap();
+        assert_eq!(config.delay_scale, 4.0);
+
+        let config = Config::from_str("delay_scale: 0.001\nhotkeys: []").unwrap();
+        assert_eq!(config.delay_scale, MIN_DELAY_SCALE);
+
+        for invalid in ["0", "-1", ".nan", "0.0004"] {
+            let yaml = format!("delay_scale: {}\nhotkeys: []", invalid);
+            assert!(Config::from_str(&yaml).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_parse_wait_pixel_step() {
+        let yaml = r##"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - type: "wait_pixel"
+          x: 100
+          y: -20
+          color: "#00ff80"
+          tolerance: 8
+        - type: "wait_pixel"
+          x: 0
+          y: 0
+          color: "FFFFFF"
+          timeout_ms: 300
+"##;
+        let config = Config::from_str(yaml).unwrap();
+        let ActionParams::Sequence(params) = &config.hotkeys[0].params else {
+            panic!("Expected Sequence params");
+        };
+        assert!(matches!(
+            &params.steps[0],
+            Step::WaitPixel { x: 100, y: -20, color, tolerance: 8, timeout_ms: 5000 } if color == "#00ff80"
+        ));
+        assert!(matches!(&params.steps[1], Step::WaitPixel { tolerance: 0, timeout_ms: 300, .. }));
+
+        let err = Config::from_str(&yaml.replace("#00ff80", "#00ff8")).unwrap_err().to_string();
+        assert!(err.contains("无效的颜色"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_scan_code_step() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "scan_code", code: 0x1D, extended: true, action: "press" }
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        let ActionParams::Sequence(params) = &config.hotkeys[0].params else {
+            panic!("Expected Sequence params");
+        };
+        assert_eq!(params.steps[0], Step::ScanCode { code: 0x1D, action: Some(KeyAction::Press), extended: true });
+
+        let err = Config::from_str(&yaml.replace("0x1D", "0")).unwrap_err().to_string();
+        assert!(err.contains("扫描码不能为 0"), "{}", err);
+    }
+
+    #[test]
+    fn test_rgb_parse_and_tolerance() {
+        let target = Rgb::parse("#10Ff80").unwrap();
+        assert_eq!(target, Rgb { r: 0x10, g: 0xFF, b: 0x80 });
+        assert_eq!(target.to_string(), "#10FF80");
+        assert_eq!(Rgb::parse("10FF80"), Some(target));
+        for invalid in ["", "#12345", "#1234567", "#GG0000", "#+1+2+3"] {
+            assert_eq!(Rgb::parse(invalid), None, "{}", invalid);
+        }
+
+        assert!(target.matches(target, 0));
+        assert!(!target.matches(Rgb { r: 0x11, ..target }, 0));
+        // 每个通道分别比较，差值恰好等于容差时仍匹配
+        assert!(target.matches(Rgb { r: 0x08, g: 0xF7, b: 0x88 }, 8));
+        assert!(!target.matches(Rgb { r: 0x08, g: 0xF7, b: 0x89 }, 8));
+        assert!(Rgb { r: 0, g: 0, b: 0 }.matches(Rgb { r: 255, g: 255, b: 255 }, 255));
+    }
+
+    #[test]
+    fn test_dangerous_hotkeys() {
+        let config_with = |suppress: bool, keys: &[&str]| {
+            let hotkeys: String = keys
+                .iter()
+                .map(|k| format!("  - type: keyboard\n    key: \"{}\"\n    action: \"type_text\"\n    params:\n      text: \"x\"\n", k))
+                .collect();
+            Config::from_str(&format!("suppress_triggers: {}\nhotkeys:\n{}", suppress, hotkeys)).unwrap()
+        };
+
+        // 单独的修饰键（包括别名）总是危险，重复的键只列出一次
+        let config = config_with(false, &["Control", "Alt", "shift", "Win", "RWin", "Ctrl", "F1"]);
+        assert_eq!(config.dangerous_hotkeys(), vec!["Ctrl", "Alt", "Shift", "LWin", "RWin"]);
+
+        // 常用键只在拦截触发键时危险
+        let common = ["Enter", "Esc", "Tab", "Space", "Backspace", "A"];
+        assert!(config_with(false, &common).dangerous_hotkeys().is_empty());
+        assert_eq!(
+            config_with(true, &common).dangerous_hotkeys(),
+            vec!["Enter", "Escape", "Tab", "Space", "Backspace"]
+        );
+
+        // 组合序列的第一个键总是被拦截
+        assert_eq!(config_with(false, &["Enter 1"]).dangerous_hotkeys(), vec!["Enter"]);
+        assert!(config_with(true, &["F1 Enter"]).dangerous_hotkeys().is_empty());
+
+        assert!(config_with(true, &["F1", "Delete"]).dangerous_hotkeys_warning().is_none());
+        assert!(config_with(true, &["Ctrl"]).dangerous_hotkeys_warning().unwrap().contains("Ctrl"));
+        assert!(config_with(true, &[]).dangerous_key_dialog);
+
+        // 始终放行的按键不计入
+        let mut config = config_with(true, &["Ctrl", "Esc", "Enter 1"]);
+        config.never_suppress = vec!["control".to_string(), "Escape".to_string(), "Return".to_string()];
+        assert!(config.dangerous_hotkeys().is_empty());
+    }
+
+    #[test]
+    fn test_global_hotkey_conflicts() {
+        let config_with = |suppress: bool, keys: &[&str]| {
+            let hotkeys: String = keys
+                .iter()
+                .map(|k| format!("  - type: keyboard\n    key: \"{}\"\n    action: \"type_text\"\n    params:\n      text: \"x\"\n", k))
+                .collect();
+            Config::from_str(&format!("suppress_triggers: {}\nhotkeys:\n{}", suppress, hotkeys)).unwrap()
+        };
+
+        // 绑定了开关热键中的按键（不论是否按住修饰键都会被拦截）
+        let config = config_with(true, &["`", "F1", "Control"]);
+        assert_eq!(config.global_hotkey_conflicts("Ctrl+`"), vec!["`", "Ctrl"]);
+        let warning = config.global_hotkey_conflict_warning("开关热键", "Ctrl+`").unwrap();
+        assert!(warning.contains("开关热键 Ctrl+`") && warning.contains("`, Ctrl"), "{}", warning);
+        assert!(config.global_hotkey_conflicts("Ctrl+Alt+F12").contains(&"Ctrl".to_string()));
+
+        // 组合序列的第一个键总是被拦截，不拦截触发键时单键不冲突
+        let config = config_with(false, &["` 1", "m"]);
+        assert_eq!(config.global_hotkey_conflicts("Ctrl+`"), vec!["`"]);
+        assert!(config.global_hotkey_conflicts("Ctrl+Alt+M").is_empty());
+        assert!(config_with(true, &["m"]).global_hotkey_conflict_warning("开关热键", "Ctrl+Alt+M").is_some());
+
+        // 始终放行的按键不冲突
+        let mut config = config_with(true, &["`"]);
+        config.never_suppress = vec!["`".to_string()];
+        assert!(config.global_hotkey_conflict_warning("开关热键", "Ctrl+`").is_none());
+    }
+
+    #[test]
+    fn test_no_active_hotkeys_warning() {
+        let config = Config::from_str("hotkeys: []").unwrap();
+        assert_eq!(config.active_hotkey_count(), 0);
+        assert!(config.no_active_hotkeys_warning().unwrap().contains("没有定义任何热键"));
+
+        let yaml = r#"
+gamepad:
+  enabled: false
+hotkeys:
+  - type: gamepad
+    key: "A"
+    action: "type_text"
+    params:
+      text: "x"
+  - type: keyboard
+    key: ["GP:B"]
+    action: "type_text"
+    params:
+      text: "y"
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert_eq!(config.active_hotkey_count(), 0);
+        assert!(config.no_active_hotkeys_warning().unwrap().contains("gamepad.enabled"));
+
+        // 按键组中混有键盘按键，手柄关闭时仍能触发
+        let config = Config::from_str(&yaml.replace(r#"["GP:B"]"#, r#"["GP:B", "F1"]"#)).unwrap();
+        assert_eq!(config.active_hotkey_count(), 1);
+        assert_eq!(config.no_active_hotkeys_warning(), None);
+
+        let config = Config::from_str(&yaml.replace("enabled: false", "enabled: true")).unwrap();
+        assert_eq!(config.active_hotkey_count(), 2);
+
+        // 顶层没有热键，但其他配置方案中有能够触发的热键
+        let config = Config::from_str(r#"
+hotkeys: []
+profiles:
+  - name: "游戏"
+    hotkeys:
+      - type: keyboard
+        key: "F1"
+        action: "type_text"
+        params:
+          text: "x"
+"#).unwrap();
+        assert_eq!(config.active_hotkey_count(), 0);
+        assert_eq!(config.no_active_hotkeys_warning(), None);
+        assert_eq!(config.with_profile(1).unwrap().active_hotkey_count(), 1);
+    }
+
+    #[test]
+    fn test_duration_spec_units() {
+        assert_eq!(DurationSpec::parse("500us"), Some(DurationSpec(Duration::from_micros(500))));
+        assert_eq!(DurationSpec::parse("250µs"), Some(DurationSpec(Duration::from_micros(250))));
+        assert_eq!(DurationSpec::parse("2ms"), Some(ms(2)));
+        assert_eq!(DurationSpec::parse("0.5ms"), Some(DurationSpec(Duration::from_micros(500))));
+        assert_eq!(DurationSpec::parse("1s"), Some(ms(1000)));
+        assert_eq!(DurationSpec::parse("1.5s"), Some(ms(1500)));
+        // 不带单位时按毫秒计
+        assert_eq!(DurationSpec::parse("30"), Some(ms(30)));
+        assert_eq!(DurationSpec::parse(" 3 ms "), Some(ms(3)));
+
+        for invalid in ["", "ms", "-1ms", "1m", "1h", "fast", "NaNms", "infs"] {
+            assert_eq!(DurationSpec::parse(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_duration_fields_accept_units() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      start_delay: "1.5s"
+      steps:
+        - { type: "key", value: "A", delay: "500us" }
+        - { type: "wait", value: 20 }
+        - { type: "text", value: "b", delay: { min: "100us", max: 2 } }
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        let ActionParams::Sequence(params) = &config.hotkeys[0].params else {
+            panic!("Expected Sequence params");
+        };
+        assert_eq!(params.start_delay, ms(1500));
+        assert!(matches!(
+            &params.steps[0],
+            Step::Key { delay: Some(DelayConfig::Fixed(d)), .. } if d.duration() == Duration::from_micros(500)
+        ));
+        assert!(matches!(&params.steps[1], Step::Wait { value, .. } if *value == ms(20)));
+        let Step::Text { delay: Some(delay), .. } = &params.steps[2] else {
+            panic!("Expected Text step with delay");
+        };
+        for _ in 0..20 {
+            let d = delay.get_delay();
+            assert!(d >= Duration::from_micros(100) && d <= Duration::from_millis(2), "{:?}", d);
+        }
+
+        let bad = yaml.replace("\"500us\"", "\"500 years\"");
+        assert!(Config::from_str(&bad).is_err());
+        let negative = yaml.replace("value: 20", "value: -20");
+        assert!(Config::from_str(&negative).is_err());
+    }
+
+    #[test]
+    fn test_duration_spec_serializes_whole_millis_as_numbers() {
+        assert_eq!(serde_yaml::to_string(&ms(20)).unwrap().trim(), "20");
+        assert_eq!(serde_yaml::to_string(&DurationSpec(Duration::from_micros(1500))).unwrap().trim(), "1500us");
     }
-}
 
-/// 序列中的单个步骤
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "lowercase")]
-pub enum Step {
-    Key { 
-        value: String, 
-        #[serde(default)] 
-        delay: Option<DelayConfig>,
-        #[serde(default)]
-        action: Option<KeyAction>,
-    },
-    Wait { 
-        value: u64,
-        #[serde(default)]
-        random: Option<bool>,
-    },
-    Text { value: String, #[serde(default)] delay: Option<DelayConfig> },
-}
+    #[test]
+    fn test_chord_keys() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "` 1"
+    action: "type_text"
+    params:
+      text: "one"
+  - type: keyboard
+    key: ["F1", "`  2"]
+    action: "type_text"
+    params:
+      text: "two"
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert_eq!(config.chords(), vec!["` 1", "`  2"]);
+        assert_eq!(config.chord_timeout_ms, 1000);
+        assert!(!config.chord_replay);
+        assert!(config.find_hotkey("` 1").is_some());
 
-impl Config {
-    /// 从文件加载配置
-    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
-        let content = fs::read_to_string(path)?;
-        let config: Config = serde_yaml::from_str(&content)?;
-        Ok(config)
+        assert_eq!(chord_keys("` 1"), Some(vec!["`", "1"]));
+        assert_eq!(chord_keys("Ctrl x  s"), Some(vec!["Ctrl", "x", "s"]));
+        assert_eq!(chord_keys("F1"), None);
     }
 
-    /// 从字符串加载配置（用于测试）
-    #[allow(dead_code)]
-    pub fn from_str(yaml_str: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let config: Config = serde_yaml::from_str(yaml_str)?;
-        Ok(config)
+    #[test]
+    fn test_invalid_chords_rejected() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "` 1"
+    action: "type_text"
+    params:
+      text: "one"
+  - type: keyboard
+    key: "KEY"
+    action: "type_text"
+    params:
+      text: "two"
+"#;
+        // 第一个键同时作为单独的触发键
+        let err = Config::from_str(&yaml.replace("\"KEY\"", "\"`\"")).unwrap_err().to_string();
+        assert!(err.contains("第一个键"), "{}", err);
+        assert!(Config::from_str(&yaml.replace("\"KEY\"", "\"F2\"")).is_ok());
+
+        // 手柄和鼠标按键不能出现在组合序列中
+        assert!(Config::from_str(&yaml.replace("\"` 1\"", "\"` GP:A\"")).is_err());
+        assert!(Config::from_str(&yaml.replace("\"` 1\"", "\"MouseX1 1\"")).is_err());
     }
 
-    /// 查找指定键的配置
-    pub fn find_hotkey(&self, key: &str) -> Option<&HotkeyConfig> {
-        self.hotkeys.iter().find(|h| h.trigger.matches(key))
+    const PROFILES_YAML: &str = r#"
+next_profile_hotkey: "Ctrl+Alt+P"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "default"
+profiles:
+  - name: "游戏"
+    hotkeys:
+      - type: keyboard
+        key: "F2"
+        action: "type_text"
+        params:
+          text: "game"
+          speed: 20
+  - name: "办公"
+    hotkeys:
+      - type: keyboard
+        key: "MouseX1"
+        action: "type_text"
+        params:
+          text: "work"
+"#;
+
+    #[test]
+    fn test_profiles() {
+        let config = Config::from_str(PROFILES_YAML).unwrap();
+        assert_eq!(config.profile_names(), vec!["默认", "游戏", "办公"]);
+        assert_eq!(config.next_profile_hotkey.as_deref(), Some("Ctrl+Alt+P"));
+        // 鼠标侧键只在其他方案中使用时也需要安装鼠标钩子
+        assert!(config.uses_mouse_buttons());
+
+        let game = config.with_profile(1).unwrap();
+        assert!(game.find_hotkey("F1").is_none());
+        let ActionParams::TypeText(params) = &game.find_hotkey("F2").unwrap().params else {
+            panic!("Expected TypeText params");
+        };
+        // 方案中的旧版字段同样会迁移
+        assert_eq!(params.delay, Some(DelayConfig::Fixed(ms(20))));
+
+        assert!(config.with_profile(0).unwrap().find_hotkey("F1").is_some());
+        assert!(config.with_profile(3).is_none());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_next_profile_index_wraps() {
+        let config = Config::from_str(PROFILES_YAML).unwrap();
+        assert_eq!(config.next_profile_index(0), 1);
+        assert_eq!(config.next_profile_index(1), 2);
+        assert_eq!(config.next_profile_index(2), 0);
+
+        // 没有其他方案时始终是默认方案
+        let config = Config::from_str("hotkeys: []").unwrap();
+        assert_eq!(config.profile_names(), vec!["默认"]);
+        assert_eq!(config.next_profile_index(0), 0);
+    }
 
     #[test]
-    fn test_parse_type_text_config() {
+    fn test_bound_keys() {
         let yaml = r#"
 hotkeys:
   - type: keyboard
-    key: "F2"
+    key: ["F1", "GP:A"]
     action: "type_text"
     params:
-      text: "hello"
-      delay: 5
+      text: "x"
+  - type: gamepad
+    key: ["a", "B"]
+    action: "type_text"
+    params:
+      text: "y"
+  - type: keyboard
+    key: "'"
+    action: "type_text"
+    params:
+      text: "z"
 "#;
         let config = Config::from_str(yaml).unwrap();
-        assert_eq!(config.hotkeys.len(), 1);
+        assert_eq!(config.bound_keys(), vec!["F1", "GP:A", "GP:B", "'"]);
+        assert!(Config::from_str("hotkeys: []").unwrap().bound_keys().is_empty());
+    }
 
-        let hotkey = &config.hotkeys[0];
-        assert_eq!(hotkey.key(), "F2");
-        assert_eq!(hotkey.action, "type_text");
+    #[test]
+    fn test_parse_type_text_affixes() {
+        let config = Config::from_str("hotkeys: []").unwrap();
+        assert!(config.type_text_prefix.is_empty() && config.type_text_suffix.is_empty());
 
-        if let ActionParams::TypeText(params) = &hotkey.params {
-            assert_eq!(params.text, "hello");
-            assert!(matches!(params.delay, Some(DelayConfig::Fixed(5))));
-        } else {
+        let yaml = r#"
+type_text_prefix: "[bot] "
+type_text_suffix: "!"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "hello"
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert_eq!(config.type_text_prefix, "[bot] ");
+        assert_eq!(config.type_text_suffix, "!");
+        let ActionParams::TypeText(params) = &config.hotkeys[0].params else {
             panic!("Expected TypeText params");
+        };
+        assert_eq!(params.with_affixes(&config.type_text_prefix, &config.type_text_suffix).text, "[bot] hello!");
+        assert_eq!(params.with_affixes("", "").text, "hello");
+    }
+
+    #[test]
+    fn test_bindings_follow_declaration_order() {
+        let keys = ["F9", "Z", "GP:X", "F1", "'"];
+        let mut yaml = String::from("hotkeys:\n");
+        for key in keys {
+            yaml.push_str(&format!(
+                "  - type: keyboard\n    key: \"{}\"\n    action: \"type_text\"\n    params:\n      text: \"{}\"\n",
+                key, key
+            ));
         }
+        let config = Config::from_str(&yaml).unwrap();
+
+        // 建立了查找索引后，列举顺序仍与声明顺序一致
+        assert_eq!(config.bound_keys(), keys);
+        let expected: Vec<String> = keys.iter().map(|k| format!("{} — type_text", k)).collect();
+        assert_eq!(config.menu_labels(), expected);
+        assert!(config.find_hotkey("F1").is_some());
     }
 
     #[test]
-    fn test_parse_gamepad_config() {
+    fn test_gamepad_enabled_config() {
         let yaml = r#"
+gamepad:
+  enabled: false
 hotkeys:
   - type: gamepad
     key: "A"
-    action: "sequence"
+    action: "type_text"
     params:
-      steps:
-        - { type: "key", value: "Space", action: "press" }
+      text: "x"
+  - type: keyboard
+    key: ["F1", "gp:B"]
+    action: "type_text"
+    params:
+      text: "y"
 "#;
         let config = Config::from_str(yaml).unwrap();
-        assert_eq!(config.hotkeys.len(), 1);
+        assert!(!config.gamepad.enabled);
+        assert_eq!(config.gamepad_bindings(), vec!["GP:A", "gp:B"]);
 
-        let hotkey = &config.hotkeys[0];
-        assert_eq!(hotkey.key(), "GP:A");
-        assert_eq!(hotkey.action, "sequence");
+        // 其他配置方案中的手柄按键同样列出
+        let with_profile = format!("{}profiles:\n  - name: \"游戏\"\n    hotkeys:\n      - {{ type: gamepad, key: \"X\", action: \"none\" }}\n", yaml);
+        assert_eq!(Config::from_str(&with_profile).unwrap().gamepad_bindings(), vec!["GP:A", "gp:B", "GP:X"]);
 
-        match &hotkey.trigger {
-            TriggerSource::Gamepad { key } => assert_eq!(key, "A"),
-            _ => panic!("Expected Gamepad trigger"),
-        }
+        assert!(Config::from_str("hotkeys: []").unwrap().gamepad.enabled);
+    }
+
+    #[test]
+    fn test_overlay_monitor_config() {
+        let monitor = |value: &str| Config::from_str(&format!("overlay_monitor: {}\nhotkeys: []", value)).map(|c| c.overlay_monitor);
+        assert_eq!(Config::from_str("hotkeys: []").unwrap().overlay_monitor, OverlayMonitor::Primary);
+        assert_eq!(monitor("\"primary\"").unwrap(), OverlayMonitor::Primary);
+        assert_eq!(monitor("Cursor").unwrap(), OverlayMonitor::Cursor);
+        assert_eq!(monitor("2").unwrap(), OverlayMonitor::Index(2));
+
+        assert!(monitor("0").unwrap_err().to_string().contains("从 1 开始"));
+        assert!(monitor("left").unwrap_err().to_string().contains("未知的 overlay_monitor 'left'"));
+        assert!(!Config::from_str("hotkeys: []").unwrap().overlay_progress);
+        assert!(Config::from_str("overlay_progress: true\nhotkeys: []").unwrap().overlay_progress);
+    }
+
+    #[test]
+    fn test_gamepad_key_names_validated() {
+        let yaml = r#"
+hotkeys:
+  - type: gamepad
+    key: ["a", "LSUp", "Guide"]
+    action: "none"
+  - type: keyboard
+    key: ["F1", "GP:DLeft"]
+    action: "none"
+"#;
+        assert!(Config::from_str(yaml).is_ok());
+
+        let err = |key: &str| {
+            let yaml = format!("hotkeys:\n  - type: keyboard\n    key: [\"F1\", \"{}\"]\n    action: \"none\"\n", key);
+            Config::from_str(&yaml).unwrap_err().to_string()
+        };
+        assert!(err("GP:Triangle").contains("是否为 GP:Y？"), "{}", err("GP:Triangle"));
+        assert!(err("GP:Strat").contains("是否为 GP:Start？"), "{}", err("GP:Strat"));
+        assert!(err("GP:LT").contains("暂未支持"), "{}", err("GP:LT"));
+        assert!(err("GP:Turbo").contains("可选值: DUp"), "{}", err("GP:Turbo"));
+
+        let yaml = r#"
+hotkeys: []
+profiles:
+  - name: "游戏"
+    hotkeys:
+      - type: gamepad
+        key: "Square"
+        action: "none"
+"#;
+        let message = Config::from_str(yaml).unwrap_err().to_string();
+        assert!(message.contains("未知的手柄按键 'GP:Square'，是否为 GP:X？"), "{}", message);
+    }
+
+    #[test]
+    fn test_mouse_button_trigger_config() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: ["F1", "MouseX1"]
+    action: "type_text"
+    params:
+      text: "x"
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert!(config.uses_mouse_buttons());
+        assert!(config.find_hotkey("MouseX1").is_some());
+        assert!(config.find_hotkey("MouseX2").is_none());
+
+        assert!(!Config::from_str("hotkeys: []").unwrap().uses_mouse_buttons());
     }
 
     #[test]
@@ -235,7 +3343,7 @@ hotkeys:
             match &params.steps[0] {
                 Step::Key { value, delay, action } => {
                     assert_eq!(value, "a");
-                    assert!(matches!(delay, Some(DelayConfig::Fixed(50))));
+                    assert_eq!(delay, &Some(DelayConfig::Fixed(ms(50))));
                     assert_eq!(*action, None); // 默认值为 None，会使用 KeyAction::Complete
                 }
                 _ => panic!("Expected Key step"),
@@ -288,6 +3396,32 @@ hotkeys:
         }
     }
 
+    #[test]
+    fn test_parse_combo_step_config() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - type: "combo"
+          keys: ["Ctrl", "Shift", "Escape"]
+          delay: 30
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        let ActionParams::Sequence(params) = &config.hotkeys[0].params else {
+            panic!("Expected Sequence params");
+        };
+        match &params.steps[0] {
+            Step::Combo { keys, delay } => {
+                assert_eq!(keys, &["Ctrl", "Shift", "Escape"]);
+                assert_eq!(delay, &Some(DelayConfig::Fixed(ms(30))));
+            }
+            _ => panic!("Expected Combo step"),
+        }
+    }
+
     #[test]
     fn test_parse_random_delay_config() {
         let yaml = r#"
@@ -311,7 +3445,7 @@ hotkeys:
             match &params.steps[0] {
                 Step::Key { value, delay, .. } => {
                     assert_eq!(value, "a");
-                    assert!(matches!(delay, Some(DelayConfig::Range { min: 10, max: 30 })));
+                    assert_eq!(delay, &Some(DelayConfig::Range { min: ms(10), max: ms(30) }));
                 }
                 _ => panic!("Expected Key step"),
             }
@@ -319,7 +3453,7 @@ hotkeys:
             // 测试随机等待
             match &params.steps[1] {
                 Step::Wait { value, random } => {
-                    assert_eq!(*value, 100);
+                    assert_eq!(*value, ms(100));
                     assert_eq!(*random, Some(true));
                 }
                 _ => panic!("Expected Wait step"),
@@ -329,7 +3463,7 @@ hotkeys:
             match &params.steps[2] {
                 Step::Text { value, delay } => {
                     assert_eq!(value, "done");
-                    assert!(matches!(delay, Some(DelayConfig::Range { min: 5, max: 15 })));
+                    assert_eq!(delay, &Some(DelayConfig::Range { min: ms(5), max: ms(15) }));
                 }
                 _ => panic!("Expected Text step"),
             }
@@ -337,4 +3471,242 @@ hotkeys:
             panic!("Expected Sequence params");
         }
     }
+
+    #[test]
+    fn test_reject_inverted_delay_range() {
+        let err = load_error("type_text", "      text: \"x\"\n      delay: { min: 30, max: 10 }\n");
+        assert!(err.contains("min") && err.contains("max"), "{}", err);
+
+        let sequence = "      steps:\n        - { type: \"key\", value: \"a\", delay: { min: \"2ms\", max: 1 } }\n";
+        assert!(load_error("sequence", sequence).contains("不能大于"));
+
+        // min 与 max 相同时为固定延迟
+        let yaml = "hotkeys:\n  - type: keyboard\n    key: \"F2\"\n    action: \"type_text\"\n    params:\n      text: \"x\"\n      delay: { min: 10, max: 10 }\n";
+        assert!(Config::from_str(yaml).is_ok());
+    }
+
+    #[test]
+    fn test_reject_misspelled_fields() {
+        let cases = [
+            // type_text 参数拼写错误
+            ("type_text", "    params:\n      txt: \"hello\"\n", "txt"),
+            ("type_text", "    params:\n      text: \"hello\"\n      dleay: 5\n", "dleay"),
+            // 序列步骤拼写错误
+            ("sequence", "    params:\n      steps:\n        - { type: \"key\", value: \"a\", dealy: 5 }\n", "dealy"),
+            // 热键层级拼写错误
+            ("type_text", "    repeat_on_hodl: true\n    params:\n      text: \"hello\"\n", "repeat_on_hodl"),
+        ];
+
+        for (action, body, field) in cases {
+            let yaml = format!(
+                "hotkeys:\n  - type: keyboard\n    key: \"F2\"\n    action: \"{}\"\n{}",
+                action, body
+            );
+            let err = Config::from_str(&yaml).unwrap_err().to_string();
+            assert!(err.contains(field), "{}: {}", field, err);
+        }
+
+        // 顶层拼写错误
+        let err = Config::from_str("hotkey: []\n").unwrap_err().to_string();
+        assert!(err.contains("hotkey"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_yaml_anchor_config() {
+        let yaml = r#"
+# 注释会被忽略
+hotkeys:
+  - type: keyboard
+    key: "F2"
+    action: "type_text"
+    params: &greeting   # 定义锚点
+      text: "hello"
+      delay: 5
+  - type: gamepad
+    key: "A"
+    action: "type_text"
+    params: *greeting   # 引用锚点
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert_eq!(config.hotkeys.len(), 2);
+
+        for hotkey in &config.hotkeys {
+            match &hotkey.params {
+                ActionParams::TypeText(params) => assert_eq!(params.text, "hello"),
+                _ => panic!("Expected TypeText params"),
+            }
+        }
+    }
+
+    /// 按给定的 action 和 params 构造配置并返回加载错误
+    fn load_error(action: &str, params: &str) -> String {
+        let yaml = format!(
+            "hotkeys:\n  - type: keyboard\n    key: \"F2\"\n    action: \"{}\"\n    params:\n{}",
+            action, params
+        );
+        Config::from_str(&yaml).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_reject_malformed_type_text_params() {
+        let err = load_error("type_text", "      delay: 5\n");
+        assert!(err.contains("action 'type_text'") && err.contains("text"), "{}", err);
+
+        // 序列参数不能用于 type_text
+        let err = load_error("type_text", "      steps:\n        - { type: \"wait\", value: 10 }\n");
+        assert!(err.contains("action 'type_text'") && err.contains("steps"), "{}", err);
+
+        let err = load_error("type_text", "      text: 42\n      delay: \"fast\"\n");
+        assert!(err.contains("action 'type_text'"), "{}", err);
+    }
+
+    #[test]
+    fn test_reject_malformed_sequence_params() {
+        let err = load_error("sequence", "      text: \"hello\"\n");
+        assert!(err.contains("action 'sequence'") && err.contains("steps"), "{}", err);
+
+        let err = load_error("sequence", "      steps:\n        - { type: \"key\" }\n");
+        assert!(err.contains("action 'sequence'") && err.contains("value"), "{}", err);
+
+        let err = load_error("sequence", "      steps:\n        - { type: \"jump\", value: 1 }\n");
+        assert!(err.contains("action 'sequence'") && err.contains("jump"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_chat_config() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "F5"
+    action: "chat"
+    params:
+      message: "gg"
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        let ActionParams::Chat(params) = &config.hotkeys[0].params else {
+            panic!("Expected Chat params");
+        };
+        assert_eq!(params.open_key, "Enter");
+        assert_eq!(params.close_key, "Enter");
+        assert_eq!(params.pre_delay, ms(100));
+        assert_eq!(params.post_delay, ms(50));
+
+        let err = load_error("chat", "      text: \"gg\"\n");
+        assert!(err.contains("action 'chat' 的参数无效"), "{}", err);
+
+        // 打开键和关闭键在加载时检查
+        let err = load_error("chat", "      message: \"gg\"\n      open_key: \"Entr\"\n");
+        assert!(err.contains("open_key") && err.contains("Entr"), "{}", err);
+        let err = load_error("chat", "      message: \"gg\"\n      close_key: \"F25\"\n");
+        assert!(err.contains("close_key") && err.contains("F25"), "{}", err);
+        assert!(Config::from_str(&yaml.replace("message: \"gg\"", "message: \"gg\"\n      open_key: \"T\"")).is_ok());
+    }
+
+    #[test]
+    fn test_parse_none_action() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "none"
+  - type: keyboard
+    key: "F2"
+    action: "none"
+    params: {}
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert!(config.hotkeys.iter().all(|h| matches!(h.params, ActionParams::None)));
+        assert_eq!(config.bound_keys(), vec!["F1", "F2"]);
+
+        let err = load_error("none", "      text: \"hello\"\n");
+        assert!(err.contains("action 'none' 不接受参数"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_tap_hold_action() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "tap_hold"
+    params:
+      key: "W"
+      hold_ms: 1500
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        let ActionParams::TapHold(params) = &config.hotkeys[0].params else {
+            panic!("应解析为 tap_hold 参数");
+        };
+        assert_eq!(params.key, "W");
+        assert_eq!(params.hold_ms, 1500);
+
+        let err = load_error("tap_hold", "      key: \"W\"\n");
+        assert!(err.contains("action 'tap_hold'") && err.contains("hold_ms"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_tap_and_hold_actions() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    hold_threshold_ms: 400
+    tap_action:
+      action: "type_text"
+      params:
+        text: "tap"
+    hold_action:
+      action: "sequence"
+      params:
+        steps:
+          - { type: "text", value: "hold" }
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        let hotkey = &config.hotkeys[0];
+        let Some((ActionParams::TypeText(tap), ActionParams::Sequence(_))) = hotkey.tap_or_hold() else {
+            panic!("应解析出轻按和长按操作");
+        };
+        assert_eq!(tap.text, "tap");
+        assert_eq!(hotkey.hold_threshold_ms, 400);
+        assert_eq!(hotkey.menu_label(), "F1 — type_text / sequence");
+
+        let load = |fields: &str| {
+            Config::from_str(&format!("hotkeys:\n  - type: keyboard\n    key: \"F1\"\n{}", fields)).map_err(|e| e.to_string())
+        };
+        let tap_only = load("    tap_action:\n      action: \"none\"\n");
+        assert!(tap_only.unwrap_err().contains("同时设置"));
+        let with_action = load("    action: \"none\"\n    tap_action:\n      action: \"none\"\n    hold_action:\n      action: \"none\"\n");
+        assert!(with_action.unwrap_err().contains("不能再设置 action"));
+        let toggle = load("    tap_action:\n      action: \"none\"\n    hold_action:\n      action: \"toggle_system\"\n");
+        assert!(toggle.unwrap_err().contains("hold_action 不支持 toggle_system"));
+        let threshold_only = load("    action: \"none\"\n    hold_threshold_ms: 200\n");
+        assert!(threshold_only.unwrap_err().contains("hold_threshold_ms"));
+    }
+
+    #[test]
+    fn test_parse_toggle_system_action() {
+        let yaml = r#"
+hotkeys:
+  - type: gamepad
+    key: "Back"
+    action: "toggle_system"
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "x"
+"#;
+        let config = Config::from_str(yaml).unwrap();
+        assert!(config.has_system_toggle());
+        assert!(config.find_hotkey("GP:Back").unwrap().is_system_toggle());
+        assert!(!config.find_hotkey("F1").unwrap().is_system_toggle());
+        assert!(!Config::from_str("hotkeys: []").unwrap().has_system_toggle());
+    }
+
+    #[test]
+    fn test_reject_unknown_action() {
+        let err = load_error("typetext", "      text: \"hello\"\n");
+        assert!(err.contains("未知的 action 'typetext'"), "{}", err);
+        assert!(err.contains("F2"), "{}", err);
+    }
 }
@@ -0,0 +1,137 @@
+//! 日期时间格式化模块
+//!
+//! 按 strftime 风格的格式串格式化本地时间，供序列的 `datetime` 步骤输入时间戳。
+//! 只支持常用的格式符，月份和星期使用英文名称（与 C 语言默认区域设置相同）
+
+/// 本地日期时间，读取系统时钟见 `winapi::clock::local_now`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalDateTime {
+    pub year: u16,
+    /// 月份（1–12）
+    pub month: u16,
+    /// 日（1–31）
+    pub day: u16,
+    /// 星期（0 为星期日，1–6 为星期一至星期六）
+    pub weekday: u16,
+    pub hour: u16,
+    pub minute: u16,
+    pub second: u16,
+    pub millisecond: u16,
+}
+
+/// 检查格式时使用的时间（各格式符都能正常展开）
+const SAMPLE_TIME: LocalDateTime = LocalDateTime {
+    year: 2000,
+    month: 1,
+    day: 1,
+    weekday: 6,
+    hour: 0,
+    minute: 0,
+    second: 0,
+    millisecond: 0,
+};
+
+const WEEKDAY_NAMES: [&str; 7] = ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
+
+const MONTH_NAMES: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+/// 按格式串格式化日期时间
+///
+/// # 参数
+///
+/// * `pattern` - 格式串，支持 `%Y` `%y` `%m` `%d` `%e` `%H` `%I` `%M` `%S` `%3f`（毫秒）`%p`
+///   `%a` `%A` `%b` `%B` `%F`（等同 `%Y-%m-%d`）`%T`（等同 `%H:%M:%S`）和 `%%`，其余字符原样输出
+/// * `time` - 要格式化的时间
+///
+/// # 返回
+///
+/// 格式化后的文本；格式串包含不支持的格式符或以单独的 `%` 结尾时返回错误
+pub fn format(pattern: &str, time: &LocalDateTime) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = pattern.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            out.push(ch);
+            continue;
+        }
+        let spec = chars.next().ok_or("格式串不能以单独的 % 结尾")?;
+        let hour12 = match time.hour % 12 {
+            0 => 12,
+            h => h,
+        };
+        let text = match spec {
+            'Y' => format!("{:04}", time.year),
+            'y' => format!("{:02}", time.year % 100),
+            'm' => format!("{:02}", time.month),
+            'd' => format!("{:02}", time.day),
+            'e' => format!("{:>2}", time.day),
+            'H' => format!("{:02}", time.hour),
+            'I' => format!("{:02}", hour12),
+            'M' => format!("{:02}", time.minute),
+            'S' => format!("{:02}", time.second),
+            '3' if chars.next() == Some('f') => format!("{:03}", time.millisecond),
+            'p' => (if time.hour < 12 { "AM" } else { "PM" }).to_string(),
+            'a' => weekday_name(time)?[..3].to_string(),
+            'A' => weekday_name(time)?.to_string(),
+            'b' => month_name(time)?[..3].to_string(),
+            'B' => month_name(time)?.to_string(),
+            'F' => format!("{:04}-{:02}-{:02}", time.year, time.month, time.day),
+            'T' => format!("{:02}:{:02}:{:02}", time.hour, time.minute, time.second),
+            '%' => "%".to_string(),
+            '3' => return Err("不支持的格式符 %3，毫秒应写为 %3f".to_string()),
+            other => return Err(format!("不支持的格式符 %{}", other)),
+        };
+        out.push_str(&text);
+    }
+    Ok(out)
+}
+
+/// 检查格式串是否有效（供加载配置时提前报错）
+pub fn check_format(pattern: &str) -> Result<(), String> {
+    format(pattern, &SAMPLE_TIME).map(|_| ())
+}
+
+fn weekday_name(time: &LocalDateTime) -> Result<&'static str, String> {
+    WEEKDAY_NAMES
+        .get(usize::from(time.weekday))
+        .copied()
+        .ok_or_else(|| format!("无效的星期: {}", time.weekday))
+}
+
+fn month_name(time: &LocalDateTime) -> Result<&'static str, String> {
+    usize::from(time.month)
+        .checked_sub(1)
+        .and_then(|i| MONTH_NAMES.get(i))
+        .copied()
+        .ok_or_else(|| format!("无效的月份: {}", time.month))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_datetime() {
+        let time = LocalDateTime {
+            year: 2026,
+            month: 3,
+            day: 7,
+            weekday: 6,
+            hour: 14,
+            minute: 5,
+            second: 9,
+            millisecond: 42,
+        };
+        assert_eq!(format("%Y-%m-%d %H:%M:%S.%3f", &time).unwrap(), "2026-03-07 14:05:09.042");
+        assert_eq!(format("[%F %T] %I%p %a %b %e 100%%", &time).unwrap(), "[2026-03-07 14:05:09] 02PM Sat Mar  7 100%");
+        assert_eq!(format("%A, %d %B %y", &time).unwrap(), "Saturday, 07 March 26");
+
+        assert!(check_format("%Y/%m/%d").is_ok());
+        assert!(check_format("%Q").unwrap_err().contains("%Q"));
+        assert!(check_format("%3").is_err());
+        assert!(check_format("100%").is_err());
+    }
+}
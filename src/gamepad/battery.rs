@@ -0,0 +1,138 @@
+//! 手柄电池模块
+//!
+//! 记录各手柄的连接状态和电量，用于在托盘提示中显示
+
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+/// XInput 支持的手柄数量
+pub const MAX_CONTROLLERS: usize = 4;
+/// 读取电池信息的间隔（电量变化很慢，不需要与按键一起高频轮询）
+pub const BATTERY_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 手柄电量
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatteryLevel {
+    Empty,   // 电量耗尽
+    Low,     // 低
+    Medium,  // 中
+    Full,    // 满
+    Wired,   // 有线连接，没有电池
+    Unknown, // 无法获取电池信息
+}
+
+impl BatteryLevel {
+    /// 显示名称（用于托盘提示）
+    pub fn label(self) -> &'static str {
+        match self {
+            BatteryLevel::Empty => "空",
+            BatteryLevel::Low => "低",
+            BatteryLevel::Medium => "中",
+            BatteryLevel::Full => "满",
+            BatteryLevel::Wired => "有线",
+            BatteryLevel::Unknown => "未知",
+        }
+    }
+}
+
+/// 单个手柄的状态说明，如 "手柄 0: 已连接 (电量 低)"
+pub fn controller_status_text(controller: usize, level: BatteryLevel) -> String {
+    match level {
+        BatteryLevel::Wired | BatteryLevel::Unknown => format!("手柄 {}: 已连接 ({})", controller, level.label()),
+        _ => format!("手柄 {}: 已连接 (电量 {})", controller, level.label()),
+    }
+}
+
+/// 各手柄的连接状态和电量
+///
+/// 由手柄线程更新，托盘在状态变化后刷新提示
+#[derive(Debug, Default)]
+pub struct ControllerStatus {
+    state: Mutex<StatusState>,
+}
+
+#[derive(Debug, Default)]
+struct StatusState {
+    /// 各手柄的电量，未连接时为 None
+    levels: [Option<BatteryLevel>; MAX_CONTROLLERS],
+    /// 上次读取后是否有变化
+    changed: bool,
+}
+
+impl ControllerStatus {
+    /// 创建空状态（所有手柄未连接）
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 更新手柄状态
+    ///
+    /// # 参数
+    ///
+    /// * `controller` - 手柄序号（0 ~ 3，超出范围时忽略）
+    /// * `level` - 电量，手柄断开时为 None
+    pub fn update(&self, controller: usize, level: Option<BatteryLevel>) {
+        let mut state = self.lock();
+        let Some(slot) = state.levels.get_mut(controller) else {
+            return;
+        };
+        if *slot != level {
+            *slot = level;
+            state.changed = true;
+        }
+    }
+
+    /// 上次调用后状态是否有变化（调用后清除变化标记）
+    pub fn take_changed(&self) -> bool {
+        std::mem::take(&mut self.lock().changed)
+    }
+
+    /// 已连接手柄的状态说明，按手柄序号排列
+    pub fn summary(&self) -> Vec<String> {
+        self.lock()
+            .levels
+            .iter()
+            .enumerate()
+            .filter_map(|(i, level)| level.map(|level| controller_status_text(i, level)))
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, StatusState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_battery_level_text() {
+        assert_eq!(controller_status_text(0, BatteryLevel::Low), "手柄 0: 已连接 (电量 低)");
+        assert_eq!(controller_status_text(1, BatteryLevel::Full), "手柄 1: 已连接 (电量 满)");
+        assert_eq!(controller_status_text(2, BatteryLevel::Wired), "手柄 2: 已连接 (有线)");
+        assert_eq!(BatteryLevel::Empty.label(), "空");
+        assert_eq!(BatteryLevel::Medium.label(), "中");
+        assert_eq!(BatteryLevel::Unknown.label(), "未知");
+    }
+
+    #[test]
+    fn test_controller_status_tracks_changes() {
+        let status = ControllerStatus::new();
+        assert!(!status.take_changed());
+
+        status.update(1, Some(BatteryLevel::Medium));
+        status.update(3, Some(BatteryLevel::Wired));
+        status.update(7, Some(BatteryLevel::Full));
+        assert!(status.take_changed());
+        assert!(!status.take_changed());
+        assert_eq!(status.summary(), vec!["手柄 1: 已连接 (电量 中)", "手柄 3: 已连接 (有线)"]);
+
+        // 相同的状态不算变化，断开后不再显示
+        status.update(1, Some(BatteryLevel::Medium));
+        assert!(!status.take_changed());
+        status.update(1, None);
+        assert!(status.take_changed());
+        assert_eq!(status.summary(), vec!["手柄 3: 已连接 (有线)"]);
+    }
+}
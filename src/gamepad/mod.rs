@@ -2,118 +2,211 @@
 //!
 //! 使用 Windows XInput API 支持 Xbox 协议手柄
 
+pub mod battery;
+pub mod stick;
+
 use std::sync::mpsc::{self, Receiver};
 use std::thread;
-use std::time::Duration;
-use windows::Win32::Foundation::ERROR_SUCCESS;
+use std::time::{Duration, Instant};
 use windows::Win32::UI::Input::XboxController::*;
+use crate::config::GamepadConfig;
+use crate::winapi::xinput;
+use battery::{BatteryLevel, BATTERY_POLL_INTERVAL};
+use stick::{stick_directions, LEFT_STICK, RIGHT_STICK, STICK_BUTTONS};
+
+/// Guide 键（Xbox 徽标键）的按钮位，只有 `XInputGetStateEx` 会报告，XInput 头文件中没有定义
+const XINPUT_GAMEPAD_GUIDE: u16 = 0x0400;
+
+/// XInput 按钮位与配置键名
+static BUTTONS: [(u16, &str); 15] = [
+    (XINPUT_GAMEPAD_DPAD_UP.0, "DUp"),
+    (XINPUT_GAMEPAD_DPAD_DOWN.0, "DDown"),
+    (XINPUT_GAMEPAD_DPAD_LEFT.0, "DLeft"),
+    (XINPUT_GAMEPAD_DPAD_RIGHT.0, "DRight"),
+    (XINPUT_GAMEPAD_START.0, "Start"),
+    (XINPUT_GAMEPAD_BACK.0, "Back"),
+    (XINPUT_GAMEPAD_LEFT_THUMB.0, "LS"),
+    (XINPUT_GAMEPAD_RIGHT_THUMB.0, "RS"),
+    (XINPUT_GAMEPAD_LEFT_SHOULDER.0, "LB"),
+    (XINPUT_GAMEPAD_RIGHT_SHOULDER.0, "RB"),
+    (XINPUT_GAMEPAD_A.0, "A"),
+    (XINPUT_GAMEPAD_B.0, "B"),
+    (XINPUT_GAMEPAD_X.0, "X"),
+    (XINPUT_GAMEPAD_Y.0, "Y"),
+    (XINPUT_GAMEPAD_GUIDE, "Guide"),
+];
+
+/// 所有可在配置中使用的手柄按键名称（实体按钮和摇杆方向，不带 "GP:" 前缀）
+pub fn button_names() -> impl Iterator<Item = &'static str> {
+    BUTTONS.iter().map(|(_, name)| *name).chain(STICK_BUTTONS.iter().map(|(_, name)| *name))
+}
+
+/// 手柄监听线程的名称
+const GAMEPAD_THREAD_NAME: &str = "keymacro-gamepad";
 
 /// 手柄事件类型
 #[derive(Debug, Clone)]
 pub enum GamepadEvent {
     ButtonPressed { button: String },
     ButtonReleased { button: String },
+    /// 手柄连接状态或电量（连接时和每隔 `BATTERY_POLL_INTERVAL` 读取一次），断开时 `level` 为 None
+    BatteryUpdate { controller: u32, level: Option<BatteryLevel> },
 }
 
 /// 启动手柄监听线程
 ///
-/// 返回一个 Receiver，用于接收手柄事件
-pub fn start_gamepad_thread() -> Receiver<GamepadEvent> {
+/// # 参数
+///
+/// * `settings` - 摇杆死区和垂直方向反转设置
+///
+/// # 返回
+///
+/// 一个 Receiver，用于接收手柄事件
+pub fn start_gamepad_thread(settings: GamepadConfig) -> Receiver<GamepadEvent> {
     let (sender, receiver) = mpsc::channel::<GamepadEvent>();
 
-    thread::spawn(move || {
-        log::info!("手柄监听线程启动 (XInput)");
-
-        // 检查 XInput 是否可用
-        let mut found_controller = false;
-        for i in 0..4u32 {
-            let mut state = XINPUT_STATE::default();
-            let result = unsafe { XInputGetState(i, &mut state) };
-            if result == ERROR_SUCCESS.0 {
-                log::info!("检测到手柄 [{}] 已连接", i);
-                found_controller = true;
+    thread::Builder::new()
+        .name(GAMEPAD_THREAD_NAME.to_string())
+        .spawn(move || {
+            log::info!("手柄监听线程启动 (XInput)");
+
+            if !xinput::guide_button_supported() {
+                log::info!("系统不支持 XInputGetStateEx，Guide 键不可用");
             }
-        }
 
-        if !found_controller {
-            log::warn!("未检测到手柄，等待手柄连接...");
-        }
+            // 检查 XInput 是否可用
+            let mut found_controller = false;
+            for i in 0..4u32 {
+                if xinput::get_state(i).is_some() {
+                    log::info!("检测到手柄 [{}] 已连接", i);
+                    found_controller = true;
+                }
+            }
 
-        // 跟踪每个手柄的按钮状态
-        let mut prev_states: [u16; 4] = [0; 4];
-        let mut controller_connected: [bool; 4] = [false; 4];
+            if !found_controller {
+                log::warn!("未检测到手柄，等待手柄连接...");
+            }
 
-        loop {
-            for i in 0..4usize {
-                let mut state = XINPUT_STATE::default();
-                let result = unsafe { XInputGetState(i as u32, &mut state) };
+            // 跟踪每个手柄的按钮状态（低 16 位为实体按钮，其上为摇杆方向）
+            let mut prev_states: [u32; 4] = [0; 4];
+            let mut controller_connected: [bool; 4] = [false; 4];
+            let mut last_battery_poll: Option<Instant> = None;
 
-                if result == ERROR_SUCCESS.0 {
-                    // 手柄已连接
-                    if !controller_connected[i] {
-                        log::info!("手柄 [{}] 已连接", i);
-                        controller_connected[i] = true;
-                    }
+            loop {
+                let poll_battery = last_battery_poll.is_none_or(|t| t.elapsed() >= BATTERY_POLL_INTERVAL);
+                if poll_battery {
+                    last_battery_poll = Some(Instant::now());
+                }
 
-                    let current_buttons = state.Gamepad.wButtons.0;
-                    let changed = current_buttons ^ prev_states[i];
-
-                    if changed != 0 {
-                        // 有按钮状态变化
-                        check_button_changes(
-                            i as u32,
-                            prev_states[i],
-                            current_buttons,
-                            changed,
-                            &sender,
-                        );
-                        prev_states[i] = current_buttons;
-                    }
-                } else {
-                    // 手柄未连接或断开
-                    if controller_connected[i] {
-                        log::info!("手柄 [{}] 已断开", i);
-                        controller_connected[i] = false;
-                        prev_states[i] = 0;
+                for i in 0..4usize {
+                    if let Some(state) = xinput::get_state(i as u32) {
+                        // 手柄已连接
+                        let newly_connected = !controller_connected[i];
+                        if newly_connected {
+                            log::info!("手柄 [{}] 已连接", i);
+                            controller_connected[i] = true;
+                        }
+                        if newly_connected || poll_battery {
+                            send_battery_update(i as u32, Some(battery_level(i as u32)), &sender);
+                        }
+
+                        let current_buttons = gamepad_state_bits(&state.Gamepad, &settings);
+                        let changed = current_buttons ^ prev_states[i];
+
+                        if changed != 0 {
+                            // 有按钮状态变化
+                            check_button_changes(
+                                i as u32,
+                                prev_states[i],
+                                current_buttons,
+                                changed,
+                                settings.log_unknown,
+                                &sender,
+                            );
+                            prev_states[i] = current_buttons;
+                        }
+                    } else {
+                        // 手柄未连接或断开
+                        if controller_connected[i] {
+                            log::info!("手柄 [{}] 已断开", i);
+                            controller_connected[i] = false;
+                            prev_states[i] = 0;
+                            send_battery_update(i as u32, None, &sender);
+                        }
                     }
                 }
-            }
 
-            // 降低 CPU 占用 (约 60Hz 轮询)
-            thread::sleep(Duration::from_millis(16));
-        }
-    });
+                // 降低 CPU 占用 (约 60Hz 轮询)
+                thread::sleep(Duration::from_millis(16));
+            }
+        })
+        .expect("创建手柄监听线程失败");
 
     receiver
 }
 
+/// 读取手柄电量
+fn battery_level(controller: u32) -> BatteryLevel {
+    let Some(info) = crate::winapi::xinput::battery_information(controller) else {
+        return BatteryLevel::Unknown;
+    };
+    match info.BatteryType {
+        BATTERY_TYPE_WIRED => BatteryLevel::Wired,
+        BATTERY_TYPE_DISCONNECTED | BATTERY_TYPE_UNKNOWN => BatteryLevel::Unknown,
+        _ => match info.BatteryLevel {
+            BATTERY_LEVEL_EMPTY => BatteryLevel::Empty,
+            BATTERY_LEVEL_LOW => BatteryLevel::Low,
+            BATTERY_LEVEL_MEDIUM => BatteryLevel::Medium,
+            BATTERY_LEVEL_FULL => BatteryLevel::Full,
+            _ => BatteryLevel::Unknown,
+        },
+    }
+}
+
+/// 发送手柄连接状态和电量
+fn send_battery_update(controller: u32, level: Option<BatteryLevel>, sender: &mpsc::Sender<GamepadEvent>) {
+    log::debug!("手柄 [{}] 电量: {}", controller, level.map_or("未连接", BatteryLevel::label));
+    if let Err(e) = sender.send(GamepadEvent::BatteryUpdate { controller, level }) {
+        log::error!("发送手柄电量事件失败: {}", e);
+    }
+}
+
+/// 合并实体按钮和摇杆方向的状态位
+fn gamepad_state_bits(gamepad: &XINPUT_GAMEPAD, settings: &GamepadConfig) -> u32 {
+    u32::from(gamepad.wButtons.0)
+        | stick_directions(gamepad.sThumbLX, gamepad.sThumbLY, settings.left_deadzone, settings.invert_left_y, LEFT_STICK)
+        | stick_directions(gamepad.sThumbRX, gamepad.sThumbRY, settings.right_deadzone, settings.invert_right_y, RIGHT_STICK)
+}
+
 /// 检查按钮变化并发送事件
+///
+/// `log_unknown` 为 true 时，按钮表中没有的按钮位发生变化时在日志中记录原始位掩码
 fn check_button_changes(
     controller_id: u32,
-    _prev: u16,
-    current: u16,
-    changed: u16,
+    _prev: u32,
+    current: u32,
+    changed: u32,
+    log_unknown: bool,
     sender: &mpsc::Sender<GamepadEvent>,
 ) {
-    // 定义按钮映射
-    let buttons: [(u16, &str); 14] = [
-        (XINPUT_GAMEPAD_DPAD_UP.0, "DUp"),
-        (XINPUT_GAMEPAD_DPAD_DOWN.0, "DDown"),
-        (XINPUT_GAMEPAD_DPAD_LEFT.0, "DLeft"),
-        (XINPUT_GAMEPAD_DPAD_RIGHT.0, "DRight"),
-        (XINPUT_GAMEPAD_START.0, "Start"),
-        (XINPUT_GAMEPAD_BACK.0, "Back"),
-        (XINPUT_GAMEPAD_LEFT_THUMB.0, "LS"),
-        (XINPUT_GAMEPAD_RIGHT_THUMB.0, "RS"),
-        (XINPUT_GAMEPAD_LEFT_SHOULDER.0, "LB"),
-        (XINPUT_GAMEPAD_RIGHT_SHOULDER.0, "RB"),
-        (XINPUT_GAMEPAD_A.0, "A"),
-        (XINPUT_GAMEPAD_B.0, "B"),
-        (XINPUT_GAMEPAD_X.0, "X"),
-        (XINPUT_GAMEPAD_Y.0, "Y"),
-    ];
-
-    for (mask, name) in &buttons {
+    if log_unknown {
+        let unknown = unknown_button_bits(changed);
+        if unknown != 0 {
+            log::info!(
+                "手柄 [{}] 未知按钮位变化: 0x{:04X}（当前 wButtons: 0x{:04X}）",
+                controller_id,
+                unknown,
+                current & 0xFFFF
+            );
+        }
+    }
+
+    let buttons = BUTTONS
+        .iter()
+        .map(|(mask, name)| (u32::from(*mask), *name))
+        .chain(STICK_BUTTONS.iter().copied());
+
+    for (mask, name) in buttons {
         if changed & mask != 0 {
             if current & mask != 0 {
                 // 按钮按下
@@ -136,6 +229,12 @@ fn check_button_changes(
     }
 }
 
+/// 变化的按钮位中不在按钮表里的部分（只看 `wButtons` 所在的低 16 位，摇杆方向位都是已知的）
+fn unknown_button_bits(changed: u32) -> u16 {
+    let known = BUTTONS.iter().fold(0u16, |bits, (mask, _)| bits | mask);
+    (changed & 0xFFFF) as u16 & !known
+}
+
 /// 将 gilrs Button 映射为配置键名（保留此函数以兼容现有代码）
 pub fn button_to_key_name(button: &str) -> String {
     // Xbox 标准按键映射
@@ -160,3 +259,20 @@ pub fn button_to_key_name(button: &str) -> String {
         _ => button.to_string(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_button_bits() {
+        // A 键和 0x0800（按钮表中没有）同时变化，只报告 0x0800
+        let changed = u32::from(XINPUT_GAMEPAD_A.0) | 0x0800;
+        assert_eq!(unknown_button_bits(changed), 0x0800);
+
+        // 按钮表中的按钮和摇杆方向位都不算未知
+        let known = BUTTONS.iter().fold(0u32, |bits, (mask, _)| bits | u32::from(*mask));
+        let sticks = STICK_BUTTONS.iter().fold(0u32, |bits, (mask, _)| bits | mask);
+        assert_eq!(unknown_button_bits(known | sticks), 0);
+    }
+}
@@ -0,0 +1,124 @@
+//! 摇杆方向模块
+//!
+//! 将摇杆偏移转换为上下左右四个虚拟按键，与实体按键一起参与按下/释放检测
+
+/// XInput 文档建议的左摇杆死区（`XINPUT_GAMEPAD_LEFT_THUMB_DEADZONE`）
+pub const DEFAULT_LEFT_DEADZONE: u16 = 7849;
+/// XInput 文档建议的右摇杆死区（`XINPUT_GAMEPAD_RIGHT_THUMB_DEADZONE`）
+pub const DEFAULT_RIGHT_DEADZONE: u16 = 8689;
+/// 死区上限（摇杆单轴的最大偏移）
+pub const MAX_DEADZONE: u16 = 32767;
+
+/// 摇杆方向位，放在 XInput 按钮位（16 位）之上，便于统一比较状态变化
+pub const LS_UP: u32 = 1 << 16;
+pub const LS_DOWN: u32 = 1 << 17;
+pub const LS_LEFT: u32 = 1 << 18;
+pub const LS_RIGHT: u32 = 1 << 19;
+pub const RS_UP: u32 = 1 << 20;
+pub const RS_DOWN: u32 = 1 << 21;
+pub const RS_LEFT: u32 = 1 << 22;
+pub const RS_RIGHT: u32 = 1 << 23;
+
+/// 摇杆方向位与配置键名
+pub static STICK_BUTTONS: &[(u32, &str)] = &[
+    (LS_UP, "LSUp"),
+    (LS_DOWN, "LSDown"),
+    (LS_LEFT, "LSLeft"),
+    (LS_RIGHT, "LSRight"),
+    (RS_UP, "RSUp"),
+    (RS_DOWN, "RSDown"),
+    (RS_LEFT, "RSLeft"),
+    (RS_RIGHT, "RSRight"),
+];
+
+/// 单个摇杆的方向位（上、下、左、右）
+#[derive(Debug, Clone, Copy)]
+pub struct StickBits {
+    pub up: u32,
+    pub down: u32,
+    pub left: u32,
+    pub right: u32,
+}
+
+/// 左摇杆的方向位
+pub const LEFT_STICK: StickBits = StickBits { up: LS_UP, down: LS_DOWN, left: LS_LEFT, right: LS_RIGHT };
+/// 右摇杆的方向位
+pub const RIGHT_STICK: StickBits = StickBits { up: RS_UP, down: RS_DOWN, left: RS_LEFT, right: RS_RIGHT };
+
+/// 将摇杆偏移转换为方向位
+///
+/// # 参数
+///
+/// * `x` - 水平偏移（向右为正）
+/// * `y` - 垂直偏移（向上为正）
+/// * `deadzone` - 死区，单轴偏移的绝对值超过该值才视为按下
+/// * `invert_y` - 是否反转垂直方向
+/// * `bits` - 该摇杆使用的方向位
+///
+/// # 说明
+///
+/// 两个轴分别判断，斜推时会同时按下两个方向
+pub fn stick_directions(x: i16, y: i16, deadzone: u16, invert_y: bool, bits: StickBits) -> u32 {
+    // 负方向比正方向多 1（-32768），先限制到对称范围，使死区上限在两个方向上都能屏蔽输入；
+    // 转为 i32 再取反，避免溢出
+    let max = i32::from(MAX_DEADZONE);
+    let x = i32::from(x).max(-max);
+    let y = i32::from(y).max(-max);
+    let y = if invert_y { -y } else { y };
+    let deadzone = i32::from(deadzone);
+
+    let mut state = 0;
+    if y > deadzone {
+        state |= bits.up;
+    } else if y < -deadzone {
+        state |= bits.down;
+    }
+    if x > deadzone {
+        state |= bits.right;
+    } else if x < -deadzone {
+        state |= bits.left;
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadzone_threshold() {
+        let dz = DEFAULT_LEFT_DEADZONE;
+        let dz_i = dz as i16;
+
+        assert_eq!(stick_directions(0, 0, dz, false, LEFT_STICK), 0);
+        // 恰好等于死区时不算按下
+        assert_eq!(stick_directions(dz_i, -dz_i, dz, false, LEFT_STICK), 0);
+        assert_eq!(stick_directions(dz_i + 1, 0, dz, false, LEFT_STICK), LS_RIGHT);
+        assert_eq!(stick_directions(-dz_i - 1, 0, dz, false, LEFT_STICK), LS_LEFT);
+        assert_eq!(stick_directions(0, i16::MAX, dz, false, LEFT_STICK), LS_UP);
+        assert_eq!(stick_directions(0, i16::MIN, dz, false, LEFT_STICK), LS_DOWN);
+        assert_eq!(stick_directions(i16::MAX, i16::MAX, dz, false, RIGHT_STICK), RS_UP | RS_RIGHT);
+
+        // 死区为最大值时任何偏移都不会触发（-32768 也不例外）
+        assert_eq!(stick_directions(i16::MIN, i16::MIN, MAX_DEADZONE, false, LEFT_STICK), 0);
+        assert_eq!(stick_directions(i16::MAX, i16::MAX, MAX_DEADZONE, false, LEFT_STICK), 0);
+    }
+
+    #[test]
+    fn test_invert_y() {
+        let dz = DEFAULT_RIGHT_DEADZONE;
+        assert_eq!(stick_directions(0, 20000, dz, true, RIGHT_STICK), RS_DOWN);
+        assert_eq!(stick_directions(0, -20000, dz, true, RIGHT_STICK), RS_UP);
+        // -32768 反转时不会溢出
+        assert_eq!(stick_directions(0, i16::MIN, dz, true, RIGHT_STICK), RS_UP);
+        // 反转只影响垂直方向
+        assert_eq!(stick_directions(20000, 0, dz, true, RIGHT_STICK), RS_RIGHT);
+    }
+
+    #[test]
+    fn test_stick_bits_do_not_overlap_buttons() {
+        for (bit, _) in STICK_BUTTONS {
+            assert!(*bit > u32::from(u16::MAX));
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! 按键输入模块
+//!
+//! 供其他程序直接发送按键的简单接口，不受宏开关状态影响；
+//! 通过这里按下的按键同样会被记录，紧急停止时一并释放
+
+use crate::config::{DurationSpec, TypeMethod, TypeTextParams, DEFAULT_PASTE_THRESHOLD};
+use crate::macros::{execute_type_text_with, parse_key_string, KeyInjector, SendInputInjector};
+
+/// 按下按键（不释放）
+///
+/// # 参数
+///
+/// * `key` - 键名，写法与序列步骤中的 `key` 相同（如 "A"、"F5"、"Ctrl"、"0x41"）
+///
+/// # 返回
+///
+/// 键名无效或发送失败时返回错误
+///
+/// # 示例
+///
+/// ```no_run
+/// use rust_keymacro::input;
+///
+/// // 按住 Ctrl 输入 C（复制）
+/// input::press("Ctrl")?;
+/// input::press("C")?;
+/// input::release("C")?;
+/// input::release("Ctrl")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn press(key: &str) -> Result<(), Box<dyn std::error::Error>> {
+    SendInputInjector.key_down(key_code(key)?)
+}
+
+/// 释放按键
+///
+/// # 参数
+///
+/// * `key` - 键名，写法与 `press` 相同
+///
+/// # 返回
+///
+/// 键名无效或发送失败时返回错误
+///
+/// # 示例
+///
+/// ```no_run
+/// use rust_keymacro::input;
+///
+/// input::press("Shift")?;
+/// input::release("Shift")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn release(key: &str) -> Result<(), Box<dyn std::error::Error>> {
+    SendInputInjector.key_up(key_code(key)?)
+}
+
+/// 输入文本
+///
+/// # 参数
+///
+/// * `text` - 要输入的文本
+///
+/// # 返回
+///
+/// 发送失败时返回错误
+///
+/// # 说明
+///
+/// 与未设置 `delay` 的 `type_text` 操作相同，按美式键盘布局逐个输入字符，每个字符之间等待 10 毫秒
+///
+/// # 示例
+///
+/// ```no_run
+/// use rust_keymacro::input;
+///
+/// input::type_text("Hello World\n")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn type_text(text: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let params = TypeTextParams {
+        text: text.to_string(),
+        delay: None,
+        jitter: 0,
+        speed: None,
+        start_delay: DurationSpec::ZERO,
+        normalize_caps_lock: false,
+        method: TypeMethod::Type,
+        paste_threshold: DEFAULT_PASTE_THRESHOLD,
+    };
+    execute_type_text_with(&params, &SendInputInjector)
+}
+
+/// 将键名转换为虚拟键码
+fn key_code(key: &str) -> Result<u16, Box<dyn std::error::Error>> {
+    parse_key_string(key).ok_or_else(|| format!("无效的按键: {}", key).into())
+}
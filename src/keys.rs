@@ -0,0 +1,267 @@
+//! 按键名称模块
+//!
+//! 统一管理具名按键的名称、别名与虚拟键码的对应关系，
+//! 序列按键解析、键盘钩子上报的键名和热键匹配共用同一张表；
+//! 鼠标侧键只能作为触发键，单独使用一张表；
+//! 只由 Shift / Ctrl / Alt 组成的触发键（如 `LShift+RShift`）按修饰键组合匹配，见 `ModifierChord`
+
+use windows::Win32::UI::Input::KeyboardAndMouse::*;
+
+/// 具名按键的规范名称与虚拟键码（键盘钩子上报的就是规范名称）
+static NAMED_KEYS: &[(&str, VIRTUAL_KEY)] = &[
+    ("Space", VK_SPACE),
+    ("Enter", VK_RETURN),
+    ("Tab", VK_TAB),
+    ("Backspace", VK_BACK),
+    ("Escape", VK_ESCAPE),
+    ("Shift", VK_SHIFT),
+    ("Ctrl", VK_CONTROL),
+    ("Alt", VK_MENU),
+    ("LShift", VK_LSHIFT),
+    ("RShift", VK_RSHIFT),
+    ("LCtrl", VK_LCONTROL),
+    ("RCtrl", VK_RCONTROL),
+    ("LAlt", VK_LMENU),
+    ("RAlt", VK_RMENU),
+    ("LWin", VK_LWIN),
+    ("RWin", VK_RWIN),
+    ("Delete", VK_DELETE),
+    ("Insert", VK_INSERT),
+    ("Home", VK_HOME),
+    ("End", VK_END),
+    ("PageUp", VK_PRIOR),
+    ("PageDown", VK_NEXT),
+    ("`", VK_OEM_3),
+    ("'", VK_OEM_7),
+];
+
+/// 按键别名与对应的规范名称
+static KEY_ALIASES: &[(&str, &str)] = &[
+    ("Return", "Enter"),
+    ("Esc", "Escape"),
+    ("Control", "Ctrl"),
+    // 单独的 Win 指左 Win 键，按下时与按物理左 Win 键效果相同
+    ("Win", "LWin"),
+    ("Super", "LWin"),
+    ("Meta", "LWin"),
+    ("Del", "Delete"),
+    ("Ins", "Insert"),
+    ("PgUp", "PageUp"),
+    ("PgDn", "PageDown"),
+];
+
+/// 鼠标侧键名称与侧键编号（`XBUTTON1` = 1，`XBUTTON2` = 2）
+static MOUSE_BUTTONS: &[(&str, u16)] = &[
+    ("MouseX1", 1),
+    ("MouseX2", 2),
+];
+
+/// 获取具名按键的规范名称（不区分大小写，支持别名）
+///
+/// # 返回
+///
+/// 不是具名按键（如字母、F 键、手柄按键）时返回 None
+pub fn canonical_name(name: &str) -> Option<&'static str> {
+    let name = KEY_ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+        .map_or(name, |(_, canonical)| *canonical);
+
+    NAMED_KEYS
+        .iter()
+        .find(|(canonical, _)| canonical.eq_ignore_ascii_case(name))
+        .map(|(canonical, _)| *canonical)
+}
+
+/// 将键名转换为规范名称，非具名按键原样返回
+pub fn canonicalize(name: &str) -> &str {
+    canonical_name(name).unwrap_or(name)
+}
+
+/// 获取具名按键（含别名）的虚拟键码
+pub fn named_key_vk(name: &str) -> Option<u16> {
+    let canonical = canonical_name(name)?;
+    NAMED_KEYS
+        .iter()
+        .find(|(n, _)| *n == canonical)
+        .map(|(_, vk)| vk.0)
+}
+
+/// 获取虚拟键码对应的具名按键规范名称
+pub fn named_key_name(vk: u16) -> Option<&'static str> {
+    NAMED_KEYS
+        .iter()
+        .find(|(_, key)| key.0 == vk)
+        .map(|(name, _)| *name)
+}
+
+/// 区分左右的修饰键（键盘钩子上报的是这些键码），顺序即 `modifier_bit` 中的位序
+static SIDED_MODIFIERS: [(&str, VIRTUAL_KEY); 6] = [
+    ("LShift", VK_LSHIFT),
+    ("RShift", VK_RSHIFT),
+    ("LCtrl", VK_LCONTROL),
+    ("RCtrl", VK_RCONTROL),
+    ("LAlt", VK_LMENU),
+    ("RAlt", VK_RMENU),
+];
+
+/// 不区分左右的修饰键名称，以及左侧键在 `modifier_bit` 中的位（右侧键为下一位）
+static EITHER_SIDE_MODIFIERS: [(&str, u8); 3] = [("Shift", 1 << 0), ("Ctrl", 1 << 2), ("Alt", 1 << 4)];
+
+/// 区分左右的修饰键在修饰键状态中对应的位，不是 Shift / Ctrl / Alt 时返回 None
+pub fn modifier_bit(vk: u16) -> Option<u8> {
+    SIDED_MODIFIERS.iter().position(|(_, key)| key.0 == vk).map(|i| 1 << i)
+}
+
+/// 读取实际按住的修饰键
+///
+/// # 参数
+///
+/// * `is_held` - 判断虚拟键码对应的按键是否按住（如 `winapi::keyboard::is_key_held`）
+///
+/// # 返回
+///
+/// 按住的修饰键（`modifier_bit` 的组合）
+pub fn held_modifiers(is_held: impl Fn(u16) -> bool) -> u8 {
+    SIDED_MODIFIERS
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, key))| is_held(key.0))
+        .fold(0, |bits, (i, _)| bits | 1 << i)
+}
+
+/// 只由修饰键组成的触发键，如 `LShift+RShift`、`Alt`
+///
+/// 按下的修饰键与组合完全一致（没有多余的修饰键）时匹配；`Shift`、`Ctrl`、`Alt` 匹配任意一侧，
+/// 同一组写两次（如 `Shift+Shift`）表示两侧同时按下
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModifierChord {
+    /// 所有可以匹配的修饰键状态（`modifier_bit` 的组合）
+    states: Vec<u8>,
+}
+
+impl ModifierChord {
+    /// 解析以 `+` 连接的修饰键名称（不区分大小写）
+    ///
+    /// # 返回
+    ///
+    /// 含有修饰键以外的按键（或同一个键写了两次）时返回 None
+    pub fn parse(name: &str) -> Option<Self> {
+        let mut states = vec![0u8];
+        for term in name.split('+') {
+            let term = canonical_name(term.trim())?;
+            let sides: Vec<u8> = if let Some(&(_, left)) = EITHER_SIDE_MODIFIERS.iter().find(|(n, _)| *n == term) {
+                vec![left, left << 1]
+            } else {
+                let vk = named_key_vk(term)?;
+                vec![modifier_bit(vk)?]
+            };
+            // 每一项占用一个尚未使用的键
+            states = states
+                .iter()
+                .flat_map(|&state| sides.iter().filter(move |&&bit| state & bit == 0).map(move |&bit| state | bit))
+                .collect();
+            states.sort_unstable();
+            states.dedup();
+        }
+        (!states.is_empty()).then_some(Self { states })
+    }
+
+    /// 按下的修饰键是否与组合完全一致
+    ///
+    /// # 参数
+    ///
+    /// * `active` - 当前按下的修饰键（`modifier_bit` 的组合）
+    pub fn matches(&self, active: u8) -> bool {
+        self.states.contains(&active)
+    }
+}
+
+/// 获取鼠标侧键编号对应的触发键名称
+pub fn mouse_button_name(button: u16) -> Option<&'static str> {
+    MOUSE_BUTTONS
+        .iter()
+        .find(|(_, b)| *b == button)
+        .map(|(name, _)| *name)
+}
+
+/// 是否为鼠标侧键名称（不区分大小写）
+pub fn is_mouse_button(name: &str) -> bool {
+    MOUSE_BUTTONS.iter().any(|(n, _)| n.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aliases_resolve_to_canonical_vk() {
+        for (alias, canonical) in KEY_ALIASES {
+            let vk = named_key_vk(canonical).expect("规范名称必须在按键表中");
+            assert_eq!(named_key_vk(alias), Some(vk), "{}", alias);
+            assert_eq!(named_key_vk(&alias.to_uppercase()), Some(vk), "{}", alias);
+            assert_eq!(named_key_name(vk), Some(*canonical), "{}", alias);
+        }
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        assert_eq!(canonicalize("return"), "Enter");
+        assert_eq!(canonicalize("ESC"), "Escape");
+        assert_eq!(canonicalize("Meta"), "LWin");
+        assert_eq!(canonicalize("win"), "LWin");
+        assert_eq!(canonicalize("rwin"), "RWin");
+        assert_eq!(canonicalize("pgup"), "PageUp");
+        assert_eq!(canonicalize("Enter"), "Enter");
+        assert_eq!(canonicalize("F1"), "F1");
+        assert_eq!(canonicalize("GP:A"), "GP:A");
+        assert_eq!(canonical_name("A"), None);
+    }
+
+    #[test]
+    fn test_windows_keys() {
+        assert_eq!(named_key_vk("LWin"), Some(0x5B));
+        assert_eq!(named_key_vk("RWin"), Some(0x5C));
+        assert_eq!(named_key_vk("Win"), Some(0x5B));
+        assert_eq!(named_key_name(0x5B), Some("LWin"));
+        assert_eq!(named_key_name(0x5C), Some("RWin"));
+    }
+
+    #[test]
+    fn test_modifier_chords() {
+        let (lshift, rshift, lctrl, lalt, ralt) = (1 << 0, 1 << 1, 1 << 2, 1 << 4, 1 << 5);
+        assert_eq!(modifier_bit(0xA0), Some(lshift));
+        assert_eq!(modifier_bit(0xA5), Some(ralt));
+        assert_eq!(modifier_bit(0x10), None);
+        assert_eq!(named_key_name(0xA0), Some("LShift"));
+        assert_eq!(held_modifiers(|vk| vk == 0xA0 || vk == 0xA4), lshift | lalt);
+        assert_eq!(held_modifiers(|_| false), 0);
+
+        let both_shifts = ModifierChord::parse("LShift+RShift").unwrap();
+        assert!(both_shifts.matches(lshift | rshift));
+        assert!(!both_shifts.matches(lshift));
+        assert!(!both_shifts.matches(lshift | rshift | lctrl));
+        assert_eq!(ModifierChord::parse("shift + shift"), Some(both_shifts));
+
+        // 不区分左右时任意一侧都可以，但不能有多余的修饰键
+        let alt = ModifierChord::parse("Alt").unwrap();
+        assert!(alt.matches(lalt) && alt.matches(ralt));
+        assert!(!alt.matches(lalt | ralt) && !alt.matches(lalt | lctrl) && !alt.matches(0));
+        assert!(ModifierChord::parse("Control+LAlt").unwrap().matches(lctrl | lalt));
+
+        assert_eq!(ModifierChord::parse("Ctrl+A"), None);
+        assert_eq!(ModifierChord::parse("LWin"), None);
+        assert_eq!(ModifierChord::parse("LShift+LShift"), None);
+        assert_eq!(ModifierChord::parse("` 1"), None);
+    }
+
+    #[test]
+    fn test_mouse_buttons() {
+        assert_eq!(mouse_button_name(1), Some("MouseX1"));
+        assert_eq!(mouse_button_name(2), Some("MouseX2"));
+        assert_eq!(mouse_button_name(3), None);
+        assert!(is_mouse_button("mousex2"));
+        assert!(!is_mouse_button("X1"));
+        assert_eq!(named_key_vk("MouseX1"), None);
+    }
+}
@@ -1,12 +1,34 @@
 //! 键盘宏系统库
 //!
 //! 提供基于配置文件的全局热键管理和键盘宏执行功能。
+//!
+//! 作为库使用时，常用的入口直接从根模块导出：
+//!
+//! - [`Config`] - 从 YAML 加载的热键配置
+//! - [`MacroSystem`] - 按配置启动键盘宏系统，句柄释放时卸载钩子
+//! - [`input`] - 不经过热键直接发送按键和文本
+//!
+//! ```no_run
+//! use rust_keymacro::{input, Config, MacroSystem};
+//!
+//! let _system = MacroSystem::start(Config::from_file("config.yaml")?)?;
+//! input::type_text("ready")?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
 
 pub mod app;
 pub mod bootstrap;
 pub mod config;
+pub mod datetime;
+pub mod input;
+pub mod keys;
 pub mod logger;
 pub mod macros;
 pub mod overlay;
+pub mod random;
+pub mod state;
 pub mod gamepad;
 pub mod winapi;
+
+pub use config::Config;
+pub use macros::MacroSystem;
@@ -0,0 +1,231 @@
+//! 组合序列模块
+//!
+//! 匹配以空格分隔的多键触发序列（如 "` 1"：先按 `，再按 1）。
+//! 按下序列的前缀键后进入等待状态，在超时前按下后续按键即触发对应的热键
+
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use crate::config::chord_keys;
+
+/// 组合序列对单个按键事件的处理结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChordAction {
+    /// 与组合序列无关，按普通按键处理
+    Continue,
+    /// 拦截按键（前缀键、前缀键的松开事件或自动重复）
+    Block,
+    /// 拦截按键并触发组合序列对应的热键（值为配置中的触发键名称）
+    Dispatch(String),
+    /// 拦截按键并结束组合序列对应的热键（序列最后一个键松开）
+    Release(String),
+    /// 序列不匹配：拦截当前按键，按原顺序重新发送之前拦截的前缀键和当前按键
+    Replay(Vec<String>),
+}
+
+/// 组合序列的匹配状态
+#[derive(Debug, Default)]
+struct ChordState {
+    /// 已按下、等待后续按键的前缀键
+    pending: Vec<String>,
+    /// 最近一次按下前缀键的时间
+    last_press: Option<Instant>,
+    /// 按下事件已被拦截、松开事件也需要拦截的按键，以及松开时要结束的热键
+    swallowed: Vec<(String, Option<String>)>,
+}
+
+/// 组合序列匹配器（键盘钩子线程使用，内部加锁）
+#[derive(Debug, Default)]
+pub struct ChordTracker {
+    state: Mutex<ChordState>,
+}
+
+impl ChordTracker {
+    /// 创建处于空闲状态的匹配器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 处理按键按下事件
+    ///
+    /// # 参数
+    ///
+    /// * `chords` - 配置中的组合序列触发键（见 `Config::chords`）
+    /// * `key` - 按下的键名
+    /// * `is_repeat` - 是否为长按产生的自动重复事件
+    /// * `now` - 事件发生的时间
+    /// * `timeout` - 两次按键之间的最长间隔，超过后丢弃已按下的前缀键
+    /// * `replay` - 序列不匹配时是否重新发送已拦截的按键
+    ///
+    /// # 说明
+    ///
+    /// 不重新发送时，不匹配的按键会作为新序列的开始或普通按键再处理一次
+    pub fn key_down(
+        &self,
+        chords: &[String],
+        key: &str,
+        is_repeat: bool,
+        now: Instant,
+        timeout: Duration,
+        replay: bool,
+    ) -> ChordAction {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        // 按住已拦截的键时产生的自动重复同样拦截
+        if is_repeat && state.swallowed.iter().any(|(k, _)| same_key(k, key)) {
+            return ChordAction::Block;
+        }
+
+        if state.last_press.is_some_and(|t| now.duration_since(t) > timeout) {
+            log::debug!("组合序列超时: {}", state.pending.join(" "));
+            state.pending.clear();
+            state.last_press = None;
+        }
+
+        let mut candidate = state.pending.clone();
+        candidate.push(key.to_string());
+
+        if let Some(chord) = chords.iter().find(|c| chord_matches(c, &candidate, true)) {
+            state.pending.clear();
+            state.last_press = None;
+            state.swallowed.push((key.to_string(), Some(chord.clone())));
+            return ChordAction::Dispatch(chord.clone());
+        }
+
+        if chords.iter().any(|c| chord_matches(c, &candidate, false)) {
+            state.pending = candidate;
+            state.last_press = Some(now);
+            state.swallowed.push((key.to_string(), None));
+            return ChordAction::Block;
+        }
+
+        if state.pending.is_empty() {
+            return ChordAction::Continue;
+        }
+
+        let mut cancelled = std::mem::take(&mut state.pending);
+        state.last_press = None;
+        log::debug!("组合序列不匹配: {} {}", cancelled.join(" "), key);
+        if replay {
+            cancelled.push(key.to_string());
+            return ChordAction::Replay(cancelled);
+        }
+
+        drop(state);
+        self.key_down(chords, key, is_repeat, now, timeout, replay)
+    }
+
+    /// 处理按键松开事件
+    ///
+    /// # 返回
+    ///
+    /// 按下事件被拦截过的按键返回 `Block` 或 `Release`，其他按键返回 `Continue`
+    pub fn key_up(&self, key: &str) -> ChordAction {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(index) = state.swallowed.iter().position(|(k, _)| same_key(k, key)) else {
+            return ChordAction::Continue;
+        };
+        match state.swallowed.remove(index).1 {
+            Some(chord) => ChordAction::Release(chord),
+            None => ChordAction::Block,
+        }
+    }
+}
+
+/// 检查已按下的按键是否与组合序列相同（`exact`）或是其真前缀
+fn chord_matches(chord: &str, pressed: &[String], exact: bool) -> bool {
+    let Some(keys) = chord_keys(chord) else {
+        return false;
+    };
+    let length_ok = if exact { keys.len() == pressed.len() } else { keys.len() > pressed.len() };
+    length_ok && keys.iter().zip(pressed).all(|(k, p)| same_key(k, p))
+}
+
+/// 两个键名是否为同一按键（具名按键按规范名称比较，不区分大小写）
+fn same_key(a: &str, b: &str) -> bool {
+    crate::keys::canonicalize(a).eq_ignore_ascii_case(crate::keys::canonicalize(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(1000);
+
+    fn chords() -> Vec<String> {
+        vec!["` 1".to_string(), "` 2".to_string(), "Esc x y".to_string()]
+    }
+
+    #[test]
+    fn test_two_key_chord() {
+        let tracker = ChordTracker::new();
+        let t0 = Instant::now();
+
+        assert_eq!(tracker.key_down(&chords(), "`", false, t0, TIMEOUT, false), ChordAction::Block);
+        // 按住前缀键时的自动重复和松开都被拦截
+        assert_eq!(tracker.key_down(&chords(), "`", true, t0, TIMEOUT, false), ChordAction::Block);
+        assert_eq!(tracker.key_up("`"), ChordAction::Block);
+
+        let t1 = t0 + Duration::from_millis(300);
+        assert_eq!(tracker.key_down(&chords(), "1", false, t1, TIMEOUT, false), ChordAction::Dispatch("` 1".to_string()));
+        assert_eq!(tracker.key_down(&chords(), "1", true, t1, TIMEOUT, false), ChordAction::Block);
+        assert_eq!(tracker.key_up("1"), ChordAction::Release("` 1".to_string()));
+
+        // 序列完成后回到空闲状态，单独按 1 照常输入
+        assert_eq!(tracker.key_down(&chords(), "1", false, t1, TIMEOUT, false), ChordAction::Continue);
+        assert_eq!(tracker.key_up("1"), ChordAction::Continue);
+    }
+
+    #[test]
+    fn test_longer_chord_uses_canonical_names() {
+        let tracker = ChordTracker::new();
+        let t0 = Instant::now();
+        assert_eq!(tracker.key_down(&chords(), "Escape", false, t0, TIMEOUT, false), ChordAction::Block);
+        assert_eq!(tracker.key_down(&chords(), "X", false, t0, TIMEOUT, false), ChordAction::Block);
+        assert_eq!(tracker.key_down(&chords(), "Y", false, t0, TIMEOUT, false), ChordAction::Dispatch("Esc x y".to_string()));
+    }
+
+    #[test]
+    fn test_timeout_resets_pending_keys() {
+        let tracker = ChordTracker::new();
+        let t0 = Instant::now();
+        assert_eq!(tracker.key_down(&chords(), "`", false, t0, TIMEOUT, false), ChordAction::Block);
+        assert_eq!(tracker.key_up("`"), ChordAction::Block);
+
+        // 超时后按 1 不再完成序列，作为普通按键处理
+        let late = t0 + TIMEOUT + Duration::from_millis(1);
+        assert_eq!(tracker.key_down(&chords(), "1", false, late, TIMEOUT, false), ChordAction::Continue);
+
+        // 超时后可以重新开始序列
+        assert_eq!(tracker.key_down(&chords(), "`", false, late, TIMEOUT, false), ChordAction::Block);
+        assert_eq!(tracker.key_down(&chords(), "2", false, late, TIMEOUT, false), ChordAction::Dispatch("` 2".to_string()));
+    }
+
+    #[test]
+    fn test_mismatched_second_key_cancels() {
+        let tracker = ChordTracker::new();
+        let t0 = Instant::now();
+        assert_eq!(tracker.key_down(&chords(), "`", false, t0, TIMEOUT, false), ChordAction::Block);
+        assert_eq!(tracker.key_down(&chords(), "3", false, t0, TIMEOUT, false), ChordAction::Continue);
+        // 取消后不再处于等待状态
+        assert_eq!(tracker.key_down(&chords(), "1", false, t0, TIMEOUT, false), ChordAction::Continue);
+
+        // 不匹配的按键本身是前缀键时开始新的序列
+        assert_eq!(tracker.key_down(&chords(), "Esc", false, t0, TIMEOUT, false), ChordAction::Block);
+        assert_eq!(tracker.key_down(&chords(), "`", false, t0, TIMEOUT, false), ChordAction::Block);
+        assert_eq!(tracker.key_down(&chords(), "1", false, t0, TIMEOUT, false), ChordAction::Dispatch("` 1".to_string()));
+    }
+
+    #[test]
+    fn test_mismatched_second_key_replays() {
+        let tracker = ChordTracker::new();
+        let t0 = Instant::now();
+        assert_eq!(tracker.key_down(&chords(), "`", false, t0, TIMEOUT, true), ChordAction::Block);
+        assert_eq!(
+            tracker.key_down(&chords(), "3", false, t0, TIMEOUT, true),
+            ChordAction::Replay(vec!["`".to_string(), "3".to_string()])
+        );
+        // 重新发送的按键由系统重新产生松开事件，当前按键的松开照常放行
+        assert_eq!(tracker.key_up("`"), ChordAction::Block);
+        assert_eq!(tracker.key_up("3"), ChordAction::Continue);
+    }
+}
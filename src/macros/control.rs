@@ -0,0 +1,156 @@
+//! 宏运行控制模块
+//!
+//! 提供正在执行的序列的暂停、继续和中止，序列在每个步骤开始前检查运行状态
+
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// 运行状态
+#[derive(Debug, Default)]
+struct RunState {
+    paused: bool,
+    aborted: bool,
+}
+
+/// 序列运行控制
+///
+/// 暂停时执行线程阻塞在条件变量上等待，不会忙等
+#[derive(Debug, Default)]
+pub struct RunControl {
+    state: Mutex<RunState>,
+    changed: Condvar,
+}
+
+impl RunControl {
+    /// 创建运行控制（未暂停、未中止）
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 开始新的执行，清除上一次遗留的暂停和中止状态
+    pub fn begin(&self) {
+        let mut state = self.lock();
+        state.paused = false;
+        state.aborted = false;
+    }
+
+    /// 切换暂停状态
+    ///
+    /// # 返回
+    ///
+    /// 切换后是否处于暂停状态
+    pub fn toggle_pause(&self) -> bool {
+        let mut state = self.lock();
+        state.paused = !state.paused;
+        self.changed.notify_all();
+        state.paused
+    }
+
+    /// 是否处于暂停状态
+    pub fn is_paused(&self) -> bool {
+        self.lock().paused
+    }
+
+    /// 中止正在执行的序列（同时解除暂停，使等待中的序列立即退出）
+    pub fn abort(&self) {
+        let mut state = self.lock();
+        state.aborted = true;
+        state.paused = false;
+        self.changed.notify_all();
+    }
+
+    /// 步骤之间的检查点
+    ///
+    /// # 返回
+    ///
+    /// 暂停时阻塞直到继续或中止；已中止时返回错误，由调用方结束序列
+    pub fn checkpoint(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let state = self
+            .changed
+            .wait_while(self.lock(), |s| s.paused && !s.aborted)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if state.aborted {
+            return Err("序列已中止".into());
+        }
+        Ok(())
+    }
+
+    /// 可被中止的等待
+    ///
+    /// # 返回
+    ///
+    /// 在条件变量上等待 `duration`，期间中止时立即返回错误；暂停不影响等待，由之后的检查点处理
+    pub fn sleep(&self, duration: Duration) -> Result<(), Box<dyn std::error::Error>> {
+        let (state, _) = self
+            .changed
+            .wait_timeout_while(self.lock(), duration, |s| !s.aborted)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if state.aborted {
+            return Err("序列已中止".into());
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RunState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Instant;
+
+    #[test]
+    fn test_checkpoint_blocks_until_resumed() {
+        let control = RunControl::new();
+        assert!(control.toggle_pause());
+
+        thread::scope(|s| {
+            let handle = s.spawn(|| control.checkpoint().is_ok());
+            thread::sleep(Duration::from_millis(30));
+            assert!(!handle.is_finished());
+
+            assert!(!control.toggle_pause());
+            assert!(handle.join().unwrap());
+        });
+    }
+
+    #[test]
+    fn test_abort_wakes_paused_checkpoint() {
+        let control = RunControl::new();
+        control.toggle_pause();
+
+        thread::scope(|s| {
+            let handle = s.spawn(|| control.checkpoint().is_ok());
+            thread::sleep(Duration::from_millis(30));
+            control.abort();
+            assert!(!handle.join().unwrap());
+        });
+
+        // 新的执行不受上一次中止的影响
+        control.begin();
+        assert!(control.checkpoint().is_ok());
+    }
+
+    #[test]
+    fn test_abort_wakes_sleep() {
+        let control = RunControl::new();
+        assert!(control.sleep(Duration::from_millis(1)).is_ok());
+
+        let started = Instant::now();
+        thread::scope(|s| {
+            let handle = s.spawn(|| control.sleep(Duration::from_secs(30)).is_ok());
+            thread::sleep(Duration::from_millis(30));
+            control.abort();
+            assert!(!handle.join().unwrap());
+        });
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        // 已中止时不再等待
+        assert!(control.sleep(Duration::from_secs(30)).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+//! 热键冷却模块
+//!
+//! 记录每个热键上一次执行的时间，冷却期间再次按下触发键时不执行宏；
+//! 开关热键的防抖判断也在这里
+
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// 各热键上一次执行的时间
+#[derive(Debug, Default)]
+pub struct Cooldowns {
+    last_fire: Mutex<HashMap<String, Instant>>,
+}
+
+impl Cooldowns {
+    /// 创建空记录
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 热键是否已冷却完毕，可以再次执行
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 热键名称（按键组以 "|" 连接，整组共用冷却时间）
+    /// * `cooldown` - 冷却时间，为 0 时总是可以执行
+    /// * `now` - 当前时间
+    pub fn is_ready(&self, name: &str, cooldown: Duration, now: Instant) -> bool {
+        if cooldown.is_zero() {
+            return true;
+        }
+        let last_fire = self.last_fire.lock().unwrap_or_else(PoisonError::into_inner);
+        last_fire
+            .get(name)
+            .is_none_or(|last| now.saturating_duration_since(*last) >= cooldown)
+    }
+
+    /// 记录一次执行
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 热键名称
+    /// * `now` - 执行时间
+    pub fn record(&self, name: &str, now: Instant) {
+        let mut last_fire = self.last_fire.lock().unwrap_or_else(PoisonError::into_inner);
+        last_fire.insert(name.to_string(), now);
+    }
+}
+
+/// 开关热键的一次按下是否应当生效（防抖）
+///
+/// # 参数
+///
+/// * `last` - 上一次生效的时间，还没有生效过时为 None
+/// * `now` - 本次按下的时间
+/// * `window` - 防抖时间，为 0 时总是生效
+///
+/// # 返回
+///
+/// 距上一次生效不足 `window` 时返回 false（视为按键抖动产生的重复按下）
+pub fn debounce_passed(last: Option<Instant>, now: Instant, window: Duration) -> bool {
+    last.is_none_or(|last| now.saturating_duration_since(last) >= window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOLDOWN: Duration = Duration::from_millis(500);
+
+    /// 模拟一次按下：冷却完毕时执行并记录，返回是否执行
+    fn fire(cooldowns: &Cooldowns, name: &str, now: Instant) -> bool {
+        if !cooldowns.is_ready(name, COOLDOWN, now) {
+            return false;
+        }
+        cooldowns.record(name, now);
+        true
+    }
+
+    #[test]
+    fn test_second_fire_within_cooldown_is_ignored() {
+        let cooldowns = Cooldowns::new();
+        let start = Instant::now();
+
+        let executions = [start, start + Duration::from_millis(100)]
+            .into_iter()
+            .filter(|&now| fire(&cooldowns, "F1", now))
+            .count();
+        assert_eq!(executions, 1);
+
+        // 冷却结束后可以再次执行，其他热键不受影响
+        assert!(fire(&cooldowns, "F1", start + COOLDOWN));
+        assert!(fire(&cooldowns, "F2", start + Duration::from_millis(600)));
+    }
+
+    #[test]
+    fn test_toggle_debounce() {
+        let window = Duration::from_millis(200);
+        let start = Instant::now();
+
+        assert!(debounce_passed(None, start, window));
+        assert!(!debounce_passed(Some(start), start + Duration::from_millis(30), window));
+        assert!(debounce_passed(Some(start), start + window, window));
+        // 为 0 时不防抖
+        assert!(debounce_passed(Some(start), start, Duration::ZERO));
+    }
+
+    #[test]
+    fn test_zero_cooldown_never_blocks() {
+        let cooldowns = Cooldowns::new();
+        let now = Instant::now();
+        cooldowns.record("F1", now);
+        assert!(cooldowns.is_ready("F1", Duration::ZERO, now));
+    }
+}
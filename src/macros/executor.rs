@@ -2,126 +2,725 @@
 //!
 //! 负责执行各种宏操作，包括输入文本和按键序列
 
-use rand::Rng;
-use std::thread;
-use std::time::Duration;
-use crate::config::{TypeTextParams, SequenceParams, Step, KeyAction};
-use crate::winapi::keyboard;
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+use crate::config::{ActionParams, ChatParams, Config, DurationSpec, TypeFileParams, TypeTextParams, SequenceParams, Step, KeyAction, Rgb, TapHoldParams, DelayConfig};
+use crate::datetime::LocalDateTime;
+use crate::macros::control::RunControl;
+use crate::macros::ime::{ImeGuard, SystemIme};
+use crate::macros::trigger::HeldTriggers;
+use crate::macros::vars::Variables;
+use crate::macros::injector::{AltNumpadInjector, CharKey, DryRunInjector, KeyInjector, KeyStroke, ScaledInjector, SendInputInjector};
+use crate::winapi::timer::{TimerResolution, MACRO_TIMER_PERIOD_MS};
 
-/// 执行输入文本操作
+/// `wait_pixel` 步骤读取屏幕像素的间隔
+const PIXEL_POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// `wait_release` 步骤等待期间检查暂停/中止的间隔
+const RELEASE_POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// `tap_hold` 按住期间检查暂停/中止的间隔（每段等待的最长时长）
+const HOLD_CHECK_INTERVAL: Duration = Duration::from_millis(20);
+/// 未设置 `delay` 时每个字符的延迟
+const DEFAULT_CHAR_DELAY: Duration = Duration::from_millis(10);
+
+/// 获取当前使用的注入器（演练模式下只记录日志，不发送真实输入）
+fn current_injector() -> &'static dyn KeyInjector {
+    if crate::macros::is_dry_run() {
+        &DryRunInjector
+    } else {
+        &SendInputInjector
+    }
+}
+
+/// 开启 `ime_safe` 时在输入期间关闭前台窗口的输入法（演练模式下不操作输入法）
+fn suspend_ime() -> Option<ImeGuard<'static>> {
+    (crate::macros::ime_safe() && !crate::macros::is_dry_run()).then(|| ImeGuard::suspend(&SystemIme))
+}
+
+/// 执行输入文本操作（文本前后加上全局前缀和后缀，等待时间按全局延迟倍率缩放，按配置启用 Alt+小键盘输入和关闭输入法，执行期间提高系统定时器精度）
 pub fn execute_type_text(params: &TypeTextParams) -> Result<(), Box<dyn std::error::Error>> {
-    // 输入每个字符
+    let _timer = TimerResolution::raise(MACRO_TIMER_PERIOD_MS);
+    let _ime = suspend_ime();
+    let scaled = ScaledInjector::new(current_injector(), crate::macros::delay_scale_permille());
+    let injector = AltNumpadInjector::new(&scaled, crate::macros::alt_numpad_fallback());
+    let (prefix, suffix) = crate::macros::type_text_affixes();
+    execute_type_text_with(&params.with_affixes(&prefix, &suffix), &injector)
+}
+
+/// 使用指定的注入器执行输入文本操作
+///
+/// 延迟为 0 且无抖动时，连续的按键会合并为一次批量发送
+pub fn execute_type_text_with(params: &TypeTextParams, injector: &dyn KeyInjector) -> Result<(), Box<dyn std::error::Error>> {
+    // 输入文本不受暂停/中止热键控制
+    start_delay(params.start_delay, injector, &RunControl::new())?;
+
+    if params.uses_paste() {
+        paste_text(&params.text, injector)?;
+        log::info!("序列执行完成");
+        return Ok(());
+    }
+
+    // Caps Lock 开启时字母会以大写输入，需要时先临时关闭，输入结束后（包括出错时）恢复
+    let restore_caps_lock = params.normalize_caps_lock && injector.caps_lock_on();
+    if restore_caps_lock {
+        log::debug!("临时关闭 Caps Lock");
+        toggle_caps_lock(injector)?;
+    }
+
+    let result = type_text_chars(params, injector);
+
+    if restore_caps_lock {
+        if let Err(e) = toggle_caps_lock(injector) {
+            log::warn!("恢复 Caps Lock 状态失败: {}", e);
+        }
+    }
+    result?;
+
+    log::info!("序列执行完成");
+    Ok(())
+}
+
+/// 执行输入文件内容操作（与 `execute_type_text` 相同的注入器设置）
+pub fn execute_type_file(params: &TypeFileParams) -> Result<(), Box<dyn std::error::Error>> {
+    let text = read_text_file(&params.path)?;
+    execute_type_text(&params.to_type_text(text))
+}
+
+/// 使用指定的注入器执行输入文件内容操作
+pub fn execute_type_file_with(params: &TypeFileParams, injector: &dyn KeyInjector) -> Result<(), Box<dyn std::error::Error>> {
+    let text = read_text_file(&params.path)?;
+    execute_type_text_with(&params.to_type_text(text), injector)
+}
+
+/// 执行按住按键操作（受全局暂停/中止热键控制，按住时长按全局延迟倍率缩放，执行期间提高系统定时器精度）
+pub fn execute_tap_hold(params: &TapHoldParams) -> Result<(), Box<dyn std::error::Error>> {
+    let _timer = TimerResolution::raise(MACRO_TIMER_PERIOD_MS);
+    let control = crate::macros::run_control();
+    control.begin();
+    let scaled = ScaledInjector::new(current_injector(), crate::macros::delay_scale_permille());
+    execute_tap_hold_with(params, &scaled, control)
+}
+
+/// 使用指定的注入器执行按住按键操作
+///
+/// 按下后等待 `hold_ms` 毫秒再释放；设置了 `repeat_interval_ms` 时按住期间按该间隔重新发送按下事件。
+/// 等待分为不超过 `HOLD_CHECK_INTERVAL` 的多段，每段之后检查 `control`，按住期间也能暂停或中止；
+/// 中止或出错时按键同样会被释放
+pub fn execute_tap_hold_with(params: &TapHoldParams, injector: &dyn KeyInjector, control: &RunControl) -> Result<(), Box<dyn std::error::Error>> {
+    let vk = parse_key_string(&params.key).ok_or_else(|| format!("无法解析按键: {}", params.key))?;
+    let mut held = HeldKeys::new(injector);
+    held.press(vk)?;
+    let interval = Duration::from_millis(params.repeat_interval_ms);
+    let mut remaining = Duration::from_millis(params.hold_ms);
+    while !remaining.is_zero() {
+        // 按重复间隔逐段等待，未设置间隔时一直等到按住结束
+        let segment = if interval.is_zero() { remaining } else { remaining.min(interval) };
+        sleep_checked(segment, injector, control)?;
+        remaining -= segment;
+        if !remaining.is_zero() {
+            // 紧急停止先中止再释放按键，重新按下前检查运行控制，避免在释放之后再次按下
+            control.checkpoint()?;
+            // 重新发送的按下事件同样带有自身注入标记，钩子不会把它当作触发键，但会照常传给目标程序
+            injector.key_down(vk)?;
+        }
+    }
+    held.release(vk)?;
+    log::info!("已按住 {} {} 毫秒", params.key, params.hold_ms);
+    Ok(())
+}
+
+/// 分段等待，每段不超过 `HOLD_CHECK_INTERVAL`，每段之后检查运行控制（中止时返回错误）
+fn sleep_checked(duration: Duration, injector: &dyn KeyInjector, control: &RunControl) -> Result<(), Box<dyn std::error::Error>> {
+    let mut remaining = duration;
+    while !remaining.is_zero() {
+        let slice = remaining.min(HOLD_CHECK_INTERVAL);
+        injector.sleep(slice);
+        remaining -= slice;
+        control.checkpoint()?;
+    }
+    Ok(())
+}
+
+/// 读取 UTF-8 文本文件，文件不存在或不是有效的 UTF-8 时返回包含路径的错误
+fn read_text_file(path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(path).map_err(|e| format!("读取文件 {} 失败: {}", path, e))?;
+    let text = String::from_utf8(bytes).map_err(|e| format!("文件 {} 不是有效的 UTF-8 文本: {}", path, e))?;
+    log::debug!("已读取文件 {}，共 {} 个字符", path, text.chars().count());
+    Ok(text)
+}
+
+/// 将文本写入剪贴板后按 Ctrl+V 粘贴
+fn paste_text(text: &str, injector: &dyn KeyInjector) -> Result<(), Box<dyn std::error::Error>> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{VK_CONTROL, VK_V};
+
+    log::debug!("以粘贴方式输入 {} 个字符", text.chars().count());
+    injector.set_clipboard_text(text)?;
+    injector.send_keys(&[
+        KeyStroke::Down(VK_CONTROL.0),
+        KeyStroke::Down(VK_V.0),
+        KeyStroke::Up(VK_V.0),
+        KeyStroke::Up(VK_CONTROL.0),
+    ])
+}
+
+/// 输入文本中的每个字符
+fn type_text_chars(params: &TypeTextParams, injector: &dyn KeyInjector) -> Result<(), Box<dyn std::error::Error>> {
+    if params.delay.as_ref().is_some_and(|d| d.is_zero()) && params.jitter == 0 {
+        return type_text_batched(&params.text, injector);
+    }
+
     for ch in params.text.chars() {
         // 获取当前字符的延迟
-        let char_delay_ms = params.delay.as_ref().map_or(10, |d| d.get_delay());
-        let char_delay = Duration::from_millis(char_delay_ms);
-        
-        if let Some(vk) = char_to_vk(ch) {
-            keyboard::simulate_key_press(vk)?;
-            thread::sleep(char_delay);
-            keyboard::simulate_key_release(vk)?;
-            thread::sleep(char_delay);
+        let char_delay = params.delay.as_ref().map_or(DEFAULT_CHAR_DELAY, |d| d.get_delay());
+
+        if let Some(key) = injector.char_key(ch) {
+            let hold = apply_jitter(char_delay, params.jitter);
+            type_char_key(key, Some(hold), injector)?;
+            injector.sleep(apply_jitter(char_delay, params.jitter));
         } else {
             // 尝试发送 Unicode 字符
-            simulate_unicode_char(ch)?;
+            injector.unicode_char(ch)?;
         }
     }
+    Ok(())
+}
+
+/// 输入单个字符按键
+///
+/// 先按下所需的修饰键，再按下并释放字符键（中间等待 `hold`），最后按相反顺序释放修饰键
+fn type_char_key(key: CharKey, hold: Option<Duration>, injector: &dyn KeyInjector) -> Result<(), Box<dyn std::error::Error>> {
+    let modifiers = key.modifiers();
+    for &m in &modifiers {
+        injector.key_down(m)?;
+    }
 
-    log::info!("序列执行完成");
+    injector.key_down(key.vk)?;
+    if let Some(hold) = hold {
+        injector.sleep(hold);
+    }
+    injector.key_up(key.vk)?;
+
+    for &m in modifiers.iter().rev() {
+        injector.key_up(m)?;
+    }
+    Ok(())
+}
+
+/// 按一次 Caps Lock 切换其状态
+fn toggle_caps_lock(injector: &dyn KeyInjector) -> Result<(), Box<dyn std::error::Error>> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::VK_CAPITAL;
+
+    injector.send_keys(&[KeyStroke::Down(VK_CAPITAL.0), KeyStroke::Up(VK_CAPITAL.0)])
+}
+
+/// 无延迟输入文本，将连续可映射的字符合并为一次批量发送
+fn type_text_batched(text: &str, injector: &dyn KeyInjector) -> Result<(), Box<dyn std::error::Error>> {
+    let mut strokes = Vec::with_capacity(text.len() * 2);
+    for ch in text.chars() {
+        if let Some(key) = injector.char_key(ch) {
+            let modifiers = key.modifiers();
+            strokes.extend(modifiers.iter().map(|&m| KeyStroke::Down(m)));
+            strokes.push(KeyStroke::Down(key.vk));
+            strokes.push(KeyStroke::Up(key.vk));
+            strokes.extend(modifiers.iter().rev().map(|&m| KeyStroke::Up(m)));
+        } else {
+            // Unicode 字符单独发送，先发出之前累积的按键以保持顺序
+            injector.send_keys(&strokes)?;
+            strokes.clear();
+            injector.unicode_char(ch)?;
+        }
+    }
+    if !strokes.is_empty() {
+        injector.send_keys(&strokes)?;
+    }
     Ok(())
 }
 
 /// 执行序列操作
-pub fn execute_sequence(params: &SequenceParams) -> Result<(), Box<dyn std::error::Error>> {
+///
+/// # 参数
+///
+/// * `params` - 序列参数
+/// * `key_name` - 触发本次执行的热键名称，`wait_release` 步骤等待该键松开；为 None 时 `wait_release` 不等待
+///
+/// # 说明
+///
+/// - 受全局暂停/中止热键控制
+/// - `text` 步骤加上全局前缀和后缀
+/// - 等待时间按全局延迟倍率缩放
+/// - 按配置启用 Alt+小键盘输入、关闭输入法和报告执行进度
+/// - 执行期间提高系统定时器精度
+/// - 变量步骤读写 `key_name` 对应的变量存储，变量在同一热键的多次执行之间保留，不同热键的变量互不影响
+pub fn execute_sequence(params: &SequenceParams, key_name: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let _timer = TimerResolution::raise(MACRO_TIMER_PERIOD_MS);
+    let _ime = suspend_ime();
+    let control = crate::macros::run_control();
+    control.begin();
+    let scaled = ScaledInjector::new(current_injector(), crate::macros::delay_scale_permille());
+    let injector = AltNumpadInjector::new(&scaled, crate::macros::alt_numpad_fallback());
+    let trigger = key_name.map(|name| Trigger { key_name: name, held: crate::macros::held_triggers() });
+    let (prefix, suffix) = crate::macros::type_text_affixes();
+    let vars = crate::macros::variables(key_name);
+    let progress = crate::macros::progress_sender();
+    let default_key_hold = crate::macros::default_key_hold();
+    execute_triggered_sequence_with(&params.with_text_affixes(&prefix, &suffix), &injector, control, trigger, &vars, progress.as_ref(), default_key_hold)
+}
+
+/// 执行聊天操作（转换为按键序列执行，同样受全局暂停/中止热键控制）
+pub fn execute_chat(params: &ChatParams) -> Result<(), Box<dyn std::error::Error>> {
+    execute_sequence(&params.to_sequence(), None)
+}
+
+/// 单个热键动作的执行结果
+pub type ActionResult = Result<(), Box<dyn std::error::Error>>;
+
+/// 使用指定的注入器逐个执行配置中所有热键的动作，检查配置能否完整执行
+///
+/// # 参数
+///
+/// * `config` - 要检查的配置
+/// * `injector` - 接收按键的注入器（如只记录事件的测试注入器）
+///
+/// # 返回
+///
+/// 按配置顺序排列的热键名称和执行结果
+///
+/// # 说明
+///
+/// 不经过钩子和全局状态，不受宏开关、演练模式和全局延迟倍率影响，可以在没有显示器和键盘的环境（如 CI）中运行；
+/// 只执行顶层 `hotkeys`，配置方案中的热键可以通过 `Config::with_profile` 切换后检查；
+/// 设置了 `tap_action` / `hold_action` 的热键依次执行这两个操作。
+/// 所有等待和 `wait_pixel` 步骤读取的屏幕像素都交给注入器处理。
+/// 与实际执行相同，输入的文本前后加上配置中的 `type_text_prefix` / `type_text_suffix`，未设置 `key_hold` 的序列使用 `default_key_hold_ms`。
+/// 开始前按配置的 `rng_seed` 重新设置当前线程的随机数种子，设置了种子时每次检查得到相同的等待时间
+pub fn run_config_dry(config: &Config, injector: &dyn KeyInjector) -> Vec<(String, ActionResult)> {
+    crate::random::set_seed(config.rng_seed);
+    config
+        .hotkeys
+        .iter()
+        .map(|hotkey| {
+            let (prefix, suffix) = (config.type_text_prefix.as_str(), config.type_text_suffix.as_str());
+            let run_sequence = |params: &SequenceParams| {
+                let params = params.with_text_affixes(prefix, suffix);
+                execute_triggered_sequence_with(&params, injector, &RunControl::new(), None, &Variables::new(), None, config.default_key_hold())
+            };
+            let result = hotkey.actions().try_for_each(|action| match action {
+                ActionParams::TypeText(params) => execute_type_text_with(&params.with_affixes(prefix, suffix), injector),
+                ActionParams::Sequence(params) => run_sequence(params),
+                ActionParams::Chat(params) => run_sequence(&params.to_sequence()),
+                ActionParams::TypeFile(params) => read_text_file(&params.path)
+                    .and_then(|text| execute_type_text_with(&params.to_type_text(text).with_affixes(prefix, suffix), injector)),
+                ActionParams::TapHold(params) => execute_tap_hold_with(params, injector, &RunControl::new()),
+                // 切换宏开关会改变全局状态，检查时不执行
+                ActionParams::None | ActionParams::ToggleSystem => Ok(()),
+            });
+            (hotkey.key(), result)
+        })
+        .collect()
+}
+
+/// 使用指定的注入器和运行控制执行序列操作
+///
+/// 每个步骤开始前检查运行控制：暂停时等待继续，中止时提前结束。
+/// 序列结束时（无论成功、中止还是中途出错）都会释放本次执行中按下但尚未释放的按键。
+/// 没有触发键，`wait_release` 步骤直接跳过；变量只在本次执行内有效，每次执行都从空的变量存储开始；
+/// 不使用顶层的 `default_key_hold_ms`，只有序列设置了 `key_hold` 时才在按下与释放之间等待
+pub fn execute_sequence_with(params: &SequenceParams, injector: &dyn KeyInjector, control: &RunControl) -> Result<(), Box<dyn std::error::Error>> {
+    execute_triggered_sequence_with(params, injector, control, None, &Variables::new(), None, DurationSpec::ZERO)
+}
+
+/// 触发序列执行的按键
+#[derive(Debug, Clone, Copy)]
+pub struct Trigger<'a> {
+    /// 热键名称（与派发的 `MacroEvent` 中的名称相同）
+    pub key_name: &'a str,
+    /// 记录触发键是否仍被按住
+    pub held: &'a HeldTriggers,
+}
+
+/// 序列执行进度，开启 `overlay_progress` 时通过通道发给屏幕提示
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequenceProgress {
+    /// 热键的序列开始执行，`name` 为显示的名称
+    Started { name: String },
+    /// 开始执行第 `index` 个（从 1 开始）顶层步骤，共 `total` 个
+    Step { index: usize, total: usize },
+    /// 序列结束（无论成功、中止还是出错）
+    Finished,
+}
+
+/// 使用指定的注入器和运行控制执行由按键触发的序列，`wait_release` 步骤等待 `trigger` 松开
+///
+/// # 参数
+///
+/// * `params` - 序列参数
+/// * `injector` - 接收按键的注入器
+/// * `control` - 运行控制
+/// * `trigger` - 触发键，为 None 时 `wait_release` 步骤直接跳过
+/// * `vars` - 变量步骤读写的变量存储，传入同一个存储时变量在多次执行之间保留
+/// * `progress` - 接收进度的通道，每个顶层步骤开始前发送一次 `SequenceProgress::Step`（`if_var` 分支内的步骤不单独计数）；为 None 时不报告
+/// * `default_key_hold` - 序列未设置 `key_hold` 时完整按键的按住时长（即配置中的 `default_key_hold_ms`），为 0 时立即释放
+pub fn execute_triggered_sequence_with(
+    params: &SequenceParams,
+    injector: &dyn KeyInjector,
+    control: &RunControl,
+    trigger: Option<Trigger>,
+    vars: &Variables,
+    progress: Option<&Sender<SequenceProgress>>,
+    default_key_hold: DurationSpec,
+) -> Result<(), Box<dyn std::error::Error>> {
     log::info!("开始执行序列，共 {} 个步骤", params.steps.len());
-    for (idx, step) in params.steps.iter().enumerate() {
-        log::debug!("执行步骤 {}: {:?}", idx + 1, step);
-        match step {
-            Step::Key { value, delay, action } => {
-                if let Some(vk) = parse_key_string(value) {
-                    let key_action = action.as_ref().unwrap_or(&KeyAction::Complete);
-                    log::debug!("按键: {}, 动作: {:?}", value, key_action);
-
-                    match key_action {
-                        KeyAction::Press => {
-                            keyboard::simulate_key_press(vk)?;
-                            log::debug!("按下按键: {}", value);
-                            if let Some(d) = delay {
-                                thread::sleep(Duration::from_millis(d.get_delay()));
+    start_delay(params.start_delay, injector, control)?;
+
+    let mut held = HeldKeys::new(injector);
+    let runner = StepRunner { params, injector, control, trigger, vars, clock: crate::winapi::clock::local_now, progress, default_key_hold };
+    runner.run(&params.steps, &mut held, true)?;
+
+    log::info!("序列执行完成");
+    Ok(())
+}
+
+/// 一次序列执行的上下文，`if_var` 的分支递归使用同一个上下文执行
+struct StepRunner<'a> {
+    params: &'a SequenceParams,
+    injector: &'a dyn KeyInjector,
+    control: &'a RunControl,
+    trigger: Option<Trigger<'a>>,
+    vars: &'a Variables,
+    /// 读取 `datetime` 步骤输入的当前时间
+    clock: fn() -> LocalDateTime,
+    /// 接收顶层步骤进度的通道
+    progress: Option<&'a Sender<SequenceProgress>>,
+    /// 序列未设置 `key_hold` 时使用的按住时长
+    default_key_hold: DurationSpec,
+}
+
+impl StepRunner<'_> {
+    /// 完整按键按下与释放之间的间隔：序列的 `key_hold` 优先，其次是顶层默认值，为 0 时返回 None
+    fn key_hold(&self) -> Option<DurationSpec> {
+        Some(self.params.key_hold.unwrap_or(self.default_key_hold)).filter(|h| !h.is_zero())
+    }
+
+    /// 依次执行步骤，按下的按键记录在 `held` 中，由外层在序列结束时统一释放；`top_level` 为 true 时报告每个步骤的进度
+    fn run(&self, steps: &[Step], held: &mut HeldKeys, top_level: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let (injector, control) = (self.injector, self.control);
+        for (idx, step) in steps.iter().enumerate() {
+            control.checkpoint()?;
+            log::debug!("执行步骤 {}: {:?}", idx + 1, step);
+            if let Some(progress) = self.progress.filter(|_| top_level) {
+                // 接收端已关闭时不影响执行
+                let _ = progress.send(SequenceProgress::Step { index: idx + 1, total: steps.len() });
+            }
+            match step {
+                Step::Key { value, delay, action } => {
+                    if let Some(vk) = parse_key_string(value) {
+                        let key_action = action.as_ref().unwrap_or(&KeyAction::Complete);
+                        log::debug!("按键: {}, 动作: {:?}", value, key_action);
+
+                        match key_action {
+                            KeyAction::Press => {
+                                held.press(vk)?;
+                                log::debug!("按下按键: {}", value);
+                                if let Some(d) = delay {
+                                    injector.sleep(d.get_delay());
+                                }
                             }
-                        }
-                        KeyAction::Release => {
-                            keyboard::simulate_key_release(vk)?;
-                            log::debug!("释放按键: {}", value);
-                            if let Some(d) = delay {
-                                thread::sleep(Duration::from_millis(d.get_delay()));
+                            KeyAction::Release => {
+                                held.release(vk)?;
+                                log::debug!("释放按键: {}", value);
+                                if let Some(d) = delay {
+                                    injector.sleep(d.get_delay());
+                                }
                             }
-                        }
-                        KeyAction::Complete => {
-                            keyboard::simulate_key_press(vk)?;
-                            log::debug!("按下按键: {}", value);
-                            if let Some(d) = delay {
-                                thread::sleep(Duration::from_millis(d.get_delay()));
+                            KeyAction::Complete => {
+                                held.press(vk)?;
+                                log::debug!("按下按键: {}", value);
+                                // 步骤的 delay 优先，其次是序列的按住时长
+                                if let Some(d) = delay {
+                                    injector.sleep(d.get_delay());
+                                } else if let Some(hold) = self.key_hold() {
+                                    injector.sleep(hold.duration());
+                                }
+                                held.release(vk)?;
+                                log::debug!("释放按键: {}", value);
                             }
-                            keyboard::simulate_key_release(vk)?;
-                            log::debug!("释放按键: {}", value);
                         }
+                    } else {
+                        log::warn!("无法解析按键: {}", value);
                     }
-                } else {
-                    log::warn!("无法解析按键: {}", value);
                 }
-            }
-            Step::Wait { value, random } => {
-                if random == &Some(true) {
-                    // 随机范围：0 ~ value
-                    let actual_delay = rand::thread_rng().gen_range(0..=*value);
-                    thread::sleep(Duration::from_millis(actual_delay));
-                } else {
-                    thread::sleep(Duration::from_millis(*value));
+                Step::Wait { value, random } => {
+                    if random == &Some(true) {
+                        // 随机范围：0 ~ value（精确到微秒）
+                        let max = u64::try_from(value.duration().as_micros()).unwrap_or(u64::MAX);
+                        injector.interruptible_sleep(Duration::from_micros(crate::random::gen_range(0..=max)), control)?;
+                    } else {
+                        injector.interruptible_sleep(value.duration(), control)?;
+                    }
                 }
-            }
-            Step::Text { value, delay } => {
-                for ch in value.chars() {
-                    if let Some(vk) = char_to_vk(ch) {
-                        keyboard::simulate_key_press(vk)?;
-                        if let Some(d) = delay {
-                            thread::sleep(Duration::from_millis(d.get_delay()));
+                Step::Text { value, delay } => type_step_text(value, delay.as_ref(), injector)?,
+                Step::Combo { keys, delay } => {
+                    // 任一按键无法解析时跳过整个组合，避免只按下其中一部分
+                    let Some(vks) = keys.iter().map(|k| parse_key_string(k)).collect::<Option<Vec<_>>>() else {
+                        log::warn!("无法解析组合键: {}", keys.join("+"));
+                        continue;
+                    };
+
+                    log::debug!("组合键: {}", keys.join("+"));
+                    for &vk in &vks {
+                        held.press(vk)?;
+                    }
+                    if let Some(d) = delay {
+                        injector.sleep(d.get_delay());
+                    }
+                    for &vk in vks.iter().rev() {
+                        held.release(vk)?;
+                    }
+                }
+                Step::WaitPixel { x, y, color, tolerance, timeout_ms } => {
+                    let target = Rgb::parse(color).ok_or_else(|| format!("无效的颜色: {}", color))?;
+                    let wait = PixelWait {
+                        x: *x,
+                        y: *y,
+                        target,
+                        tolerance: *tolerance,
+                        timeout: Duration::from_millis(*timeout_ms),
+                    };
+                    wait.run(control, &|x, y| injector.read_pixel(x, y, target))?;
+                }
+                Step::WaitRelease { timeout_ms } => match self.trigger {
+                    Some(trigger) => wait_trigger_release(trigger, Duration::from_millis(*timeout_ms), control)?,
+                    None => log::debug!("没有触发键，跳过等待松开"),
+                },
+                Step::SetVar { name, value } => {
+                    self.vars.set(name, *value);
+                    log::debug!("变量 {} = {}", name, value);
+                }
+                Step::IncVar { name, by } => {
+                    let value = self.vars.add(name, *by);
+                    log::debug!("变量 {} 加 {}，现为 {}", name, by, value);
+                }
+                Step::IfVar { name, equals, then, else_ } => {
+                    let value = self.vars.get(name);
+                    let matched = value == *equals;
+                    log::debug!("变量 {} = {}，执行 {} 分支", name, value, if matched { "then" } else { "else" });
+                    self.run(if matched { then } else { else_ }, held, false)?;
+                }
+                Step::DateTime { format } => {
+                    // 执行时读取时钟，每次执行输入当时的时间
+                    let text = crate::datetime::format(format, &(self.clock)())?;
+                    log::debug!("输入时间: {}", text);
+                    type_step_text(&text, None, injector)?;
+                }
+                Step::ScanCode { code, action, extended } => {
+                    let key = HeldKey::ScanCode { code: *code, extended: *extended };
+                    log::debug!("扫描码: 0x{:X}, 扩展键: {}, 动作: {:?}", code, extended, action);
+                    match action.as_ref().unwrap_or(&KeyAction::Complete) {
+                        KeyAction::Press => held.press_key(key)?,
+                        KeyAction::Release => held.release_key(key)?,
+                        KeyAction::Complete => {
+                            held.press_key(key)?;
+                            if let Some(hold) = self.key_hold() {
+                                injector.sleep(hold.duration());
+                            }
+                            held.release_key(key)?;
                         }
-                        keyboard::simulate_key_release(vk)?;
-                    } else {
-                        simulate_unicode_char(ch)?;
                     }
                 }
+                Step::ReleaseAll => {
+                    log::debug!("释放序列按下的全部 {} 个按键", held.keys.len());
+                    held.release_all()?;
+                }
             }
         }
+        Ok(())
     }
+}
 
-    log::info!("序列执行完成");
+/// 逐字符输入序列中的文本，能映射到当前键盘布局的字符按键输入，其余字符以 Unicode 输入
+fn type_step_text(text: &str, delay: Option<&DelayConfig>, injector: &dyn KeyInjector) -> Result<(), Box<dyn std::error::Error>> {
+    for ch in text.chars() {
+        if let Some(key) = injector.char_key(ch) {
+            let hold = delay.map(|d| d.get_delay());
+            type_char_key(key, hold, injector)?;
+        } else {
+            injector.unicode_char(ch)?;
+        }
+    }
     Ok(())
 }
 
-/// 将字符转换为虚拟键码
-fn char_to_vk(ch: char) -> Option<u16> {
-    match ch {
-        'a'..='z' => Some(ch as u16 - 'a' as u16 + 0x41),
-        'A'..='Z' => Some(ch as u16 - 'A' as u16 + 0x41),
-        '0'..='9' => Some(ch as u16 - '0' as u16 + 0x30),
-        ' ' => Some(windows::Win32::UI::Input::KeyboardAndMouse::VK_SPACE.0),
-        '\r' | '\n' => Some(windows::Win32::UI::Input::KeyboardAndMouse::VK_RETURN.0),
-        '\t' => Some(windows::Win32::UI::Input::KeyboardAndMouse::VK_TAB.0),
-        _ => None,
+/// 等待触发键松开
+///
+/// # 说明
+///
+/// 分段等待，每段之间检查运行控制，等待期间也能暂停或中止；超时后记录警告并返回 Ok，序列继续执行
+fn wait_trigger_release(trigger: Trigger, timeout: Duration, control: &RunControl) -> Result<(), Box<dyn std::error::Error>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let slice = deadline.saturating_duration_since(Instant::now()).min(RELEASE_POLL_INTERVAL);
+        if trigger.held.wait_release(trigger.key_name, slice) {
+            log::debug!("触发键 {} 已松开", trigger.key_name);
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            log::warn!("等待触发键 {} 松开超时（{} ms），继续执行", trigger.key_name, timeout.as_millis());
+            return Ok(());
+        }
+        control.checkpoint()?;
+    }
+}
+
+/// 等待屏幕像素变为指定颜色
+struct PixelWait {
+    x: i32,
+    y: i32,
+    target: Rgb,
+    tolerance: u8,
+    timeout: Duration,
+}
+
+impl PixelWait {
+    /// 轮询像素颜色直到匹配或超时
+    ///
+    /// # 参数
+    ///
+    /// * `control` - 运行控制，每次轮询前检查，等待期间也能暂停或中止
+    /// * `read_pixel` - 读取像素颜色，无法读取时返回 None（视为不匹配）
+    ///
+    /// # 说明
+    ///
+    /// 至少读取一次；轮询间隔固定，不经过注入器（不受延迟倍率影响，演练模式下也不逐次记录日志）
+    fn run(&self, control: &RunControl, read_pixel: &dyn Fn(i32, i32) -> Option<Rgb>) -> Result<(), Box<dyn std::error::Error>> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let current = read_pixel(self.x, self.y);
+            if current.is_some_and(|c| c.matches(self.target, self.tolerance)) {
+                log::debug!("像素 ({}, {}) 已变为 {}", self.x, self.y, self.target);
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                let current = current.map_or_else(|| "无法读取".to_string(), |c| c.to_string());
+                return Err(format!(
+                    "等待像素 ({}, {}) 变为 {} 超时（{} ms，当前为 {}）",
+                    self.x, self.y, self.target, self.timeout.as_millis(), current
+                ).into());
+            }
+            control.checkpoint()?;
+            std::thread::sleep(PIXEL_POLL_INTERVAL);
+        }
+    }
+}
+
+/// 开始执行前等待，给目标程序留出准备时间（为 0 时不等待，等待期间中止时返回错误）
+fn start_delay(delay: DurationSpec, injector: &dyn KeyInjector, control: &RunControl) -> Result<(), Box<dyn std::error::Error>> {
+    if !delay.is_zero() {
+        log::debug!("开始前等待 {}", delay);
+        injector.interruptible_sleep(delay.duration(), control)?;
+    }
+    Ok(())
+}
+
+/// 在基础延迟上叠加随机抖动
+///
+/// 返回值在 `[base - jitter, base + jitter]` 范围内（不小于 0，`jitter` 以毫秒计），`jitter` 为 0 时原样返回
+fn apply_jitter(base: Duration, jitter: u64) -> Duration {
+    if jitter == 0 {
+        return base;
+    }
+    let jitter = Duration::from_millis(jitter);
+    let offset = crate::random::gen_range(Duration::ZERO..=jitter.saturating_mul(2));
+    base.saturating_add(offset).saturating_sub(jitter)
+}
+
+/// 序列中按下的按键
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HeldKey {
+    /// 虚拟键码
+    Vk(u16),
+    /// `scan_code` 步骤直接指定的扫描码
+    ScanCode { code: u16, extended: bool },
+}
+
+/// 序列执行期间按下但尚未释放的按键
+///
+/// 析构时释放仍处于按下状态的按键，避免序列提前返回时 Shift、Ctrl 等修饰键卡住
+struct HeldKeys<'a> {
+    injector: &'a dyn KeyInjector,
+    keys: Vec<HeldKey>,
+}
+
+impl<'a> HeldKeys<'a> {
+    fn new(injector: &'a dyn KeyInjector) -> Self {
+        Self { injector, keys: Vec::new() }
+    }
+
+    /// 按下按键并记录
+    fn press(&mut self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+        self.press_key(HeldKey::Vk(vk))
+    }
+
+    /// 释放按键并移除记录
+    ///
+    /// 按键不在记录中（如强制释放用户正在按住的按键）时照常发送释放事件，记录保持不变
+    fn release(&mut self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+        self.release_key(HeldKey::Vk(vk))
+    }
+
+    /// 发送按下事件并记录，同 `press`
+    fn press_key(&mut self, key: HeldKey) -> Result<(), Box<dyn std::error::Error>> {
+        match key {
+            HeldKey::Vk(vk) => self.injector.key_down(vk)?,
+            HeldKey::ScanCode { code, extended } => self.injector.scan_code_down(code, extended)?,
+        }
+        if !self.keys.contains(&key) {
+            self.keys.push(key);
+        }
+        Ok(())
+    }
+
+    /// 发送释放事件并移除记录，同 `release`
+    fn release_key(&mut self, key: HeldKey) -> Result<(), Box<dyn std::error::Error>> {
+        self.send_up(key)?;
+        self.keys.retain(|&k| k != key);
+        Ok(())
+    }
+
+    fn send_up(&self, key: HeldKey) -> Result<(), Box<dyn std::error::Error>> {
+        match key {
+            HeldKey::Vk(vk) => self.injector.key_up(vk),
+            HeldKey::ScanCode { code, extended } => self.injector.scan_code_up(code, extended),
+        }
+    }
+
+    /// 按与按下相反的顺序释放所有记录的按键
+    ///
+    /// 发送失败时停止，尚未释放的按键仍保留在记录中
+    fn release_all(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        while let Some(&key) = self.keys.last() {
+            self.send_up(key)?;
+            self.keys.pop();
+        }
+        Ok(())
     }
 }
 
-/// 模拟 Unicode 字符输入（备用方案）
-fn simulate_unicode_char(_ch: char) -> Result<(), Box<dyn std::error::Error>> {
-    // 这里可以实现 Unicode 字符输入，使用 SendInput 的 Unicode 模式
-    // 为简化实现，这里暂时返回错误
-    Err("Unicode 字符不支持".into())
+impl Drop for HeldKeys<'_> {
+    fn drop(&mut self) {
+        // 按与按下相反的顺序释放
+        for key in std::mem::take(&mut self.keys).into_iter().rev() {
+            log::debug!("释放序列遗留的按键: {:?}", key);
+            if let Err(e) = self.send_up(key) {
+                log::warn!("释放按键失败 ({:?}): {}", key, e);
+            }
+        }
+    }
 }
 
 /// 将键名字符串解析为虚拟键码
-fn parse_key_string(key: &str) -> Option<u16> {
+///
+/// 除键名外也可以直接写虚拟键码：`0x` 前缀的十六进制（如 "0xA6"）或两位以上的十进制数（如 "166"），
+/// 单个数字仍表示数字键；键码为 0 或超出 u16 范围时返回 None
+pub(crate) fn parse_key_string(key: &str) -> Option<u16> {
     use windows::Win32::UI::Input::KeyboardAndMouse::*;
     
     match key.to_uppercase().as_str() {
@@ -154,14 +753,1380 @@ fn parse_key_string(key: &str) -> Option<u16> {
         s if s.len() == 1 && s.chars().next().unwrap().is_ascii_digit() => {
             s.chars().next().map(|c| c as u16 - '0' as u16 + 0x30)
         }
-        "SPACE" | "Space" => Some(VK_SPACE.0),
-        "ENTER" | "Enter" => Some(VK_RETURN.0),
-        "TAB" | "Tab" => Some(VK_TAB.0),
-        "BACKSPACE" | "Backspace" => Some(VK_BACK.0),
-        "ESC" | "Escape" => Some(VK_ESCAPE.0),
-        "SHIFT" | "Shift" => Some(VK_SHIFT.0),
-        "CTRL" | "Ctrl" => Some(VK_CONTROL.0),
-        "ALT" | "Alt" => Some(VK_MENU.0),
-        _ => None,
+        // F1 ~ F24 连续分布在 VK_F1 (0x70) ~ VK_F24 (0x87)
+        s if s.starts_with('F') => match s[1..].parse::<u16>() {
+            Ok(n @ 1..=24) => Some(VK_F1.0 + n - 1),
+            _ => None,
+        },
+        // 小键盘数字 Numpad0 ~ Numpad9 连续分布在 VK_NUMPAD0 (0x60) ~ VK_NUMPAD9 (0x69)，与顶行数字键是不同的按键
+        s if s.starts_with("NUMPAD") => match s[6..].parse::<u16>() {
+            Ok(n @ 0..=9) if s.len() == 7 => Some(VK_NUMPAD0.0 + n),
+            _ => None,
+        },
+        // 直接指定的虚拟键码
+        s if s.starts_with("0X") && s[2..].chars().all(|c| c.is_ascii_hexdigit()) => {
+            u16::from_str_radix(&s[2..], 16).ok().filter(|&vk| vk != 0)
+        }
+        s if s.chars().all(|c| c.is_ascii_digit()) => s.parse::<u16>().ok().filter(|&vk| vk != 0),
+        // Space、Enter、Esc 等具名按键及其别名
+        _ => crate::keys::named_key_vk(key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::macros::injector::recording::{Injected, RecordingInjector};
+    use crate::macros::vars::HotkeyVariables;
+
+    const VK_SHIFT: u16 = 0x10;
+
+    fn sequence_params(yaml: &str) -> SequenceParams {
+        let config = Config::from_str(yaml).unwrap();
+        match &config.hotkeys[0].params {
+            crate::config::ActionParams::Sequence(params) => params.clone(),
+            _ => panic!("Expected Sequence params"),
+        }
+    }
+
+    fn type_text_params(yaml: &str) -> TypeTextParams {
+        let config = Config::from_str(yaml).unwrap();
+        match &config.hotkeys[0].params {
+            crate::config::ActionParams::TypeText(params) => params.clone(),
+            _ => panic!("Expected TypeText params"),
+        }
+    }
+
+    #[test]
+    fn test_type_text_jitter_within_bounds() {
+        let params = type_text_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "abcdefghijklmnopqrstuvwxyz"
+      delay: 20
+      jitter: 5
+"#);
+        let injector = RecordingInjector::default();
+
+        execute_type_text_with(&params, &injector).unwrap();
+        let sleeps = injector.sleeps();
+        assert_eq!(sleeps.len(), 52);
+        for d in sleeps {
+            assert!(d >= Duration::from_millis(15) && d <= Duration::from_millis(25), "{:?}", d);
+        }
+    }
+
+    /// 可以切换的测试布局：1 为美式布局，2 为德语布局（Y 和 Z 互换）
+    #[derive(Default)]
+    struct SwitchableLayout {
+        current: std::sync::atomic::AtomicIsize,
+    }
+
+    impl crate::macros::injector::KeyboardLayout for SwitchableLayout {
+        fn current(&self) -> isize {
+            self.current.load(std::sync::atomic::Ordering::SeqCst)
+        }
+
+        fn char_key(&self, layout: isize, ch: char) -> Option<CharKey> {
+            match (layout, ch) {
+                (2, 'y') => Some(CharKey::plain(0x5A)),
+                (2, 'z') => Some(CharKey::plain(0x59)),
+                _ => crate::macros::injector::us_char_key(ch),
+            }
+        }
+    }
+
+    #[test]
+    fn test_type_text_uses_layout_switched_between_executions() {
+        use std::sync::atomic::Ordering;
+
+        let params = type_text_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "zy"
+"#);
+        let layout = std::sync::Arc::new(SwitchableLayout::default());
+        let run = || {
+            let recorder = RecordingInjector { layout: Some(layout.clone()), ..Default::default() };
+            execute_type_text_with(&params, &recorder).unwrap();
+            recorder.key_events()
+        };
+        let (y, z) = (0x59, 0x5A);
+
+        layout.current.store(1, Ordering::SeqCst);
+        assert_eq!(run(), vec![Injected::Down(z), Injected::Up(z), Injected::Down(y), Injected::Up(y)]);
+
+        // 两次执行之间切换了输入语言，第二次按新布局映射
+        layout.current.store(2, Ordering::SeqCst);
+        assert_eq!(run(), vec![Injected::Down(y), Injected::Up(y), Injected::Down(z), Injected::Up(z)]);
+    }
+
+    fn method_params(method: &str, text: &str) -> TypeTextParams {
+        type_text_params(&format!(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "{}"
+      method: "{}"
+      paste_threshold: 5
+"#, text, method))
+    }
+
+    fn paste_events(text: &str) -> Vec<Injected> {
+        vec![
+            Injected::Clipboard(text.to_string()),
+            Injected::Down(0x11),
+            Injected::Down(0x56),
+            Injected::Up(0x56),
+            Injected::Up(0x11),
+        ]
+    }
+
+    #[test]
+    fn test_auto_method_pastes_long_text() {
+        let injector = RecordingInjector::default();
+        execute_type_text_with(&method_params("auto", "abcdefghij"), &injector).unwrap();
+        assert_eq!(injector.key_events(), paste_events("abcdefghij"));
+
+        // 不超过阈值时仍逐字符输入
+        let injector = RecordingInjector::default();
+        execute_type_text_with(&method_params("auto", "abcde"), &injector).unwrap();
+        assert_eq!(injector.key_events().len(), 10);
+        assert!(!injector.events().iter().any(|e| matches!(e, Injected::Clipboard(_))));
+    }
+
+    #[test]
+    fn test_paste_and_type_methods_ignore_threshold() {
+        let injector = RecordingInjector::default();
+        execute_type_text_with(&method_params("paste", "ab"), &injector).unwrap();
+        assert_eq!(injector.key_events(), paste_events("ab"));
+
+        let injector = RecordingInjector::default();
+        execute_type_text_with(&method_params("type", "abcdefghij"), &injector).unwrap();
+        assert_eq!(injector.key_events().len(), 20);
+    }
+
+    fn type_file_params(path: &std::path::Path) -> TypeFileParams {
+        let config = Config::from_str(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_file"
+    params:
+      path: "snippet.txt"
+      method: "paste"
+"#).unwrap();
+        match &config.hotkeys[0].params {
+            crate::config::ActionParams::TypeFile(params) => TypeFileParams {
+                path: path.to_string_lossy().into_owned(),
+                ..params.clone()
+            },
+            _ => panic!("Expected TypeFile params"),
+        }
+    }
+
+    #[test]
+    fn test_type_file_reads_at_fire_time() {
+        let path = std::env::temp_dir().join(format!("keymacro_type_file_{}.txt", std::process::id()));
+        let params = type_file_params(&path);
+
+        std::fs::write(&path, "第一版\n").unwrap();
+        let injector = RecordingInjector::default();
+        execute_type_file_with(&params, &injector).unwrap();
+        assert_eq!(injector.key_events(), paste_events("第一版\n"));
+
+        // 修改文件后再次触发使用新内容
+        std::fs::write(&path, "second").unwrap();
+        let injector = RecordingInjector::default();
+        execute_type_file_with(&params, &injector).unwrap();
+        assert_eq!(injector.key_events(), paste_events("second"));
+
+        // 不是有效的 UTF-8 时返回错误，不发送任何输入
+        std::fs::write(&path, [0xFF, 0xFE, 0x41]).unwrap();
+        let injector = RecordingInjector::default();
+        let err = execute_type_file_with(&params, &injector).unwrap_err().to_string();
+        assert!(err.contains("UTF-8"), "{}", err);
+        assert!(injector.events().is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+        let err = execute_type_file_with(&params, &injector).unwrap_err().to_string();
+        assert!(err.contains("读取文件") && err.contains(&params.path), "{}", err);
+    }
+
+    #[test]
+    fn test_rng_seed_makes_dry_runs_reproducible() {
+        let yaml = |seed: u64| format!(r#"
+rng_seed: {}
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "abcdef"
+      delay: {{ min: 1, max: 50 }}
+      jitter: 5
+  - type: keyboard
+    key: "F2"
+    action: "sequence"
+    params:
+      steps:
+        - {{ type: "wait", value: 1000, random: true }}
+        - {{ type: "key", value: "A", delay: {{ min: 1, max: 50 }} }}
+"#, seed);
+        let sleeps = |seed: u64| {
+            let config = Config::from_str(&yaml(seed)).unwrap();
+            let injector = RecordingInjector::default();
+            assert!(run_config_dry(&config, &injector).iter().all(|(_, r)| r.is_ok()));
+            injector.sleeps()
+        };
+
+        let first = sleeps(7);
+        assert_eq!(first.len(), 14);
+        assert_eq!(sleeps(7), first);
+        assert_ne!(sleeps(8), first);
+        crate::random::set_seed(None);
+    }
+
+    #[test]
+    fn test_type_text_affixes_wrap_typed_text() {
+        let config = Config::from_str(r#"
+type_text_prefix: "【"
+type_text_suffix: "】"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "a"
+      delay: 0
+  - type: keyboard
+    key: "F2"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "key", value: "B" }
+        - { type: "text", value: "a" }
+"#).unwrap();
+        let injector = RecordingInjector::default();
+        assert!(run_config_dry(&config, &injector).iter().all(|(_, r)| r.is_ok()));
+
+        // 前缀和后缀只加在文本前后，序列中的按键步骤不受影响
+        let wrapped_a = [Injected::Unicode('【'), Injected::Down(0x41), Injected::Up(0x41), Injected::Unicode('】')];
+        let mut expected = wrapped_a.to_vec();
+        expected.extend([Injected::Down(0x42), Injected::Up(0x42)]);
+        expected.extend(wrapped_a);
+        assert_eq!(injector.key_events(), expected);
+    }
+
+    #[test]
+    fn test_type_text_without_jitter_is_deterministic() {
+        let params = type_text_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "abc"
+      delay: 20
+"#);
+        let injector = RecordingInjector::default();
+
+        execute_type_text_with(&params, &injector).unwrap();
+        assert_eq!(injector.sleeps(), vec![Duration::from_millis(20); 6]);
+    }
+
+    #[test]
+    fn test_type_text_holds_shift_for_uppercase() {
+        let params = type_text_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "Ab"
+"#);
+        let injector = RecordingInjector::default();
+
+        execute_type_text_with(&params, &injector).unwrap();
+        assert_eq!(
+            injector.key_events(),
+            vec![
+                Injected::Down(VK_SHIFT),
+                Injected::Down(0x41),
+                Injected::Up(0x41),
+                Injected::Up(VK_SHIFT),
+                Injected::Down(0x42),
+                Injected::Up(0x42),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_text_zero_delay_is_batched() {
+        let params = type_text_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "ab中c"
+      delay: 0
+"#);
+        let injector = RecordingInjector::default();
+
+        execute_type_text_with(&params, &injector).unwrap();
+        assert_eq!(injector.events(), vec![
+            Injected::Batch(4),
+            Injected::Down(0x41),
+            Injected::Up(0x41),
+            Injected::Down(0x42),
+            Injected::Up(0x42),
+            Injected::Unicode('中'),
+            Injected::Batch(2),
+            Injected::Down(0x43),
+            Injected::Up(0x43),
+        ]);
+    }
+
+    const VK_CAPITAL: u16 = 0x14;
+
+    fn caps_lock_params(normalize: bool) -> TypeTextParams {
+        type_text_params(&format!(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "a"
+      delay: 0
+      normalize_caps_lock: {}
+"#, normalize))
+    }
+
+    #[test]
+    fn test_type_text_normalizes_caps_lock() {
+        let injector = RecordingInjector { caps_lock: true, ..Default::default() };
+
+        execute_type_text_with(&caps_lock_params(true), &injector).unwrap();
+        assert_eq!(
+            injector.key_events(),
+            vec![
+                Injected::Down(VK_CAPITAL),
+                Injected::Up(VK_CAPITAL),
+                Injected::Down(0x41),
+                Injected::Up(0x41),
+                Injected::Down(VK_CAPITAL),
+                Injected::Up(VK_CAPITAL),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_text_keeps_caps_lock_when_not_needed() {
+        // Caps Lock 未开启
+        let injector = RecordingInjector::default();
+        execute_type_text_with(&caps_lock_params(true), &injector).unwrap();
+        assert_eq!(injector.key_events(), vec![Injected::Down(0x41), Injected::Up(0x41)]);
+
+        // 未开启选项
+        let injector = RecordingInjector { caps_lock: true, ..Default::default() };
+        execute_type_text_with(&caps_lock_params(false), &injector).unwrap();
+        assert_eq!(injector.key_events(), vec![Injected::Down(0x41), Injected::Up(0x41)]);
+    }
+
+    #[test]
+    fn test_type_text_restores_caps_lock_on_error() {
+        let params = type_text_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "é"
+      normalize_caps_lock: true
+"#);
+        let injector = RecordingInjector { caps_lock: true, fail_unicode: true, ..Default::default() };
+
+        assert!(execute_type_text_with(&params, &injector).is_err());
+        assert_eq!(
+            injector.key_events(),
+            vec![
+                Injected::Down(VK_CAPITAL),
+                Injected::Up(VK_CAPITAL),
+                Injected::Down(VK_CAPITAL),
+                Injected::Up(VK_CAPITAL),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_start_delay_sleeps_once_before_typing() {
+        let params = type_text_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "ab"
+      delay: 0
+      start_delay: 200
+"#);
+        let injector = RecordingInjector::default();
+
+        execute_type_text_with(&params, &injector).unwrap();
+        assert_eq!(injector.events()[0], Injected::Sleep(Duration::from_millis(200)));
+        assert_eq!(injector.sleeps(), vec![Duration::from_millis(200)]);
+    }
+
+    #[test]
+    fn test_sequence_start_delay_sleeps_once_before_steps() {
+        let params = sequence_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      start_delay: 150
+      steps:
+        - { type: "key", value: "A" }
+        - { type: "key", value: "B" }
+"#);
+        let injector = RecordingInjector::default();
+
+        execute_sequence_with(&params, &injector, &RunControl::new()).unwrap();
+        assert_eq!(injector.events()[0], Injected::Sleep(Duration::from_millis(150)));
+        assert_eq!(injector.sleeps(), vec![Duration::from_millis(150)]);
+    }
+
+    #[test]
+    fn test_parse_named_key_aliases() {
+        assert_eq!(parse_key_string("Enter"), Some(0x0D));
+        assert_eq!(parse_key_string("return"), Some(0x0D));
+        assert_eq!(parse_key_string("Esc"), Some(0x1B));
+        assert_eq!(parse_key_string("Escape"), Some(0x1B));
+        assert_eq!(parse_key_string("Del"), Some(0x2E));
+        assert_eq!(parse_key_string("PgUp"), Some(0x21));
+        assert_eq!(parse_key_string("Win"), Some(0x5B));
+        assert_eq!(parse_key_string("LWin"), Some(0x5B));
+        assert_eq!(parse_key_string("RWin"), Some(0x5C));
+    }
+
+    #[test]
+    fn test_parse_numeric_vk_codes() {
+        assert_eq!(parse_key_string("0xA6"), Some(0xA6));
+        assert_eq!(parse_key_string("0Xa6"), Some(0xA6));
+        assert_eq!(parse_key_string("166"), Some(0xA6));
+        assert_eq!(parse_key_string("0xFFFF"), Some(0xFFFF));
+        // 单个数字仍是数字键
+        assert_eq!(parse_key_string("7"), Some(0x37));
+        // 超出 u16 范围、键码为 0 或格式无效
+        assert_eq!(parse_key_string("0x10000"), None);
+        assert_eq!(parse_key_string("65536"), None);
+        assert_eq!(parse_key_string("0x0"), None);
+        assert_eq!(parse_key_string("00"), None);
+        assert_eq!(parse_key_string("0x"), None);
+        assert_eq!(parse_key_string("0xG1"), None);
+        assert_eq!(parse_key_string("0x+A"), None);
+    }
+
+    #[test]
+    fn test_default_key_hold() {
+        let yaml = r#"
+default_key_hold_ms: 30
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "key", value: "A" }
+        - { type: "key", value: "B", delay: 5 }
+        - { type: "key", value: "Shift", action: "press" }
+        - { type: "key", value: "Shift", action: "release" }
+"#;
+        let run = |yaml: &str| {
+            let injector = RecordingInjector::default();
+            assert!(run_config_dry(&Config::from_str(yaml).unwrap(), &injector).iter().all(|(_, r)| r.is_ok()));
+            injector
+        };
+        // 按住时长只用于完整按键，步骤的 delay 优先
+        assert_eq!(
+            run(yaml).events(),
+            vec![
+                Injected::Down(0x41),
+                Injected::Sleep(Duration::from_millis(30)),
+                Injected::Up(0x41),
+                Injected::Down(0x42),
+                Injected::Sleep(Duration::from_millis(5)),
+                Injected::Up(0x42),
+                Injected::Down(VK_SHIFT),
+                Injected::Up(VK_SHIFT),
+            ]
+        );
+
+        // 默认值在执行时使用，不写入解析后的序列，保存配置时也不会多出 key_hold
+        let config = Config::from_str(yaml).unwrap();
+        assert_eq!(sequence_params(yaml).key_hold, None);
+        assert!(!serde_yaml::to_string(&config).unwrap().contains("key_hold:"));
+
+        // 序列的 key_hold 优先于顶层默认值
+        let injector = run(&yaml.replace("      steps:", "      key_hold: \"1.5ms\"\n      steps:"));
+        assert_eq!(injector.sleeps()[0], Duration::from_micros(1500));
+
+        // 未设置时立即释放
+        assert_eq!(run(&yaml.replace("default_key_hold_ms: 30\n", "")).sleeps(), vec![Duration::from_millis(5)]);
+
+        // 不经过配置直接执行时不使用默认值
+        let injector = RecordingInjector::default();
+        execute_sequence_with(&sequence_params(yaml), &injector, &RunControl::new()).unwrap();
+        assert_eq!(injector.sleeps(), vec![Duration::from_millis(5)]);
+    }
+
+    #[test]
+    fn test_default_key_hold_applies_to_chat() {
+        let yaml = r#"
+default_key_hold_ms: 30
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "chat"
+    params:
+      message: "hi"
+"#;
+        let injector = RecordingInjector::default();
+        assert!(run_config_dry(&Config::from_str(yaml).unwrap(), &injector).iter().all(|(_, r)| r.is_ok()));
+        // 打开和关闭聊天框的按键都按住默认时长
+        assert_eq!(injector.sleeps().iter().filter(|d| **d == Duration::from_millis(30)).count(), 2);
+    }
+
+    #[test]
+    fn test_numpad_digits_are_distinct_from_top_row() {
+        assert_eq!(parse_key_string("Numpad0"), Some(0x60));
+        assert_eq!(parse_key_string("numpad5"), Some(0x65));
+        assert_eq!(parse_key_string("NUMPAD9"), Some(0x69));
+        assert_eq!(parse_key_string("5"), Some(0x35));
+        assert_eq!(parse_key_string("Numpad10"), None);
+        assert_eq!(parse_key_string("Numpad05"), None);
+        assert_eq!(parse_key_string("Numpad"), None);
+
+        let params = sequence_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "key", value: "Numpad5" }
+        - { type: "key", value: "5" }
+"#);
+        let injector = RecordingInjector::default();
+
+        execute_sequence_with(&params, &injector, &RunControl::new()).unwrap();
+        assert_eq!(
+            injector.key_events(),
+            vec![Injected::Down(0x65), Injected::Up(0x65), Injected::Down(0x35), Injected::Up(0x35)]
+        );
+    }
+
+    #[test]
+    fn test_parse_function_keys() {
+        assert_eq!(parse_key_string("F1"), Some(0x70));
+        assert_eq!(parse_key_string("f12"), Some(0x7B));
+        assert_eq!(parse_key_string("F13"), Some(0x7C));
+        assert_eq!(parse_key_string("F24"), Some(0x87));
+        assert_eq!(parse_key_string("F"), Some(0x46));
+
+        for name in ["F0", "F25", "F01x", "F-1", "Fn"] {
+            assert_eq!(parse_key_string(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_apply_jitter_saturates_at_zero() {
+        for _ in 0..100 {
+            assert!(apply_jitter(Duration::from_millis(2), 5) <= Duration::from_millis(7));
+        }
+        assert_eq!(apply_jitter(Duration::from_millis(10), 0), Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_chat_opens_types_and_closes() {
+        let config = Config::from_str(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "chat"
+    params:
+      open_key: "T"
+      message: "gg"
+      pre_delay: 80
+"#).unwrap();
+        let crate::config::ActionParams::Chat(params) = &config.hotkeys[0].params else {
+            panic!("Expected Chat params");
+        };
+        let injector = RecordingInjector::default();
+
+        execute_sequence_with(&params.to_sequence(), &injector, &RunControl::new()).unwrap();
+        assert_eq!(
+            injector.events(),
+            vec![
+                Injected::Down(0x54),
+                Injected::Up(0x54),
+                Injected::Sleep(Duration::from_millis(80)),
+                Injected::Down(0x47),
+                Injected::Up(0x47),
+                Injected::Down(0x47),
+                Injected::Up(0x47),
+                Injected::Sleep(Duration::from_millis(50)),
+                Injected::Down(0x0D),
+                Injected::Up(0x0D),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_combo_releases_in_reverse_order() {
+        let params = sequence_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "combo", keys: ["A", "B", "C"], delay: 20 }
+"#);
+        let injector = RecordingInjector::default();
+
+        execute_sequence_with(&params, &injector, &RunControl::new()).unwrap();
+        assert_eq!(
+            injector.events(),
+            vec![
+                Injected::Down(0x41),
+                Injected::Down(0x42),
+                Injected::Down(0x43),
+                Injected::Sleep(Duration::from_millis(20)),
+                Injected::Up(0x43),
+                Injected::Up(0x42),
+                Injected::Up(0x41),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_combo_with_unknown_key_is_skipped() {
+        let params = sequence_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "combo", keys: ["Ctrl", "NoSuchKey"] }
+        - { type: "key", value: "A" }
+"#);
+        let injector = RecordingInjector::default();
+
+        execute_sequence_with(&params, &injector, &RunControl::new()).unwrap();
+        assert_eq!(injector.key_events(), vec![Injected::Down(0x41), Injected::Up(0x41)]);
+    }
+
+    #[test]
+    fn test_release_of_unpressed_key_keeps_tracking() {
+        let params = sequence_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "key", value: "Shift", action: "press" }
+        - { type: "key", value: "A", action: "release" }
+"#);
+        let injector = RecordingInjector::default();
+
+        execute_sequence_with(&params, &injector, &RunControl::new()).unwrap();
+        // A 只发送一次释放事件，结束时只释放仍按住的 Shift
+        assert_eq!(
+            injector.key_events(),
+            vec![Injected::Down(VK_SHIFT), Injected::Up(0x41), Injected::Up(VK_SHIFT)]
+        );
+    }
+
+    #[test]
+    fn test_sequence_error_releases_held_keys() {
+        let params = sequence_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "key", value: "Shift", action: "press" }
+        - { type: "text", value: "é" }
+        - { type: "key", value: "A" }
+"#);
+        let injector = RecordingInjector { fail_unicode: true, ..Default::default() };
+
+        assert!(execute_sequence_with(&params, &injector, &RunControl::new()).is_err());
+        assert_eq!(injector.key_events(), vec![Injected::Down(VK_SHIFT), Injected::Up(VK_SHIFT)]);
+    }
+
+    #[test]
+    fn test_sequence_success_releases_held_keys() {
+        let params = sequence_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "key", value: "Shift", action: "press" }
+        - { type: "key", value: "A", action: "press" }
+        - { type: "key", value: "A", action: "release" }
+"#);
+        let injector = RecordingInjector::default();
+
+        execute_sequence_with(&params, &injector, &RunControl::new()).unwrap();
+        assert_eq!(
+            injector.key_events(),
+            vec![
+                Injected::Down(VK_SHIFT),
+                Injected::Down(0x41),
+                Injected::Up(0x41),
+                Injected::Up(VK_SHIFT),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_release_all_step_releases_held_keys_in_reverse() {
+        let params = sequence_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "key", value: "A", action: "press" }
+        - { type: "key", value: "B", action: "press" }
+        - { type: "key", value: "C", action: "press" }
+        - { type: "release_all" }
+        - { type: "key", value: "D" }
+"#);
+        let injector = RecordingInjector::default();
+
+        execute_sequence_with(&params, &injector, &RunControl::new()).unwrap();
+        assert_eq!(
+            injector.key_events(),
+            vec![
+                Injected::Down(0x41),
+                Injected::Down(0x42),
+                Injected::Down(0x43),
+                Injected::Up(0x43),
+                Injected::Up(0x42),
+                Injected::Up(0x41),
+                Injected::Down(0x44),
+                Injected::Up(0x44),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_code_steps_bypass_virtual_keys() {
+        let params = sequence_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "scan_code", code: 0x1E }
+        - { type: "scan_code", code: 0x1D, extended: true, action: "press" }
+        - { type: "key", value: "Shift", action: "press" }
+"#);
+        let injector = RecordingInjector::default();
+
+        // 扫描码按下的按键同样在序列结束时按相反顺序释放，释放时保留扩展键标记
+        execute_sequence_with(&params, &injector, &RunControl::new()).unwrap();
+        assert_eq!(
+            injector.key_events(),
+            vec![
+                Injected::ScanDown(0x1E, false),
+                Injected::ScanUp(0x1E, false),
+                Injected::ScanDown(0x1D, true),
+                Injected::Down(VK_SHIFT),
+                Injected::Up(VK_SHIFT),
+                Injected::ScanUp(0x1D, true),
+            ]
+        );
+    }
+
+    /// 每次等待后执行回调的注入器，用于在序列中途暂停或中止
+    struct SleepHook<'a> {
+        inner: &'a RecordingInjector,
+        on_sleep: &'a (dyn Fn() + Sync),
+    }
+
+    impl KeyInjector for SleepHook<'_> {
+        fn key_down(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+            self.inner.key_down(vk)
+        }
+
+        fn key_up(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+            self.inner.key_up(vk)
+        }
+
+        fn unicode_char(&self, ch: char) -> Result<(), Box<dyn std::error::Error>> {
+            self.inner.unicode_char(ch)
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.inner.sleep(duration);
+            (self.on_sleep)();
+        }
+
+        fn set_clipboard_text(&self, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+            self.inner.set_clipboard_text(text)
+        }
+
+        fn caps_lock_on(&self) -> bool {
+            self.inner.caps_lock_on()
+        }
+    }
+
+    /// 等待时真正阻塞在运行控制上的注入器，用于验证等待期间的中止
+    struct BlockingSleep<'a> {
+        inner: &'a RecordingInjector,
+    }
+
+    impl KeyInjector for BlockingSleep<'_> {
+        fn key_down(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+            self.inner.key_down(vk)
+        }
+
+        fn key_up(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+            self.inner.key_up(vk)
+        }
+
+        fn unicode_char(&self, ch: char) -> Result<(), Box<dyn std::error::Error>> {
+            self.inner.unicode_char(ch)
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.inner.sleep(duration);
+        }
+
+        fn interruptible_sleep(&self, duration: Duration, control: &RunControl) -> Result<(), Box<dyn std::error::Error>> {
+            self.inner.sleep(duration);
+            control.sleep(duration)
+        }
+
+        fn set_clipboard_text(&self, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+            self.inner.set_clipboard_text(text)
+        }
+
+        fn caps_lock_on(&self) -> bool {
+            self.inner.caps_lock_on()
+        }
+    }
+
+    #[test]
+    fn test_abort_interrupts_wait_and_start_delay() {
+        let long_wait = |start_delay: u64, wait: u64| sequence_params(&format!(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      start_delay: {}
+      steps:
+        - {{ type: "key", value: "Shift", action: "press" }}
+        - {{ type: "wait", value: {} }}
+        - {{ type: "key", value: "A" }}
+"#, start_delay, wait));
+
+        // 在 wait 步骤中中止时释放已按下的 Shift；在 start_delay 中中止时不发送任何按键
+        let cases = [
+            (long_wait(0, 60_000), vec![Injected::Down(VK_SHIFT), Injected::Up(VK_SHIFT)]),
+            (long_wait(60_000, 0), vec![]),
+        ];
+        for (params, expected) in cases {
+            let recorder = RecordingInjector::default();
+            let injector = BlockingSleep { inner: &recorder };
+            let control = RunControl::new();
+            let started = Instant::now();
+
+            std::thread::scope(|s| {
+                let handle = s.spawn(|| execute_sequence_with(&params, &injector, &control).is_ok());
+                std::thread::sleep(Duration::from_millis(30));
+                control.abort();
+                assert!(!handle.join().unwrap());
+            });
+
+            // 不等满 60 秒即结束
+            assert!(started.elapsed() < Duration::from_secs(10));
+            assert_eq!(recorder.key_events(), expected);
+        }
+    }
+
+    const PAUSE_SEQUENCE: &str = r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "key", value: "Shift", action: "press" }
+        - { type: "wait", value: 10 }
+        - { type: "key", value: "A" }
+"#;
+
+    #[test]
+    fn test_paused_sequence_waits_for_resume() {
+        let params = sequence_params(PAUSE_SEQUENCE);
+        let recorder = RecordingInjector::default();
+        let control = RunControl::new();
+        let pause = || {
+            control.toggle_pause();
+        };
+        let injector = SleepHook { inner: &recorder, on_sleep: &pause };
+
+        std::thread::scope(|s| {
+            let handle = s.spawn(|| execute_sequence_with(&params, &injector, &control).is_ok());
+
+            // 等待步骤 2 触发暂停，之后的步骤在继续前都不应执行
+            while !control.is_paused() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            std::thread::sleep(Duration::from_millis(30));
+            assert!(!handle.is_finished());
+            assert_eq!(recorder.key_events(), vec![Injected::Down(VK_SHIFT)]);
+
+            control.toggle_pause();
+            assert!(handle.join().unwrap());
+        });
+
+        assert_eq!(
+            recorder.key_events(),
+            vec![
+                Injected::Down(VK_SHIFT),
+                Injected::Down(0x41),
+                Injected::Up(0x41),
+                Injected::Up(VK_SHIFT),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aborted_sequence_releases_held_keys() {
+        let params = sequence_params(PAUSE_SEQUENCE);
+        let recorder = RecordingInjector::default();
+        let control = RunControl::new();
+        let abort = || control.abort();
+        let injector = SleepHook { inner: &recorder, on_sleep: &abort };
+
+        assert!(execute_sequence_with(&params, &injector, &control).is_err());
+        assert_eq!(recorder.key_events(), vec![Injected::Down(VK_SHIFT), Injected::Up(VK_SHIFT)]);
+    }
+
+    #[test]
+    fn test_disabling_macros_stops_sequence_between_steps() {
+        let params = sequence_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "key", value: "Shift", action: "press" }
+        - { type: "key", value: "A" }
+        - { type: "wait", value: 10 }
+        - { type: "key", value: "B" }
+        - { type: "text", value: "c" }
+"#);
+        let recorder = RecordingInjector::default();
+        let enabled = std::sync::Mutex::new(true);
+        let control = RunControl::new();
+        let disable = || crate::macros::set_enabled_in(&enabled, &control, false);
+        let injector = SleepHook { inner: &recorder, on_sleep: &disable };
+
+        let result = execute_sequence_with(&params, &injector, &control);
+
+        // 关闭宏之后的步骤都不执行，按住的 Shift 被释放
+        assert!(result.is_err());
+        assert!(!*enabled.lock().unwrap());
+        assert_eq!(
+            recorder.key_events(),
+            vec![
+                Injected::Down(VK_SHIFT),
+                Injected::Down(0x41),
+                Injected::Up(0x41),
+                Injected::Up(VK_SHIFT),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wait_release_blocks_until_trigger_released() {
+        let params = sequence_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "key", value: "Shift", action: "press" }
+        - { type: "wait_release", timeout_ms: 5000 }
+        - { type: "key", value: "Shift", action: "release" }
+"#);
+        let recorder = RecordingInjector::default();
+        let held = HeldTriggers::new();
+        held.press("F1");
+        let trigger = Trigger { key_name: "F1", held: &held };
+
+        let started = Instant::now();
+        let result = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(Duration::from_millis(50));
+                held.release("F1");
+            });
+            execute_triggered_sequence_with(&params, &recorder, &RunControl::new(), Some(trigger), &Variables::new(), None, DurationSpec::ZERO)
+        });
+
+        // 松开触发键后立即继续，不会等到超时
+        assert!(result.is_ok());
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(50) && elapsed < Duration::from_secs(5), "{:?}", elapsed);
+        assert_eq!(recorder.key_events(), vec![Injected::Down(VK_SHIFT), Injected::Up(VK_SHIFT)]);
+
+        // 没有触发键时直接跳过
+        assert!(execute_sequence_with(&params, &recorder, &RunControl::new()).is_ok());
+    }
+
+    #[test]
+    fn test_set_and_inc_var() {
+        let params = sequence_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "set_var", name: "count", value: 5 }
+        - { type: "inc_var", name: "count" }
+        - { type: "inc_var", name: "count", by: -3 }
+        - { type: "inc_var", name: "other", by: 2 }
+"#);
+        let vars = Variables::new();
+        let recorder = RecordingInjector::default();
+        execute_triggered_sequence_with(&params, &recorder, &RunControl::new(), None, &vars, None, DurationSpec::ZERO).unwrap();
+        assert_eq!(vars.get("count"), 3);
+        assert_eq!(vars.get("other"), 2);
+        assert!(recorder.events().is_empty());
+    }
+
+    #[test]
+    fn test_datetime_types_formatted_clock() {
+        fn fixed_now() -> LocalDateTime {
+            LocalDateTime { year: 2026, month: 3, day: 7, weekday: 6, hour: 14, minute: 5, second: 9, millisecond: 0 }
+        }
+        let params = sequence_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "datetime", format: "[%Y-%m-%d %H:%M] " }
+"#);
+        let recorder = RecordingInjector::default();
+        let (control, vars) = (RunControl::new(), Variables::new());
+        let runner = StepRunner { params: &params, injector: &recorder, control: &control, trigger: None, vars: &vars, clock: fixed_now, progress: None, default_key_hold: DurationSpec::ZERO };
+        runner.run(&params.steps, &mut HeldKeys::new(&recorder), true).unwrap();
+
+        // 与直接输入格式化后的文本相同
+        let expected = RecordingInjector::default();
+        let text = SequenceParams { steps: vec![Step::Text { value: "[2026-03-07 14:05] ".to_string(), delay: None }], ..params.clone() };
+        execute_sequence_with(&text, &expected, &RunControl::new()).unwrap();
+        assert_eq!(recorder.events(), expected.events());
+
+        let err = Config::from_str("hotkeys:\n  - type: keyboard\n    key: \"F1\"\n    action: \"sequence\"\n    params:\n      steps:\n        - { type: \"datetime\", format: \"%Q\" }\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("无效的日期时间格式 '%Q'"), "{}", err);
+    }
+
+    /// 变量 `toggle` 为 0 时按 A 并置为 1，否则按 B 并置为 0
+    const TOGGLE_SEQUENCE: &str = r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - type: "if_var"
+          name: "toggle"
+          equals: 0
+          then:
+            - { type: "key", value: "A" }
+            - { type: "set_var", name: "toggle", value: 1 }
+          else:
+            - { type: "key", value: "B" }
+            - { type: "set_var", name: "toggle", value: 0 }
+"#;
+
+    #[test]
+    fn test_if_var_alternates_between_executions() {
+        let params = sequence_params(TOGGLE_SEQUENCE);
+        let vars = Variables::new();
+        let run = || {
+            let recorder = RecordingInjector::default();
+            execute_triggered_sequence_with(&params, &recorder, &RunControl::new(), None, &vars, None, DurationSpec::ZERO).unwrap();
+            recorder.key_events()
+        };
+
+        let (a, b) = (0x41, 0x42);
+        assert_eq!(run(), vec![Injected::Down(a), Injected::Up(a)]);
+        assert_eq!(run(), vec![Injected::Down(b), Injected::Up(b)]);
+        assert_eq!(run(), vec![Injected::Down(a), Injected::Up(a)]);
+
+        // 不共享存储时每次都从 0 开始
+        let recorder = RecordingInjector::default();
+        execute_sequence_with(&params, &recorder, &RunControl::new()).unwrap();
+        assert_eq!(recorder.key_events(), vec![Injected::Down(a), Injected::Up(a)]);
+    }
+
+    #[test]
+    fn test_hotkeys_use_same_variable_name_independently() {
+        let params = sequence_params(TOGGLE_SEQUENCE);
+        let stores = HotkeyVariables::new();
+        let run = |key_name: &str| {
+            let recorder = RecordingInjector::default();
+            let vars = stores.for_key(key_name);
+            execute_triggered_sequence_with(&params, &recorder, &RunControl::new(), None, &vars, None, DurationSpec::ZERO).unwrap();
+            recorder.key_events()
+        };
+
+        let (a, b) = (0x41, 0x42);
+        assert_eq!(run("F1"), vec![Injected::Down(a), Injected::Up(a)]);
+        // F2 的 toggle 不受 F1 影响
+        assert_eq!(run("F2"), vec![Injected::Down(a), Injected::Up(a)]);
+        assert_eq!(run("F1"), vec![Injected::Down(b), Injected::Up(b)]);
+        assert_eq!(run("F2"), vec![Injected::Down(b), Injected::Up(b)]);
+    }
+
+    #[test]
+    fn test_sequence_reports_top_level_step_progress() {
+        let params = sequence_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - { type: "key", value: "A" }
+        - type: "if_var"
+          name: "x"
+          equals: 0
+          then:
+            - { type: "key", value: "B" }
+            - { type: "key", value: "C" }
+        - { type: "wait", value: 10 }
+"#);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let recorder = RecordingInjector::default();
+        execute_triggered_sequence_with(&params, &recorder, &RunControl::new(), None, &Variables::new(), Some(&sender), DurationSpec::ZERO).unwrap();
+        drop(sender);
+
+        // 分支内的步骤不单独计数
+        let progress: Vec<_> = receiver.iter().collect();
+        assert_eq!(
+            progress,
+            (1..=3).map(|index| SequenceProgress::Step { index, total: 3 }).collect::<Vec<_>>()
+        );
+        assert_eq!(recorder.key_events().len(), 6);
+    }
+
+    #[test]
+    fn test_tap_hold_presses_sleeps_then_releases() {
+        let params = TapHoldParams { key: "Shift".to_string(), hold_ms: 1500, repeat_interval_ms: 0 };
+        let recorder = RecordingInjector::default();
+        execute_tap_hold_with(&params, &recorder, &RunControl::new()).unwrap();
+        assert_eq!(recorder.key_events(), vec![Injected::Down(VK_SHIFT), Injected::Up(VK_SHIFT)]);
+
+        // 按住时长分段等待，每段不超过检查间隔
+        let sleeps = recorder.sleeps();
+        assert_eq!(sleeps.iter().sum::<Duration>(), Duration::from_millis(1500));
+        assert!(sleeps.iter().all(|&d| d <= HOLD_CHECK_INTERVAL));
+
+        let unknown = TapHoldParams { key: "NoSuchKey".to_string(), hold_ms: 10, repeat_interval_ms: 0 };
+        assert!(execute_tap_hold_with(&unknown, &recorder, &RunControl::new()).is_err());
+    }
+
+    #[test]
+    fn test_tap_hold_reinjects_press_at_repeat_interval() {
+        let ms = Duration::from_millis;
+        // 每次按下前累计等待的时长，以及总等待时长
+        let press_times = |hold_ms, repeat_interval_ms| {
+            let params = TapHoldParams { key: "Shift".to_string(), hold_ms, repeat_interval_ms };
+            let recorder = RecordingInjector::default();
+            execute_tap_hold_with(&params, &recorder, &RunControl::new()).unwrap();
+            assert_eq!(recorder.events().last(), Some(&Injected::Up(VK_SHIFT)));
+
+            let mut waited = Duration::ZERO;
+            let mut presses = Vec::new();
+            for event in recorder.events() {
+                match event {
+                    Injected::Sleep(d) => waited += d,
+                    Injected::Down(VK_SHIFT) => presses.push(waited),
+                    _ => {}
+                }
+            }
+            (presses, waited)
+        };
+
+        assert_eq!(press_times(700, 300), (vec![ms(0), ms(300), ms(600)], ms(700)));
+        assert_eq!(press_times(900, 300), (vec![ms(0), ms(300), ms(600)], ms(900)));
+        assert_eq!(press_times(200, 300), (vec![ms(0)], ms(200)));
+        assert_eq!(press_times(200, 0), (vec![ms(0)], ms(200)));
+    }
+
+    #[test]
+    fn test_tap_hold_does_not_repress_after_abort() {
+        let params = TapHoldParams { key: "Shift".to_string(), hold_ms: 1000, repeat_interval_ms: 10 };
+        let recorder = RecordingInjector::default();
+        let control = RunControl::new();
+        let abort = || control.abort();
+        let injector = SleepHook { inner: &recorder, on_sleep: &abort };
+
+        // 重复间隔到达前已中止，不会再次按下
+        assert!(execute_tap_hold_with(&params, &injector, &control).is_err());
+        assert_eq!(recorder.key_events(), vec![Injected::Down(VK_SHIFT), Injected::Up(VK_SHIFT)]);
+    }
+
+    #[test]
+    fn test_tap_hold_aborted_mid_hold_releases_key() {
+        let params = TapHoldParams { key: "Shift".to_string(), hold_ms: 10_000, repeat_interval_ms: 0 };
+        let recorder = RecordingInjector::default();
+        let control = RunControl::new();
+        let abort = || control.abort();
+        let injector = SleepHook { inner: &recorder, on_sleep: &abort };
+
+        // 第一段等待后即中止，不会等满按住时长，按键照常释放
+        assert!(execute_tap_hold_with(&params, &injector, &control).is_err());
+        assert_eq!(
+            recorder.events(),
+            vec![Injected::Down(VK_SHIFT), Injected::Sleep(HOLD_CHECK_INTERVAL), Injected::Up(VK_SHIFT)]
+        );
+    }
+
+    #[test]
+    fn test_delay_scale_multiplies_sequence_sleeps() {
+        let params = sequence_params(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      start_delay: 100
+      steps:
+        - type: "key"
+          value: "A"
+          delay: 25
+        - type: "wait"
+          value: 200
+        - type: "text"
+          value: "b"
+          delay: 10
+"#);
+        let total = |permille: u64| {
+            let recorder = RecordingInjector::default();
+            let injector = ScaledInjector::new(&recorder, permille);
+            execute_sequence_with(&params, &injector, &RunControl::new()).unwrap();
+            assert_eq!(recorder.key_events().len(), 4);
+            recorder.sleeps().into_iter().sum::<Duration>()
+        };
+
+        assert_eq!(total(1000), Duration::from_millis(335));
+        assert_eq!(total(4000), Duration::from_millis(1340));
+        assert_eq!(total(500), Duration::from_micros(167_500));
+    }
+
+    fn pixel_wait(timeout_ms: u64) -> PixelWait {
+        PixelWait {
+            x: 10,
+            y: 20,
+            target: Rgb { r: 0x10, g: 0x20, b: 0x30 },
+            tolerance: 4,
+            timeout: Duration::from_millis(timeout_ms),
+        }
+    }
+
+    #[test]
+    fn test_pixel_wait_returns_once_color_matches() {
+        let reads = std::cell::Cell::new(0);
+        let read = |x: i32, y: i32| {
+            assert_eq!((x, y), (10, 20));
+            reads.set(reads.get() + 1);
+            // 第三次读取时在容差范围内
+            Some(if reads.get() < 3 { Rgb { r: 0, g: 0, b: 0 } } else { Rgb { r: 0x14, g: 0x1C, b: 0x30 } })
+        };
+
+        assert!(pixel_wait(5000).run(&RunControl::new(), &read).is_ok());
+        assert_eq!(reads.get(), 3);
+    }
+
+    #[test]
+    fn test_pixel_wait_times_out_and_releases_held_keys() {
+        assert!(pixel_wait(0).run(&RunControl::new(), &|_, _| None).is_err());
+
+        let err = pixel_wait(30)
+            .run(&RunControl::new(), &|_, _| Some(Rgb { r: 0x15, g: 0x20, b: 0x30 }))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("超时") && err.contains("#152030"), "{}", err);
+
+        // 超时的步骤使序列出错结束，之前按下的按键被释放
+        let params = sequence_params(r##"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "sequence"
+    params:
+      steps:
+        - type: "key"
+          value: "Shift"
+          action: "press"
+        - type: "wait_pixel"
+          x: 0
+          y: 0
+          color: "#000000"
+          timeout_ms: 0
+        - type: "key"
+          value: "A"
+"##);
+        let injector = RecordingInjector { screen: Some(Rgb { r: 255, g: 255, b: 255 }), ..Default::default() };
+        assert!(execute_sequence_with(&params, &injector, &RunControl::new()).is_err());
+        assert_eq!(injector.key_events(), vec![Injected::Down(VK_SHIFT), Injected::Up(VK_SHIFT)]);
+
+        // 注入器读取的颜色与等待的颜色相同时不等待，也不读取真实屏幕
+        let injector = RecordingInjector::default();
+        assert!(execute_sequence_with(&params, &injector, &RunControl::new()).is_ok());
+    }
+
+    #[test]
+    fn test_pixel_wait_stops_when_aborted() {
+        let control = RunControl::new();
+        control.abort();
+        assert!(pixel_wait(5000).run(&control, &|_, _| None).is_err());
     }
 }
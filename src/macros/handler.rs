@@ -3,10 +3,17 @@
 //! 负责处理键盘和手柄事件、执行热键动作和管理事件循环
 
 use std::thread;
-use std::sync::mpsc::{Receiver, Sender};
-use crate::config::ActionParams;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+use crate::config::{ActionParams, Config, HotkeyConfig};
 use crate::gamepad::GamepadEvent;
-use crate::macros::{get_config, get_event_sender, get_macro_phase, get_toggle_state, set_macro_phase};
+use crate::macros::{
+    chord_tracker, get_config, get_event_sender, get_macro_phase, get_repeat_pending, get_toggle_state, held_triggers,
+    hook_counters, last_presses, modifier_tracker, set_macro_phase, set_repeat_pending, ChordAction, ChordTracker, Cooldowns,
+    HeldTriggers, HookCounter, HookStats, MacroStats, ModifierTracker, PendingPresses, PressKind, SequenceProgress,
+};
+use crate::winapi::process::ForegroundWindow;
 
 /// 宏执行阶段
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -16,18 +23,59 @@ pub enum MacroPhase {
 }
 
 /// 宏事件类型
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum MacroEvent {
-    HotkeyPressed { key_name: String },
+    /// `window` 为钩子查找热键时使用的前台窗口（只在该键有限定窗口的配置时记录），宏处理线程按同一窗口查找热键
+    HotkeyPressed { key_name: String, window: Option<Arc<ForegroundWindow>> },
     HotkeyReleased { key_name: String },
     GamepadButtonPressed { button: String },
     GamepadButtonReleased { button: String },
+    /// 鼠标侧键，`button` 为触发键名称（如 "MouseX1"），`window` 同 `HotkeyPressed`
+    MouseButtonPressed { button: String, window: Option<Arc<ForegroundWindow>> },
+    MouseButtonReleased { button: String },
+    /// 轻按/长按热键（`tap_action` / `hold_action`）按住达到 `hold_threshold_ms`，由宏处理线程在到达阈值时自行产生
+    HoldThresholdReached { key_name: String },
 }
 
+impl MacroEvent {
+    /// 事件对应的触发键名称（手柄按键带 "GP:" 前缀），用于日志和查找热键配置
+    pub fn key_name(&self) -> String {
+        match self {
+            MacroEvent::HotkeyPressed { key_name, .. } | MacroEvent::HotkeyReleased { key_name } => key_name.clone(),
+            MacroEvent::GamepadButtonPressed { button } | MacroEvent::GamepadButtonReleased { button } => {
+                format!("GP:{}", button)
+            }
+            MacroEvent::MouseButtonPressed { button, .. } | MacroEvent::MouseButtonReleased { button } => button.clone(),
+            MacroEvent::HoldThresholdReached { key_name } => key_name.clone(),
+        }
+    }
+
+    /// 钩子派发按下事件时记录的前台窗口
+    fn window(&self) -> Option<&ForegroundWindow> {
+        match self {
+            MacroEvent::HotkeyPressed { window, .. } | MacroEvent::MouseButtonPressed { window, .. } => window.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// 宏处理线程的名称（便于在调试器和性能分析工具中识别）
+pub(crate) const WORKER_THREAD_NAME: &str = "keymacro-worker";
+
+/// 手柄事件转发线程的名称
+const FORWARDER_THREAD_NAME: &str = "keymacro-forwarder";
+
+/// 宏处理线程处理每个事件前调用的回调（见 `MacroSystem::start_with_observer`）
+pub type EventObserver = Box<dyn Fn(&MacroEvent) + Send>;
+
 /// 启动宏处理线程
 ///
 /// 返回一个 Sender，用于手柄事件转发
-pub fn start_macro_thread() -> Sender<MacroEvent> {
+///
+/// # 参数
+///
+/// * `on_event` - 处理每个事件前在宏处理线程中调用的回调，为 None 时不做额外处理
+pub fn start_macro_thread(on_event: Option<EventObserver>) -> Sender<MacroEvent> {
     use std::sync::mpsc::{self, Sender, Receiver};
 
     let (sender, receiver): (Sender<MacroEvent>, Receiver<MacroEvent>) = mpsc::channel();
@@ -40,58 +88,211 @@ pub fn start_macro_thread() -> Sender<MacroEvent> {
     }
 
     // 启动处理线程
-    thread::spawn(move || {
-        while let Ok(event) = receiver.recv() {
-            // 检查宏是否启用
-            let should_execute = get_toggle_state();
-
-            if should_execute {
-                match event {
-                    MacroEvent::HotkeyPressed { key_name } => {
-                        if let Err(e) = execute_hotkey_action(&key_name) {
-                            log::debug!("执行热键动作失败 ({}): {}", key_name, e);
-                        }
-                    }
-                    MacroEvent::HotkeyReleased { key_name } => {
-                        if let Err(e) = execute_hotkey_release(&key_name) {
-                            log::debug!("执行热键释放失败 ({}): {}", key_name, e);
-                        }
-                    }
-                    MacroEvent::GamepadButtonPressed { button } => {
-                        let key_name = format!("GP:{}", button);
-                        log::debug!("手柄按下事件: button={}, key_name={}", button, key_name);
-                        if let Err(e) = execute_hotkey_action(&key_name) {
-                            log::debug!("执行手柄动作失败 ({}): {}", key_name, e);
-                        }
-                    }
-                    MacroEvent::GamepadButtonReleased { button } => {
-                        let key_name = format!("GP:{}", button);
-                        log::debug!("手柄释放事件: button={}, key_name={}", button, key_name);
-                        if let Err(e) = execute_hotkey_release(&key_name) {
-                            log::debug!("执行手柄释放失败 ({}): {}", key_name, e);
-                        }
-                    }
-                }
+    spawn_named(WORKER_THREAD_NAME, move || {
+        // 宏都在本线程执行，按配置的种子设置本线程的随机数
+        let config = get_config();
+        crate::random::set_seed(config.as_ref().and_then(|c| c.rng_seed));
+        if config.is_some_and(|c| c.high_priority_worker) {
+            match crate::winapi::process::raise_current_thread_priority() {
+                Ok(()) => log::info!("宏处理线程已提高优先级"),
+                Err(e) => log::warn!("提高宏处理线程优先级失败: {}", e),
             }
         }
-    });
+        run_event_loop(receiver, crate::macros::pending_presses(), observed(on_event, handle_macro_event));
+    })
+    .expect("创建宏处理线程失败");
 
     sender
 }
 
+/// 以指定名称启动线程
+fn spawn_named(name: &str, f: impl FnOnce() + Send + 'static) -> std::io::Result<thread::JoinHandle<()>> {
+    thread::Builder::new().name(name.to_string()).spawn(f)
+}
+
+/// 处理事件前先调用 `on_event`（为 None 时直接使用 `handle`）
+fn observed(on_event: Option<EventObserver>, handle: impl Fn(&MacroEvent)) -> impl Fn(&MacroEvent) {
+    move |event| {
+        if let Some(on_event) = &on_event {
+            on_event(event);
+        }
+        handle(event);
+    }
+}
+
+/// 逐个处理宏事件，直到所有发送者都被释放
+///
+/// # 参数
+///
+/// * `receiver` - 宏事件接收者
+/// * `presses` - 尚未判定的轻按/长按按下
+/// * `handle` - 处理单个事件的函数
+///
+/// # 说明
+///
+/// 有尚未判定的按下时，等待事件的超时为其中最早到达阈值的时刻，到期后为到达阈值的按下产生 `HoldThresholdReached` 事件。
+/// 处理单个事件时发生 panic 不会结束循环：记录日志并复位执行状态后继续处理后续事件，
+/// 避免一个出错的宏使所有热键都失效
+fn run_event_loop(receiver: Receiver<MacroEvent>, presses: &PendingPresses, handle: impl Fn(&MacroEvent)) {
+    loop {
+        for key_name in presses.take_expired(Instant::now()) {
+            handle_event_caught(&MacroEvent::HoldThresholdReached { key_name }, &handle);
+        }
+        let received = match presses.next_deadline() {
+            Some(deadline) => receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => receiver.recv().map_err(RecvTimeoutError::from),
+        };
+        match received {
+            Ok(event) => handle_event_caught(&event, &handle),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+/// 处理单个事件，捕获处理过程中的 panic
+fn handle_event_caught(event: &MacroEvent, handle: &impl Fn(&MacroEvent)) {
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| handle(event)));
+    if let Err(payload) = result {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("未知错误");
+        log::error!("处理 {} 的宏事件时发生 panic: {}", event.key_name(), message);
+        set_macro_phase(MacroPhase::Idle);
+        set_repeat_pending(false);
+    }
+}
+
+/// 宏处理线程执行热键动作依据的程序状态
+///
+/// 与 `HookContext` 相同，执行阶段、冷却时间、统计和尚未判定的按下都是普通的内存对象，测试中可以传入新建的实例
+struct ActionContext<'a> {
+    /// 当前生效的配置
+    config: &'a Config,
+    /// 宏是否开启
+    enabled: bool,
+    /// 当前宏执行阶段
+    phase: &'a Mutex<MacroPhase>,
+    cooldowns: &'a Cooldowns,
+    stats: &'a MacroStats,
+    /// 尚未判定为轻按或长按的按下，与事件循环使用同一个实例
+    presses: &'a PendingPresses,
+    /// 执行热键的一个操作（实际运行时为 `run_action`，测试中可以只记录执行的操作）
+    run: &'a dyn Fn(&HotkeyConfig, &ActionParams, &str) -> Result<(), Box<dyn std::error::Error>>,
+}
+
+impl ActionContext<'_> {
+    fn phase(&self) -> MacroPhase {
+        *self.phase.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn set_phase(&self, phase: MacroPhase) {
+        *self.phase.lock().unwrap_or_else(PoisonError::into_inner) = phase;
+    }
+}
+
+/// 按全局状态处理单个宏事件
+fn handle_macro_event(event: &MacroEvent) {
+    let Some(config) = get_config() else {
+        log::debug!("配置未加载，忽略 {} 的事件", event.key_name());
+        return;
+    };
+    let context = ActionContext {
+        config: &config,
+        enabled: get_toggle_state(),
+        phase: crate::macros::macro_phase(),
+        cooldowns: crate::macros::cooldowns(),
+        stats: crate::macros::stats(),
+        presses: crate::macros::pending_presses(),
+        run: &run_action,
+    };
+    handle_event_in(event, &context);
+}
+
+/// 处理单个宏事件（宏禁用时只处理切换宏开关的按下事件）
+fn handle_event_in(event: &MacroEvent, context: &ActionContext) {
+    if !context.enabled && !is_system_toggle_press(event, context.config) {
+        return;
+    }
+
+    match event {
+        MacroEvent::HotkeyPressed { key_name, window } => {
+            if let Err(e) = execute_hotkey_action(context, key_name, window.as_deref()) {
+                log::debug!("执行热键动作失败 ({}): {}", key_name, e);
+            }
+            // 自动重复触发的事件已处理完，允许钩子派发下一次
+            set_repeat_pending(false);
+        }
+        MacroEvent::HotkeyReleased { key_name } => {
+            if let Err(e) = execute_hotkey_release(context, key_name) {
+                log::debug!("执行热键释放失败 ({}): {}", key_name, e);
+            }
+        }
+        MacroEvent::GamepadButtonPressed { button } => {
+            let key_name = format!("GP:{}", button);
+            log::debug!("手柄按下事件: button={}, key_name={}", button, key_name);
+            if let Err(e) = execute_hotkey_action(context, &key_name, None) {
+                log::debug!("执行手柄动作失败 ({}): {}", key_name, e);
+            }
+        }
+        MacroEvent::GamepadButtonReleased { button } => {
+            let key_name = format!("GP:{}", button);
+            log::debug!("手柄释放事件: button={}, key_name={}", button, key_name);
+            if let Err(e) = execute_hotkey_release(context, &key_name) {
+                log::debug!("执行手柄释放失败 ({}): {}", key_name, e);
+            }
+        }
+        MacroEvent::MouseButtonPressed { button, window } => {
+            log::debug!("鼠标侧键按下事件: {}", button);
+            if let Err(e) = execute_hotkey_action(context, button, window.as_deref()) {
+                log::debug!("执行鼠标动作失败 ({}): {}", button, e);
+            }
+        }
+        MacroEvent::MouseButtonReleased { button } => {
+            log::debug!("鼠标侧键释放事件: {}", button);
+            if let Err(e) = execute_hotkey_release(context, button) {
+                log::debug!("执行鼠标释放失败 ({}): {}", button, e);
+            }
+        }
+        MacroEvent::HoldThresholdReached { key_name } => {
+            if let Err(e) = execute_hold_action(context, key_name) {
+                log::debug!("执行长按动作失败 ({}): {}", key_name, e);
+            }
+        }
+    }
+}
+
+/// 事件是否为 `config` 中绑定了 `toggle_system` 的热键按下
+fn is_system_toggle_press(event: &MacroEvent, config: &Config) -> bool {
+    let pressed = matches!(
+        event,
+        MacroEvent::HotkeyPressed { .. } | MacroEvent::GamepadButtonPressed { .. } | MacroEvent::MouseButtonPressed { .. }
+    );
+    pressed && find_hotkey_in(config, &event.key_name(), event.window()).is_some_and(HotkeyConfig::is_system_toggle)
+}
+
 /// 启动手柄事件转发线程
 pub fn start_gamepad_forwarder(gamepad_receiver: Receiver<GamepadEvent>, macro_sender: Sender<MacroEvent>) {
     log::info!("手柄事件转发线程已启动");
-    thread::spawn(move || {
+    spawn_named(FORWARDER_THREAD_NAME, move || {
         while let Ok(event) = gamepad_receiver.recv() {
             log::debug!("转发手柄事件: {:?}", event);
             let macro_event = match event {
                 GamepadEvent::ButtonPressed { button } => {
+                    held_triggers().press(&format!("GP:{}", button));
                     MacroEvent::GamepadButtonPressed { button }
                 }
                 GamepadEvent::ButtonReleased { button } => {
+                    held_triggers().release(&format!("GP:{}", button));
                     MacroEvent::GamepadButtonReleased { button }
                 }
+                // 电量只用于托盘提示，不经过宏处理线程
+                GamepadEvent::BatteryUpdate { controller, level } => {
+                    crate::macros::gamepad_status().update(controller as usize, level);
+                    continue;
+                }
             };
 
             if let Err(e) = macro_sender.send(macro_event) {
@@ -100,64 +301,204 @@ pub fn start_gamepad_forwarder(gamepad_receiver: Receiver<GamepadEvent>, macro_s
             }
         }
         log::warn!("手柄事件转发线程已退出");
-    });
+    })
+    .expect("创建手柄事件转发线程失败");
 }
 
-/// 执行热键动作（按下阶段）
-fn execute_hotkey_action(key_name: &str) -> Result<(), Box<dyn std::error::Error>> {
-    // 检查并设置状态
+/// 执行热键动作（按下阶段），`window` 为钩子派发事件时记录的前台窗口
+fn execute_hotkey_action(context: &ActionContext, key_name: &str, window: Option<&ForegroundWindow>) -> Result<(), Box<dyn std::error::Error>> {
+    let config = context.config;
+
+    // 查找热键配置
+    log::debug!("查找热键配置: {}", key_name);
+    let hotkey_config = find_hotkey_in(config, key_name, window)
+        .ok_or_else(|| {
+            log::debug!("未找到热键配置: {}，可用热键: {:?}", key_name, 
+                config.hotkeys.iter().map(|h| h.key()).collect::<Vec<_>>());
+            format!("未找到热键配置: {}", key_name)
+        })?;
+    
+    // 冷却期间不执行（按键仍被拦截）
+    let now = Instant::now();
+    let cooldown = Duration::from_millis(hotkey_config.cooldown_ms);
+    if !context.cooldowns.is_ready(&hotkey_config.key(), cooldown, now) {
+        log::debug!("热键 {} 冷却中，忽略本次触发", key_name);
+        return Ok(());
+    }
+
+    // 检查并设置状态（开启 repeat_on_hold 的热键在按住期间可以再次执行）
     let can_execute = {
-        let phase = get_macro_phase();
+        let phase = context.phase();
         if phase == MacroPhase::Idle {
-            set_macro_phase(MacroPhase::Executing);
+            context.set_phase(MacroPhase::Executing);
             true
         } else {
-            false
+            hotkey_config.repeat_on_hold
         }
     };
     
     if !can_execute {
         return Ok(());
     }
-    
-    // 获取配置
-    let config = get_config().ok_or("配置未加载")?;
-    
-    // 查找热键配置
-    log::debug!("查找热键配置: {}", key_name);
-    let hotkey_config = config.find_hotkey(key_name)
-        .ok_or_else(|| {
-            log::debug!("未找到热键配置: {}，可用热键: {:?}", key_name, 
-                config.hotkeys.iter().map(|h| h.key()).collect::<Vec<_>>());
-            format!("未找到热键配置: {}", key_name)
-        })?;
-    
-    // 执行动作
-    match hotkey_config.action.as_str() {
-        "type_text" => {
-            if let ActionParams::TypeText(params) = &hotkey_config.params {
-                crate::macros::execute_type_text(params)?;
-            }
+
+    // 轻按/长按热键先记录按下，松开或到达阈值时再执行对应的操作（到达阈值时由事件循环产生 `HoldThresholdReached`）
+    if hotkey_config.tap_or_hold().is_some() {
+        context.presses.press(key_name, Duration::from_millis(hotkey_config.hold_threshold_ms), now);
+        return Ok(());
+    }
+
+    run_hotkey_action(context, hotkey_config, &hotkey_config.params, key_name, now)
+}
+
+/// 执行热键的一个操作，并记入执行统计和冷却时间
+///
+/// # 参数
+///
+/// * `context` - 执行依据的程序状态
+/// * `hotkey_config` - 热键配置
+/// * `params` - 要执行的操作（`params`、`tap_action` 或 `hold_action`）
+/// * `key_name` - 触发键名称
+/// * `now` - 执行时间
+fn run_hotkey_action(
+    context: &ActionContext,
+    hotkey_config: &HotkeyConfig,
+    params: &ActionParams,
+    key_name: &str,
+    now: Instant,
+) -> Result<(), Box<dyn std::error::Error>> {
+    context.stats.record(&hotkey_config.key());
+    context.cooldowns.record(&hotkey_config.key(), now);
+    (context.run)(hotkey_config, params, key_name)
+}
+
+/// 实际执行热键的一个操作
+///
+/// # 说明
+///
+/// 设置了 `name` 的热键在执行前先在屏幕提示中显示名称（开启 `overlay_progress` 时随后被进度提示替换）
+fn run_action(hotkey_config: &HotkeyConfig, params: &ActionParams, key_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(name) = &hotkey_config.name {
+        crate::overlay::show_macro_name(name);
+    }
+    match params {
+        ActionParams::TypeText(params) => crate::macros::execute_type_text(params)?,
+        ActionParams::Sequence(params) => {
+            let progress = crate::macros::progress_sender();
+            with_progress(progress.as_ref(), hotkey_config, key_name, || crate::macros::execute_sequence(params, Some(key_name)))?
         }
-        "sequence" => {
-            if let ActionParams::Sequence(params) = &hotkey_config.params {
-                crate::macros::execute_sequence(params)?;
-            }
+        ActionParams::Chat(params) => {
+            let progress = crate::macros::progress_sender();
+            with_progress(progress.as_ref(), hotkey_config, key_name, || crate::macros::execute_chat(params))?
         }
-        _ => {
-            return Err(format!("未知的动作类型: {}", hotkey_config.action).into());
+        ActionParams::TypeFile(params) => crate::macros::execute_type_file(params)?,
+        ActionParams::TapHold(params) => crate::macros::execute_tap_hold(params)?,
+        ActionParams::None => {
+            log::debug!("热键 {} 是占位绑定，不执行任何操作", key_name);
+        }
+        ActionParams::ToggleSystem => {
+            let enabled = crate::macros::toggle_macro_enabled();
+            log::info!("热键 {} 已{}宏", key_name, if enabled { "开启" } else { "关闭" });
         }
     }
     
     Ok(())
 }
 
+/// 执行序列，有进度通道时在执行前后报告开始和结束（中途出错或中止时同样报告结束）
+///
+/// # 参数
+///
+/// * `progress` - 进度通道（`crate::macros::progress_sender`，未开启 `overlay_progress` 时为 None）
+///
+/// # 说明
+///
+/// 显示的名称为热键的 `name`，未设置时为触发键名称；各步骤的进度由执行器报告
+fn with_progress(
+    progress: Option<&Sender<SequenceProgress>>,
+    hotkey_config: &HotkeyConfig,
+    key_name: &str,
+    run: impl FnOnce() -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(progress) = progress else {
+        return run();
+    };
+    let name = hotkey_config.name.clone().unwrap_or_else(|| key_name.to_string());
+    let _ = progress.send(SequenceProgress::Started { name });
+    let result = run();
+    let _ = progress.send(SequenceProgress::Finished);
+    result
+}
+
+/// 轻按/长按热键按住达到阈值（事件循环已取出该次按下）：执行 `hold_action`
+fn execute_hold_action(context: &ActionContext, key_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let hotkey_config = find_active_hotkey(context.config, key_name).ok_or_else(|| format!("未找到热键配置: {}", key_name))?;
+    let Some((_, hold_action)) = hotkey_config.tap_or_hold() else {
+        return Ok(());
+    };
+    log::debug!("热键 {} 按住达到 {} 毫秒，执行长按操作", key_name, hotkey_config.hold_threshold_ms);
+    run_hotkey_action(context, hotkey_config, hold_action, key_name, Instant::now())
+}
+
+/// 轻按/长按热键松开：尚未到达阈值时执行 `tap_action`（事件循环还未取出到期的按下时按按住时长判定）
+fn execute_tap_action(context: &ActionContext, key_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let now = Instant::now();
+    let Some(kind) = context.presses.release(key_name, now) else {
+        return Ok(());
+    };
+    let hotkey_config = find_active_hotkey(context.config, key_name).ok_or_else(|| format!("未找到热键配置: {}", key_name))?;
+    let Some((tap_action, hold_action)) = hotkey_config.tap_or_hold() else {
+        return Ok(());
+    };
+    let action = match kind {
+        PressKind::Tap => tap_action,
+        PressKind::Hold => hold_action,
+    };
+    run_hotkey_action(context, hotkey_config, action, key_name, now)
+}
+
+/// 查找触发键在当前前台窗口（见 `crate::macros::foreground_window`）中生效的热键配置
+///
+/// 没有配置在当前窗口中生效时返回 None，钩子放行原始按键
+fn find_active_hotkey<'a>(config: &'a Config, key_name: &str) -> Option<&'a HotkeyConfig> {
+    let window = window_for(config, key_name, crate::macros::foreground_window);
+    resolve_hotkey_in(config, key_name, window.as_deref())
+}
+
+/// 查找触发键在 `window` 中生效的热键配置，`window` 为 None 时同 `find_active_hotkey`
+fn find_hotkey_in<'a>(config: &'a Config, key_name: &str, window: Option<&ForegroundWindow>) -> Option<&'a HotkeyConfig> {
+    match window {
+        Some(window) => resolve_hotkey_in(config, key_name, Some(window)),
+        None => find_active_hotkey(config, key_name),
+    }
+}
+
+/// 触发键存在限定窗口（`when_window`）的配置时用 `foreground_window` 读取前台窗口，否则返回 None
+fn window_for(
+    config: &Config,
+    key_name: &str,
+    foreground_window: fn() -> Option<Arc<ForegroundWindow>>,
+) -> Option<Arc<ForegroundWindow>> {
+    config.has_window_variants(key_name).then(foreground_window).flatten()
+}
+
+/// 查找触发键在 `window` 中生效的热键配置（没有前台窗口时按标题和进程名都为空匹配）
+fn resolve_hotkey_in<'a>(config: &'a Config, key_name: &str, window: Option<&ForegroundWindow>) -> Option<&'a HotkeyConfig> {
+    if !config.has_window_variants(key_name) {
+        return config.find_hotkey(key_name);
+    }
+    let (title, process_name) = window.map_or(("", ""), |w| (w.title.as_str(), w.process_name.as_str()));
+    config.resolve_hotkey(key_name, title, process_name)
+}
+
 /// 执行热键释放（清理阶段）
-fn execute_hotkey_release(_key_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+fn execute_hotkey_release(context: &ActionContext, key_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // 轻按/长按热键在松开时才确定是否为轻按，先执行操作再复位执行状态
+    let tap_result = if context.phase() == MacroPhase::Executing { execute_tap_action(context, key_name) } else { Ok(()) };
+
     let should_release = {
-        let phase = get_macro_phase();
+        let phase = context.phase();
         if phase == MacroPhase::Executing {
-            set_macro_phase(MacroPhase::Idle);
+            context.set_phase(MacroPhase::Idle);
             true
         } else {
             false
@@ -165,83 +506,564 @@ fn execute_hotkey_release(_key_name: &str) -> Result<(), Box<dyn std::error::Err
     };
     
     if !should_release {
-        return Ok(());
+        return tap_result;
     }
     
     // 这里可以添加释放按键的逻辑，如果有需要的话
     // 例如，如果某些键在按下后需要保持，在这里释放
     
-    Ok(())
+    tap_result
 }
 
 /// 键盘钩子回调
 ///
-/// 监听低级键盘事件，当按下配置中的热键时触发宏
+/// 监听低级键盘事件，当按下配置中的热键时触发宏；每个事件的处理结果计入钩子统计
 pub unsafe extern "system" fn keyboard_hook_proc(code: i32, wparam: windows::Win32::Foundation::WPARAM, lparam: windows::Win32::Foundation::LPARAM) -> windows::Win32::Foundation::LRESULT {
     use windows::Win32::UI::WindowsAndMessaging::*;
     use windows::Win32::Foundation::LRESULT;
     use crate::winapi::keyboard;
-    
+
+    if code >= 0 && count_hook_event(hook_counters(), |stats| keyboard_event_suppressed(wparam, lparam, stats)) {
+        return LRESULT(1); // 阻止原始事件
+    }
+
+    // 调用下一个钩子
+    keyboard::call_next_hook(HHOOK::default(), code, wparam, lparam)
+}
+
+/// 处理一次钩子事件并计入统计
+///
+/// # 参数
+///
+/// * `stats` - 钩子统计
+/// * `handle` - 处理事件，返回是否阻止原始事件
+///
+/// # 返回
+///
+/// 是否阻止原始事件
+fn count_hook_event(stats: &HookStats, handle: impl FnOnce(&HookStats) -> bool) -> bool {
+    stats.record(HookCounter::Seen);
+    let suppressed = handle(stats);
+    stats.record(if suppressed { HookCounter::Suppressed } else { HookCounter::PassedThrough });
+    suppressed
+}
+
+/// 处理键盘钩子收到的事件：读取事件和程序状态交给 `decide`，再执行它的决定
+///
+/// # 返回
+///
+/// 是否阻止原始事件（为 false 时交给下一个钩子）
+///
+/// # Safety
+///
+/// `lparam` 必须指向有效的 KBDLLHOOKSTRUCT
+unsafe fn keyboard_event_suppressed(
+    wparam: windows::Win32::Foundation::WPARAM,
+    lparam: windows::Win32::Foundation::LPARAM,
+    stats: &HookStats,
+) -> bool {
+    use crate::winapi::keyboard;
+
+    let kb_struct = keyboard::get_keyboard_hook_struct(lparam);
+    let input = HookInput {
+        vk: kb_struct.vkCode,
+        is_down: keyboard::is_key_down(wparam),
+        is_up: keyboard::is_key_up(wparam),
+        is_repeat: keyboard::is_key_down(wparam) && keyboard::is_key_repeat(lparam),
+        is_self_injected: crate::winapi::is_self_injected(kb_struct.dwExtraInfo),
+    };
+
+    // 程序自己发送的按键数量最多，不为它们读取配置
+    let config = if input.is_self_injected { None } else { get_config() };
+    let context = HookContext {
+        config: config.as_deref(),
+        enabled: get_toggle_state(),
+        phase: get_macro_phase(),
+        repeat_pending: get_repeat_pending(),
+        chords: chord_tracker(),
+        held: held_triggers(),
+        last_presses: last_presses(),
+        modifiers: modifier_tracker(),
+        stats,
+        now: Instant::now(),
+        foreground_window: crate::macros::foreground_window,
+        held_modifiers: || crate::keys::held_modifiers(keyboard::is_key_held),
+    };
+
+    match decide(input, &context) {
+        HookDecision::PassThrough => false,
+        HookDecision::Block => true,
+        HookDecision::Dispatch { event, block } => {
+            if input.is_repeat {
+                set_repeat_pending(true);
+            }
+            send_hook_event(stats, event);
+            block
+        }
+        HookDecision::Replay(keys) => {
+            replay_chord_keys(&keys, input.vk as u16);
+            true
+        }
+    }
+}
+
+/// 把钩子中产生的事件发送给宏处理线程，事件队列不可用时计为未派发
+fn send_hook_event(stats: &HookStats, event: MacroEvent) {
+    let sent = get_event_sender().is_some_and(|sender| sender.send(event).is_ok());
+    if !sent {
+        stats.record(HookCounter::Dropped);
+    }
+}
+
+/// 键盘钩子收到的事件（已从 KBDLLHOOKSTRUCT 和消息类型中解析出来）
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HookInput {
+    /// 虚拟键码
+    vk: u32,
+    /// 是否为按下（包括按住 Alt 时的 WM_SYSKEYDOWN）
+    is_down: bool,
+    /// 是否为松开
+    is_up: bool,
+    /// 是否为长按产生的自动重复（只有按下事件可能为 true）
+    is_repeat: bool,
+    /// 是否为程序自己发送的按键
+    is_self_injected: bool,
+}
+
+/// `decide` 依据的程序状态
+///
+/// 组合序列匹配器、触发键状态和统计都是普通的内存对象，测试中可以传入新建的实例
+struct HookContext<'a> {
+    /// 当前生效的配置，尚未加载时为 None
+    config: Option<&'a Config>,
+    /// 宏是否开启
+    enabled: bool,
+    /// 当前宏执行阶段
+    phase: MacroPhase,
+    /// 上一次自动重复派发的事件是否尚未处理完
+    repeat_pending: bool,
+    chords: &'a ChordTracker,
+    held: &'a HeldTriggers,
+    /// 各触发键上一次按下的时间，用于 `repeat_guard_ms` 判断
+    last_presses: &'a Cooldowns,
+    /// 当前按下的修饰键，用于匹配修饰键组合触发键
+    modifiers: &'a ModifierTracker,
+    stats: &'a HookStats,
+    /// 事件发生的时间，用于组合序列超时和 `repeat_guard_ms` 判断
+    now: Instant,
+    /// 读取记录的前台窗口，只在热键限定了窗口（`when_window`）时调用
+    foreground_window: fn() -> Option<Arc<ForegroundWindow>>,
+    /// 读取实际按住的修饰键，只在记录的修饰键状态与事件不符时调用
+    held_modifiers: fn() -> u8,
+}
+
+/// 钩子对键盘事件的决定
+#[derive(Debug, Clone, PartialEq)]
+enum HookDecision {
+    /// 交给下一个钩子
+    PassThrough,
+    /// 阻止原始事件
+    Block,
+    /// 派发事件给宏处理线程；`block` 为 false 时原始事件照常生效（`suppress_triggers: false`）
+    Dispatch { event: MacroEvent, block: bool },
+    /// 重新发送不匹配的组合序列按键，并阻止原始事件
+    Replay(Vec<String>),
+}
+
+impl HookDecision {
+    fn block_if(block: bool) -> Self {
+        if block { HookDecision::Block } else { HookDecision::PassThrough }
+    }
+}
+
+/// 决定键盘钩子如何处理事件
+///
+/// 不调用任何 Windows API，需要的状态都从 `context` 读取：派发、松开触发键时更新 `context.held`，
+/// 忽略的自动重复和未派发的触发计入 `context.stats`；设置重复标记、发送事件和重新发送按键由调用方完成
+fn decide(input: HookInput, context: &HookContext) -> HookDecision {
+    // 检查是否是模拟按键（由我们自己的 simulate_key 发送）
+    // 如果是模拟按键，直接放行，避免死循环
+    if input.is_self_injected {
+        return HookDecision::PassThrough;
+    }
+
+    // 修饰键组合（如 "LShift+RShift"）在宏关闭时也要跟踪修饰键状态，修饰键本身始终放行
+    if let Some(decision) = modifier_hotkey_action(input, context) {
+        return decision;
+    }
+
+    // 宏关闭时只处理切换宏开关的热键（toggle_system），其他按键直接放行
+    let enabled = context.enabled;
+    let Some(config) = context.config.filter(|c| enabled || c.has_system_toggle()) else {
+        return HookDecision::PassThrough;
+    };
+
+    // 构建当前按键字符串（简单实现，支持单键）
+    let key_name = vk_to_key_name(input.vk);
+
+    // 始终放行的按键不拦截、不触发宏，也不参与组合序列匹配
+    if config.is_never_suppressed(&key_name) {
+        return HookDecision::PassThrough;
+    }
+
+    // 组合序列（如 "` 1"）先于单键热键处理，序列中的按键始终被拦截（宏关闭时不处理）
+    let chord_action = if enabled { chord_key_action(config, context, &key_name, input) } else { ChordAction::Continue };
+    match chord_action {
+        ChordAction::Continue => {}
+        ChordAction::Block => return HookDecision::Block,
+        ChordAction::Dispatch(chord) => {
+            let window = window_for(config, &chord, context.foreground_window);
+            if let Some(hotkey) = resolve_hotkey_in(config, &chord, window.as_deref()) {
+                let action = on_hotkey_down(hotkey, false, context.phase, context.repeat_pending);
+                if action == KeyDownAction::Dispatch {
+                    context.held.press(&chord);
+                    return HookDecision::Dispatch { event: MacroEvent::HotkeyPressed { key_name: chord, window }, block: true };
+                }
+                context.stats.record(HookCounter::Dropped);
+            }
+            return HookDecision::Block;
+        }
+        ChordAction::Release(chord) => {
+            context.held.release(&chord);
+            if context.phase == MacroPhase::Executing {
+                return HookDecision::Dispatch { event: MacroEvent::HotkeyReleased { key_name: chord }, block: true };
+            }
+            return HookDecision::Block;
+        }
+        ChordAction::Replay(keys) => return HookDecision::Replay(keys),
+    }
+
+    // 修饰键组合触发键已在前面处理，不按单键匹配，也不拦截修饰键
+    if crate::keys::ModifierChord::parse(&key_name).is_some() {
+        return HookDecision::PassThrough;
+    }
+
+    let window = window_for(config, &key_name, context.foreground_window);
+    let hotkey = resolve_hotkey_in(config, &key_name, window.as_deref()).filter(|h| enabled || h.is_system_toggle());
+    let Some(hotkey) = hotkey else {
+        return HookDecision::PassThrough;
+    };
+
+    // 处理按下事件
+    if input.is_down {
+        if !repeat_guard_passed(config, context, &key_name, input.is_repeat) {
+            context.stats.record(HookCounter::RepeatIgnored);
+            return HookDecision::block_if(config.suppress_triggers);
+        }
+        let decision = decide_hotkey_down(
+            hotkey,
+            input.is_repeat,
+            context.phase,
+            context.repeat_pending,
+            config.suppress_triggers,
+            context.stats,
+        );
+        if !decision.dispatch {
+            return HookDecision::block_if(decision.suppress);
+        }
+        context.held.press(&key_name);
+        HookDecision::Dispatch { event: MacroEvent::HotkeyPressed { key_name, window }, block: decision.suppress }
+    }
+    // 处理松开事件
+    else if input.is_up {
+        // 正在执行的序列可能在等待触发键松开（wait_release），直接通知，不经过事件队列
+        context.held.release(&key_name);
+        // 只有当前正在执行该热键的宏时才发送释放事件
+        // 这样可以防止事件堆积，也能避免处理过期的释放事件
+        if context.phase == MacroPhase::Executing {
+            return HookDecision::Dispatch { event: MacroEvent::HotkeyReleased { key_name }, block: config.suppress_triggers };
+        }
+        HookDecision::block_if(config.suppress_triggers)
+    } else {
+        HookDecision::PassThrough
+    }
+}
+
+/// 更新修饰键状态，并处理修饰键组合触发键
+///
+/// # 返回
+///
+/// 不是 Shift / Ctrl / Alt 或没有需要派发的事件时返回 None，交给后续流程处理
+///
+/// # 说明
+///
+/// 按下修饰键使状态与某个组合完全一致时派发 `HotkeyPressed`；离开已触发的组合（松开其中的键或按下其他修饰键）时
+/// 视为松开触发键，宏执行中时派发 `HotkeyReleased`。修饰键的原始事件始终照常生效。
+///
+/// 记录的状态与事件不符（非自动重复的按下时该键已记录为按下，或松开时该键未记录为按下）说明钩子漏掉了事件，
+/// 此时先按 `held_modifiers` 重新同步其他修饰键的状态再处理本次事件
+fn modifier_hotkey_action(input: HookInput, context: &HookContext) -> Option<HookDecision> {
+    let bit = u16::try_from(input.vk).ok().and_then(crate::keys::modifier_bit)?;
+    let out_of_sync = if input.is_down {
+        !input.is_repeat && context.modifiers.is_pressed(bit)
+    } else {
+        input.is_up && !context.modifiers.is_pressed(bit)
+    };
+    if out_of_sync {
+        let held = (context.held_modifiers)() & !bit;
+        log::debug!("修饰键状态与事件不符，按实际按住的修饰键重新同步: {:#08b}", held);
+        context.modifiers.resync(held);
+    }
+    let active = if input.is_down {
+        context.modifiers.key_down(bit)?
+    } else if input.is_up {
+        context.modifiers.key_up(bit)
+    } else {
+        return None;
+    };
+
+    let released = context
+        .modifiers
+        .take_matched_unless(|name| crate::keys::ModifierChord::parse(name).is_some_and(|chord| chord.matches(active)))
+        .and_then(|name| {
+            context.held.release(&name);
+            (context.phase == MacroPhase::Executing)
+                .then_some(HookDecision::Dispatch { event: MacroEvent::HotkeyReleased { key_name: name }, block: false })
+        });
+    if !input.is_down {
+        return released;
+    }
+
+    let config = context.config?;
+    let name = config.modifier_trigger_for(active)?;
+    let window = window_for(config, &name, context.foreground_window);
+    let hotkey = resolve_hotkey_in(config, &name, window.as_deref()).filter(|h| context.enabled || h.is_system_toggle());
+    let Some(hotkey) = hotkey else {
+        return released;
+    };
+    if on_hotkey_down(hotkey, false, context.phase, context.repeat_pending) != KeyDownAction::Dispatch {
+        context.stats.record(HookCounter::Dropped);
+        return released;
+    }
+    log::debug!("修饰键组合 {} 已按下", name);
+    context.modifiers.set_matched(name.clone());
+    context.held.press(&name);
+    Some(HookDecision::Dispatch { event: MacroEvent::HotkeyPressed { key_name: name, window }, block: false })
+}
+
+/// 按 `repeat_guard_ms` 判断触发键的按下是否为新的按下，并记录本次按下的时间
+///
+/// # 返回
+///
+/// 距同一触发键上一次按下（包括被忽略的按下）不足 `repeat_guard_ms` 时返回 false；
+/// 带有自动重复标记的按下和未设置 `repeat_guard_ms` 时总是返回 true
+///
+/// # 说明
+///
+/// 每次按下都会刷新时间，因此按住期间连续到达的按下事件都被忽略，直到出现超过该间隔的停顿
+fn repeat_guard_passed(config: &Config, context: &HookContext, key_name: &str, is_repeat: bool) -> bool {
+    let guard = Duration::from_millis(config.repeat_guard_ms);
+    if guard.is_zero() {
+        return true;
+    }
+    let passed = is_repeat || context.last_presses.is_ready(key_name, guard, context.now);
+    context.last_presses.record(key_name, context.now);
+    if !passed {
+        log::debug!("{} 距上一次按下不足 {} 毫秒，视为自动重复", key_name, config.repeat_guard_ms);
+    }
+    passed
+}
+
+/// 交给组合序列匹配器处理键盘事件
+///
+/// 配置中没有组合序列时直接返回 `Continue`
+fn chord_key_action(config: &Config, context: &HookContext, key_name: &str, input: HookInput) -> ChordAction {
+    let chords = config.chords();
+    if chords.is_empty() {
+        return ChordAction::Continue;
+    }
+
+    if input.is_down {
+        context.chords.key_down(
+            &chords,
+            key_name,
+            input.is_repeat,
+            context.now,
+            Duration::from_millis(config.chord_timeout_ms),
+            config.chord_replay,
+        )
+    } else if input.is_up {
+        context.chords.key_up(key_name)
+    } else {
+        ChordAction::Continue
+    }
+}
+
+/// 重新发送不匹配的组合序列按键
+///
+/// # 参数
+///
+/// * `keys` - 被拦截的前缀键和当前按键（最后一个）
+/// * `current_vk` - 当前按键的虚拟键码
+///
+/// # 说明
+///
+/// 前缀键按下并释放，当前按键只发送按下，松开事件由用户实际松开按键时产生
+fn replay_chord_keys(keys: &[String], current_vk: u16) {
+    use crate::winapi::keyboard::{self, KeyEventType};
+
+    let prefix = &keys[..keys.len().saturating_sub(1)];
+    let mut inputs = Vec::with_capacity(prefix.len() * 2 + 1);
+    for vk in prefix.iter().filter_map(|k| crate::macros::parse_key_string(k)) {
+        inputs.push(keyboard::key_input(vk, KeyEventType::Press));
+        inputs.push(keyboard::key_input(vk, KeyEventType::Release));
+    }
+    inputs.push(keyboard::key_input(current_vk, KeyEventType::Press));
+
+    if let Err(e) = keyboard::simulate_inputs(&inputs) {
+        log::warn!("重新发送组合序列按键失败: {}", e);
+    }
+}
+
+/// 鼠标钩子回调
+///
+/// 只处理鼠标侧键（XButton1/XButton2），绑定了宏的侧键按下时触发宏，其他鼠标消息直接放行
+///
+/// # Safety
+///
+/// 只能作为 `WH_MOUSE_LL` 钩子回调由系统调用，`lparam` 必须指向有效的 MSLLHOOKSTRUCT
+pub unsafe extern "system" fn mouse_hook_proc(code: i32, wparam: windows::Win32::Foundation::WPARAM, lparam: windows::Win32::Foundation::LPARAM) -> windows::Win32::Foundation::LRESULT {
+    use windows::Win32::UI::WindowsAndMessaging::*;
+    use windows::Win32::Foundation::LRESULT;
+    use crate::winapi::{keyboard, mouse};
+
     if code >= 0 {
-        let kb_struct = keyboard::get_keyboard_hook_struct(lparam);
-        
-        // 检查是否是模拟按键（由我们自己的 simulate_key 发送）
-        // 如果是模拟按键，直接放行，避免死循环
-        if kb_struct.dwExtraInfo == 0x12345678 {
+        let ms_struct = mouse::get_mouse_hook_struct(lparam);
+
+        // 与键盘钩子相同，放行程序自己发送的输入
+        if crate::winapi::is_self_injected(ms_struct.dwExtraInfo) {
             return keyboard::call_next_hook(HHOOK::default(), code, wparam, lparam);
         }
-        
-        // 检查宏是否启用
-        if get_toggle_state() {
-            // 检查是否在配置中
-            if let Some(config) = get_config() {
-                // 构建当前按键字符串（简单实现，支持单键）
-                let key_name = vk_to_key_name(kb_struct.vkCode);
-                
-                if config.find_hotkey(&key_name).is_some() {
-                    // 处理按下事件
-                    if keyboard::is_key_down(wparam) {
-                        // 检查是否是重复事件（长按自动重复）
-                        if keyboard::is_key_repeat(lparam) {
-                            // 是重复事件，忽略，不发送事件，不阻止原始事件
-                            return keyboard::call_next_hook(HHOOK::default(), code, wparam, lparam);
-                        }
-                        
-                        // 检查是否正在执行宏，如果是则丢弃新事件（防止堆积）
-                        if get_macro_phase() != MacroPhase::Idle {
-                            return LRESULT(1); // 阻止原始事件，但不发送新事件
+
+        // 先过滤出侧键消息，鼠标移动等高频消息不读取配置
+        let event = mouse::xbutton_event(wparam, ms_struct.mouseData);
+        let button = event.and_then(|e| crate::keys::mouse_button_name(e.button));
+
+        if let (Some(event), Some(button)) = (event, button) {
+            // 与键盘钩子相同，宏关闭时只处理切换宏开关的热键
+            let enabled = get_toggle_state();
+            if let Some(config) = get_config().filter(|c| enabled || c.has_system_toggle()) {
+                let window = window_for(&config, button, crate::macros::foreground_window);
+                if let Some(hotkey) = resolve_hotkey_in(&config, button, window.as_deref()).filter(|h| enabled || h.is_system_toggle()) {
+                    let button = button.to_string();
+                    if event.pressed {
+                        // 鼠标按键没有自动重复
+                        let action = on_hotkey_down(hotkey, false, get_macro_phase(), get_repeat_pending());
+                        if action == KeyDownAction::Dispatch {
+                            held_triggers().press(&button);
+                            if let Some(sender) = get_event_sender() {
+                                let _ = sender.send(MacroEvent::MouseButtonPressed { button, window });
+                            }
                         }
-                        
-                        // 首次按下且空闲状态，发送事件
-                        if let Some(sender) = get_event_sender() {
-                            let _ = sender.send(MacroEvent::HotkeyPressed { key_name });
+                        if action.blocks_original(config.suppress_triggers) {
+                            return LRESULT(1); // 阻止原始事件
                         }
-                        return LRESULT(1); // 阻止原始事件
-                    }
-                    // 处理松开事件
-                    else if keyboard::is_key_up(wparam) {
-                        // 只有当前正在执行该热键的宏时才发送释放事件
-                        // 这样可以防止事件堆积，也能避免处理过期的释放事件
+                    } else {
+                        held_triggers().release(&button);
                         if get_macro_phase() == MacroPhase::Executing {
                             if let Some(sender) = get_event_sender() {
-                                let _ = sender.send(MacroEvent::HotkeyReleased { key_name });
+                                let _ = sender.send(MacroEvent::MouseButtonReleased { button });
                             }
                         }
-                        return LRESULT(1); // 阻止原始事件
+                        if config.suppress_triggers {
+                            return LRESULT(1); // 阻止原始事件
+                        }
                     }
                 }
             }
         }
     }
-    
-    // 调用下一个钩子
+
     keyboard::call_next_hook(HHOOK::default(), code, wparam, lparam)
 }
 
+/// 热键按下时钩子的处理方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum KeyDownAction {
+    /// 放行原始事件，不触发宏
+    PassThrough,
+    /// 阻止原始事件，不触发宏
+    Block,
+    /// 阻止原始事件并派发 `HotkeyPressed`
+    Dispatch,
+}
+
+impl KeyDownAction {
+    /// 钩子是否应阻止原始按键事件
+    ///
+    /// # 参数
+    ///
+    /// * `suppress_triggers` - 配置中的 `suppress_triggers`，为 false 时触发键始终照常生效
+    fn blocks_original(self, suppress_triggers: bool) -> bool {
+        suppress_triggers && self != KeyDownAction::PassThrough
+    }
+}
+
+/// 钩子对已绑定热键按下事件的处理结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HotkeyDecision {
+    /// 是否派发 `HotkeyPressed`
+    dispatch: bool,
+    /// 是否阻止原始事件
+    suppress: bool,
+}
+
+/// 决定已绑定热键按下时钩子的处理结果，并记录忽略的自动重复和未派发的触发
+///
+/// # 参数
+///
+/// * `hotkey` - 匹配到的热键配置
+/// * `is_repeat` - 是否为长按产生的自动重复事件
+/// * `phase` - 当前宏执行阶段
+/// * `repeat_pending` - 上一次自动重复派发的事件是否尚未处理完
+/// * `suppress_triggers` - 配置中的 `suppress_triggers`
+/// * `stats` - 钩子统计
+fn decide_hotkey_down(
+    hotkey: &HotkeyConfig,
+    is_repeat: bool,
+    phase: MacroPhase,
+    repeat_pending: bool,
+    suppress_triggers: bool,
+    stats: &HookStats,
+) -> HotkeyDecision {
+    let action = on_hotkey_down(hotkey, is_repeat, phase, repeat_pending);
+    match action {
+        KeyDownAction::PassThrough if is_repeat => stats.record(HookCounter::RepeatIgnored),
+        KeyDownAction::Block => stats.record(HookCounter::Dropped),
+        _ => {}
+    }
+    HotkeyDecision { dispatch: action == KeyDownAction::Dispatch, suppress: action.blocks_original(suppress_triggers) }
+}
+
+/// 决定已绑定热键按下时的处理方式
+///
+/// # 参数
+///
+/// * `hotkey` - 匹配到的热键配置
+/// * `is_repeat` - 是否为长按产生的自动重复事件
+/// * `phase` - 当前宏执行阶段
+/// * `repeat_pending` - 上一次自动重复派发的事件是否尚未处理完
+fn on_hotkey_down(hotkey: &HotkeyConfig, is_repeat: bool, phase: MacroPhase, repeat_pending: bool) -> KeyDownAction {
+    if is_repeat {
+        if !hotkey.repeat_on_hold {
+            // 是重复事件，忽略，不发送事件，不阻止原始事件
+            return KeyDownAction::PassThrough;
+        }
+        // 上一次重复触发还在队列中或执行中时不再派发，防止松开后继续执行
+        return if repeat_pending { KeyDownAction::Block } else { KeyDownAction::Dispatch };
+    }
+
+    // 检查是否正在执行宏，如果是则丢弃新事件（防止堆积）
+    if phase != MacroPhase::Idle {
+        return KeyDownAction::Block;
+    }
+
+    // 首次按下且空闲状态，发送事件
+    KeyDownAction::Dispatch
+}
+
 /// 将虚拟键码转换为键名字符串（简单实现）
-fn vk_to_key_name(vk: u32) -> String {
-    use windows::Win32::UI::Input::KeyboardAndMouse::*;
-    
+pub(crate) fn vk_to_key_name(vk: u32) -> String {
     match vk {
         0x41 => "A".to_string(),
         0x42 => "B".to_string(),
@@ -272,16 +1094,756 @@ fn vk_to_key_name(vk: u32) -> String {
         0x30..=0x39 => format!("{}", vk - 0x30),
         0x60..=0x69 => format!("Numpad{}", vk - 0x60),
         0x70..=0x87 => format!("F{}", vk - 0x6F),
-        x if x == VK_OEM_3.0 as u32 => "`".to_string(),
-        x if x == VK_OEM_7.0 as u32 => "'".to_string(),
-        x if x == VK_SPACE.0 as u32 => "Space".to_string(),
-        x if x == VK_RETURN.0 as u32 => "Enter".to_string(),
-        x if x == VK_TAB.0 as u32 => "Tab".to_string(),
-        x if x == VK_BACK.0 as u32 => "Backspace".to_string(),
-        x if x == VK_ESCAPE.0 as u32 => "Escape".to_string(),
-        x if x == VK_SHIFT.0 as u32 => "Shift".to_string(),
-        x if x == VK_CONTROL.0 as u32 => "Ctrl".to_string(),
-        x if x == VK_MENU.0 as u32 => "Alt".to_string(),
-        _ => format!("VK_{:X}", vk),
+        // Space、Enter 等具名按键使用统一的规范名称
+        _ => u16::try_from(vk)
+            .ok()
+            .and_then(crate::keys::named_key_name)
+            .map_or_else(|| format!("VK_{:X}", vk), str::to_string),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::HookStatsSnapshot;
+
+    fn hotkey(repeat_on_hold: bool) -> HotkeyConfig {
+        let yaml = format!(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    repeat_on_hold: {}
+    params:
+      text: "x"
+"#, repeat_on_hold);
+        Config::from_str(&yaml).unwrap().hotkeys.remove(0)
+    }
+
+    /// 模拟一次按下加若干次自动重复，返回派发的事件数
+    fn count_dispatches(hotkey: &HotkeyConfig, repeats: usize) -> usize {
+        let mut dispatched = 0;
+        if on_hotkey_down(hotkey, false, MacroPhase::Idle, false) == KeyDownAction::Dispatch {
+            dispatched += 1;
+        }
+        for _ in 0..repeats {
+            // 每次派发的事件都已被工作线程处理完
+            if on_hotkey_down(hotkey, true, MacroPhase::Executing, false) == KeyDownAction::Dispatch {
+                dispatched += 1;
+            }
+        }
+        dispatched
+    }
+
+    #[test]
+    fn test_hook_stats_count_synthetic_events() {
+        let stats = HookStats::new();
+        let hotkey = hotkey(false);
+        let press = |is_repeat: bool, phase: MacroPhase| {
+            count_hook_event(&stats, |stats| decide_hotkey_down(&hotkey, is_repeat, phase, false, true, stats).suppress)
+        };
+
+        // 首次按下：派发并拦截
+        assert!(press(false, MacroPhase::Idle));
+        // 自动重复：忽略并放行
+        assert!(!press(true, MacroPhase::Executing));
+        assert!(!press(true, MacroPhase::Executing));
+        // 宏执行期间再次按下：丢弃并拦截
+        assert!(press(false, MacroPhase::Executing));
+        // 未绑定的按键直接放行
+        assert!(!count_hook_event(&stats, |_| false));
+
+        assert_eq!(
+            stats.snapshot(),
+            HookStatsSnapshot { seen: 5, suppressed: 2, passed_through: 3, repeat_ignored: 2, dropped: 1 }
+        );
+    }
+
+    #[test]
+    fn test_repeat_ignored_by_default() {
+        let hotkey = hotkey(false);
+        assert_eq!(count_dispatches(&hotkey, 5), 1);
+        assert_eq!(on_hotkey_down(&hotkey, true, MacroPhase::Executing, false), KeyDownAction::PassThrough);
+    }
+
+    #[test]
+    fn test_repeat_on_hold_dispatches_each_repeat() {
+        let hotkey = hotkey(true);
+        assert_eq!(count_dispatches(&hotkey, 5), 6);
+    }
+
+    #[test]
+    fn test_repeat_on_hold_blocks_while_pending() {
+        let hotkey = hotkey(true);
+        assert_eq!(on_hotkey_down(&hotkey, true, MacroPhase::Executing, true), KeyDownAction::Block);
+    }
+
+    #[test]
+    fn test_press_while_executing_is_blocked() {
+        let hotkey = hotkey(false);
+        assert_eq!(on_hotkey_down(&hotkey, false, MacroPhase::Executing, false), KeyDownAction::Block);
+    }
+
+    #[test]
+    fn test_suppress_triggers_controls_blocking() {
+        for action in [KeyDownAction::Block, KeyDownAction::Dispatch] {
+            assert!(action.blocks_original(true), "{:?}", action);
+            assert!(!action.blocks_original(false), "{:?}", action);
+        }
+        assert!(!KeyDownAction::PassThrough.blocks_original(true));
+        assert!(!KeyDownAction::PassThrough.blocks_original(false));
+    }
+
+    /// 发送任何按键时都会 panic 的注入器，模拟出错的宏
+    struct PanickingInjector;
+
+    impl crate::macros::KeyInjector for PanickingInjector {
+        fn key_down(&self, _vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+            panic!("测试注入器: key_down");
+        }
+
+        fn key_up(&self, _vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+            panic!("测试注入器: key_up");
+        }
+
+        fn unicode_char(&self, _ch: char) -> Result<(), Box<dyn std::error::Error>> {
+            panic!("测试注入器: unicode_char");
+        }
+
+        fn sleep(&self, _duration: Duration) {}
+
+        fn set_clipboard_text(&self, _text: &str) -> Result<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+
+        fn caps_lock_on(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_event_loop_survives_panicking_action() {
+        let config = Config::from_str(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "boom"
+"#).unwrap();
+        let ActionParams::TypeText(params) = &config.hotkeys[0].params else {
+            panic!("Expected TypeText params");
+        };
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        for key_name in ["F1", "F2", "F1", "F3"] {
+            sender.send(MacroEvent::HotkeyPressed { key_name: key_name.to_string(), window: None }).unwrap();
+        }
+        drop(sender);
+
+        let handled = std::sync::Mutex::new(Vec::new());
+        run_event_loop(receiver, &PendingPresses::new(), |event| {
+            let key_name = event.key_name();
+            if key_name == "F1" {
+                set_macro_phase(MacroPhase::Executing);
+                let _ = crate::macros::execute_type_text_with(params, &PanickingInjector);
+            }
+            handled.lock().unwrap().push(key_name);
+        });
+
+        // panic 的事件不计入，之后的事件照常处理，执行状态已复位
+        assert_eq!(handled.into_inner().unwrap(), vec!["F2", "F3"]);
+        assert_eq!(get_macro_phase(), MacroPhase::Idle);
+    }
+
+    #[test]
+    fn test_event_loop_reports_hold_threshold_without_events() {
+        let presses = PendingPresses::new();
+        presses.press("F1", Duration::from_millis(20), Instant::now());
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let (handled_sender, handled) = std::sync::mpsc::channel();
+        thread::scope(|scope| {
+            scope.spawn(|| run_event_loop(receiver, &presses, |event| {
+                let _ = handled_sender.send(event.clone());
+            }));
+            // 没有新的事件时同样在到达阈值后判定为长按
+            let event = handled.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert_eq!(event, MacroEvent::HoldThresholdReached { key_name: "F1".to_string() });
+            drop(sender);
+        });
+        assert_eq!(presses.next_deadline(), None);
+    }
+
+    /// `ActionContext` 使用的状态，执行的操作只记录下来，不发送按键
+    struct ActionFixture {
+        config: Config,
+        enabled: bool,
+        phase: Mutex<MacroPhase>,
+        cooldowns: Cooldowns,
+        stats: MacroStats,
+        presses: PendingPresses,
+        ran: Arc<Mutex<Vec<ActionParams>>>,
+        run: Box<dyn Fn(&HotkeyConfig, &ActionParams, &str) -> Result<(), Box<dyn std::error::Error>> + Send + Sync>,
+    }
+
+    impl ActionFixture {
+        fn new(yaml: &str) -> Self {
+            let ran = Arc::new(Mutex::new(Vec::new()));
+            let recorded = Arc::clone(&ran);
+            Self {
+                config: Config::from_str(yaml).unwrap(),
+                enabled: true,
+                phase: Mutex::new(MacroPhase::Idle),
+                cooldowns: Cooldowns::new(),
+                stats: MacroStats::new(),
+                presses: PendingPresses::new(),
+                ran,
+                run: Box::new(move |_, params, _| {
+                    recorded.lock().unwrap().push(params.clone());
+                    Ok(())
+                }),
+            }
+        }
+
+        fn context(&self) -> ActionContext<'_> {
+            ActionContext {
+                config: &self.config,
+                enabled: self.enabled,
+                phase: &self.phase,
+                cooldowns: &self.cooldowns,
+                stats: &self.stats,
+                presses: &self.presses,
+                run: &*self.run,
+            }
+        }
+
+        fn handle(&self, event: MacroEvent) {
+            handle_event_in(&event, &self.context());
+        }
+
+        /// 已执行的操作
+        fn ran(&self) -> Vec<ActionParams> {
+            self.ran.lock().unwrap().clone()
+        }
+    }
+
+    fn key_pressed(key_name: &str) -> MacroEvent {
+        MacroEvent::HotkeyPressed { key_name: key_name.to_string(), window: None }
+    }
+
+    fn key_released(key_name: &str) -> MacroEvent {
+        MacroEvent::HotkeyReleased { key_name: key_name.to_string() }
+    }
+
+    #[test]
+    fn test_event_loop_runs_tap_and_hold_actions() {
+        let fixture = ActionFixture::new(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    hold_threshold_ms: 200
+    tap_action: { action: "type_text", params: { text: "tap" } }
+    hold_action: { action: "type_text", params: { text: "hold" } }
+"#);
+        let (tap_action, hold_action) = fixture.config.hotkeys[0].tap_or_hold().unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        thread::scope(|scope| {
+            scope.spawn(|| run_event_loop(receiver, &fixture.presses, |event| handle_event_in(event, &fixture.context())));
+
+            // 立即松开为轻按
+            sender.send(key_pressed("F1")).unwrap();
+            sender.send(key_released("F1")).unwrap();
+
+            // 按住不放时由事件循环在到达阈值后执行长按操作，之后松开不再执行
+            sender.send(key_pressed("F1")).unwrap();
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while fixture.ran().len() < 2 && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(5));
+            }
+            sender.send(key_released("F1")).unwrap();
+            drop(sender);
+        });
+
+        assert_eq!(fixture.ran(), vec![tap_action.clone(), hold_action.clone()]);
+        assert_eq!(*fixture.phase.lock().unwrap(), MacroPhase::Idle);
+    }
+
+    #[test]
+    fn test_event_observer_sees_dispatched_events() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let observer_seen = Arc::clone(&seen);
+        let observer: EventObserver = Box::new(move |event: &MacroEvent| {
+            observer_seen.lock().unwrap().push(format!("观察 {}", event.key_name()));
+        });
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        // 没有绑定的按键同样经过回调
+        sender.send(key_released("F24")).unwrap();
+        sender.send(key_pressed("F1")).unwrap();
+        drop(sender);
+        run_event_loop(receiver, &PendingPresses::new(), observed(Some(observer), |event: &MacroEvent| {
+            seen.lock().unwrap().push(format!("处理 {}", event.key_name()));
+        }));
+
+        // 每个事件先经过回调再处理
+        assert_eq!(*seen.lock().unwrap(), vec!["观察 F24", "处理 F24", "观察 F1", "处理 F1"]);
+    }
+
+    #[test]
+    fn test_spawned_thread_is_named() {
+        let handle = spawn_named(WORKER_THREAD_NAME, || {
+            assert_eq!(thread::current().name(), Some(WORKER_THREAD_NAME));
+        })
+        .unwrap();
+        assert_eq!(handle.thread().name(), Some(WORKER_THREAD_NAME));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_toggle_system_handled_while_disabled() {
+        let mut fixture = ActionFixture::new(r#"
+hotkeys:
+  - type: gamepad
+    key: "Back"
+    action: "toggle_system"
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "x"
+"#);
+        let toggle = MacroEvent::GamepadButtonPressed { button: "Back".to_string() };
+        assert!(is_system_toggle_press(&toggle, &fixture.config));
+        assert!(!is_system_toggle_press(&key_pressed("F1"), &fixture.config));
+        assert!(!is_system_toggle_press(&MacroEvent::GamepadButtonReleased { button: "Back".to_string() }, &fixture.config));
+
+        // 宏关闭时只处理切换宏开关的热键
+        fixture.enabled = false;
+        fixture.handle(key_pressed("F1"));
+        fixture.handle(toggle.clone());
+        assert_eq!(fixture.ran(), vec![ActionParams::ToggleSystem]);
+        fixture.handle(MacroEvent::GamepadButtonReleased { button: "Back".to_string() });
+        assert_eq!(*fixture.phase.lock().unwrap(), MacroPhase::Idle);
+
+        fixture.enabled = true;
+        fixture.handle(key_pressed("F1"));
+        assert_eq!(fixture.ran().len(), 2);
+    }
+
+    #[test]
+    fn test_cooldown_ignores_second_press() {
+        let fixture = ActionFixture::new(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    cooldown_ms: 60000
+    params:
+      text: "x"
+  - type: keyboard
+    key: "F2"
+    action: "type_text"
+    params:
+      text: "y"
+"#);
+        // 冷却期间再次按下不执行，其他热键不受影响
+        for key_name in ["F1", "F1", "F2", "F2"] {
+            fixture.handle(key_pressed(key_name));
+            fixture.handle(key_released(key_name));
+        }
+        let params = |i: usize| fixture.config.hotkeys[i].params.clone();
+        assert_eq!(fixture.ran(), vec![params(0), params(1), params(1)]);
+        assert_eq!(*fixture.phase.lock().unwrap(), MacroPhase::Idle);
+    }
+
+    #[test]
+    fn test_executed_actions_are_counted() {
+        let fixture = ActionFixture::new(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    action: "type_text"
+    params:
+      text: "x"
+  - type: keyboard
+    key: "F2"
+    hold_threshold_ms: 200
+    tap_action: { action: "type_text", params: { text: "tap" } }
+    hold_action: { action: "type_text", params: { text: "hold" } }
+"#);
+        for key_name in ["F1", "F1", "F2"] {
+            fixture.handle(key_pressed(key_name));
+            fixture.handle(key_released(key_name));
+        }
+        // 没有配置的按键不计入统计
+        fixture.handle(key_pressed("F24"));
+
+        assert_eq!(fixture.stats.snapshot(), vec![("F1".to_string(), 2), ("F2".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_with_progress_reports_finished_on_error() {
+        let config = Config::from_str(r#"
+hotkeys:
+  - type: keyboard
+    key: "F1"
+    name: "连招"
+    action: "none"
+  - type: keyboard
+    key: "F2"
+    action: "none"
+"#).unwrap();
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        let result = with_progress(Some(&sender), &config.hotkeys[0], "F1", || Err("步骤失败".into()));
+        assert_eq!(result.unwrap_err().to_string(), "步骤失败");
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            vec![SequenceProgress::Started { name: "连招".to_string() }, SequenceProgress::Finished]
+        );
+
+        // 未设置 name 时显示触发键名称
+        assert!(with_progress(Some(&sender), &config.hotkeys[1], "F2", || Ok(())).is_ok());
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            vec![SequenceProgress::Started { name: "F2".to_string() }, SequenceProgress::Finished]
+        );
+
+        // 没有进度通道时只执行
+        let mut ran = false;
+        assert!(with_progress(None, &config.hotkeys[1], "F2", || { ran = true; Ok(()) }).is_ok());
+        assert!(ran);
+    }
+
+    const VK_F1: u32 = 0x70;
+    const VK_F2: u32 = 0x71;
+    const VK_F3: u32 = 0x72;
+    const VK_F4: u32 = 0x73;
+
+    fn key_down(vk: u32) -> HookInput {
+        HookInput { vk, is_down: true, is_up: false, is_repeat: false, is_self_injected: false }
+    }
+
+    fn key_repeat(vk: u32) -> HookInput {
+        HookInput { is_repeat: true, ..key_down(vk) }
+    }
+
+    fn key_up(vk: u32) -> HookInput {
+        HookInput { vk, is_down: false, is_up: true, is_repeat: false, is_self_injected: false }
+    }
+
+    fn pressed(key_name: &str, block: bool) -> HookDecision {
+        HookDecision::Dispatch { event: MacroEvent::HotkeyPressed { key_name: key_name.to_string(), window: None }, block }
+    }
+
+    fn released(key_name: &str, block: bool) -> HookDecision {
+        HookDecision::Dispatch { event: MacroEvent::HotkeyReleased { key_name: key_name.to_string() }, block }
+    }
+
+    /// 钩子决定所需的状态，各字段可以在测试中直接修改
+    struct HookFixture {
+        config: Config,
+        chords: ChordTracker,
+        held: HeldTriggers,
+        last_presses: Cooldowns,
+        modifiers: ModifierTracker,
+        stats: HookStats,
+        enabled: bool,
+        phase: MacroPhase,
+        repeat_pending: bool,
+        held_modifiers: fn() -> u8,
+    }
+
+    impl HookFixture {
+        fn new(yaml: &str) -> Self {
+            Self {
+                config: Config::from_str(yaml).unwrap(),
+                chords: ChordTracker::new(),
+                held: HeldTriggers::new(),
+                last_presses: Cooldowns::new(),
+                modifiers: ModifierTracker::new(),
+                stats: HookStats::new(),
+                enabled: true,
+                phase: MacroPhase::Idle,
+                repeat_pending: false,
+                held_modifiers: || 0,
+            }
+        }
+
+        fn context(&self) -> HookContext<'_> {
+            self.context_at(Instant::now())
+        }
+
+        fn context_at(&self, now: Instant) -> HookContext<'_> {
+            HookContext {
+                config: Some(&self.config),
+                enabled: self.enabled,
+                phase: self.phase,
+                repeat_pending: self.repeat_pending,
+                chords: &self.chords,
+                held: &self.held,
+                last_presses: &self.last_presses,
+                modifiers: &self.modifiers,
+                stats: &self.stats,
+                now,
+                foreground_window: || None,
+                held_modifiers: self.held_modifiers,
+            }
+        }
+
+        fn decide(&self, input: HookInput) -> HookDecision {
+            decide(input, &self.context())
+        }
+    }
+
+    const HOOK_CONFIG: &str = r#"
+never_suppress: ["F4"]
+hotkeys:
+  - type: keyboard
+    key: ["F1", "F4"]
+    action: "type_text"
+    params:
+      text: "x"
+  - type: keyboard
+    key: "F2"
+    action: "toggle_system"
+"#;
+
+    #[test]
+    fn test_decide_dispatches_bound_key_and_release() {
+        let mut fixture = HookFixture::new(HOOK_CONFIG);
+        assert_eq!(fixture.decide(key_down(VK_F1)), pressed("F1", true));
+        assert!(fixture.held.is_held("F1"));
+
+        // 宏执行中松开：派发释放事件
+        fixture.phase = MacroPhase::Executing;
+        assert_eq!(fixture.decide(key_up(VK_F1)), released("F1", true));
+        assert!(!fixture.held.is_held("F1"));
+
+        // 空闲时松开：只拦截
+        fixture.phase = MacroPhase::Idle;
+        assert_eq!(fixture.decide(key_up(VK_F1)), HookDecision::Block);
+    }
+
+    #[test]
+    fn test_decide_passes_unbound_self_injected_and_never_suppressed_keys() {
+        let fixture = HookFixture::new(HOOK_CONFIG);
+        assert_eq!(fixture.decide(key_down(VK_F3)), HookDecision::PassThrough);
+        assert_eq!(fixture.decide(key_down(VK_F4)), HookDecision::PassThrough);
+        let injected = HookInput { is_self_injected: true, ..key_down(VK_F1) };
+        assert_eq!(fixture.decide(injected), HookDecision::PassThrough);
+
+        // 尚未加载配置
+        let context = HookContext { config: None, ..fixture.context() };
+        assert_eq!(decide(key_down(VK_F1), &context), HookDecision::PassThrough);
+    }
+
+    #[test]
+    fn test_decide_while_disabled_only_handles_system_toggle() {
+        let mut fixture = HookFixture::new(HOOK_CONFIG);
+        fixture.enabled = false;
+        assert_eq!(fixture.decide(key_down(VK_F1)), HookDecision::PassThrough);
+        assert_eq!(fixture.decide(key_down(VK_F2)), pressed("F2", true));
+    }
+
+    #[test]
+    fn test_decide_repeat_and_busy_presses() {
+        let mut fixture = HookFixture::new(HOOK_CONFIG);
+        fixture.phase = MacroPhase::Executing;
+
+        // 未开启 repeat_on_hold 的自动重复放行，执行中的再次按下拦截但不派发
+        assert_eq!(fixture.decide(key_repeat(VK_F1)), HookDecision::PassThrough);
+        assert_eq!(fixture.decide(key_down(VK_F1)), HookDecision::Block);
+        assert!(!fixture.held.is_held("F1"));
+
+        let stats = fixture.stats.snapshot();
+        assert_eq!((stats.repeat_ignored, stats.dropped), (1, 1));
+    }
+
+    #[test]
+    fn test_decide_repeat_guard_ignores_rapid_presses() {
+        let fixture = HookFixture::new(&format!("repeat_guard_ms: 100\n{}", HOOK_CONFIG));
+        let start = Instant::now();
+        let decide_at = |input: HookInput, ms: u64| decide(input, &fixture.context_at(start + Duration::from_millis(ms)));
+
+        // 没有重复标记的连续按下只派发一次，按住期间每次按下都重新计时
+        assert_eq!(decide_at(key_down(VK_F1), 0), pressed("F1", true));
+        assert_eq!(decide_at(key_down(VK_F1), 30), HookDecision::Block);
+        assert_eq!(decide_at(key_down(VK_F1), 120), HookDecision::Block);
+        assert_eq!(fixture.stats.snapshot().repeat_ignored, 2);
+
+        // 停顿超过间隔后的按下和其他触发键照常派发
+        assert_eq!(decide_at(key_down(VK_F1), 300), pressed("F1", true));
+        assert_eq!(decide_at(key_down(VK_F4), 310), HookDecision::PassThrough);
+        assert_eq!(decide_at(key_down(VK_F2), 320), pressed("F2", true));
+
+        // 未设置时同样的连续按下都会派发
+        let fixture = HookFixture::new(HOOK_CONFIG);
+        assert_eq!(fixture.decide(key_down(VK_F1)), pressed("F1", true));
+        assert_eq!(fixture.decide(key_down(VK_F1)), pressed("F1", true));
+    }
+
+    const VK_LSHIFT: u32 = 0xA0;
+    const VK_RSHIFT: u32 = 0xA1;
+    const VK_LCONTROL: u32 = 0xA2;
+    const VK_LMENU: u32 = 0xA4;
+
+    #[test]
+    fn test_decide_modifier_only_hotkeys() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "LShift+RShift"
+    action: "type_text"
+    params:
+      text: "x"
+  - type: keyboard
+    key: "Alt"
+    action: "type_text"
+    params:
+      text: "y"
+"#;
+        let mut fixture = HookFixture::new(yaml);
+
+        // 第二个 Shift 按下时触发，修饰键始终放行，按住的自动重复不再触发
+        assert_eq!(fixture.decide(key_down(VK_LSHIFT)), HookDecision::PassThrough);
+        assert_eq!(fixture.decide(key_down(VK_RSHIFT)), pressed("LShift+RShift", false));
+        assert_eq!(fixture.decide(key_repeat(VK_RSHIFT)), HookDecision::PassThrough);
+        assert!(fixture.held.is_held("LShift+RShift"));
+
+        // 松开其中一个即离开组合，宏执行中时派发释放；回到单个 Shift 不会再次触发
+        fixture.phase = MacroPhase::Executing;
+        assert_eq!(fixture.decide(key_up(VK_RSHIFT)), released("LShift+RShift", false));
+        assert!(!fixture.held.is_held("LShift+RShift"));
+        assert_eq!(fixture.decide(key_up(VK_LSHIFT)), HookDecision::PassThrough);
+        fixture.phase = MacroPhase::Idle;
+
+        // 单独的 Alt 匹配任意一侧；Ctrl+Alt 不是完全一致的组合
+        assert_eq!(fixture.decide(key_down(VK_LMENU)), pressed("Alt", false));
+        assert_eq!(fixture.decide(key_up(VK_LMENU)), HookDecision::PassThrough);
+        assert_eq!(fixture.decide(key_down(VK_LCONTROL)), HookDecision::PassThrough);
+        assert_eq!(fixture.decide(key_down(VK_LMENU)), HookDecision::PassThrough);
+    }
+
+    #[test]
+    fn test_decide_modifier_hotkeys_resync_after_missed_events() {
+        let yaml = r#"
+hotkeys:
+  - type: keyboard
+    key: "Alt"
+    action: "type_text"
+    params:
+      text: "y"
+"#;
+        let mut fixture = HookFixture::new(yaml);
+
+        // 漏掉了 Ctrl 和 Alt 的松开：再次按下 Alt（不是自动重复）时按实际状态重新同步，仍然触发
+        assert_eq!(fixture.decide(key_down(VK_LCONTROL)), HookDecision::PassThrough);
+        assert_eq!(fixture.decide(key_down(VK_LMENU)), HookDecision::PassThrough);
+        assert_eq!(fixture.decide(key_down(VK_LMENU)), pressed("Alt", false));
+        assert!(!fixture.modifiers.is_pressed(1 << 2));
+        assert_eq!(fixture.decide(key_up(VK_LMENU)), HookDecision::PassThrough);
+
+        // 漏掉了 Ctrl 的按下：松开未记录的 Shift 时同步到实际按住的 Ctrl，之后的 Alt 不是完全一致的组合
+        fixture.held_modifiers = || 1 << 2;
+        assert_eq!(fixture.decide(key_up(VK_LSHIFT)), HookDecision::PassThrough);
+        assert!(fixture.modifiers.is_pressed(1 << 2));
+        assert_eq!(fixture.decide(key_down(VK_LMENU)), HookDecision::PassThrough);
+    }
+
+    #[test]
+    fn test_decide_without_suppress_triggers_dispatches_but_passes_original() {
+        let fixture = HookFixture::new(&format!("suppress_triggers: false\n{}", HOOK_CONFIG));
+        assert_eq!(fixture.decide(key_down(VK_F1)), pressed("F1", false));
+        assert_eq!(fixture.decide(key_up(VK_F1)), HookDecision::PassThrough);
+    }
+
+    #[test]
+    fn test_decide_chord_sequence() {
+        let yaml = r#"
+chord_replay: true
+hotkeys:
+  - type: keyboard
+    key: "F2 F3"
+    action: "type_text"
+    params:
+      text: "x"
+"#;
+        let mut fixture = HookFixture::new(yaml);
+
+        // 前缀键拦截，后续按键触发组合序列
+        assert_eq!(fixture.decide(key_down(VK_F2)), HookDecision::Block);
+        assert_eq!(fixture.decide(key_down(VK_F3)), pressed("F2 F3", true));
+        assert!(fixture.held.is_held("F2 F3"));
+        assert_eq!(fixture.decide(key_up(VK_F2)), HookDecision::Block);
+        fixture.phase = MacroPhase::Executing;
+        assert_eq!(fixture.decide(key_up(VK_F3)), released("F2 F3", true));
+        assert!(!fixture.held.is_held("F2 F3"));
+
+        // 不匹配时重新发送已拦截的按键
+        fixture.phase = MacroPhase::Idle;
+        assert_eq!(fixture.decide(key_down(VK_F2)), HookDecision::Block);
+        assert_eq!(fixture.decide(key_down(VK_F1)), HookDecision::Replay(vec!["F2".to_string(), "F1".to_string()]));
+
+        // 宏关闭时不处理组合序列
+        fixture.enabled = false;
+        assert_eq!(fixture.decide(key_down(VK_F2)), HookDecision::PassThrough);
+    }
+
+    #[test]
+    fn test_never_suppress_key_passes_through_when_bound() {
+        let config = Config::from_str(r#"
+never_suppress: ["Esc"]
+hotkeys:
+  - type: keyboard
+    key: ["Escape", "F1"]
+    action: "type_text"
+    params:
+      text: "x"
+"#).unwrap();
+
+        // 钩子上报的键名即使绑定了热键，也在最开始放行
+        let escape = vk_to_key_name(0x1B);
+        assert!(find_active_hotkey(&config, &escape).is_some());
+        assert!(config.is_never_suppressed(&escape));
+        assert!(!config.is_never_suppressed(&vk_to_key_name(0x70)));
+    }
+
+    #[test]
+    fn test_function_key_name_round_trip() {
+        for vk in 0x70..=0x87u32 {
+            let name = vk_to_key_name(vk);
+            assert_eq!(crate::macros::executor::parse_key_string(&name), Some(vk as u16), "{}", name);
+        }
+        assert_eq!(vk_to_key_name(0x70), "F1");
+        assert_eq!(vk_to_key_name(0x87), "F24");
+    }
+
+    #[test]
+    fn test_digit_key_names_round_trip() {
+        // 顶行数字与小键盘数字是不同的按键，键名可以原样用于序列步骤
+        for vk in (0x30..=0x39u32).chain(0x60..=0x69) {
+            let name = vk_to_key_name(vk);
+            assert_eq!(crate::macros::executor::parse_key_string(&name), Some(vk as u16), "{}", name);
+        }
+        assert_eq!(vk_to_key_name(0x65), "Numpad5");
+    }
+
+    #[test]
+    fn test_named_key_names() {
+        assert_eq!(vk_to_key_name(0x0D), "Enter");
+        assert_eq!(vk_to_key_name(0x1B), "Escape");
+        assert_eq!(vk_to_key_name(0x2E), "Delete");
+        assert_eq!(vk_to_key_name(0xFF), "VK_FF");
+    }
+
+    #[test]
+    fn test_windows_key_names_round_trip() {
+        for (vk, name) in [(0x5Bu32, "LWin"), (0x5C, "RWin")] {
+            assert_eq!(vk_to_key_name(vk), name);
+            assert_eq!(crate::macros::executor::parse_key_string(name), Some(vk as u16));
+        }
     }
 }
@@ -0,0 +1,102 @@
+//! 键盘钩子统计模块
+//!
+//! 用原子计数器记录键盘钩子对每个事件的处理结果，排查“按键消失”一类的输入问题时查看
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 钩子统计的计数项
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HookCounter {
+    /// 钩子收到的事件
+    Seen,
+    /// 被拦截的事件（钩子返回 1，原始按键不再传给系统）
+    Suppressed,
+    /// 交给下一个钩子的事件
+    PassedThrough,
+    /// 被忽略的自动重复事件（热键没有开启 `repeat_on_hold`）
+    RepeatIgnored,
+    /// 因宏正在执行、上一次重复尚未处理完或事件队列不可用而没有派发的触发
+    Dropped,
+}
+
+/// 键盘钩子事件计数
+///
+/// 钩子回调中只做 Relaxed 原子加法，不加锁，不影响钩子的响应速度
+#[derive(Debug, Default)]
+pub struct HookStats {
+    seen: AtomicU64,
+    suppressed: AtomicU64,
+    passed_through: AtomicU64,
+    repeat_ignored: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// 某一时刻的钩子统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HookStatsSnapshot {
+    pub seen: u64,
+    pub suppressed: u64,
+    pub passed_through: u64,
+    pub repeat_ignored: u64,
+    pub dropped: u64,
+}
+
+impl HookStats {
+    /// 创建计数全为 0 的统计
+    pub const fn new() -> Self {
+        Self {
+            seen: AtomicU64::new(0),
+            suppressed: AtomicU64::new(0),
+            passed_through: AtomicU64::new(0),
+            repeat_ignored: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// 计数加 1
+    pub fn record(&self, counter: HookCounter) {
+        let value = match counter {
+            HookCounter::Seen => &self.seen,
+            HookCounter::Suppressed => &self.suppressed,
+            HookCounter::PassedThrough => &self.passed_through,
+            HookCounter::RepeatIgnored => &self.repeat_ignored,
+            HookCounter::Dropped => &self.dropped,
+        };
+        value.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 读取当前计数
+    ///
+    /// # 说明
+    ///
+    /// 各计数分别读取，钩子同时在更新时各项之间可能相差一两个事件
+    pub fn snapshot(&self) -> HookStatsSnapshot {
+        HookStatsSnapshot {
+            seen: self.seen.load(Ordering::Relaxed),
+            suppressed: self.suppressed.load(Ordering::Relaxed),
+            passed_through: self.passed_through.load(Ordering::Relaxed),
+            repeat_ignored: self.repeat_ignored.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_each_counter() {
+        let stats = HookStats::new();
+        stats.record(HookCounter::Seen);
+        stats.record(HookCounter::Seen);
+        stats.record(HookCounter::Suppressed);
+        stats.record(HookCounter::PassedThrough);
+        stats.record(HookCounter::Dropped);
+
+        assert_eq!(
+            stats.snapshot(),
+            HookStatsSnapshot { seen: 2, suppressed: 1, passed_through: 1, repeat_ignored: 0, dropped: 1 }
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! 输入法保护模块
+//!
+//! 开启 `ime_safe` 时，输入文本前临时关闭前台窗口的输入法，避免注入的按键被输入法组合截获，
+//! 输入结束后（包括出错时）恢复原来的状态
+
+/// 输入法开启状态的查询和设置
+///
+/// 输入法窗口以句柄值表示，输入期间前台窗口可能切换，恢复时使用关闭时记录的窗口
+pub trait ImeControl {
+    /// 当前前台窗口的输入法窗口，没有前台窗口或没有输入法时返回 None
+    fn foreground_window(&self) -> Option<isize>;
+
+    /// 输入法窗口对应的输入法是否开启，无法查询时返回 None
+    fn is_open(&self, window: isize) -> Option<bool>;
+
+    /// 开启或关闭输入法窗口对应的输入法，返回是否成功
+    fn set_open(&self, window: isize, open: bool) -> bool;
+}
+
+/// 操作前台窗口输入法的实现
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemIme;
+
+impl ImeControl for SystemIme {
+    fn foreground_window(&self) -> Option<isize> {
+        crate::winapi::ime::foreground_ime_window()
+    }
+
+    fn is_open(&self, window: isize) -> Option<bool> {
+        crate::winapi::ime::ime_open_status(window)
+    }
+
+    fn set_open(&self, window: isize, open: bool) -> bool {
+        crate::winapi::ime::set_ime_open_status(window, open)
+    }
+}
+
+/// 输入期间关闭输入法，释放时恢复
+///
+/// 只有输入法原本开启且成功关闭时才会在释放时重新开启；原本关闭或不可用时不做任何事。
+/// 恢复的是关闭时的前台窗口，输入期间切换了前台窗口也不会影响新窗口的输入法
+pub struct ImeGuard<'a> {
+    control: &'a dyn ImeControl,
+    /// 需要在释放时重新开启的输入法窗口
+    restore: Option<isize>,
+}
+
+impl<'a> ImeGuard<'a> {
+    /// 记录前台窗口的输入法状态，开启时临时关闭
+    pub fn suspend(control: &'a dyn ImeControl) -> Self {
+        let restore = control
+            .foreground_window()
+            .filter(|&window| control.is_open(window) == Some(true) && control.set_open(window, false));
+        if restore.is_some() {
+            log::debug!("临时关闭输入法");
+        }
+        Self { control, restore }
+    }
+}
+
+impl Drop for ImeGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(window) = self.restore {
+            if !self.control.set_open(window, true) {
+                log::warn!("恢复输入法状态失败");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
+
+    /// 记录设置操作的输入法，`foreground` 为当前前台窗口
+    struct FakeIme {
+        foreground: Cell<Option<isize>>,
+        open: RefCell<HashMap<isize, bool>>,
+        accept: bool,
+        calls: RefCell<Vec<(isize, bool)>>,
+    }
+
+    impl FakeIme {
+        fn new(open: Option<bool>, accept: bool) -> Self {
+            Self {
+                foreground: Cell::new(Some(1)),
+                open: RefCell::new(open.map(|open| (1, open)).into_iter().collect()),
+                accept,
+                calls: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn is_open_at(&self, window: isize) -> Option<bool> {
+            self.open.borrow().get(&window).copied()
+        }
+    }
+
+    impl ImeControl for FakeIme {
+        fn foreground_window(&self) -> Option<isize> {
+            self.foreground.get()
+        }
+
+        fn is_open(&self, window: isize) -> Option<bool> {
+            self.is_open_at(window)
+        }
+
+        fn set_open(&self, window: isize, open: bool) -> bool {
+            self.calls.borrow_mut().push((window, open));
+            if self.accept {
+                self.open.borrow_mut().insert(window, open);
+            }
+            self.accept
+        }
+    }
+
+    #[test]
+    fn test_guard_closes_open_ime_and_restores_it() {
+        let ime = FakeIme::new(Some(true), true);
+        {
+            let _guard = ImeGuard::suspend(&ime);
+            assert_eq!(ime.is_open_at(1), Some(false));
+        }
+        assert_eq!(ime.is_open_at(1), Some(true));
+        assert_eq!(*ime.calls.borrow(), vec![(1, false), (1, true)]);
+    }
+
+    #[test]
+    fn test_guard_restores_original_window_after_focus_change() {
+        let ime = FakeIme::new(Some(true), true);
+        ime.open.borrow_mut().insert(2, false);
+        {
+            let _guard = ImeGuard::suspend(&ime);
+            ime.foreground.set(Some(2));
+        }
+        // 恢复关闭时的窗口，新的前台窗口不受影响
+        assert_eq!(ime.is_open_at(1), Some(true));
+        assert_eq!(ime.is_open_at(2), Some(false));
+        assert_eq!(*ime.calls.borrow(), vec![(1, false), (1, true)]);
+    }
+
+    #[test]
+    fn test_guard_leaves_closed_or_missing_ime_alone() {
+        for open in [Some(false), None] {
+            let ime = FakeIme::new(open, true);
+            drop(ImeGuard::suspend(&ime));
+            assert_eq!(ime.is_open_at(1), open);
+            assert!(ime.calls.borrow().is_empty());
+        }
+
+        // 没有前台窗口时不做任何事
+        let ime = FakeIme::new(Some(true), true);
+        ime.foreground.set(None);
+        drop(ImeGuard::suspend(&ime));
+        assert!(ime.calls.borrow().is_empty());
+
+        // 关闭失败时不应在释放时重新开启
+        let ime = FakeIme::new(Some(true), false);
+        drop(ImeGuard::suspend(&ime));
+        assert_eq!(*ime.calls.borrow(), vec![(1, false)]);
+    }
+}
@@ -0,0 +1,737 @@
+//! 按键注入模块
+//!
+//! 抽象按键的发送方式，执行器只依赖该接口，便于替换为其他实现（如测试中的记录器）
+
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+use windows::Win32::UI::Input::KeyboardAndMouse::{VK_MENU, VK_NUMPAD0, VK_RETURN, VK_SPACE, VK_TAB};
+use crate::config::Rgb;
+use crate::macros::control::RunControl;
+use crate::winapi::clipboard;
+use crate::winapi::keyboard::{self, KeyEventType};
+
+pub use crate::winapi::keyboard::CharKey;
+
+/// 批量发送中的单个按键事件
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyStroke {
+    /// 按下
+    Down(u16),
+    /// 释放
+    Up(u16),
+}
+
+/// 按美式键盘布局将字符转换为按键
+///
+/// 只支持字母、数字、空格和换行、制表符，其他字符返回 None
+pub fn us_char_key(ch: char) -> Option<CharKey> {
+    match ch {
+        'a'..='z' => Some(CharKey::plain(ch as u16 - 'a' as u16 + 0x41)),
+        'A'..='Z' => Some(CharKey { shift: true, ..CharKey::plain(ch as u16 - 'A' as u16 + 0x41) }),
+        '0'..='9' => Some(CharKey::plain(ch as u16 - '0' as u16 + 0x30)),
+        ' ' => Some(CharKey::plain(VK_SPACE.0)),
+        '\r' | '\n' => Some(CharKey::plain(VK_RETURN.0)),
+        '\t' => Some(CharKey::plain(VK_TAB.0)),
+        _ => None,
+    }
+}
+
+/// 键盘布局查询接口
+///
+/// 把“当前是哪个布局”和“字符在该布局中对应哪个键”分开，便于测试切换输入法后的映射
+pub trait KeyboardLayout {
+    /// 当前生效的布局标识（系统实现为前台窗口的 HKL 句柄值）
+    fn current(&self) -> isize;
+
+    /// 按指定布局将字符转换为按键，该布局中无法用按键输入时返回 None
+    fn char_key(&self, layout: isize, ch: char) -> Option<CharKey>;
+}
+
+/// 系统键盘布局（前台窗口当前使用的输入法）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemLayout;
+
+impl KeyboardLayout for SystemLayout {
+    fn current(&self) -> isize {
+        keyboard::foreground_layout()
+    }
+
+    fn char_key(&self, layout: isize, ch: char) -> Option<CharKey> {
+        keyboard::char_to_layout_key(ch, layout)
+    }
+}
+
+/// 按当前键盘布局将字符转换为按键
+///
+/// # 说明
+///
+/// 每个字符都重新查询当前布局，不缓存：运行期间切换输入语言后，之后输入的字符（包括正在输入的文本的剩余部分）
+/// 立即按新布局映射。没有改为监听 `WM_INPUTLANGCHANGE`，因为该消息只发给切换了输入法的窗口，钩子线程收不到。
+/// 换行和制表符在布局中会映射为 Ctrl+Enter 等组合，直接使用对应的按键
+pub fn layout_char_key(layout: &dyn KeyboardLayout, ch: char) -> Option<CharKey> {
+    match ch {
+        '\r' | '\n' | '\t' => us_char_key(ch),
+        _ => layout.char_key(layout.current(), ch),
+    }
+}
+
+/// 按键注入接口
+pub trait KeyInjector {
+    /// 按下按键
+    fn key_down(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// 释放按键
+    fn key_up(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// 按下扫描码对应的按键（不经过虚拟键码），`extended` 为 true 时带扩展键标记
+    ///
+    /// 默认返回错误，不支持扫描码输入的实现无需处理
+    fn scan_code_down(&self, code: u16, _extended: bool) -> Result<(), Box<dyn std::error::Error>> {
+        Err(format!("不支持发送扫描码 0x{:X}", code).into())
+    }
+
+    /// 释放扫描码对应的按键，默认返回错误
+    fn scan_code_up(&self, code: u16, _extended: bool) -> Result<(), Box<dyn std::error::Error>> {
+        Err(format!("不支持发送扫描码 0x{:X}", code).into())
+    }
+
+    /// 输入无法映射为虚拟键码的 Unicode 字符
+    fn unicode_char(&self, ch: char) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// 等待指定时长
+    fn sleep(&self, duration: Duration);
+
+    /// 等待指定时长，期间被中止时提前返回错误（序列的 `wait` 步骤和 `start_delay`）
+    ///
+    /// 默认调用 `sleep`，等待结束后再检查是否已中止；发送真实输入的实现用 `RunControl::sleep` 在等待期间响应中止
+    fn interruptible_sleep(&self, duration: Duration, control: &RunControl) -> Result<(), Box<dyn std::error::Error>> {
+        self.sleep(duration);
+        control.sleep(Duration::ZERO)
+    }
+
+    /// 将文本写入剪贴板（用于以粘贴方式输入文本）
+    fn set_clipboard_text(&self, text: &str) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Caps Lock 当前是否开启
+    fn caps_lock_on(&self) -> bool;
+
+    /// 读取屏幕像素颜色（`wait_pixel` 步骤），无法读取时返回 None（视为不匹配）
+    ///
+    /// `expected` 为步骤等待的颜色。默认不读取屏幕，直接返回 `expected`（视为已匹配），
+    /// 只有发送真实输入的实现读取真实屏幕
+    fn read_pixel(&self, _x: i32, _y: i32, expected: Rgb) -> Option<Rgb> {
+        Some(expected)
+    }
+
+    /// 将字符转换为按键，无法用按键输入时返回 None（改用 Unicode 输入）
+    ///
+    /// 默认按美式键盘布局转换
+    fn char_key(&self, ch: char) -> Option<CharKey> {
+        us_char_key(ch)
+    }
+
+    /// 连续发送多个按键事件，中间不等待
+    ///
+    /// 默认逐个调用 `key_down` / `key_up`，实现可以改为一次性发送以减少系统调用
+    fn send_keys(&self, strokes: &[KeyStroke]) -> Result<(), Box<dyn std::error::Error>> {
+        for stroke in strokes {
+            match *stroke {
+                KeyStroke::Down(vk) => self.key_down(vk)?,
+                KeyStroke::Up(vk) => self.key_up(vk)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 已按下但尚未释放的按键记录
+///
+/// 记录所有通过 `SendInputInjector` 按下的按键，紧急停止时据此释放全部按键
+#[derive(Debug, Default)]
+pub struct PressedKeys {
+    keys: Mutex<Vec<u16>>,
+}
+
+impl PressedKeys {
+    /// 创建空记录
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录按下的按键
+    pub fn press(&self, vk: u16) {
+        let mut keys = self.keys.lock().unwrap_or_else(PoisonError::into_inner);
+        if !keys.contains(&vk) {
+            keys.push(vk);
+        }
+    }
+
+    /// 移除已释放的按键（按键不在记录中时不做任何事）
+    pub fn release(&self, vk: u16) {
+        self.keys
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|&k| k != vk);
+    }
+
+    /// 取出所有记录的按键并清空记录
+    ///
+    /// # 返回
+    ///
+    /// 按与按下相反的顺序排列的按键
+    pub fn take_all(&self) -> Vec<u16> {
+        let mut keys = std::mem::take(&mut *self.keys.lock().unwrap_or_else(PoisonError::into_inner));
+        keys.reverse();
+        keys
+    }
+}
+
+/// 通过 SendInput 发送真实按键
+pub struct SendInputInjector;
+
+impl KeyInjector for SendInputInjector {
+    fn key_down(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+        keyboard::simulate_key_press(vk)?;
+        crate::macros::pressed_keys().press(vk);
+        Ok(())
+    }
+
+    fn key_up(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+        keyboard::simulate_key_release(vk)?;
+        crate::macros::pressed_keys().release(vk);
+        Ok(())
+    }
+
+    fn scan_code_down(&self, code: u16, extended: bool) -> Result<(), Box<dyn std::error::Error>> {
+        // 扫描码没有对应的虚拟键码，不记录到按下的按键中，由序列结束时的释放负责
+        keyboard::simulate_scancode(code, extended, KeyEventType::Press)?;
+        Ok(())
+    }
+
+    fn scan_code_up(&self, code: u16, extended: bool) -> Result<(), Box<dyn std::error::Error>> {
+        keyboard::simulate_scancode(code, extended, KeyEventType::Release)?;
+        Ok(())
+    }
+
+    fn unicode_char(&self, ch: char) -> Result<(), Box<dyn std::error::Error>> {
+        // Unicode 输入不使用虚拟键码，不需要记录按下的按键
+        keyboard::simulate_unicode_char(ch)?;
+        Ok(())
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+
+    fn interruptible_sleep(&self, duration: Duration, control: &RunControl) -> Result<(), Box<dyn std::error::Error>> {
+        control.sleep(duration)
+    }
+
+    fn set_clipboard_text(&self, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+        clipboard::set_text(text)?;
+        Ok(())
+    }
+
+    fn caps_lock_on(&self) -> bool {
+        keyboard::is_caps_lock_on()
+    }
+
+    fn read_pixel(&self, x: i32, y: i32, _expected: Rgb) -> Option<Rgb> {
+        crate::winapi::gdi::get_pixel(x, y).map(|(r, g, b)| Rgb { r, g, b })
+    }
+
+    fn char_key(&self, ch: char) -> Option<CharKey> {
+        // 按前台窗口当前的键盘布局转换，保证非美式布局下输入的字符正确
+        layout_char_key(&SystemLayout, ch)
+    }
+
+    fn send_keys(&self, strokes: &[KeyStroke]) -> Result<(), Box<dyn std::error::Error>> {
+        let inputs: Vec<_> = strokes
+            .iter()
+            .map(|stroke| match *stroke {
+                KeyStroke::Down(vk) => keyboard::key_input(vk, KeyEventType::Press),
+                KeyStroke::Up(vk) => keyboard::key_input(vk, KeyEventType::Release),
+            })
+            .collect();
+        keyboard::simulate_inputs(&inputs)?;
+
+        let pressed = crate::macros::pressed_keys();
+        for stroke in strokes {
+            match *stroke {
+                KeyStroke::Down(vk) => pressed.press(vk),
+                KeyStroke::Up(vk) => pressed.release(vk),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 演练模式的注入器，只在日志中记录将要执行的操作，不发送真实输入
+///
+/// 等待仍会真实发生，使热键的执行时长、长按重复等行为与正常运行一致；
+/// 字符转换和 Caps Lock 状态只读取系统状态，与 `SendInputInjector` 相同；
+/// 不读取屏幕，`wait_pixel` 步骤视为已匹配；按下的按键不记录到 `PressedKeys`，紧急停止时不会去释放
+pub struct DryRunInjector;
+
+impl DryRunInjector {
+    fn log_stroke(action: &str, vk: u16) {
+        log::info!("[演练] {} {} (0x{:X})", action, crate::macros::key_display_name(vk), vk);
+    }
+}
+
+impl KeyInjector for DryRunInjector {
+    fn key_down(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+        Self::log_stroke("按下", vk);
+        Ok(())
+    }
+
+    fn key_up(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+        Self::log_stroke("释放", vk);
+        Ok(())
+    }
+
+    fn scan_code_down(&self, code: u16, extended: bool) -> Result<(), Box<dyn std::error::Error>> {
+        log::info!("[演练] 按下扫描码 0x{:X}{}", code, if extended { "（扩展键）" } else { "" });
+        Ok(())
+    }
+
+    fn scan_code_up(&self, code: u16, extended: bool) -> Result<(), Box<dyn std::error::Error>> {
+        log::info!("[演练] 释放扫描码 0x{:X}{}", code, if extended { "（扩展键）" } else { "" });
+        Ok(())
+    }
+
+    fn unicode_char(&self, ch: char) -> Result<(), Box<dyn std::error::Error>> {
+        log::info!("[演练] 输入 Unicode 字符 {:?} (U+{:04X}，{} 个 UTF-16 编码单元)", ch, u32::from(ch), ch.len_utf16());
+        Ok(())
+    }
+
+    fn sleep(&self, duration: Duration) {
+        log::info!("[演练] 等待 {:?}", duration);
+        thread::sleep(duration);
+    }
+
+    fn interruptible_sleep(&self, duration: Duration, control: &RunControl) -> Result<(), Box<dyn std::error::Error>> {
+        log::info!("[演练] 等待 {:?}", duration);
+        control.sleep(duration)
+    }
+
+    fn set_clipboard_text(&self, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+        log::info!("[演练] 写入剪贴板 {} 个字符", text.chars().count());
+        Ok(())
+    }
+
+    fn caps_lock_on(&self) -> bool {
+        SendInputInjector.caps_lock_on()
+    }
+
+    fn read_pixel(&self, x: i32, y: i32, expected: Rgb) -> Option<Rgb> {
+        log::info!("[演练] 等待像素 ({}, {}) 变为 {}，视为已匹配", x, y, expected);
+        Some(expected)
+    }
+
+    fn char_key(&self, ch: char) -> Option<CharKey> {
+        SendInputInjector.char_key(ch)
+    }
+
+    fn send_keys(&self, strokes: &[KeyStroke]) -> Result<(), Box<dyn std::error::Error>> {
+        log::info!("[演练] 批量发送 {} 个按键事件", strokes.len());
+        for stroke in strokes {
+            match *stroke {
+                KeyStroke::Down(vk) => self.key_down(vk)?,
+                KeyStroke::Up(vk) => self.key_up(vk)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 按倍率缩放等待时间的注入器，按键操作原样交给内部注入器
+///
+/// 倍率以千分比表示（1000 为原速，4000 为慢 4 倍），用整数计算使原速时的等待时间保持不变
+pub struct ScaledInjector<'a> {
+    inner: &'a dyn KeyInjector,
+    permille: u64,
+}
+
+impl<'a> ScaledInjector<'a> {
+    /// 创建缩放注入器
+    ///
+    /// # 参数
+    ///
+    /// * `inner` - 实际执行操作的注入器
+    /// * `permille` - 等待时间倍率（千分比）
+    pub fn new(inner: &'a dyn KeyInjector, permille: u64) -> Self {
+        Self { inner, permille }
+    }
+
+    fn scale(&self, duration: Duration) -> Duration {
+        let nanos = duration.as_nanos() * u128::from(self.permille) / 1000;
+        Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+    }
+}
+
+impl KeyInjector for ScaledInjector<'_> {
+    fn key_down(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.key_down(vk)
+    }
+
+    fn key_up(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.key_up(vk)
+    }
+
+    fn scan_code_down(&self, code: u16, extended: bool) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.scan_code_down(code, extended)
+    }
+
+    fn scan_code_up(&self, code: u16, extended: bool) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.scan_code_up(code, extended)
+    }
+
+    fn unicode_char(&self, ch: char) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.unicode_char(ch)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.inner.sleep(self.scale(duration));
+    }
+
+    fn interruptible_sleep(&self, duration: Duration, control: &RunControl) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.interruptible_sleep(self.scale(duration), control)
+    }
+
+    fn set_clipboard_text(&self, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.set_clipboard_text(text)
+    }
+
+    fn caps_lock_on(&self) -> bool {
+        self.inner.caps_lock_on()
+    }
+
+    fn read_pixel(&self, x: i32, y: i32, expected: Rgb) -> Option<Rgb> {
+        self.inner.read_pixel(x, y, expected)
+    }
+
+    fn char_key(&self, ch: char) -> Option<CharKey> {
+        self.inner.char_key(ch)
+    }
+
+    fn send_keys(&self, strokes: &[KeyStroke]) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.send_keys(strokes)
+    }
+}
+
+/// Unicode 输入失败时改用 Alt+小键盘输入字符编码的注入器，其他操作原样交给内部注入器
+///
+/// 按住 Alt，在小键盘上输入 `0` 加字符编码的十进制数，再释放 Alt；
+/// 编码是否被识别为 Unicode 取决于键盘布局和目标程序，因此需要在配置中显式开启
+pub struct AltNumpadInjector<'a> {
+    inner: &'a dyn KeyInjector,
+    enabled: bool,
+}
+
+impl<'a> AltNumpadInjector<'a> {
+    /// 创建注入器
+    ///
+    /// # 参数
+    ///
+    /// * `inner` - 实际执行操作的注入器
+    /// * `enabled` - 是否启用 Alt+小键盘输入，为 false 时 Unicode 输入失败直接返回错误
+    pub fn new(inner: &'a dyn KeyInjector, enabled: bool) -> Self {
+        Self { inner, enabled }
+    }
+}
+
+/// 字符的 Alt+小键盘输入按键（不含 Alt）
+///
+/// 以 `0` 开头再输入编码的十进制数，使 0~255 的编码按 Windows-1252 解释而不是 OEM 代码页
+fn alt_code_strokes(ch: char) -> Vec<KeyStroke> {
+    format!("0{}", u32::from(ch))
+        .bytes()
+        .flat_map(|digit| {
+            let vk = VK_NUMPAD0.0 + u16::from(digit - b'0');
+            [KeyStroke::Down(vk), KeyStroke::Up(vk)]
+        })
+        .collect()
+}
+
+impl KeyInjector for AltNumpadInjector<'_> {
+    fn key_down(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.key_down(vk)
+    }
+
+    fn key_up(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.key_up(vk)
+    }
+
+    fn scan_code_down(&self, code: u16, extended: bool) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.scan_code_down(code, extended)
+    }
+
+    fn scan_code_up(&self, code: u16, extended: bool) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.scan_code_up(code, extended)
+    }
+
+    fn unicode_char(&self, ch: char) -> Result<(), Box<dyn std::error::Error>> {
+        let Err(e) = self.inner.unicode_char(ch) else {
+            return Ok(());
+        };
+        if !self.enabled {
+            return Err(e);
+        }
+
+        log::debug!("Unicode 输入失败 ({})，改用 Alt+小键盘输入 {:?}", e, ch);
+        // 整组按键一次性发送，避免中途出错时 Alt 保持按下
+        let mut strokes = vec![KeyStroke::Down(VK_MENU.0)];
+        strokes.extend(alt_code_strokes(ch));
+        strokes.push(KeyStroke::Up(VK_MENU.0));
+        self.inner.send_keys(&strokes)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.inner.sleep(duration);
+    }
+
+    fn interruptible_sleep(&self, duration: Duration, control: &RunControl) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.interruptible_sleep(duration, control)
+    }
+
+    fn set_clipboard_text(&self, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.set_clipboard_text(text)
+    }
+
+    fn caps_lock_on(&self) -> bool {
+        self.inner.caps_lock_on()
+    }
+
+    fn read_pixel(&self, x: i32, y: i32, expected: Rgb) -> Option<Rgb> {
+        self.inner.read_pixel(x, y, expected)
+    }
+
+    fn char_key(&self, ch: char) -> Option<CharKey> {
+        self.inner.char_key(ch)
+    }
+
+    fn send_keys(&self, strokes: &[KeyStroke]) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.send_keys(strokes)
+    }
+}
+
+/// 测试用注入器，记录所有按键事件而不发送真实输入
+#[cfg(test)]
+pub(crate) mod recording {
+    use super::{layout_char_key, us_char_key, CharKey, KeyInjector, KeyStroke, KeyboardLayout};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// 记录的注入事件
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Injected {
+        Down(u16),
+        Up(u16),
+        /// 扫描码按下（扫描码，是否为扩展键）
+        ScanDown(u16, bool),
+        /// 扫描码释放
+        ScanUp(u16, bool),
+        Unicode(char),
+        Sleep(Duration),
+        /// 写入剪贴板的文本
+        Clipboard(String),
+        /// 一次批量发送的事件数，其后紧跟该批次的各个按键事件
+        Batch(usize),
+    }
+
+    /// 记录按键事件的注入器
+    #[derive(Default)]
+    pub struct RecordingInjector {
+        pub events: Mutex<Vec<Injected>>,
+        /// 为 true 时 Unicode 输入返回错误，用于模拟步骤失败
+        pub fail_unicode: bool,
+        /// 模拟的 Caps Lock 状态
+        pub caps_lock: bool,
+        /// 字符映射使用的键盘布局，为 None 时按美式键盘布局转换
+        pub layout: Option<Arc<dyn KeyboardLayout + Send + Sync>>,
+        /// 模拟的屏幕颜色（所有像素相同），为 None 时读取的颜色总是与等待的颜色相同
+        pub screen: Option<Rgb>,
+    }
+
+    impl RecordingInjector {
+        /// 获取已记录的事件
+        pub fn events(&self) -> Vec<Injected> {
+            self.events.lock().unwrap().clone()
+        }
+
+        /// 获取已记录的按键事件（忽略等待和批次标记）
+        pub fn key_events(&self) -> Vec<Injected> {
+            self.events()
+                .into_iter()
+                .filter(|e| !matches!(e, Injected::Sleep(_) | Injected::Batch(_)))
+                .collect()
+        }
+
+        /// 获取已记录的等待时长
+        pub fn sleeps(&self) -> Vec<Duration> {
+            self.events()
+                .into_iter()
+                .filter_map(|e| match e {
+                    Injected::Sleep(d) => Some(d),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        fn record(&self, event: Injected) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    impl KeyInjector for RecordingInjector {
+        fn key_down(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+            self.record(Injected::Down(vk));
+            Ok(())
+        }
+
+        fn key_up(&self, vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+            self.record(Injected::Up(vk));
+            Ok(())
+        }
+
+        fn scan_code_down(&self, code: u16, extended: bool) -> Result<(), Box<dyn std::error::Error>> {
+            self.record(Injected::ScanDown(code, extended));
+            Ok(())
+        }
+
+        fn scan_code_up(&self, code: u16, extended: bool) -> Result<(), Box<dyn std::error::Error>> {
+            self.record(Injected::ScanUp(code, extended));
+            Ok(())
+        }
+
+        fn unicode_char(&self, ch: char) -> Result<(), Box<dyn std::error::Error>> {
+            if self.fail_unicode {
+                return Err("Unicode 字符不支持".into());
+            }
+            self.record(Injected::Unicode(ch));
+            Ok(())
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.record(Injected::Sleep(duration));
+        }
+
+        fn set_clipboard_text(&self, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+            self.record(Injected::Clipboard(text.to_string()));
+            Ok(())
+        }
+
+        fn caps_lock_on(&self) -> bool {
+            self.caps_lock
+        }
+
+        fn read_pixel(&self, _x: i32, _y: i32, expected: Rgb) -> Option<Rgb> {
+            Some(self.screen.unwrap_or(expected))
+        }
+
+        fn char_key(&self, ch: char) -> Option<CharKey> {
+            match &self.layout {
+                Some(layout) => layout_char_key(layout.as_ref(), ch),
+                None => us_char_key(ch),
+            }
+        }
+
+        fn send_keys(&self, strokes: &[KeyStroke]) -> Result<(), Box<dyn std::error::Error>> {
+            self.record(Injected::Batch(strokes.len()));
+            for stroke in strokes {
+                self.record(match *stroke {
+                    KeyStroke::Down(vk) => Injected::Down(vk),
+                    KeyStroke::Up(vk) => Injected::Up(vk),
+                });
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_us_char_key() {
+        assert_eq!(us_char_key('a'), Some(CharKey::plain(0x41)));
+        assert_eq!(us_char_key('A'), Some(CharKey { shift: true, ..CharKey::plain(0x41) }));
+        assert_eq!(us_char_key('7'), Some(CharKey::plain(0x37)));
+        assert_eq!(us_char_key('\n'), Some(CharKey::plain(0x0D)));
+        assert_eq!(us_char_key('中'), None);
+    }
+
+    #[test]
+    fn test_char_key_modifiers_order() {
+        let key = CharKey { vk: 0x32, shift: true, ctrl: true, alt: true };
+        assert_eq!(key.modifiers(), vec![0x11, 0x12, 0x10]);
+        assert!(CharKey::plain(0x41).modifiers().is_empty());
+    }
+
+    #[test]
+    fn test_pressed_keys_take_all_in_reverse_order() {
+        let pressed = PressedKeys::new();
+        pressed.press(0x10);
+        pressed.press(0x41);
+        pressed.press(0x10);
+        pressed.press(0x42);
+        pressed.release(0x41);
+
+        assert_eq!(pressed.take_all(), vec![0x42, 0x10]);
+        assert!(pressed.take_all().is_empty());
+    }
+
+    #[test]
+    fn test_pressed_keys_release_of_unpressed_key() {
+        let pressed = PressedKeys::new();
+        pressed.press(0x10);
+        pressed.release(0x41);
+        pressed.release(0x41);
+
+        assert_eq!(pressed.take_all(), vec![0x10]);
+    }
+
+    #[test]
+    fn test_dry_run_injector_sends_nothing() {
+        let injector = DryRunInjector;
+        assert!(injector.key_down(0x41).is_ok());
+        assert!(injector.send_keys(&[KeyStroke::Down(0x42), KeyStroke::Up(0x42)]).is_ok());
+        assert!(injector.unicode_char('中').is_ok());
+        assert!(injector.unicode_char('😀').is_ok());
+    }
+
+    #[test]
+    fn test_alt_numpad_fallback_order() {
+        use recording::{Injected, RecordingInjector};
+
+        let recorder = RecordingInjector { fail_unicode: true, ..Default::default() };
+        let injector = AltNumpadInjector::new(&recorder, true);
+        injector.unicode_char('é').unwrap();
+
+        // é = U+00E9 = 233，Alt 按住期间输入小键盘 0 2 3 3
+        let numpad = |d: u16| [Injected::Down(0x60 + d), Injected::Up(0x60 + d)];
+        let mut expected = vec![Injected::Batch(10), Injected::Down(0x12)];
+        for d in [0, 2, 3, 3] {
+            expected.extend(numpad(d));
+        }
+        expected.push(Injected::Up(0x12));
+        assert_eq!(recorder.events(), expected);
+    }
+
+    #[test]
+    fn test_alt_numpad_fallback_disabled_or_unneeded() {
+        use recording::{Injected, RecordingInjector};
+
+        let recorder = RecordingInjector { fail_unicode: true, ..Default::default() };
+        assert!(AltNumpadInjector::new(&recorder, false).unicode_char('é').is_err());
+        assert!(recorder.events().is_empty());
+
+        // Unicode 输入成功时不使用 Alt+小键盘
+        let recorder = RecordingInjector::default();
+        AltNumpadInjector::new(&recorder, true).unicode_char('é').unwrap();
+        assert_eq!(recorder.events(), vec![Injected::Unicode('é')]);
+    }
+}
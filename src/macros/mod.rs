@@ -2,23 +2,84 @@
 //!
 //! 从配置文件加载热键映射，支持多种操作类型
 
+mod chord;
+mod control;
+mod cooldown;
 mod executor;
 mod handler;
+mod hook_stats;
+mod ime;
+mod injector;
+mod modifiers;
+mod stats;
+mod tap_or_hold;
+mod trigger;
+mod vars;
 
-pub use executor::{execute_type_text, execute_sequence};
-pub use handler::{keyboard_hook_proc, MacroEvent, MacroPhase, start_gamepad_forwarder};
+pub use executor::{execute_type_text, execute_type_text_with, execute_sequence, execute_sequence_with, execute_chat, execute_type_file, execute_type_file_with, execute_tap_hold, execute_tap_hold_with, execute_triggered_sequence_with, run_config_dry, ActionResult, SequenceProgress, Trigger};
+pub use chord::{ChordAction, ChordTracker};
+pub use control::RunControl;
+pub use cooldown::{debounce_passed, Cooldowns};
+pub use stats::MacroStats;
+pub use ime::{ImeControl, ImeGuard, SystemIme};
+pub use hook_stats::{HookCounter, HookStats, HookStatsSnapshot};
+pub use modifiers::ModifierTracker;
+pub use tap_or_hold::{PendingPresses, PressKind};
+pub use trigger::HeldTriggers;
+pub use vars::{HotkeyVariables, Variables};
+pub use injector::{layout_char_key, AltNumpadInjector, DryRunInjector, KeyInjector, KeyboardLayout, PressedKeys, ScaledInjector, SendInputInjector, SystemLayout};
+pub use handler::{keyboard_hook_proc, mouse_hook_proc, EventObserver, MacroEvent, MacroPhase, start_gamepad_forwarder};
+pub(crate) use executor::parse_key_string;
 
-use std::sync::{Mutex, mpsc::Sender};
+use std::sync::{Arc, Mutex, PoisonError, mpsc::Sender};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use once_cell::sync::Lazy;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::Accessibility::HWINEVENTHOOK;
 use windows::Win32::UI::WindowsAndMessaging::HHOOK;
-use crate::config::Config;
+use crate::config::{Config, DurationSpec, GamepadConfig};
+use crate::gamepad::battery::ControllerStatus;
 use crate::gamepad::start_gamepad_thread;
+use crate::winapi::process::ForegroundWindow;
 
 // 全局变量
 static TOGGLE_STATE: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(true));
 static MACRO_PHASE: Lazy<Mutex<MacroPhase>> = Lazy::new(|| Mutex::new(MacroPhase::Idle));
 static MACRO_EVENT_SENDER: Lazy<Mutex<Option<Sender<MacroEvent>>>> = Lazy::new(|| Mutex::new(None));
-static CONFIG: Lazy<Mutex<Option<Config>>> = Lazy::new(|| Mutex::new(None));
+/// 生效的配置，钩子每次按键都会读取，以 `Arc` 共享避免复制整个配置
+static CONFIG: Lazy<Mutex<Option<Arc<Config>>>> = Lazy::new(|| Mutex::new(None));
+static REPEAT_PENDING: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
+static RUN_CONTROL: Lazy<RunControl> = Lazy::new(RunControl::new);
+static PRESSED_KEYS: Lazy<PressedKeys> = Lazy::new(PressedKeys::new);
+static MACRO_STATS: Lazy<MacroStats> = Lazy::new(MacroStats::new);
+static HOOK_STATS: HookStats = HookStats::new();
+static CHORD_TRACKER: Lazy<ChordTracker> = Lazy::new(ChordTracker::new);
+static COOLDOWNS: Lazy<Cooldowns> = Lazy::new(Cooldowns::new);
+/// 各触发键上一次按下的时间（`repeat_guard_ms`）
+static LAST_PRESSES: Lazy<Cooldowns> = Lazy::new(Cooldowns::new);
+static GAMEPAD_STATUS: Lazy<ControllerStatus> = Lazy::new(ControllerStatus::new);
+static HELD_TRIGGERS: Lazy<HeldTriggers> = Lazy::new(HeldTriggers::new);
+/// 当前按下的修饰键（修饰键组合触发键）
+static MODIFIERS: Lazy<ModifierTracker> = Lazy::new(ModifierTracker::new);
+/// 尚未判定为轻按或长按的触发键（`tap_action` / `hold_action`）
+static PENDING_PRESSES: Lazy<PendingPresses> = Lazy::new(PendingPresses::new);
+/// 序列变量（每个触发键一份），程序运行期间一直保留
+static VARIABLES: Lazy<HotkeyVariables> = Lazy::new(HotkeyVariables::new);
+/// 接收宏系统通知（`AppNotice`）的回调，由托盘应用或无托盘模式设置，未设置时通知只记录日志
+static APP_NOTIFIER: Lazy<Mutex<Option<AppNotifier>>> = Lazy::new(|| Mutex::new(None));
+/// 接收序列执行进度的通道（开启 `overlay_progress` 时由托盘应用或无托盘模式设置）
+static PROGRESS_SENDER: Lazy<Mutex<Option<Sender<SequenceProgress>>>> = Lazy::new(|| Mutex::new(None));
+static KEYBOARD_HOOK: Lazy<Mutex<Option<isize>>> = Lazy::new(|| Mutex::new(None));
+/// 鼠标钩子句柄值（只在配置使用了鼠标侧键时安装）
+static MOUSE_HOOK: Lazy<Mutex<Option<isize>>> = Lazy::new(|| Mutex::new(None));
+/// 前台窗口切换事件钩子句柄值（只在配置中有热键限定了窗口时设置）
+static FOREGROUND_HOOK: Lazy<Mutex<Option<isize>>> = Lazy::new(|| Mutex::new(None));
+/// 当前前台窗口，前台窗口切换时更新；钩子回调中不能查询窗口所属进程（会超出 `LowLevelHooksTimeout`），只读取这里
+static FOREGROUND_WINDOW: Lazy<Mutex<Option<Arc<ForegroundWindow>>>> = Lazy::new(|| Mutex::new(None));
+/// 全局延迟倍率（千分比，1000 为原速），托盘菜单可在运行时修改
+static DELAY_SCALE_PERMILLE: AtomicU64 = AtomicU64::new(1000);
+/// 当前配置是否开启演练模式（`dry_run` 或环境变量 `KEYMACRO_DRY_RUN`），设置配置时更新，每次执行操作时读取
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
 
 /// 初始化键盘宏系统
 ///
@@ -32,24 +93,43 @@ static CONFIG: Lazy<Mutex<Option<Config>>> = Lazy::new(|| Mutex::new(None));
 ///
 /// # 说明
 ///
-/// 设置低级键盘钩子监听全局键盘事件，启动宏处理线程和手柄监听线程
+/// 设置低级键盘钩子监听全局键盘事件，启动宏处理线程和手柄监听线程（`gamepad.enabled: false` 时不启动）；
+/// 配置中有热键使用鼠标侧键触发时，同时设置低级鼠标钩子；有热键限定了窗口（`when_window`）时，监听前台窗口切换
 pub fn init_keyboard_macro_system(config: Config) -> Option<HHOOK> {
-    // 保存配置
-    if let Ok(mut config_guard) = CONFIG.lock() {
-        *config_guard = Some(config);
+    init_macro_system(config, None)
+}
+
+/// 初始化键盘宏系统，宏处理线程处理每个事件前先调用 `on_event`
+fn init_macro_system(config: Config, on_event: Option<EventObserver>) -> Option<HHOOK> {
+    if config.dry_run_enabled() {
+        log::warn!("演练模式已开启，宏只记录日志，不会发送真实按键");
     }
 
-    // 启动宏处理线程（接收键盘事件）
-    let macro_sender = handler::start_macro_thread();
+    if config.uses_mouse_buttons() {
+        install_mouse_hook();
+    }
+    if config.uses_window_variants() {
+        install_foreground_hook();
+    }
+    let gamepad_settings = config.gamepad.clone();
+    set_delay_scale(config.delay_scale);
 
-    // 启动手柄监听线程
-    let gamepad_receiver = start_gamepad_thread();
+    // 保存配置
+    set_config(config);
 
-    // 启动手柄事件转发
-    handler::start_gamepad_forwarder(gamepad_receiver, macro_sender);
+    // 启动宏处理线程（接收键盘事件）
+    let macro_sender = handler::start_macro_thread(on_event);
+
+    // 启动手柄监听线程和事件转发
+    start_gamepad_subsystem(gamepad_settings, macro_sender);
 
     match crate::winapi::keyboard::set_keyboard_hook(Some(handler::keyboard_hook_proc), 0) {
-        Ok(hook) => Some(hook),
+        Ok(hook) => {
+            if let Ok(mut keyboard_hook) = KEYBOARD_HOOK.lock() {
+                *keyboard_hook = Some(hook.0 as isize);
+            }
+            Some(hook)
+        }
         Err(e) => {
             log::warn!("设置键盘钩子失败: {}", e);
             None
@@ -57,11 +137,11 @@ pub fn init_keyboard_macro_system(config: Config) -> Option<HHOOK> {
     }
 }
 
-/// 设置配置（用于运行时重载）
-#[allow(dead_code)]
+/// 设置配置（用于运行时切换配置方案或重载）
 pub fn set_config(config: Config) {
+    DRY_RUN.store(config.dry_run_enabled(), Ordering::Relaxed);
     if let Ok(mut config_guard) = CONFIG.lock() {
-        *config_guard = Some(config);
+        *config_guard = Some(Arc::new(config));
     }
 }
 
@@ -70,20 +150,409 @@ pub fn set_config(config: Config) {
 /// # 参数
 ///
 /// * `enabled` - true 启用，false 禁用
+///
+/// # 说明
+///
+/// 禁用时同时中止正在执行的序列：序列在下一个步骤开始前退出，并释放执行中按下但尚未释放的按键
 pub fn set_macro_enabled(enabled: bool) {
-    if let Ok(mut state) = TOGGLE_STATE.lock() {
-        *state = enabled;
+    set_enabled_in(&TOGGLE_STATE, &RUN_CONTROL, enabled);
+    if enabled {
+        return;
+    }
+
+    // 宏禁用后工作线程不再处理释放事件，这里直接复位执行状态，避免重新启用后首次触发被忽略
+    set_macro_phase(MacroPhase::Idle);
+    set_repeat_pending(false);
+}
+
+/// 设置 `state` 中的宏启用状态，禁用时中止 `control` 上正在执行的序列
+pub(crate) fn set_enabled_in(state: &Mutex<bool>, control: &RunControl, enabled: bool) {
+    *state.lock().unwrap_or_else(PoisonError::into_inner) = enabled;
+    if !enabled {
+        control.abort();
+    }
+}
+
+/// 切换宏启用状态，并把新的状态通知托盘应用（见 `set_app_notifier`）
+///
+/// # 返回
+///
+/// 切换后是否启用
+///
+/// # 说明
+///
+/// 供绑定了 `toggle_system` 的热键使用；托盘的全局开关热键在主线程中直接调用 `set_macro_enabled`
+pub fn toggle_macro_enabled() -> bool {
+    let enabled = !get_toggle_state();
+    set_macro_enabled(enabled);
+    notify_app(AppNotice::EnabledChanged(enabled));
+    enabled
+}
+
+/// 宏系统需要托盘应用处理的通知
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AppNotice {
+    /// 宏启用状态已由绑定的热键切换，托盘需要更新图标、提示并保存状态
+    EnabledChanged(bool),
+}
+
+/// 接收 `AppNotice` 的回调
+type AppNotifier = Box<dyn Fn(AppNotice) + Send>;
+
+/// 设置接收宏系统通知的回调（如向托盘应用的事件循环发送用户事件）
+///
+/// # 参数
+///
+/// * `notifier` - 回调，在宏处理线程中调用，不应阻塞
+pub fn set_app_notifier(notifier: impl Fn(AppNotice) + Send + 'static) {
+    if let Ok(mut guard) = APP_NOTIFIER.lock() {
+        *guard = Some(Box::new(notifier));
+    }
+}
+
+/// 清除 `set_app_notifier` 设置的回调，之后的通知只记录日志
+pub fn clear_app_notifier() {
+    if let Ok(mut guard) = APP_NOTIFIER.lock() {
+        *guard = None;
     }
 }
 
-/// 清理键盘钩子
+/// 设置接收序列执行进度的通道
+///
+/// # 参数
+///
+/// * `sender` - 通道发送端，只在配置开启 `overlay_progress` 时发送进度
+pub fn set_progress_sender(sender: Sender<SequenceProgress>) {
+    if let Ok(mut guard) = PROGRESS_SENDER.lock() {
+        *guard = Some(sender);
+    }
+}
+
+/// 当前配置开启了 `overlay_progress` 且设置了通道时返回进度通道
+pub(crate) fn progress_sender() -> Option<Sender<SequenceProgress>> {
+    if !get_config().is_some_and(|c| c.overlay_progress) {
+        return None;
+    }
+    PROGRESS_SENDER.lock().ok()?.clone()
+}
+
+/// 通知托盘应用（没有设置回调时，如作为库使用，只记录日志）
+fn notify_app(notice: AppNotice) {
+    match APP_NOTIFIER.lock().ok().as_deref() {
+        Some(Some(notifier)) => notifier(notice),
+        _ => log::debug!("未设置通知回调，忽略: {:?}", notice),
+    }
+}
+
+/// 切换正在执行的序列的暂停状态
+///
+/// # 返回
+///
+/// 切换后是否处于暂停状态
+pub fn toggle_macro_pause() -> bool {
+    RUN_CONTROL.toggle_pause()
+}
+
+/// 中止正在执行的序列
+///
+/// # 说明
+///
+/// 序列会在当前步骤结束后退出，并释放执行中按下但尚未释放的按键
+pub fn abort_macro() {
+    RUN_CONTROL.abort();
+}
+
+/// 设置全局延迟倍率
+///
+/// # 参数
+///
+/// * `scale` - 倍率，如 0.5 为加快一倍、4.0 为放慢 4 倍（精确到千分之一，小于 0.001 的值按 0.001 处理）
+///
+/// # 说明
+///
+/// 所有 `type_text`、`sequence` 和 `chat` 的等待时间都乘以该倍率，从下一次执行宏开始生效
+pub fn set_delay_scale(scale: f64) {
+    let permille = (scale * 1000.0).round().max(1.0) as u64;
+    DELAY_SCALE_PERMILLE.store(permille, Ordering::Relaxed);
+}
+
+/// 获取全局延迟倍率
+pub fn delay_scale() -> f64 {
+    delay_scale_permille() as f64 / 1000.0
+}
+
+pub(crate) fn delay_scale_permille() -> u64 {
+    DELAY_SCALE_PERMILLE.load(Ordering::Relaxed)
+}
+
+/// 获取本次运行中各热键的触发次数
+///
+/// # 返回
+///
+/// 按次数从多到少排列的 (热键名称, 次数) 列表，从未触发的热键不包含在内
+pub fn macro_stats() -> Vec<(String, u64)> {
+    MACRO_STATS.snapshot()
+}
+
+/// 获取本次运行中键盘钩子处理事件的计数（收到、拦截、放行、忽略的自动重复、未派发的触发）
+pub fn hook_stats() -> HookStatsSnapshot {
+    HOOK_STATS.snapshot()
+}
+
+/// 释放所有通过宏按下但尚未释放的按键
+pub fn release_all_held_keys() {
+    for vk in PRESSED_KEYS.take_all() {
+        log::debug!("释放按键: 0x{:X}", vk);
+        if let Err(e) = crate::winapi::keyboard::simulate_key_release(vk) {
+            log::warn!("释放按键失败 (0x{:X}): {}", vk, e);
+        }
+    }
+}
+
+/// 紧急停止
+///
+/// # 说明
+///
+/// 禁用宏、中止正在执行的序列并立即释放所有按下的按键。
+/// 由主线程直接调用，不经过宏事件通道，宏执行中也能立即生效
+pub fn emergency_stop() {
+    // 禁用宏时会中止正在执行的序列并复位执行状态
+    set_macro_enabled(false);
+    release_all_held_keys();
+}
+
+/// 启动手柄监听线程和手柄事件转发线程
+///
+/// # 参数
+///
+/// * `settings` - 配置中的手柄设置，`enabled` 为 false 时不启动任何线程
+/// * `macro_sender` - 宏事件发送者
+///
+/// # 返回
+///
+/// 是否启动了手柄线程
+fn start_gamepad_subsystem(settings: GamepadConfig, macro_sender: Sender<MacroEvent>) -> bool {
+    if !settings.enabled {
+        log::info!("手柄支持已关闭，不启动手柄监听线程");
+        return false;
+    }
+
+    let gamepad_receiver = start_gamepad_thread(settings);
+    handler::start_gamepad_forwarder(gamepad_receiver, macro_sender);
+    true
+}
+
+/// 设置鼠标钩子（已设置时不重复设置）
+fn install_mouse_hook() {
+    let Ok(mut mouse_hook) = MOUSE_HOOK.lock() else {
+        return;
+    };
+    if mouse_hook.is_some() {
+        return;
+    }
+
+    match crate::winapi::mouse::set_mouse_hook(Some(handler::mouse_hook_proc), 0) {
+        Ok(hook) => *mouse_hook = Some(hook.0 as isize),
+        Err(e) => log::warn!("设置鼠标钩子失败: {}", e),
+    }
+}
+
+/// 开始监听前台窗口切换并记录当前前台窗口（已开始时不重复设置）
+fn install_foreground_hook() {
+    let Ok(mut foreground_hook) = FOREGROUND_HOOK.lock() else {
+        return;
+    };
+    if foreground_hook.is_some() {
+        return;
+    }
+
+    match crate::winapi::process::set_foreground_event_hook(Some(foreground_event_proc)) {
+        Ok(hook) => *foreground_hook = Some(hook.0 as isize),
+        Err(e) => log::warn!("监听前台窗口切换失败，限定窗口的热键按启动时的前台窗口匹配: {}", e),
+    }
+    refresh_foreground_window();
+}
+
+/// 前台窗口切换事件回调，更新记录的前台窗口
+unsafe extern "system" fn foreground_event_proc(
+    _hook: HWINEVENTHOOK,
+    _event: u32,
+    _hwnd: HWND,
+    _id_object: i32,
+    _id_child: i32,
+    _event_thread: u32,
+    _event_time: u32,
+) {
+    refresh_foreground_window();
+}
+
+/// 重新查询并记录当前前台窗口（包括所属进程）
+fn refresh_foreground_window() {
+    let window = crate::winapi::process::foreground_window().map(Arc::new);
+    *FOREGROUND_WINDOW.lock().unwrap_or_else(PoisonError::into_inner) = window;
+}
+
+/// 最近一次记录的前台窗口（见 `FOREGROUND_WINDOW`），没有前台窗口时返回 None
+pub(crate) fn foreground_window() -> Option<Arc<ForegroundWindow>> {
+    FOREGROUND_WINDOW.lock().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+/// 清理键盘钩子（同时卸载鼠标钩子，并停止监听前台窗口切换）
 ///
 /// # 参数
 ///
 /// * `hook` - 要卸载的钩子句柄
+///
+/// # 说明
+///
+/// 可以重复调用：空句柄、已经卸载过的句柄和不是由 `init_keyboard_macro_system` 设置的句柄都直接跳过，
+/// 不会对同一个句柄调用两次 `UnhookWindowsHookEx`
 pub fn cleanup_keyboard_hook(hook: HHOOK) {
-    if let Err(e) = crate::winapi::keyboard::unhook_keyboard_hook(hook) {
-        log::debug!("卸载键盘钩子失败: {}", e);
+    if let Some(hook) = take_installed_hook(&KEYBOARD_HOOK, hook) {
+        if let Err(e) = crate::winapi::keyboard::unhook_keyboard_hook(hook) {
+            log::debug!("卸载键盘钩子失败: {}", e);
+        }
+    }
+
+    if let Some(mouse_hook) = MOUSE_HOOK.lock().ok().and_then(|mut h| h.take()) {
+        if let Err(e) = crate::winapi::mouse::unhook_mouse_hook(HHOOK(mouse_hook as *mut _)) {
+            log::debug!("卸载鼠标钩子失败: {}", e);
+        }
+    }
+
+    if let Some(foreground_hook) = FOREGROUND_HOOK.lock().ok().and_then(|mut h| h.take()) {
+        if !crate::winapi::process::unhook_foreground_event_hook(HWINEVENTHOOK(foreground_hook as *mut _)) {
+            log::debug!("停止监听前台窗口切换失败");
+        }
+    }
+}
+
+/// 取出记录中与 `hook` 相同的钩子句柄并清除记录
+///
+/// # 返回
+///
+/// 句柄为空或与记录不符（如已经取出过）时返回 None
+fn take_installed_hook(slot: &Mutex<Option<isize>>, hook: HHOOK) -> Option<HHOOK> {
+    if hook.is_invalid() {
+        return None;
+    }
+    let mut installed = slot.lock().unwrap_or_else(PoisonError::into_inner);
+    if *installed != Some(hook.0 as isize) {
+        log::debug!("键盘钩子已卸载或不是由本程序设置，跳过");
+        return None;
+    }
+    installed.take().map(|h| HHOOK(h as *mut _))
+}
+
+/// 键盘宏系统句柄
+///
+/// 作为库使用时的入口：启动时安装钩子并启动宏处理线程，句柄释放时卸载钩子。
+/// 宏相关的状态是进程级的，同一时间只应存在一个句柄
+pub struct MacroSystem {
+    hook: HHOOK,
+}
+
+impl MacroSystem {
+    /// 按配置启动键盘宏系统
+    ///
+    /// # 返回
+    ///
+    /// 成功返回句柄，设置键盘钩子失败时返回错误
+    ///
+    /// # 说明
+    ///
+    /// 与 `init_keyboard_macro_system` 相同。低级键盘钩子只有在调用线程运行消息循环
+    /// （如 winit 事件循环或 `GetMessageW` 循环）时才能收到按键事件
+    ///
+    /// # 示例
+    ///
+    /// ```no_run
+    /// use rust_keymacro::{Config, MacroSystem};
+    ///
+    /// let config = Config::from_file("config.yaml")?;
+    /// let system = MacroSystem::start(config)?;
+    ///
+    /// // 在当前线程运行消息循环，热键即可触发宏……
+    ///
+    /// system.set_enabled(false);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn start(config: Config) -> Result<Self, Box<dyn std::error::Error>> {
+        let hook = init_keyboard_macro_system(config).ok_or("设置键盘钩子失败")?;
+        Ok(Self { hook })
+    }
+
+    /// 按配置启动键盘宏系统，并订阅宏事件流
+    ///
+    /// # 参数
+    ///
+    /// * `config` - 配置对象
+    /// * `on_event` - 宏处理线程处理每个事件（热键按下/松开、手柄事件、托盘指令等）前调用的回调，
+    ///   宏被禁用时同样会收到事件
+    ///
+    /// # 返回
+    ///
+    /// 成功返回句柄，设置键盘钩子失败时返回错误
+    ///
+    /// # 说明
+    ///
+    /// 回调在宏处理线程中同步执行，耗时操作会推迟宏的触发，应尽快返回（如转发到通道）。
+    /// 不需要事件流时使用 `start`，不会有额外开销
+    ///
+    /// # 示例
+    ///
+    /// ```no_run
+    /// use rust_keymacro::{macros::MacroEvent, Config, MacroSystem};
+    ///
+    /// let config = Config::from_file("config.yaml")?;
+    /// let system = MacroSystem::start_with_observer(config, |event: &MacroEvent| {
+    ///     println!("宏事件: {:?}", event);
+    /// })?;
+    /// # drop(system);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn start_with_observer(
+        config: Config,
+        on_event: impl Fn(&MacroEvent) + Send + 'static,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let hook = init_macro_system(config, Some(Box::new(on_event))).ok_or("设置键盘钩子失败")?;
+        Ok(Self { hook })
+    }
+
+    /// 启用或禁用宏（禁用时热键不再触发宏，按键照常传递给其他程序，正在执行的序列随之中止）
+    pub fn set_enabled(&self, enabled: bool) {
+        set_macro_enabled(enabled);
+    }
+
+    /// 替换生效的配置，从下一次触发热键开始生效
+    ///
+    /// # 示例
+    ///
+    /// ```no_run
+    /// use rust_keymacro::{Config, MacroSystem};
+    ///
+    /// let system = MacroSystem::start(Config::from_file("config.yaml")?)?;
+    /// system.set_config(Config::from_file("game.yaml")?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_config(&self, config: Config) {
+        set_config(config);
+    }
+
+    /// 中止正在执行的序列（见 `abort_macro`）
+    pub fn abort(&self) {
+        abort_macro();
+    }
+
+    /// 紧急停止：禁用宏、中止序列并释放所有按键（见 `emergency_stop`）
+    pub fn emergency_stop(&self) {
+        emergency_stop();
+    }
+}
+
+impl Drop for MacroSystem {
+    fn drop(&mut self) {
+        cleanup_keyboard_hook(self.hook);
     }
 }
 
@@ -102,10 +571,135 @@ pub(crate) fn set_macro_phase(phase: MacroPhase) {
     }
 }
 
-pub(crate) fn get_config() -> Option<Config> {
+pub(crate) fn macro_phase() -> &'static Mutex<MacroPhase> {
+    &MACRO_PHASE
+}
+
+pub(crate) fn get_repeat_pending() -> bool {
+    REPEAT_PENDING.lock().map(|p| *p).unwrap_or(false)
+}
+
+pub(crate) fn set_repeat_pending(pending: bool) {
+    if let Ok(mut p) = REPEAT_PENDING.lock() {
+        *p = pending;
+    }
+}
+
+pub(crate) fn run_control() -> &'static RunControl {
+    &RUN_CONTROL
+}
+
+pub(crate) fn pressed_keys() -> &'static PressedKeys {
+    &PRESSED_KEYS
+}
+
+pub(crate) fn stats() -> &'static MacroStats {
+    &MACRO_STATS
+}
+
+pub(crate) fn hook_counters() -> &'static HookStats {
+    &HOOK_STATS
+}
+
+pub(crate) fn gamepad_status() -> &'static ControllerStatus {
+    &GAMEPAD_STATUS
+}
+
+pub(crate) fn chord_tracker() -> &'static ChordTracker {
+    &CHORD_TRACKER
+}
+
+pub(crate) fn cooldowns() -> &'static Cooldowns {
+    &COOLDOWNS
+}
+
+pub(crate) fn last_presses() -> &'static Cooldowns {
+    &LAST_PRESSES
+}
+
+pub(crate) fn modifier_tracker() -> &'static ModifierTracker {
+    &MODIFIERS
+}
+
+pub(crate) fn held_triggers() -> &'static HeldTriggers {
+    &HELD_TRIGGERS
+}
+
+pub(crate) fn pending_presses() -> &'static PendingPresses {
+    &PENDING_PRESSES
+}
+
+/// 触发键的序列变量存储，没有触发键（如 `chat` 转换成的序列）时使用名称为空的存储
+pub(crate) fn variables(key_name: Option<&str>) -> Arc<Variables> {
+    VARIABLES.for_key(key_name.unwrap_or_default())
+}
+
+/// 当前生效的配置（共享同一份，不复制）
+pub(crate) fn get_config() -> Option<Arc<Config>> {
     CONFIG.lock().ok().and_then(|g| g.clone())
 }
 
+pub(crate) fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+pub(crate) fn alt_numpad_fallback() -> bool {
+    get_config().is_some_and(|c| c.alt_numpad_fallback)
+}
+
+pub(crate) fn ime_safe() -> bool {
+    get_config().is_some_and(|c| c.ime_safe)
+}
+
+/// 当前配置中序列的默认按住时长（`default_key_hold_ms`），配置尚未加载时为 0
+pub(crate) fn default_key_hold() -> DurationSpec {
+    get_config().map_or(DurationSpec::ZERO, |c| c.default_key_hold())
+}
+
+/// 当前配置中的全局输入前缀和后缀（`type_text_prefix`, `type_text_suffix`），配置尚未加载时都为空
+pub(crate) fn type_text_affixes() -> (String, String) {
+    get_config().map(|c| (c.type_text_prefix.clone(), c.type_text_suffix.clone())).unwrap_or_default()
+}
+
+pub(crate) fn key_display_name(vk: u16) -> String {
+    handler::vk_to_key_name(u32::from(vk))
+}
+
 pub(crate) fn get_event_sender() -> Option<Sender<MacroEvent>> {
     MACRO_EVENT_SENDER.lock().ok().and_then(|g| g.clone())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gamepad_subsystem_not_started_when_disabled() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let settings = GamepadConfig { enabled: false, ..GamepadConfig::default() };
+        assert!(!start_gamepad_subsystem(settings, sender));
+
+        // 没有启动转发线程，发送者随函数返回被丢弃
+        assert!(receiver.recv().is_err());
+    }
+
+    #[test]
+    fn test_cleanup_keyboard_hook_twice() {
+        let slot = Mutex::new(Some(0x1234));
+        let hook = HHOOK(0x1234 as *mut _);
+
+        // 只有第一次取出句柄，之后的调用不会再次卸载
+        assert_eq!(take_installed_hook(&slot, hook), Some(hook));
+        assert_eq!(take_installed_hook(&slot, hook), None);
+        assert!(slot.lock().unwrap().is_none());
+
+        let slot = Mutex::new(Some(0x1234));
+        assert_eq!(take_installed_hook(&slot, HHOOK::default()), None);
+        assert_eq!(take_installed_hook(&slot, HHOOK(0x5678 as *mut _)), None);
+        assert!(slot.lock().unwrap().is_some());
+
+        // 空句柄不调用系统 API
+        cleanup_keyboard_hook(HHOOK::default());
+        cleanup_keyboard_hook(HHOOK::default());
+    }
+}
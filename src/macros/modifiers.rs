@@ -0,0 +1,116 @@
+//! 修饰键组合模块
+//!
+//! 记录当前按下的 Shift / Ctrl / Alt（区分左右），用于匹配只由修饰键组成的触发键（如 `LShift+RShift`）。
+//! 只有按下修饰键使状态变为与组合完全一致时才触发，按住期间的自动重复和松开途中经过的状态都不会触发。
+//! 钩子漏掉事件（如焦点切换到管理员窗口时松开）导致记录与实际不符时，由钩子按 `GetAsyncKeyState` 重新同步
+
+use std::sync::{Mutex, PoisonError};
+
+/// 修饰键状态
+#[derive(Debug, Default)]
+struct ModifierState {
+    /// 当前按下的修饰键（`keys::modifier_bit` 的组合）
+    active: u8,
+    /// 已触发、尚未离开的修饰键组合触发键
+    matched: Option<String>,
+}
+
+/// 修饰键状态记录（键盘钩子线程使用，内部加锁）
+#[derive(Debug, Default)]
+pub struct ModifierTracker {
+    state: Mutex<ModifierState>,
+}
+
+impl ModifierTracker {
+    /// 创建没有按下任何修饰键的记录
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 修饰键按下
+    ///
+    /// # 返回
+    ///
+    /// 按下后的修饰键状态；该键已经按下（自动重复）时返回 None
+    pub fn key_down(&self, bit: u8) -> Option<u8> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.active & bit != 0 {
+            return None;
+        }
+        state.active |= bit;
+        Some(state.active)
+    }
+
+    /// 修饰键松开
+    ///
+    /// # 返回
+    ///
+    /// 松开后的修饰键状态
+    pub fn key_up(&self, bit: u8) -> u8 {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.active &= !bit;
+        state.active
+    }
+
+    /// 修饰键是否已记录为按下
+    pub fn is_pressed(&self, bit: u8) -> bool {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner).active & bit != 0
+    }
+
+    /// 用实际按住的修饰键覆盖记录的状态
+    ///
+    /// # 参数
+    ///
+    /// * `held` - 实际按住的修饰键（`keys::modifier_bit` 的组合）
+    pub fn resync(&self, held: u8) {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner).active = held;
+    }
+
+    /// 记录已触发的组合
+    pub fn set_matched(&self, name: String) {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner).matched = Some(name);
+    }
+
+    /// 已触发的组合不再满足 `still_matches` 时清除并返回它的名称
+    pub fn take_matched_unless(&self, still_matches: impl FnOnce(&str) -> bool) -> Option<String> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.matched.as_deref().is_some_and(still_matches) {
+            return None;
+        }
+        state.matched.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracker_reports_transitions_and_matched_chord() {
+        let tracker = ModifierTracker::new();
+        assert_eq!(tracker.key_down(1), Some(1));
+        // 自动重复不改变状态
+        assert_eq!(tracker.key_down(1), None);
+        assert_eq!(tracker.key_down(2), Some(3));
+
+        tracker.set_matched("LShift+RShift".to_string());
+        assert_eq!(tracker.take_matched_unless(|_| true), None);
+        assert_eq!(tracker.key_up(2), 1);
+        assert_eq!(tracker.take_matched_unless(|_| false), Some("LShift+RShift".to_string()));
+        assert_eq!(tracker.take_matched_unless(|_| false), None);
+        assert_eq!(tracker.key_up(1), 0);
+    }
+
+    #[test]
+    fn test_resync_replaces_tracked_state() {
+        let tracker = ModifierTracker::new();
+        tracker.key_down(1);
+        tracker.key_down(4);
+        assert!(tracker.is_pressed(4));
+
+        // 漏掉了两个键的松开
+        tracker.resync(0);
+        assert!(!tracker.is_pressed(1) && !tracker.is_pressed(4));
+        assert_eq!(tracker.key_down(1), Some(1));
+    }
+}
@@ -0,0 +1,74 @@
+//! 宏触发统计模块
+//!
+//! 在内存中记录每个热键的触发次数，程序退出后不保留
+
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+
+/// 各热键的触发次数
+#[derive(Debug, Default)]
+pub struct MacroStats {
+    counts: Mutex<HashMap<String, u64>>,
+}
+
+impl MacroStats {
+    /// 创建空统计
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次触发
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 热键名称（按键组以 "|" 连接，整组计为同一个宏）
+    pub fn record(&self, name: &str) {
+        let mut counts = self.counts.lock().unwrap_or_else(PoisonError::into_inner);
+        *counts.entry(name.to_string()).or_insert(0) += 1;
+    }
+
+    /// 获取所有热键的触发次数
+    ///
+    /// # 返回
+    ///
+    /// 按次数从多到少排列，次数相同时按名称排列
+    pub fn snapshot(&self) -> Vec<(String, u64)> {
+        let counts = self.counts.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut stats: Vec<_> = counts.iter().map(|(name, count)| (name.clone(), *count)).collect();
+        stats.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_record_counts_each_fire() {
+        let stats = MacroStats::new();
+        for _ in 0..5 {
+            stats.record("F1");
+        }
+        stats.record("F2|GP:A");
+
+        assert_eq!(stats.snapshot(), vec![("F1".to_string(), 5), ("F2|GP:A".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_record_from_multiple_threads() {
+        let stats = MacroStats::new();
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..100 {
+                        stats.record("F1");
+                    }
+                });
+            }
+        });
+
+        assert_eq!(stats.snapshot(), vec![("F1".to_string(), 400)]);
+    }
+}
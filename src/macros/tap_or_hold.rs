@@ -0,0 +1,161 @@
+//! 轻按/长按判定模块
+//!
+//! 设置了 `tap_action` / `hold_action` 的热键按下时先记录按下时间，
+//! 在 `hold_threshold_ms` 内松开视为轻按，否则在到达阈值时视为长按。
+//! 宏处理线程等待事件时以最早的阈值时刻为超时，到期后取出到达阈值的按下执行长按操作，不为每次按下单独创建计时线程
+
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// 判定结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressKind {
+    /// 在阈值内松开，执行 `tap_action`
+    Tap,
+    /// 按住达到阈值，执行 `hold_action`
+    Hold,
+}
+
+/// 一次尚未判定的按下
+#[derive(Debug, Clone, Copy)]
+struct PendingPress {
+    pressed_at: Instant,
+    threshold: Duration,
+}
+
+impl PendingPress {
+    /// 到达阈值、判定为长按的时刻
+    fn deadline(&self) -> Instant {
+        self.pressed_at + self.threshold
+    }
+}
+
+/// 尚未判定为轻按或长按的触发键
+#[derive(Debug, Default)]
+pub struct PendingPresses {
+    pending: Mutex<HashMap<String, PendingPress>>,
+}
+
+impl PendingPresses {
+    /// 创建空记录
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次按下（同一触发键尚未判定的上一次按下随之作废）
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 触发键名称
+    /// * `threshold` - 区分轻按和长按的按住时长
+    /// * `now` - 按下时间
+    pub fn press(&self, name: &str, threshold: Duration, now: Instant) {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        pending.insert(name.to_string(), PendingPress { pressed_at: now, threshold });
+    }
+
+    /// 触发键松开
+    ///
+    /// # 返回
+    ///
+    /// 尚未判定时按按住时长返回轻按或长按（宏处理线程忙于执行其他宏、来不及取出到期的按下时也能正确判定）；
+    /// 已判定为长按或没有记录时返回 None
+    pub fn release(&self, name: &str, now: Instant) -> Option<PressKind> {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        let press = pending.remove(name)?;
+        if now.saturating_duration_since(press.pressed_at) < press.threshold {
+            Some(PressKind::Tap)
+        } else {
+            Some(PressKind::Hold)
+        }
+    }
+
+    /// 尚未判定的按下中最早到达阈值的时刻，没有尚未判定的按下时返回 None
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        pending.values().map(PendingPress::deadline).min()
+    }
+
+    /// 取出在 `now` 之前到达阈值的按下（判定为长按）
+    ///
+    /// # 返回
+    ///
+    /// 到达阈值的触发键名称，按到达阈值的先后排列；取出后松开时不再判定
+    pub fn take_expired(&self, now: Instant) -> Vec<String> {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut expired: Vec<(Instant, String)> =
+            pending.iter().filter(|(_, press)| press.deadline() <= now).map(|(name, press)| (press.deadline(), name.clone())).collect();
+        expired.sort();
+        for (_, name) in &expired {
+            pending.remove(name);
+        }
+        expired.into_iter().map(|(_, name)| name).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: Duration = Duration::from_millis(300);
+
+    #[test]
+    fn test_quick_release_resolves_to_tap() {
+        let presses = PendingPresses::new();
+        let start = Instant::now();
+
+        presses.press("F1", THRESHOLD, start);
+        assert_eq!(presses.release("F1", start + Duration::from_millis(120)), Some(PressKind::Tap));
+
+        // 松开后不再等待阈值，也不会判定为长按
+        assert_eq!(presses.next_deadline(), None);
+        assert!(presses.take_expired(start + THRESHOLD).is_empty());
+        assert_eq!(presses.release("F1", start + THRESHOLD), None);
+    }
+
+    #[test]
+    fn test_long_hold_resolves_at_threshold() {
+        let presses = PendingPresses::new();
+        let start = Instant::now();
+
+        presses.press("F1", THRESHOLD, start);
+        assert_eq!(presses.next_deadline(), Some(start + THRESHOLD));
+        assert!(presses.take_expired(start + Duration::from_millis(299)).is_empty());
+        assert_eq!(presses.take_expired(start + THRESHOLD), vec!["F1"]);
+        // 长按的操作已在到达阈值时执行，松开时不再执行任何操作
+        assert_eq!(presses.release("F1", start + Duration::from_millis(900)), None);
+
+        // 还没取出到期的按下就松开时按按住时长判定
+        presses.press("F1", THRESHOLD, start);
+        assert_eq!(presses.release("F1", start + THRESHOLD), Some(PressKind::Hold));
+    }
+
+    #[test]
+    fn test_new_press_restarts_threshold() {
+        let presses = PendingPresses::new();
+        let start = Instant::now();
+
+        presses.press("F1", THRESHOLD, start);
+        presses.release("F1", start + Duration::from_millis(50));
+        presses.press("F1", THRESHOLD, start + Duration::from_millis(100));
+
+        // 第一次按下的阈值时刻不应把第二次按下判定为长按
+        assert!(presses.take_expired(start + THRESHOLD).is_empty());
+        assert_eq!(presses.take_expired(start + Duration::from_millis(400)), vec!["F1"]);
+    }
+
+    #[test]
+    fn test_expired_presses_in_deadline_order() {
+        let presses = PendingPresses::new();
+        let start = Instant::now();
+
+        presses.press("F1", THRESHOLD, start);
+        presses.press("F2", Duration::from_millis(100), start + Duration::from_millis(50));
+        presses.press("F3", THRESHOLD, start + Duration::from_millis(200));
+        assert_eq!(presses.next_deadline(), Some(start + Duration::from_millis(150)));
+
+        assert_eq!(presses.take_expired(start + THRESHOLD), vec!["F2", "F1"]);
+        assert_eq!(presses.next_deadline(), Some(start + Duration::from_millis(500)));
+    }
+}
@@ -0,0 +1,94 @@
+//! 触发键状态模块
+//!
+//! 记录哪些触发键仍被按住，供序列中的 `wait_release` 步骤等待触发键松开
+
+use std::collections::HashSet;
+use std::sync::{Condvar, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// 仍被按住的触发键
+///
+/// 钩子和手柄转发线程在派发按下事件时标记按住、松开时清除并唤醒等待者；
+/// 宏处理线程同步执行序列，松开事件无法经过它自己的事件队列送达，所以直接在这里通知
+#[derive(Debug, Default)]
+pub struct HeldTriggers {
+    held: Mutex<HashSet<String>>,
+    released: Condvar,
+}
+
+impl HeldTriggers {
+    /// 创建空记录
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 标记触发键已按下
+    ///
+    /// # 参数
+    ///
+    /// * `key_name` - 热键名称（与派发的 `MacroEvent` 中的名称相同，如 "F1"、"GP:A"）
+    pub fn press(&self, key_name: &str) {
+        let mut held = self.held.lock().unwrap_or_else(PoisonError::into_inner);
+        held.insert(key_name.to_string());
+    }
+
+    /// 标记触发键已松开，唤醒所有等待者
+    ///
+    /// # 参数
+    ///
+    /// * `key_name` - 热键名称
+    pub fn release(&self, key_name: &str) {
+        let mut held = self.held.lock().unwrap_or_else(PoisonError::into_inner);
+        if held.remove(key_name) {
+            self.released.notify_all();
+        }
+    }
+
+    /// 触发键是否仍被按住
+    pub fn is_held(&self, key_name: &str) -> bool {
+        self.held.lock().unwrap_or_else(PoisonError::into_inner).contains(key_name)
+    }
+
+    /// 等待触发键松开
+    ///
+    /// # 参数
+    ///
+    /// * `key_name` - 热键名称
+    /// * `timeout` - 最长等待时间
+    ///
+    /// # 返回
+    ///
+    /// 触发键已松开（或本来就没有按住）时返回 true，超时仍按住时返回 false
+    pub fn wait_release(&self, key_name: &str, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut held = self.held.lock().unwrap_or_else(PoisonError::into_inner);
+        while held.contains(key_name) {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+            held = self.released.wait_timeout(held, remaining).unwrap_or_else(PoisonError::into_inner).0;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wait_release_times_out_while_held() {
+        let triggers = HeldTriggers::new();
+        assert!(triggers.wait_release("F1", Duration::ZERO));
+
+        triggers.press("F1");
+        assert!(!triggers.wait_release("F1", Duration::from_millis(20)));
+        // 其他按键松开不影响等待
+        triggers.release("F2");
+        assert!(triggers.is_held("F1"));
+
+        triggers.release("F1");
+        assert!(triggers.wait_release("F1", Duration::ZERO));
+    }
+}
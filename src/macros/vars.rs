@@ -0,0 +1,90 @@
+//! 序列变量模块
+//!
+//! 保存 `set_var` / `inc_var` 步骤写入的整数变量，供 `if_var` 步骤判断，
+//! 让同一个宏在多次触发之间交替执行不同的操作
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// 序列变量存储
+///
+/// 变量按名称区分；未设置过的变量视为 0
+#[derive(Debug, Default)]
+pub struct Variables {
+    values: Mutex<HashMap<String, i64>>,
+}
+
+impl Variables {
+    /// 创建空存储
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 读取变量，未设置时返回 0
+    pub fn get(&self, name: &str) -> i64 {
+        self.values.lock().unwrap_or_else(PoisonError::into_inner).get(name).copied().unwrap_or(0)
+    }
+
+    /// 设置变量
+    pub fn set(&self, name: &str, value: i64) {
+        self.values.lock().unwrap_or_else(PoisonError::into_inner).insert(name.to_string(), value);
+    }
+
+    /// 变量加上 `by`（超出范围时取边界值）
+    ///
+    /// # 返回
+    ///
+    /// 修改后的值
+    pub fn add(&self, name: &str, by: i64) -> i64 {
+        let mut values = self.values.lock().unwrap_or_else(PoisonError::into_inner);
+        let value = values.entry(name.to_string()).or_insert(0);
+        *value = value.saturating_add(by);
+        *value
+    }
+}
+
+/// 按触发键区分的变量存储，每个热键的变量互不影响（不同热键可以使用同名变量）
+#[derive(Debug, Default)]
+pub struct HotkeyVariables {
+    stores: Mutex<HashMap<String, Arc<Variables>>>,
+}
+
+impl HotkeyVariables {
+    /// 创建空存储
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 触发键的变量存储，第一次使用时创建
+    pub fn for_key(&self, key_name: &str) -> Arc<Variables> {
+        let mut stores = self.stores.lock().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(stores.entry(key_name.to_string()).or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unset_variable_reads_zero() {
+        let vars = Variables::new();
+        assert_eq!(vars.get("count"), 0);
+        assert_eq!(vars.add("count", 2), 2);
+        assert_eq!(vars.add("count", -5), -3);
+
+        vars.set("count", i64::MAX);
+        assert_eq!(vars.add("count", 1), i64::MAX);
+    }
+
+    #[test]
+    fn test_hotkeys_keep_separate_variables() {
+        let stores = HotkeyVariables::new();
+        stores.for_key("F1").set("toggle", 1);
+        assert_eq!(stores.for_key("F2").get("toggle"), 0);
+
+        stores.for_key("F2").add("toggle", 5);
+        assert_eq!(stores.for_key("F1").get("toggle"), 1);
+        assert_eq!(stores.for_key("F2").get("toggle"), 5);
+    }
+}
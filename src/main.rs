@@ -11,20 +11,95 @@ fn main() {
     // 初始化日志系统
     logger::init_logger();
 
-    // 加载配置文件
-    let config = match bootstrap::load_config() {
-        Ok(cfg) => cfg,
+    // 启用 DPI 感知（必须在创建窗口之前）
+    bootstrap::enable_dpi_awareness();
+
+    // 解析命令行参数
+    let args = match bootstrap::CliArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
         Err(e) => {
-            log::error!("加载配置文件失败: {}", e);
+            log::error!("解析命令行参数失败: {}", e);
             bootstrap::show_error_dialog(&e);
             std::process::exit(1);
         }
     };
 
+    // 无界面模式和 --non-interactive 不弹出需要回答的对话框
+    let interactive = args.interactive();
+
+    // 无界面模式附加到启动它的控制台，以便接收 Ctrl+C
+    if args.headless && !bootstrap::attach_console() {
+        log::info!("没有可附加的控制台，无界面模式只能通过结束进程退出");
+    }
+
+    // 单实例检查（守卫需要持有到程序退出）
+    let _instance_guard = if args.allow_multiple_instances {
+        None
+    } else {
+        match bootstrap::acquire_single_instance() {
+            Ok(guard) => guard,
+            Err(e) => {
+                log::warn!("{}", e);
+                report_error(interactive, &e);
+                std::process::exit(1);
+            }
+        }
+    };
+
+    // 启动时按住安全模式按键（默认 Shift）则跳过配置，用于从写错的配置中恢复（无界面模式不检查）
+    if !args.headless && bootstrap::safe_mode_requested() {
+        log::warn!("检测到安全模式按键，跳过加载配置");
+        let config_path = args.config_path().unwrap_or_else(|| std::path::PathBuf::from("config.yaml"));
+        if let Err(e) = bootstrap::run_safe_mode(&config_path) {
+            log::error!("安全模式运行失败: {}", e);
+            bootstrap::show_error_dialog(&e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // 加载配置文件（--config 参数或 KEYMACRO_CONFIG 环境变量优先，不能弹出对话框时不询问是否生成示例配置）
+    let (config, config_path) = match bootstrap::load_config(args.config_path().as_deref(), interactive) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            log::error!("加载配置文件失败: {}", e);
+            report_error(interactive, &e);
+            std::process::exit(1);
+        }
+    };
+
+    // 无界面模式不弹出对话框，启动警告只记录在日志中
+    if args.headless {
+        if let Err(e) = bootstrap::run_headless(config) {
+            log::error!("无界面模式运行失败: {}", e);
+            report_error(false, &e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // 没有能够触发的热键时通常是配置写错了，询问是否继续（不询问时加载配置时已记录警告）
+    if interactive && !bootstrap::confirm_active_hotkeys(&config, &config_path) {
+        log::info!("配置中没有已启用的热键，已取消启动");
+        return;
+    }
+
+    // 绑定到单独的修饰键等危险按键时提醒用户（不阻止启动）
+    bootstrap::warn_dangerous_hotkeys(&config, interactive);
+
     // 初始化并运行应用
-    if let Err(e) = bootstrap::run_application(config) {
+    if let Err(e) = bootstrap::run_application(config, &config_path) {
         log::error!("应用运行失败: {}", e);
         bootstrap::show_error_dialog(&e);
         std::process::exit(1);
     }
 }
+
+/// 报告启动错误：不能弹出对话框时（无界面模式或 `--non-interactive`）输出到控制台（没有控制台时只记录在日志中），否则弹出错误对话框
+fn report_error(interactive: bool, message: &str) {
+    if interactive {
+        bootstrap::show_error_dialog(message);
+    } else {
+        eprintln!("{}", message);
+    }
+}
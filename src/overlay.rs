@@ -1,244 +1,416 @@
 //! 屏幕置顶提示模块
 //!
-//! 在屏幕中央显示临时状态提示
+//! 在屏幕中央显示临时状态提示，以及序列执行期间一直显示的进度提示
+//!
+//! 提示窗口只创建一次，由专用线程持有并运行消息循环；每次显示只需投递消息，
+//! 由窗口过程负责定位、重绘和定时隐藏，避免多个线程同时创建/关闭窗口产生竞争
 
 use std::{
-    time::Duration,
     thread,
-    sync::{Mutex, Arc, Condvar},
+    sync::{mpsc, Mutex, atomic::{AtomicBool, AtomicU32, Ordering}},
 };
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use windows::Win32::{
     Foundation::{HWND, WPARAM, LPARAM, LRESULT, COLORREF, RECT},
     UI::WindowsAndMessaging::*,
     Graphics::Gdi::*,
 };
+use crate::config::{OverlayMonitor, Rgb};
+use crate::macros::SequenceProgress;
 use crate::winapi::window;
 
-// 窗口配置
+// 窗口配置（尺寸均为 100% 缩放下的值，显示时按显示器 DPI 缩放）
 const WINDOW_WIDTH: i32 = 200;
 const WINDOW_HEIGHT: i32 = 150;
-const DISPLAY_DURATION_MS: u64 = 500;
+const DISPLAY_DURATION_MS: u32 = 500;
 const WINDOW_ALPHA: u8 = 192;
 const FONT_NAME: &str = "Arial";
 const FONT_SIZE: i32 = 150;
 const FONT_WEIGHT: i32 = 700;
-const COLOR_RED: COLORREF = COLORREF(0x000000FF);
-const COLOR_GREEN: COLORREF = COLORREF(0x0000FF00);
+// 文字颜色（COLORREF 布局为 0x00BBGGRR，统一通过 rgb() 构造），与托盘图标颜色一致：
+// 状态 0（关闭）为红色，状态 1（开启）为绿色；其他配置方案的颜色见 `MacroState::color`
+const COLOR_RED: COLORREF = window::rgb(255, 0, 0);   // R=255, G=0, B=0
+const COLOR_GREEN: COLORREF = window::rgb(0, 255, 0); // R=0, G=255, B=0
 const CLASS_NAME: &str = "OverlayClass_001";
 
-// 窗口状态
-struct WindowState {
-    handle: Option<isize>,
-    is_closing: bool,
+/// 显示提示的自定义消息（WPARAM 为 1 时一直显示，不启动隐藏计时）
+const WM_OVERLAY_SHOW: u32 = WM_APP + 1;
+/// 立即隐藏提示的自定义消息
+const WM_OVERLAY_HIDE: u32 = WM_APP + 2;
+/// 自动隐藏定时器 ID
+const HIDE_TIMER_ID: usize = 1;
+
+/// 当前要显示的文本（UTF-16，不含结尾空字符）
+static OVERLAY_TEXT: Lazy<Mutex<Vec<u16>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// 当前文本的颜色（COLORREF 的值）
+static OVERLAY_COLOR: AtomicU32 = AtomicU32::new(COLOR_RED.0);
+
+/// 提示窗口句柄（首次显示时创建，创建失败时为 None）
+static OVERLAY_WINDOW: OnceCell<Option<isize>> = OnceCell::new();
+
+/// 是否显示提示（配置 `overlay_enabled`），关闭时不会创建提示窗口
+static OVERLAY_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// 显示提示的显示器（配置 `overlay_monitor`）
+static OVERLAY_MONITOR: Mutex<OverlayMonitor> = Mutex::new(OverlayMonitor::Primary);
+
+/// 设置显示提示的显示器
+///
+/// # 参数
+///
+/// * `monitor` - 下次显示提示时在该显示器的工作区中央显示
+pub fn set_overlay_monitor(monitor: OverlayMonitor) {
+    if let Ok(mut current) = OVERLAY_MONITOR.lock() {
+        *current = monitor;
+    }
 }
 
-static CURRENT_WINDOW: Lazy<Arc<(Mutex<WindowState>, Condvar)>> = Lazy::new(|| {
-    Arc::new((
-        Mutex::new(WindowState { handle: None, is_closing: false }),
-        Condvar::new(),
-    ))
-});
+/// 设置是否显示屏幕提示
+///
+/// # 参数
+///
+/// * `enabled` - false 时 `show_overlay` 直接返回，不创建窗口也不启动提示线程
+pub fn set_overlay_enabled(enabled: bool) {
+    OVERLAY_ENABLED.store(enabled, Ordering::Relaxed);
+}
 
-static WINDOW_CLASS_INIT: std::sync::Once = std::sync::Once::new();
+/// 当前是否显示屏幕提示
+fn overlay_enabled() -> bool {
+    OVERLAY_ENABLED.load(Ordering::Relaxed)
+}
 
 /// 在屏幕中央显示状态提示
 ///
 /// # 参数
 ///
-/// * `text` - 显示的文本（"0" 或 "1"）
+/// * `text` - 显示的文本（"0"、"1" 或 "STOPPED" 等提示）
 ///
 /// # 说明
 ///
 /// - 显示 0.5 秒后自动消失
-/// - 0 显示为红色，1 显示为绿色
-/// - 如果已有窗口，会关闭旧窗口后创建新窗口
+/// - 1 显示为绿色，其余文本显示为红色；需要其他颜色时使用 `show_overlay_colored`
+/// - 如果提示正在显示，会直接更新文本并重新计时
+/// - 提示已关闭（`set_overlay_enabled(false)`）时什么也不做
 pub fn show_overlay(text: &str) {
-    close_existing_window_async();
-    
-    let text = text.to_string();
+    let color = if text == "1" { COLOR_GREEN } else { COLOR_RED };
+    show_text(text, color, false);
+}
+
+/// 以指定颜色在屏幕中央显示提示
+///
+/// # 参数
+///
+/// * `text` - 显示的文本
+/// * `color` - 文字颜色（如 `MacroState::color` 表示的配置方案颜色）
+///
+/// # 说明
+///
+/// 除颜色外与 `show_overlay` 相同
+pub fn show_overlay_colored(text: &str, color: Rgb) {
+    show_text(text, window::rgb(color.r, color.g, color.b), false);
+}
+
+/// 在屏幕中央显示触发的宏的名称（绿色）
+///
+/// # 说明
+///
+/// 与 `show_overlay` 相同，0.5 秒后自动消失；提示窗口不会获得焦点
+pub fn show_macro_name(name: &str) {
+    show_text(name, COLOR_GREEN, false);
+}
+
+/// 启动进度提示线程，把收到的序列执行进度显示为一直可见的提示
+///
+/// # 返回
+///
+/// 进度通道的发送端，传给 `macros::set_progress_sender`
+///
+/// # 说明
+///
+/// - 序列开始时显示热键名称，每个步骤开始时更新为 `名称 3/12`，结束时隐藏
+/// - 执行期间不自动隐藏；期间显示的状态提示仍会在 0.5 秒后隐藏
+/// - 提示已关闭（`set_overlay_enabled(false)`）时不显示
+pub fn start_progress_overlay() -> mpsc::Sender<SequenceProgress> {
+    let (sender, receiver) = mpsc::channel();
     thread::spawn(move || {
-        // 注册窗口类（仅一次）
-        WINDOW_CLASS_INIT.call_once(|| {
-            let info = window::WindowClassInfo {
-                class_name: CLASS_NAME.to_string(),
-                window_proc: Some(window_proc),
-                ..Default::default()
-            };
-            
-            if let Err(e) = window::register_window_class(&info) {
-                log::warn!("注册窗口类失败: {}", e);
+        let mut name = String::new();
+        for progress in receiver {
+            match progress {
+                SequenceProgress::Started { name: started } => {
+                    name = started;
+                    show_text(&name, COLOR_GREEN, true);
+                }
+                SequenceProgress::Step { index, total } => show_text(&progress_text(&name, index, total), COLOR_GREEN, true),
+                SequenceProgress::Finished => hide_overlay(),
             }
-        });
-        
-        // 准备窗口文本和创建参数
-        let status_text_vec: Vec<u16> = text.encode_utf16().chain(Some(0)).collect();
-        let window_text = format!("状态: {}", text);
-        let create_param = status_text_vec.as_ptr() as *const core::ffi::c_void;
-        
+        }
+    });
+    sender
+}
+
+/// 进度提示的文本，如 `F5 3/12`
+fn progress_text(name: &str, index: usize, total: usize) -> String {
+    format!("{} {}/{}", name, index, total)
+}
+
+/// 立即隐藏提示（提示窗口尚未创建时什么也不做）
+fn hide_overlay() {
+    let Some(Some(hwnd_value)) = OVERLAY_WINDOW.get().copied() else {
+        return;
+    };
+    if let Err(e) = window::post_message(hwnd_value, WM_OVERLAY_HIDE, WPARAM(0), LPARAM(0)) {
+        log::warn!("发送隐藏消息失败: {}", e);
+    }
+}
+
+/// 更新提示文本和颜色并通知提示窗口显示，`persistent` 为 true 时一直显示到隐藏或下一次定时提示
+fn show_text(text: &str, color: COLORREF, persistent: bool) {
+    if !overlay_enabled() {
+        return;
+    }
+
+    OVERLAY_COLOR.store(color.0, Ordering::Relaxed);
+    if let Ok(mut current) = OVERLAY_TEXT.lock() {
+        *current = text.encode_utf16().collect();
+    }
+
+    let Some(hwnd_value) = *OVERLAY_WINDOW.get_or_init(start_overlay_thread) else {
+        return;
+    };
+
+    if let Err(e) = window::post_message(hwnd_value, WM_OVERLAY_SHOW, WPARAM(usize::from(persistent)), LPARAM(0)) {
+        log::warn!("发送显示消息失败: {}", e);
+    }
+}
+
+/// 启动提示线程并等待窗口创建完成
+///
+/// # 返回
+///
+/// 成功返回窗口句柄值，失败返回 None
+fn start_overlay_thread() -> Option<isize> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let info = window::WindowClassInfo {
+            class_name: CLASS_NAME.to_string(),
+            window_proc: Some(window_proc),
+            ..Default::default()
+        };
+
+        if let Err(e) = window::register_window_class(&info) {
+            log::warn!("注册窗口类失败: {}", e);
+        }
+
         let create_info = window::WindowCreateInfo {
             class_name: CLASS_NAME.to_string(),
-            window_name: window_text,
+            window_name: "状态提示".to_string(),
             style: WS_POPUP,
             ex_style: WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_LAYERED | WS_EX_NOACTIVATE,
             x: 0,
             y: 0,
             width: WINDOW_WIDTH,
             height: WINDOW_HEIGHT,
-            create_param: Some(create_param),
+            create_param: None,
         };
-        
+
         let hwnd = match window::create_window(&create_info) {
             Ok(hwnd) => hwnd,
             Err(e) => {
                 log::warn!("创建窗口失败: {}", e);
+                let _ = sender.send(None);
                 return;
             }
         };
-        
-        // 保存窗口句柄
-        {
-            let (mutex, _) = &**CURRENT_WINDOW;
-            let mut window_state = mutex.lock().unwrap();
-            window_state.handle = Some(hwnd.0 as isize);
-            window_state.is_closing = false;
-        }
-        
-        // 设置窗口位置（屏幕中央）和透明度
-        let screen_width = window::get_system_metrics(SM_CXSCREEN);
-        let screen_height = window::get_system_metrics(SM_CYSCREEN);
-        
-        let _ = window::set_window_position(
-            hwnd,
-            (screen_width - WINDOW_WIDTH) / 2,
-            (screen_height - WINDOW_HEIGHT) / 2,
-            WINDOW_WIDTH,
-            WINDOW_HEIGHT,
-            SWP_SHOWWINDOW,
-        );
-        
+
         let _ = window::set_window_alpha(hwnd, WINDOW_ALPHA);
-        
-        // 显示窗口
-        let _ = window::show_window(hwnd, SW_SHOW);
-        let _ = window::set_foreground_window(hwnd);
-        let _ = window::bring_window_to_top(hwnd);
-        
-        // 消息循环，确保窗口绘制
+        let _ = sender.send(Some(hwnd.0 as isize));
+
+        // 消息循环，窗口在程序运行期间一直存在
         let mut msg = MSG::default();
-        let start_time = std::time::Instant::now();
-        
-        while start_time.elapsed() < Duration::from_millis(DISPLAY_DURATION_MS) {
-            unsafe {
-                while PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE).as_bool() {
-                    let _ = TranslateMessage(&msg);
-                    let _ = DispatchMessageW(&msg);
-                }
-            }
-            thread::sleep(Duration::from_millis(10));
-        }
-        
-        // 销毁窗口并清除句柄
-        let _ = window::destroy_window(hwnd);
-        
-        {
-            let (mutex, cvar) = &**CURRENT_WINDOW;
-            let mut window_state = mutex.lock().unwrap();
-            window_state.handle = None;
-            window_state.is_closing = false;
-            cvar.notify_all();
+        while window::get_message(&mut msg) {
+            window::dispatch_message(&msg);
         }
+        log::warn!("提示窗口消息循环已退出");
     });
+
+    receiver.recv().ok().flatten()
+}
+
+/// 按窗口所在显示器 DPI 缩放后的尺寸
+struct ScaledMetrics {
+    width: i32,
+    height: i32,
+    font_size: i32,
+}
+
+impl ScaledMetrics {
+    fn for_window(hwnd: HWND, text_len: usize) -> Self {
+        Self::for_dpi(window::get_dpi_for_window(hwnd), text_len)
+    }
+
+    fn for_dpi(dpi: u32, text_len: usize) -> Self {
+        Self {
+            width: window::scale_for_dpi(WINDOW_WIDTH, dpi),
+            height: window::scale_for_dpi(WINDOW_HEIGHT, dpi),
+            font_size: window::scale_for_dpi(font_size_for(text_len), dpi),
+        }
+    }
+}
+
+/// 按文本长度选择字号，使 "STOPPED" 等多字符提示也能完整显示在窗口内
+fn font_size_for(text_len: usize) -> i32 {
+    if text_len <= 1 {
+        FONT_SIZE
+    } else {
+        FONT_SIZE * 2 / (text_len as i32 + 1)
+    }
+}
+
+/// 选择显示提示的工作区
+///
+/// # 参数
+///
+/// * `monitors` - 所有显示器
+/// * `choice` - 配置的显示器
+/// * `cursor` - 鼠标光标位置，只在 `choice` 为 `Cursor` 时使用
+///
+/// # 返回
+///
+/// 所选显示器的工作区；光标不在任何显示器上或序号超出范围时使用主显示器，没有显示器信息时返回 None
+fn select_work_area(monitors: &[window::MonitorArea], choice: OverlayMonitor, cursor: Option<(i32, i32)>) -> Option<RECT> {
+    let selected = match choice {
+        OverlayMonitor::Primary => None,
+        OverlayMonitor::Cursor => cursor.and_then(|(x, y)| {
+            monitors
+                .iter()
+                .find(|m| x >= m.bounds.left && x < m.bounds.right && y >= m.bounds.top && y < m.bounds.bottom)
+        }),
+        OverlayMonitor::Index(index) => {
+            let monitor = index.checked_sub(1).and_then(|i| monitors.get(i));
+            if monitor.is_none() {
+                log::warn!("没有第 {} 个显示器（共 {} 个），提示显示在主显示器上", index, monitors.len());
+            }
+            monitor
+        }
+    };
+    selected
+        .or_else(|| monitors.iter().find(|m| m.primary))
+        .or_else(|| monitors.first())
+        .map(|m| m.work_area)
+}
+
+/// 显示提示的工作区，无法枚举显示器时使用主显示器的整个屏幕
+fn overlay_work_area() -> RECT {
+    let choice = OVERLAY_MONITOR.lock().map(|m| *m).unwrap_or_default();
+    let cursor = if choice == OverlayMonitor::Cursor { window::cursor_position() } else { None };
+    select_work_area(&window::enum_monitors(), choice, cursor).unwrap_or_else(|| RECT {
+        left: 0,
+        top: 0,
+        right: window::get_system_metrics(SM_CXSCREEN),
+        bottom: window::get_system_metrics(SM_CYSCREEN),
+    })
+}
+
+/// 按 `dpi` 缩放窗口尺寸并移动到所选显示器的工作区中央
+fn center_window(hwnd: HWND, dpi: u32, flags: SET_WINDOW_POS_FLAGS) {
+    let area = overlay_work_area();
+    let text_len = OVERLAY_TEXT.lock().map(|t| t.len()).unwrap_or(0);
+    let metrics = ScaledMetrics::for_dpi(dpi, text_len);
+
+    let _ = window::set_window_position(
+        hwnd,
+        area.left + (area.right - area.left - metrics.width) / 2,
+        area.top + (area.bottom - area.top - metrics.height) / 2,
+        metrics.width,
+        metrics.height,
+        flags,
+    );
+}
+
+/// 在所选显示器的工作区中央显示窗口并重新开始隐藏计时（`persistent` 为 true 时取消隐藏计时）
+fn show_window_centered(hwnd: HWND, persistent: bool) {
+    center_window(hwnd, window::get_dpi_for_window(hwnd), SWP_SHOWWINDOW | SWP_NOACTIVATE);
+
+    // 不激活提示窗口，宏执行期间显示提示时按键仍发送到原来的前台窗口
+    let _ = window::show_window(hwnd, SW_SHOWNOACTIVATE);
+    window::invalidate_window(hwnd);
+
+    if persistent {
+        let _ = window::kill_timer(hwnd, HIDE_TIMER_ID);
+    } else if let Err(e) = window::set_timer(hwnd, HIDE_TIMER_ID, DISPLAY_DURATION_MS) {
+        log::warn!("设置隐藏定时器失败: {}", e);
+    }
 }
 
 /// 窗口过程（处理窗口消息）
 unsafe extern "system" fn window_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
     match msg {
-        WM_CREATE => {
-            // 保存状态文本指针
-            let create_struct = lparam.0 as *const CREATESTRUCTW;
-            if !create_struct.is_null() {
-                let text_ptr = (*create_struct).lpCreateParams;
-                if !text_ptr.is_null() {
-                    let _ = window::set_window_long_ptr(hwnd, GWLP_USERDATA, text_ptr as isize);
-                }
-            }
+        WM_OVERLAY_SHOW => {
+            show_window_centered(hwnd, wparam.0 == 1);
             LRESULT(0)
         }
-        WM_CLOSE => {
-            // 标记窗口正在关闭
-            {
-                let (mutex, cvar) = &**CURRENT_WINDOW;
-                let mut window_state = mutex.lock().unwrap();
-                if let Some(hwnd_value) = window_state.handle {
-                    if hwnd.0 as isize == hwnd_value {
-                        window_state.is_closing = true;
-                    }
-                }
-                cvar.notify_all();
-            }
-            let _ = window::destroy_window(hwnd);
+        WM_OVERLAY_HIDE => {
+            let _ = window::kill_timer(hwnd, HIDE_TIMER_ID);
+            let _ = window::show_window(hwnd, SW_HIDE);
+            LRESULT(0)
+        }
+        WM_TIMER if wparam.0 == HIDE_TIMER_ID => {
+            let _ = window::kill_timer(hwnd, HIDE_TIMER_ID);
+            let _ = window::show_window(hwnd, SW_HIDE);
+            LRESULT(0)
+        }
+        // 窗口移到 DPI 不同的显示器（或显示器缩放比例改变）时，按新的 DPI（WPARAM 的低 16 位）重新计算尺寸并居中，
+        // 不使用系统建议的矩形，以免窗口偏离工作区中央
+        WM_DPICHANGED => {
+            center_window(hwnd, (wparam.0 & 0xFFFF) as u32, SWP_NOACTIVATE);
+            window::invalidate_window(hwnd);
             LRESULT(0)
         }
         WM_PAINT => {
             let mut ps = PAINTSTRUCT::default();
-            
+
             if let Ok(hdc) = window::begin_paint(hwnd, &mut ps) {
+                // 获取状态文本
+                let text_vec = OVERLAY_TEXT.lock().map(|t| t.clone()).unwrap_or_default();
+                let metrics = ScaledMetrics::for_window(hwnd, text_vec.len());
+
                 // 创建字体
                 let font_info = window::FontInfo {
                     name: FONT_NAME.to_string(),
-                    size: FONT_SIZE,
+                    size: metrics.font_size,
                     weight: FONT_WEIGHT,
                 };
-                
+
                 if let Ok(hfont) = window::create_font(&font_info) {
                     if let Ok(old_font) = window::select_object(hdc, HGDIOBJ(hfont.0)) {
                         // 绘制文本
                         let rect = RECT {
-                            left: 0, top: 0, right: WINDOW_WIDTH, bottom: WINDOW_HEIGHT
+                            left: 0, top: 0, right: metrics.width, bottom: metrics.height
                         };
-                        
+
                         let _ = window::set_bk_mode(hdc, TRANSPARENT);
-                        
-                        // 获取状态文本并设置颜色
-                        let text_ptr = window::get_window_long_ptr(hwnd, GWLP_USERDATA) as *const u16;
-                        if !text_ptr.is_null() {
-                            let text_slice = std::slice::from_raw_parts(text_ptr, 256);
-                            let mut text_vec = Vec::new();
-                            let mut is_one = false;
-                            
-                            // 复制字符串直到遇到空字符
-                            for &ch in text_slice.iter() {
-                                if ch == 0 {
-                                    break;
-                                }
-                                if ch == 49 && text_vec.is_empty() { // 49 = '1'
-                                    is_one = true;
-                                }
-                                text_vec.push(ch);
-                            }
-                            
-                            // 根据状态设置颜色
-                            let text_color = if is_one { COLOR_GREEN } else { COLOR_RED };
-                            let _ = window::set_text_color(hdc, text_color);
-                            
-                            let mut draw_info = window::DrawTextInfo {
-                                text: text_vec,
-                                rect,
-                                format: DT_CENTER | DT_VCENTER | DT_SINGLELINE,
-                            };
-                            
-                            let _ = window::draw_text(hdc, &mut draw_info);
-                        }
-                        
+
+                        let text_color = COLORREF(OVERLAY_COLOR.load(Ordering::Relaxed));
+                        let _ = window::set_text_color(hdc, text_color);
+
+                        let mut draw_info = window::DrawTextInfo {
+                            text: text_vec,
+                            rect,
+                            format: DT_CENTER | DT_VCENTER | DT_SINGLELINE,
+                        };
+
+                        let _ = window::draw_text(hdc, &mut draw_info);
+
                         let _ = window::select_object(hdc, old_font);
                         let _ = window::delete_object(HGDIOBJ(hfont.0));
                     }
                 }
-                
+
                 let _ = window::end_paint(hwnd, &ps);
             }
-            
+
             LRESULT(0)
         }
         WM_DESTROY => {
@@ -249,22 +421,50 @@ unsafe extern "system" fn window_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lpar
     }
 }
 
-/// 关闭已存在的窗口（异步）
-fn close_existing_window_async() {
-    let (mutex, _cvar) = &**CURRENT_WINDOW;
-    let mut window_state = mutex.lock().unwrap();
-    
-    if let Some(hwnd_value) = window_state.handle {
-        if window_state.is_closing {
-            return;
-        }
-        
-        window_state.is_closing = true;
-        
-        // 发送关闭消息
-        if let Err(e) = window::post_close_message(hwnd_value) {
-            log::warn!("发送关闭消息失败: {}", e);
-            let _ = window::destroy_window(HWND(hwnd_value as *mut core::ffi::c_void));
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_disabled_overlay_creates_no_window() {
+        let config = Config::from_str("overlay_enabled: false\nhotkeys: []").unwrap();
+        set_overlay_enabled(config.overlay_enabled);
+        assert!(!overlay_enabled());
+
+        show_overlay("1");
+        // 提前返回，没有启动提示线程，也没有修改要显示的文本
+        assert!(OVERLAY_WINDOW.get().is_none());
+        assert!(OVERLAY_TEXT.lock().unwrap().is_empty());
+
+        set_overlay_enabled(Config::from_str("hotkeys: []").unwrap().overlay_enabled);
+        assert!(overlay_enabled());
+    }
+
+    #[test]
+    fn test_progress_text() {
+        assert_eq!(progress_text("F5", 3, 12), "F5 3/12");
+    }
+
+    #[test]
+    fn test_select_work_area() {
+        let area = |left: i32, top: i32, right: i32, bottom: i32| RECT { left, top, right, bottom };
+        // 主显示器在中间，左侧显示器在负坐标上，右侧显示器的任务栏在顶部
+        let monitors = [
+            window::MonitorArea { bounds: area(-1920, 0, 0, 1080), work_area: area(-1920, 0, 0, 1040), primary: false },
+            window::MonitorArea { bounds: area(0, 0, 2560, 1440), work_area: area(0, 0, 2560, 1400), primary: true },
+            window::MonitorArea { bounds: area(2560, 0, 4480, 1080), work_area: area(2560, 40, 4480, 1080), primary: false },
+        ];
+
+        assert_eq!(select_work_area(&monitors, OverlayMonitor::Primary, None), Some(area(0, 0, 2560, 1400)));
+        assert_eq!(select_work_area(&monitors, OverlayMonitor::Cursor, Some((-5, 500))), Some(area(-1920, 0, 0, 1040)));
+        assert_eq!(select_work_area(&monitors, OverlayMonitor::Cursor, Some((2560, 0))), Some(area(2560, 40, 4480, 1080)));
+        assert_eq!(select_work_area(&monitors, OverlayMonitor::Index(3), None), Some(area(2560, 40, 4480, 1080)));
+
+        // 光标不在任何显示器上、序号超出范围时使用主显示器
+        assert_eq!(select_work_area(&monitors, OverlayMonitor::Cursor, Some((0, 5000))), Some(area(0, 0, 2560, 1400)));
+        assert_eq!(select_work_area(&monitors, OverlayMonitor::Cursor, None), Some(area(0, 0, 2560, 1400)));
+        assert_eq!(select_work_area(&monitors, OverlayMonitor::Index(4), None), Some(area(0, 0, 2560, 1400)));
+        assert_eq!(select_work_area(&[], OverlayMonitor::Index(1), None), None);
     }
 }
@@ -0,0 +1,168 @@
+//! 运行状态模块
+//!
+//! 定义托盘图标和屏幕提示表示的宏状态，
+//! 并在程序目录的 state.yaml 中保存需要在重启后保留的状态（如宏开关状态）
+
+use crate::config::Rgb;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 状态文件名（与可执行文件位于同一目录）
+const STATE_FILE: &str = "state.yaml";
+
+/// 宏关闭时的颜色（红色）
+pub const DISABLED_COLOR: Rgb = Rgb { r: 255, g: 0, b: 0 };
+
+/// 各配置方案开启时的颜色，第一个（绿色）为默认方案；方案数超过颜色数时循环使用
+static PROFILE_COLORS: &[Rgb] = &[
+    Rgb { r: 0, g: 255, b: 0 },
+    Rgb { r: 0, g: 160, b: 255 },
+    Rgb { r: 255, g: 160, b: 0 },
+    Rgb { r: 200, g: 0, b: 255 },
+    Rgb { r: 0, g: 255, b: 255 },
+    Rgb { r: 255, g: 255, b: 0 },
+];
+
+/// 托盘图标和屏幕提示表示的宏状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MacroState {
+    /// 宏已关闭
+    Disabled,
+    /// 宏已开启，`profile` 为当前配置方案序号（0 为默认方案）
+    Enabled { profile: usize },
+}
+
+impl MacroState {
+    /// 由开关状态和当前配置方案构造
+    pub fn new(enabled: bool, profile: usize) -> Self {
+        if enabled {
+            Self::Enabled { profile }
+        } else {
+            Self::Disabled
+        }
+    }
+
+    /// 宏是否开启
+    pub fn is_enabled(&self) -> bool {
+        matches!(self, Self::Enabled { .. })
+    }
+
+    /// 状态文本：关闭为 "0"，开启为方案序号加 1（没有其他配置方案时就是 "1"）
+    pub fn label(&self) -> String {
+        match self {
+            Self::Disabled => "0".to_string(),
+            Self::Enabled { profile } => (profile + 1).to_string(),
+        }
+    }
+
+    /// 状态颜色：关闭为红色，开启时按配置方案区分（默认方案为绿色）
+    pub fn color(&self) -> Rgb {
+        match self {
+            Self::Disabled => DISABLED_COLOR,
+            Self::Enabled { profile } => PROFILE_COLORS[profile % PROFILE_COLORS.len()],
+        }
+    }
+}
+
+/// 重启后保留的运行状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppState {
+    /// 宏是否启用（上次退出前的全局开关状态）
+    pub enabled: bool,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+impl AppState {
+    /// 从文件加载状态
+    ///
+    /// # 说明
+    ///
+    /// 文件不存在时返回默认状态（宏启用）；文件内容无效时记录警告并同样返回默认状态
+    pub fn load(path: &Path) -> Self {
+        let Ok(content) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        Self::parse(&content).unwrap_or_else(|e| {
+            log::warn!("状态文件无效，使用默认状态 ({}): {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    /// 将状态写入文件
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// 从字符串解析状态
+    pub fn parse(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(serde_yaml::from_str(content)?)
+    }
+}
+
+/// 状态文件路径（可执行文件所在目录下的 state.yaml）
+///
+/// # 返回
+///
+/// 无法获取可执行文件路径时返回 None
+pub fn default_state_path() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    Some(exe.parent()?.join(STATE_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_macro_state_colors() {
+        // 没有其他配置方案时与原来的两种状态相同：关闭为红色 "0"，开启为绿色 "1"
+        assert_eq!(MacroState::new(false, 0).color(), Rgb { r: 255, g: 0, b: 0 });
+        assert_eq!(MacroState::new(true, 0).color(), Rgb { r: 0, g: 255, b: 0 });
+        assert_eq!(MacroState::new(false, 3).label(), "0");
+        assert_eq!(MacroState::new(true, 0).label(), "1");
+
+        // 各方案颜色互不相同，也不与关闭状态混淆
+        let colors: Vec<Rgb> = (0..PROFILE_COLORS.len()).map(|p| MacroState::new(true, p).color()).collect();
+        for (i, color) in colors.iter().enumerate() {
+            assert_ne!(*color, DISABLED_COLOR);
+            assert!(!colors[..i].contains(color), "{:?}", color);
+        }
+        assert_eq!(MacroState::new(true, 2).label(), "3");
+        assert_eq!(MacroState::new(true, PROFILE_COLORS.len()).color(), colors[0]);
+        assert!(!MacroState::Disabled.is_enabled());
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let path = std::env::temp_dir().join(format!("keymacro_state_{}.yaml", std::process::id()));
+
+        let state = AppState { enabled: false };
+        state.save(&path).unwrap();
+        assert_eq!(AppState::load(&path), state);
+
+        AppState { enabled: true }.save(&path).unwrap();
+        assert!(AppState::load(&path).enabled);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_missing_or_invalid_state_defaults_to_enabled() {
+        let path = std::env::temp_dir().join(format!("keymacro_state_missing_{}.yaml", std::process::id()));
+        assert_eq!(AppState::load(&path), AppState::default());
+        assert!(AppState::default().enabled);
+
+        assert!(AppState::parse("enabled: [").is_err());
+        assert!(AppState::parse("enabled: maybe").is_err());
+        // 缺少的字段使用默认值
+        assert_eq!(AppState::parse("{}").unwrap(), AppState::default());
+    }
+}
@@ -0,0 +1,54 @@
+//! Windows 剪贴板 API 安全封装
+//!
+//! 提供写入 Unicode 文本的接口，用于以粘贴方式输入长文本
+
+use windows::Win32::{
+    Foundation::{GlobalFree, HANDLE, HWND},
+    System::{
+        DataExchange::{CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData},
+        Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE},
+        Ole::CF_UNICODETEXT,
+    },
+};
+
+/// 将文本写入剪贴板（替换剪贴板原有内容）
+///
+/// # 参数
+///
+/// * `text` - 要写入的文本
+///
+/// # 说明
+///
+/// 剪贴板被其他程序占用时 `OpenClipboard` 会失败并返回错误，不会重试
+pub fn set_text(text: &str) -> Result<(), windows::core::Error> {
+    // CF_UNICODETEXT 要求以 NUL 结尾的 UTF-16 文本
+    let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+    let size = wide.len() * std::mem::size_of::<u16>();
+
+    unsafe {
+        OpenClipboard(HWND::default())?;
+        let result = (|| {
+            EmptyClipboard()?;
+
+            let memory = GlobalAlloc(GMEM_MOVEABLE, size)?;
+            let target = GlobalLock(memory) as *mut u16;
+            if target.is_null() {
+                let error = windows::core::Error::from_win32();
+                let _ = GlobalFree(memory);
+                return Err(error);
+            }
+            std::ptr::copy_nonoverlapping(wide.as_ptr(), target, wide.len());
+            // 解锁后锁计数为 0 时同样返回 FALSE，不视为错误
+            let _ = GlobalUnlock(memory);
+
+            // 写入成功后内存归系统所有，失败时需要自行释放
+            if let Err(e) = SetClipboardData(u32::from(CF_UNICODETEXT.0), HANDLE(memory.0)) {
+                let _ = GlobalFree(memory);
+                return Err(e);
+            }
+            Ok(())
+        })();
+        let _ = CloseClipboard();
+        result
+    }
+}
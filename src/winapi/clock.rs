@@ -0,0 +1,20 @@
+//! 系统时钟封装
+
+use windows::Win32::System::SystemInformation::GetLocalTime;
+
+use crate::datetime::LocalDateTime;
+
+/// 读取当前本地时间（按系统时区）
+pub fn local_now() -> LocalDateTime {
+    let time = unsafe { GetLocalTime() };
+    LocalDateTime {
+        year: time.wYear,
+        month: time.wMonth,
+        day: time.wDay,
+        weekday: time.wDayOfWeek,
+        hour: time.wHour,
+        minute: time.wMinute,
+        second: time.wSecond,
+        millisecond: time.wMilliseconds,
+    }
+}
@@ -0,0 +1,57 @@
+//! Windows 控制台 API 安全封装
+//!
+//! 程序以窗口子系统编译，默认没有控制台；无界面模式下附加到启动它的控制台，以便接收 Ctrl+C
+
+use std::sync::Mutex;
+use windows::Win32::{
+    Foundation::{BOOL, FALSE, TRUE},
+    System::Console::{
+        AttachConsole, SetConsoleCtrlHandler, ATTACH_PARENT_PROCESS, CTRL_BREAK_EVENT, CTRL_CLOSE_EVENT,
+        CTRL_C_EVENT, CTRL_LOGOFF_EVENT, CTRL_SHUTDOWN_EVENT,
+    },
+};
+
+/// 收到 Ctrl+C 等控制台事件时调用的函数
+static CTRL_HANDLER: Mutex<Option<fn()>> = Mutex::new(None);
+
+/// 附加到父进程（如启动程序的命令提示符）的控制台
+///
+/// # 返回
+///
+/// 父进程没有控制台（如从资源管理器启动）时返回 false
+pub fn attach_parent_console() -> bool {
+    unsafe { AttachConsole(ATTACH_PARENT_PROCESS).is_ok() }
+}
+
+/// 设置控制台事件的处理函数
+///
+/// # 参数
+///
+/// * `handler` - 收到 Ctrl+C、Ctrl+Break、关闭控制台、注销或关机事件时调用，在系统创建的线程中执行
+///
+/// # 说明
+///
+/// 多次调用时只保留最后一次设置的处理函数
+pub fn set_ctrl_handler(handler: fn()) -> Result<(), windows::core::Error> {
+    if let Ok(mut current) = CTRL_HANDLER.lock() {
+        *current = Some(handler);
+    }
+    unsafe { SetConsoleCtrlHandler(Some(ctrl_handler_routine), TRUE) }
+}
+
+/// 系统调用的控制台事件处理函数
+unsafe extern "system" fn ctrl_handler_routine(ctrl_type: u32) -> BOOL {
+    match ctrl_type {
+        CTRL_C_EVENT | CTRL_BREAK_EVENT | CTRL_CLOSE_EVENT | CTRL_LOGOFF_EVENT | CTRL_SHUTDOWN_EVENT => {
+            let handler = CTRL_HANDLER.lock().ok().and_then(|h| *h);
+            match handler {
+                Some(handler) => {
+                    handler();
+                    TRUE
+                }
+                None => FALSE,
+            }
+        }
+        _ => FALSE,
+    }
+}
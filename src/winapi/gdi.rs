@@ -0,0 +1,47 @@
+//! Windows GDI API 安全封装
+//!
+//! 提供屏幕像素颜色读取
+
+use windows::Win32::Graphics::Gdi::{GetDC, GetPixel, ReleaseDC, CLR_INVALID};
+
+/// 读取屏幕坐标处像素的颜色
+///
+/// # 参数
+///
+/// * `x` - 屏幕横坐标（像素）
+/// * `y` - 屏幕纵坐标（像素）
+///
+/// # 返回
+///
+/// 成功返回 (R, G, B)，坐标超出屏幕或无法获取屏幕设备上下文时返回 None
+pub fn get_pixel(x: i32, y: i32) -> Option<(u8, u8, u8)> {
+    unsafe {
+        let hdc = GetDC(None);
+        if hdc.is_invalid() {
+            return None;
+        }
+        let color = GetPixel(hdc, x, y);
+        ReleaseDC(None, hdc);
+
+        if color.0 == CLR_INVALID {
+            return None;
+        }
+        Some(colorref_to_rgb(color.0))
+    }
+}
+
+/// 将 COLORREF（0x00BBGGRR）拆分为 (R, G, B)
+fn colorref_to_rgb(value: u32) -> (u8, u8, u8) {
+    (value as u8, (value >> 8) as u8, (value >> 16) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colorref_to_rgb() {
+        assert_eq!(colorref_to_rgb(0x00FF8001), (0x01, 0x80, 0xFF));
+        assert_eq!(colorref_to_rgb(0), (0, 0, 0));
+    }
+}
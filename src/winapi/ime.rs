@@ -0,0 +1,71 @@
+//! 输入法（IME）封装
+//!
+//! `ImmGetContext` / `ImmAssociateContextEx` 只能操作调用线程自己的窗口，而宏输入的目标窗口属于其他进程，
+//! 因此改为向前台窗口的默认输入法窗口（`ImmGetDefaultIMEWnd`）发送 `WM_IME_CONTROL` 查询和设置输入法的开启状态
+
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::UI::Input::Ime::{ImmGetDefaultIMEWnd, IMC_SETOPENSTATUS};
+use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, SendMessageTimeoutW, SMTO_ABORTIFHUNG, WM_IME_CONTROL};
+
+/// `WM_IME_CONTROL` 查询开启状态的子命令（windows crate 未导出）
+const IMC_GETOPENSTATUS: usize = 0x0005;
+/// 等待目标窗口处理输入法消息的最长时间，目标程序无响应时不阻塞宏
+const IME_MESSAGE_TIMEOUT_MS: u32 = 200;
+
+/// 前台窗口的默认输入法窗口（句柄值），没有前台窗口或没有输入法时返回 None
+///
+/// 返回的句柄在前台窗口切换后仍指向原来的窗口，恢复输入法状态时应使用同一个句柄
+pub fn foreground_ime_window() -> Option<isize> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0.is_null() {
+            return None;
+        }
+        let ime_window = ImmGetDefaultIMEWnd(hwnd);
+        (!ime_window.0.is_null()).then_some(ime_window.0 as isize)
+    }
+}
+
+/// 向输入法窗口发送 `WM_IME_CONTROL`，超时或失败时返回 None
+fn send_ime_control(ime_window: HWND, command: usize, value: isize) -> Option<usize> {
+    let mut result = 0usize;
+    let sent = unsafe {
+        SendMessageTimeoutW(
+            ime_window,
+            WM_IME_CONTROL,
+            WPARAM(command),
+            LPARAM(value),
+            SMTO_ABORTIFHUNG,
+            IME_MESSAGE_TIMEOUT_MS,
+            Some(&mut result),
+        )
+    };
+    (sent.0 != 0).then_some(result)
+}
+
+/// 输入法窗口对应的输入法是否处于开启（输入组合）状态
+///
+/// # 参数
+///
+/// * `ime_window` - `foreground_ime_window` 返回的输入法窗口
+///
+/// # 返回
+///
+/// 查询失败（如窗口已关闭）时返回 None
+pub fn ime_open_status(ime_window: isize) -> Option<bool> {
+    send_ime_control(HWND(ime_window as *mut _), IMC_GETOPENSTATUS, 0).map(|status| status != 0)
+}
+
+/// 开启或关闭输入法窗口对应的输入法
+///
+/// # 参数
+///
+/// * `ime_window` - `foreground_ime_window` 返回的输入法窗口
+/// * `open` - true 开启，false 关闭
+///
+/// # 返回
+///
+/// 是否成功发送
+pub fn set_ime_open_status(ime_window: isize, open: bool) -> bool {
+    send_ime_control(HWND(ime_window as *mut _), IMC_SETOPENSTATUS as usize, isize::from(open)).is_some()
+}
@@ -56,51 +56,189 @@ pub fn call_next_hook(hook: HHOOK, code: i32, wparam: WPARAM, lparam: LPARAM) ->
     }
 }
 
-/// 模拟按键
+/// 是否为扩展键
+///
+/// # 说明
+///
+/// 扩展键与小键盘上的按键共用扫描码（如 Delete 与小键盘 `.`、方向键与小键盘 2/4/6/8），
+/// 以扫描码发送时必须带上 `KEYEVENTF_EXTENDEDKEY`，否则会被识别为小键盘按键；
+/// 小键盘数字本身不是扩展键
+pub fn is_extended_key(vk: u16) -> bool {
+    matches!(
+        VIRTUAL_KEY(vk),
+        VK_INSERT | VK_DELETE | VK_HOME | VK_END | VK_PRIOR | VK_NEXT
+            | VK_LEFT | VK_UP | VK_RIGHT | VK_DOWN
+            | VK_RCONTROL | VK_RMENU | VK_LWIN | VK_RWIN | VK_APPS
+            | VK_DIVIDE | VK_NUMLOCK | VK_SNAPSHOT
+    )
+}
+
+/// 构造按键输入事件
 ///
 /// # 参数
 ///
 /// * `vk` - 虚拟键码
 /// * `event_type` - 事件类型（按下或释放）
-pub fn simulate_key(vk: u16, event_type: KeyEventType) -> Result<(), windows::core::Error> {
-    unsafe {
-        let scan_code = MapVirtualKeyW(vk as u32, MAPVK_VK_TO_VSC);
-        
-        let flags = match event_type {
-            KeyEventType::Press => {
-                if scan_code != 0 {
-                    KEYEVENTF_SCANCODE
-                } else {
-                    KEYBD_EVENT_FLAGS::default()
-                }
+pub fn key_input(vk: u16, event_type: KeyEventType) -> INPUT {
+    let scan_code = unsafe { MapVirtualKeyW(vk as u32, MAPVK_VK_TO_VSC) };
+
+    let extended = if scan_code != 0 && is_extended_key(vk) {
+        KEYEVENTF_EXTENDEDKEY
+    } else {
+        KEYBD_EVENT_FLAGS::default()
+    };
+    let flags = extended | match event_type {
+        KeyEventType::Press => {
+            if scan_code != 0 {
+                KEYEVENTF_SCANCODE
+            } else {
+                KEYBD_EVENT_FLAGS::default()
             }
-            KeyEventType::Release => {
-                if scan_code != 0 {
-                    KEYEVENTF_KEYUP | KEYEVENTF_SCANCODE
-                } else {
-                    KEYEVENTF_KEYUP
-                }
+        }
+        KeyEventType::Release => {
+            if scan_code != 0 {
+                KEYEVENTF_KEYUP | KEYEVENTF_SCANCODE
+            } else {
+                KEYEVENTF_KEYUP
             }
-        };
-        
-        let mut input = INPUT::default();
-        input.r#type = INPUT_KEYBOARD;
-        input.Anonymous.ki.wVk = VIRTUAL_KEY(vk);
-        input.Anonymous.ki.wScan = scan_code as u16;
-        input.Anonymous.ki.dwFlags = flags;
-        input.Anonymous.ki.time = 0;
-        // 使用特殊标记标识这是模拟按键，避免钩子死循环
-        input.Anonymous.ki.dwExtraInfo = 0x12345678;
-        
-        let result = SendInput(&[input], std::mem::size_of::<INPUT>() as i32);
-        if result == 0 {
-            Err(windows::core::Error::from_win32())
-        } else {
-            Ok(())
         }
+    };
+
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: VIRTUAL_KEY(vk),
+                wScan: scan_code as u16,
+                dwFlags: flags,
+                time: 0,
+                // 使用特殊标记标识这是模拟按键，避免钩子死循环
+                dwExtraInfo: super::injection_tag(),
+            },
+        },
     }
 }
 
+/// 构造直接指定扫描码的按键输入事件（`KEYEVENTF_SCANCODE`，虚拟键码为 0，不经过虚拟键码映射）
+///
+/// # 参数
+///
+/// * `code` - 扫描码
+/// * `extended` - 是否为扩展键（扫描码带 0xE0 前缀的按键，如右 Ctrl、方向键）
+/// * `event_type` - 事件类型（按下或释放）
+pub fn scancode_input(code: u16, extended: bool, event_type: KeyEventType) -> INPUT {
+    let mut flags = KEYEVENTF_SCANCODE;
+    if extended {
+        flags |= KEYEVENTF_EXTENDEDKEY;
+    }
+    if matches!(event_type, KeyEventType::Release) {
+        flags |= KEYEVENTF_KEYUP;
+    }
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: VIRTUAL_KEY(0),
+                wScan: code,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: super::injection_tag(),
+            },
+        },
+    }
+}
+
+/// 模拟扫描码按键
+///
+/// # 参数
+///
+/// * `code` - 扫描码
+/// * `extended` - 是否为扩展键
+/// * `event_type` - 事件类型（按下或释放）
+pub fn simulate_scancode(code: u16, extended: bool, event_type: KeyEventType) -> Result<(), windows::core::Error> {
+    simulate_inputs(&[scancode_input(code, extended, event_type)])
+}
+
+/// 构造以 Unicode 方式输入字符的输入事件（`KEYEVENTF_UNICODE`，不依赖键盘布局）
+///
+/// # 参数
+///
+/// * `ch` - 要输入的字符
+///
+/// # 返回
+///
+/// 每个 UTF-16 编码单元依次按下并释放；基本平面以外的字符（如表情符号）编码为代理对，
+/// 先发送高位代理再发送低位代理，共 4 个事件
+pub fn unicode_inputs(ch: char) -> Vec<INPUT> {
+    let mut units = [0u16; 2];
+    ch.encode_utf16(&mut units)
+        .iter()
+        .flat_map(|&unit| [unicode_input(unit, KeyEventType::Press), unicode_input(unit, KeyEventType::Release)])
+        .collect()
+}
+
+/// 构造单个 UTF-16 编码单元的 Unicode 输入事件（编码单元放在扫描码中，虚拟键码为 0）
+fn unicode_input(unit: u16, event_type: KeyEventType) -> INPUT {
+    let flags = match event_type {
+        KeyEventType::Press => KEYEVENTF_UNICODE,
+        KeyEventType::Release => KEYEVENTF_UNICODE | KEYEVENTF_KEYUP,
+    };
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: VIRTUAL_KEY(0),
+                wScan: unit,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: super::injection_tag(),
+            },
+        },
+    }
+}
+
+/// 以 Unicode 方式输入单个字符（代理对的两个编码单元通过一次 `SendInput` 发送）
+///
+/// # 参数
+///
+/// * `ch` - 要输入的字符
+pub fn simulate_unicode_char(ch: char) -> Result<(), windows::core::Error> {
+    simulate_inputs(&unicode_inputs(ch))
+}
+
+/// 一次性发送多个输入事件
+///
+/// # 参数
+///
+/// * `inputs` - 输入事件（按顺序发送）
+///
+/// # 说明
+///
+/// 所有事件通过一次 `SendInput` 调用发送，中间不会插入其他输入；
+/// 只有部分事件被发送（如被 UIPI 拦截）时也返回错误
+pub fn simulate_inputs(inputs: &[INPUT]) -> Result<(), windows::core::Error> {
+    if inputs.is_empty() {
+        return Ok(());
+    }
+
+    let sent = unsafe { SendInput(inputs, std::mem::size_of::<INPUT>() as i32) };
+    if sent as usize != inputs.len() {
+        Err(windows::core::Error::from_win32())
+    } else {
+        Ok(())
+    }
+}
+
+/// 模拟按键
+///
+/// # 参数
+///
+/// * `vk` - 虚拟键码
+/// * `event_type` - 事件类型（按下或释放）
+pub fn simulate_key(vk: u16, event_type: KeyEventType) -> Result<(), windows::core::Error> {
+    simulate_inputs(&[key_input(vk, event_type)])
+}
+
 /// 模拟按键按下
 ///
 /// # 参数
@@ -127,6 +265,97 @@ pub fn simulate_key_complete(vk: u16) -> Result<(), windows::core::Error> {
     Ok(())
 }
 
+/// 字符对应的按键及需要同时按住的修饰键
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CharKey {
+    pub vk: u16,
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+impl CharKey {
+    /// 不需要修饰键的按键
+    pub fn plain(vk: u16) -> Self {
+        Self { vk, ..Default::default() }
+    }
+
+    /// 需要按住的修饰键（按按下顺序排列）
+    pub fn modifiers(&self) -> Vec<u16> {
+        [(self.ctrl, VK_CONTROL), (self.alt, VK_MENU), (self.shift, VK_SHIFT)]
+            .into_iter()
+            .filter(|(needed, _)| *needed)
+            .map(|(_, vk)| vk.0)
+            .collect()
+    }
+}
+
+/// 前台窗口当前使用的键盘布局（HKL 句柄值）
+///
+/// # 说明
+///
+/// 每次调用都重新查询，切换输入法后立即返回新的布局；
+/// 没有前台窗口时线程 ID 为 0，返回当前线程的布局
+pub fn foreground_layout() -> isize {
+    unsafe {
+        let thread_id = GetWindowThreadProcessId(GetForegroundWindow(), None);
+        GetKeyboardLayout(thread_id).0 as isize
+    }
+}
+
+/// 按指定的键盘布局将字符转换为按键
+///
+/// # 参数
+///
+/// * `ch` - 要输入的字符
+/// * `layout` - 键盘布局（`foreground_layout` 的返回值）
+///
+/// # 返回
+///
+/// 该布局中无法通过按键输入该字符（或字符超出基本多文种平面）时返回 None
+///
+/// # 说明
+///
+/// 数字字符总是对应顶行数字键（`VK_0` ~ `VK_9`），不会使用小键盘；
+/// 需要小键盘按键时请在序列中使用 `Numpad0` ~ `Numpad9`
+pub fn char_to_layout_key(ch: char, layout: isize) -> Option<CharKey> {
+    let mut buffer = [0u16; 2];
+    let [code_unit] = *ch.encode_utf16(&mut buffer) else {
+        return None;
+    };
+
+    unsafe { decode_vk_key_scan(VkKeyScanExW(code_unit, HKL(layout as *mut _))) }
+}
+
+/// 解析 VkKeyScanExW 的返回值
+///
+/// 低字节为虚拟键码，高字节为修饰键状态（1 = Shift，2 = Ctrl，4 = Alt），无法映射时两个字节均为 0xFF
+fn decode_vk_key_scan(result: i16) -> Option<CharKey> {
+    let [vk, state] = result.to_le_bytes();
+    if vk == 0xFF && state == 0xFF {
+        return None;
+    }
+
+    Some(CharKey {
+        vk: vk as u16,
+        shift: state & 1 != 0,
+        ctrl: state & 2 != 0,
+        alt: state & 4 != 0,
+    })
+}
+
+/// 检查 Caps Lock 是否处于开启状态
+pub fn is_caps_lock_on() -> bool {
+    // GetKeyState 返回值的最低位表示切换键的开关状态
+    unsafe { GetKeyState(VK_CAPITAL.0 as i32) & 1 != 0 }
+}
+
+/// 检查按键当前是否被按住（不依赖本线程的消息队列，程序启动时即可使用）
+pub fn is_key_held(vk: u16) -> bool {
+    // GetAsyncKeyState 返回值的最高位表示按键当前处于按下状态
+    unsafe { GetAsyncKeyState(vk as i32) < 0 }
+}
+
 /// 从 LPARAM 获取键盘钩子结构
 ///
 /// # 安全
@@ -137,14 +366,17 @@ pub unsafe fn get_keyboard_hook_struct(lparam: LPARAM) -> &'static KBDLLHOOKSTRU
 }
 
 /// 检查是否是键按下消息
+///
+/// 按住 Alt 时按下的键以 WM_SYSKEYDOWN 上报，同样视为按下
 pub fn is_key_down(wparam: WPARAM) -> bool {
-    wparam.0 as u32 == WM_KEYDOWN
+    matches!(wparam.0 as u32, WM_KEYDOWN | WM_SYSKEYDOWN)
 }
 
 /// 检查是否是键释放消息
-#[allow(dead_code)]
+///
+/// 按住 Alt 时释放的键（以及 Alt 键本身）以 WM_SYSKEYUP 上报，同样视为释放
 pub fn is_key_up(wparam: WPARAM) -> bool {
-    wparam.0 as u32 == WM_KEYUP
+    matches!(wparam.0 as u32, WM_KEYUP | WM_SYSKEYUP)
 }
 
 /// 检查按键是否是重复事件（长按自动重复）
@@ -163,3 +395,104 @@ pub fn is_key_repeat(lparam: LPARAM) -> bool {
     let flags: u32 = kb_struct.flags.0;
     (flags & LLKHF_REPEAT) != 0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_message_kinds() {
+        let message = |msg: u32| WPARAM(msg as usize);
+
+        assert!(is_key_down(message(WM_KEYDOWN)));
+        assert!(is_key_down(message(WM_SYSKEYDOWN)));
+        assert!(!is_key_down(message(WM_KEYUP)));
+        assert!(!is_key_down(message(WM_SYSKEYUP)));
+
+        assert!(is_key_up(message(WM_KEYUP)));
+        assert!(is_key_up(message(WM_SYSKEYUP)));
+        assert!(!is_key_up(message(WM_KEYDOWN)));
+        assert!(!is_key_up(message(WM_SYSKEYDOWN)));
+    }
+
+    #[test]
+    fn test_decode_vk_key_scan() {
+        assert_eq!(decode_vk_key_scan(-1), None);
+        assert_eq!(
+            decode_vk_key_scan(0x0141),
+            Some(CharKey { vk: 0x41, shift: true, ctrl: false, alt: false })
+        );
+        // AltGr 组合（Ctrl+Alt）
+        assert_eq!(
+            decode_vk_key_scan(0x0632),
+            Some(CharKey { vk: 0x32, shift: false, ctrl: true, alt: true })
+        );
+    }
+
+    #[test]
+    fn test_char_to_layout_key_on_current_layout() {
+        // 拉丁字母在常见布局上都可以直接输入，大写需要 Shift
+        let layout = foreground_layout();
+        let lower = char_to_layout_key('a', layout).expect("当前布局应能输入字母 a");
+        let upper = char_to_layout_key('A', layout).expect("当前布局应能输入字母 A");
+        assert_eq!(lower.vk, upper.vk);
+        assert!(!lower.shift);
+        assert!(upper.shift);
+
+        assert_eq!(char_to_layout_key('😀', layout), None);
+    }
+
+    #[test]
+    fn test_numpad_key_input() {
+        let input = key_input(VK_NUMPAD5.0, KeyEventType::Press);
+        let ki = unsafe { input.Anonymous.ki };
+        assert_eq!(ki.wVk, VIRTUAL_KEY(0x65));
+        assert_eq!(ki.dwFlags, KEYEVENTF_SCANCODE);
+        // 小键盘数字不是扩展键，与其共用扫描码的导航键需要扩展标记
+        assert!(!is_extended_key(VK_NUMPAD5.0));
+        assert!(is_extended_key(VK_DELETE.0));
+        let ki = unsafe { key_input(VK_DELETE.0, KeyEventType::Release).Anonymous.ki };
+        assert_eq!(ki.dwFlags, KEYEVENTF_KEYUP | KEYEVENTF_SCANCODE | KEYEVENTF_EXTENDEDKEY);
+    }
+
+    #[test]
+    fn test_scancode_input_bypasses_virtual_key() {
+        let ki = unsafe { scancode_input(0x1E, false, KeyEventType::Press).Anonymous.ki };
+        assert_eq!((ki.wVk, ki.wScan), (VIRTUAL_KEY(0), 0x1E));
+        assert_eq!(ki.dwFlags, KEYEVENTF_SCANCODE);
+
+        let input = scancode_input(0x1D, true, KeyEventType::Release);
+        let ki = unsafe { input.Anonymous.ki };
+        assert_eq!(ki.dwFlags, KEYEVENTF_SCANCODE | KEYEVENTF_EXTENDEDKEY | KEYEVENTF_KEYUP);
+        assert!(is_self_injected_input(&input));
+    }
+
+    #[test]
+    fn test_unicode_inputs_split_surrogate_pairs() {
+        let events = |ch: char| -> Vec<(u16, bool)> {
+            unicode_inputs(ch)
+                .iter()
+                .map(|input| {
+                    let ki = unsafe { input.Anonymous.ki };
+                    assert_eq!(ki.wVk, VIRTUAL_KEY(0));
+                    assert!(ki.dwFlags.contains(KEYEVENTF_UNICODE));
+                    assert!(is_self_injected_input(input));
+                    (ki.wScan, ki.dwFlags.contains(KEYEVENTF_KEYUP))
+                })
+                .collect()
+        };
+
+        // U+1F600 编码为高位代理 0xD83D 和低位代理 0xDE00
+        let emoji = events('😀');
+        let downs: Vec<u16> = emoji.iter().filter(|(_, up)| !up).map(|(unit, _)| *unit).collect();
+        assert_eq!(downs, vec![0xD83D, 0xDE00]);
+        assert_eq!(emoji, vec![(0xD83D, false), (0xD83D, true), (0xDE00, false), (0xDE00, true)]);
+
+        // 基本平面内的字符只有一个编码单元
+        assert_eq!(events('中'), vec![(0x4E2D, false), (0x4E2D, true)]);
+    }
+
+    fn is_self_injected_input(input: &INPUT) -> bool {
+        crate::winapi::is_self_injected(unsafe { input.Anonymous.ki }.dwExtraInfo)
+    }
+}
@@ -5,7 +5,46 @@
 
 pub mod window;
 pub mod keyboard;
+pub mod mouse;
+pub mod process;
+pub mod gdi;
+pub mod timer;
+pub mod clipboard;
+pub mod xinput;
+pub mod clock;
+pub mod console;
+pub mod ime;
 
 // 可以根据需要添加更多 Windows API 封装模块
-// pub mod process;
 // pub mod registry;
+
+/// 模拟输入的 `dwExtraInfo` 标记
+const INJECTION_TAG: usize = 0x12345678;
+
+/// 获取模拟输入使用的 `dwExtraInfo` 标记
+///
+/// 键盘和鼠标的模拟输入都必须使用此标记，钩子才能识别并放行程序自己发送的输入
+pub fn injection_tag() -> usize {
+    INJECTION_TAG
+}
+
+/// 检查钩子收到的输入是否由本程序模拟发送
+///
+/// # 参数
+///
+/// * `extra_info` - KBDLLHOOKSTRUCT 或 MSLLHOOKSTRUCT 的 `dwExtraInfo`
+pub fn is_self_injected(extra_info: usize) -> bool {
+    extra_info == INJECTION_TAG
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_injection_tag_round_trip() {
+        assert!(is_self_injected(injection_tag()));
+        assert!(!is_self_injected(0));
+        assert!(!is_self_injected(injection_tag() + 1));
+    }
+}
@@ -0,0 +1,93 @@
+//! Windows 鼠标 API 安全封装
+//!
+//! 提供低级鼠标钩子和鼠标侧键消息解析的安全接口
+
+use windows::Win32::{
+    Foundation::{HINSTANCE, LPARAM, WPARAM},
+    UI::WindowsAndMessaging::*,
+};
+
+/// 鼠标侧键事件
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct XButtonEvent {
+    /// 侧键编号（`XBUTTON1` = 1，`XBUTTON2` = 2）
+    pub button: u16,
+    /// true 为按下，false 为释放
+    pub pressed: bool,
+}
+
+/// 设置低级鼠标钩子
+///
+/// # 参数
+///
+/// * `hook_proc` - 钩子回调
+/// * `thread_id` - 线程 ID（0 表示所有线程）
+pub fn set_mouse_hook(hook_proc: HOOKPROC, thread_id: u32) -> Result<HHOOK, windows::core::Error> {
+    unsafe {
+        SetWindowsHookExW(WH_MOUSE_LL, hook_proc, HINSTANCE::default(), thread_id)
+    }
+}
+
+/// 卸载鼠标钩子
+///
+/// # 参数
+///
+/// * `hook` - 要卸载的钩子句柄
+pub fn unhook_mouse_hook(hook: HHOOK) -> Result<(), windows::core::Error> {
+    unsafe {
+        UnhookWindowsHookEx(hook)?;
+        Ok(())
+    }
+}
+
+/// 从 LPARAM 获取鼠标钩子结构
+///
+/// # Safety
+///
+/// 需要信任 LPARAM 包含有效的 MSLLHOOKSTRUCT 指针
+pub unsafe fn get_mouse_hook_struct(lparam: LPARAM) -> &'static MSLLHOOKSTRUCT {
+    &*(lparam.0 as *const MSLLHOOKSTRUCT)
+}
+
+/// 解析鼠标侧键消息
+///
+/// # 参数
+///
+/// * `wparam` - 鼠标消息类型
+/// * `mouse_data` - MSLLHOOKSTRUCT 的 mouseData（高 16 位为侧键编号）
+///
+/// # 返回
+///
+/// 侧键按下或释放时返回事件，其他鼠标消息（移动、左右键、滚轮等）返回 None
+pub fn xbutton_event(wparam: WPARAM, mouse_data: u32) -> Option<XButtonEvent> {
+    let pressed = match wparam.0 as u32 {
+        WM_XBUTTONDOWN => true,
+        WM_XBUTTONUP => false,
+        _ => return None,
+    };
+    let button = (mouse_data >> 16) as u16;
+    matches!(button, XBUTTON1 | XBUTTON2).then_some(XButtonEvent { button, pressed })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xbutton_event() {
+        let message = |msg: u32| WPARAM(msg as usize);
+        let data = |button: u16| (button as u32) << 16;
+
+        assert_eq!(
+            xbutton_event(message(WM_XBUTTONDOWN), data(XBUTTON1)),
+            Some(XButtonEvent { button: 1, pressed: true })
+        );
+        assert_eq!(
+            xbutton_event(message(WM_XBUTTONUP), data(XBUTTON2)),
+            Some(XButtonEvent { button: 2, pressed: false })
+        );
+        assert_eq!(xbutton_event(message(WM_XBUTTONDOWN), data(3)), None);
+        assert_eq!(xbutton_event(message(WM_LBUTTONDOWN), 0), None);
+        assert_eq!(xbutton_event(message(WM_MOUSEMOVE), data(XBUTTON1)), None);
+    }
+}
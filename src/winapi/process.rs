@@ -0,0 +1,171 @@
+//! Windows 进程 API 安全封装
+//!
+//! 提供命名互斥量等进程间同步功能，以及前台窗口所属进程的查询和前台窗口切换事件的监听
+
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::{
+    Foundation::{CloseHandle, GetLastError, ERROR_ALREADY_EXISTS, HANDLE, HMODULE, MAX_PATH},
+    System::Threading::{
+        CreateMutexW, GetCurrentThread, GetCurrentThreadId, OpenProcess, QueryFullProcessImageNameW, SetThreadPriority,
+        PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION, THREAD_PRIORITY_ABOVE_NORMAL,
+    },
+    UI::Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK, WINEVENTPROC},
+    UI::WindowsAndMessaging::{
+        GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId, EVENT_SYSTEM_FOREGROUND, WINEVENT_OUTOFCONTEXT,
+    },
+};
+
+/// 命名互斥量句柄，drop 时关闭
+///
+/// 所有打开同名互斥量的句柄都关闭（包括进程退出）后，互斥量才会被系统销毁
+#[derive(Debug)]
+pub struct NamedMutex(HANDLE);
+
+impl Drop for NamedMutex {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
+
+/// 获取调用线程的线程 ID（用于向该线程的消息队列投递消息）
+pub fn current_thread_id() -> u32 {
+    unsafe { GetCurrentThreadId() }
+}
+
+/// 将调用线程的优先级提高一级（`THREAD_PRIORITY_ABOVE_NORMAL`）
+pub fn raise_current_thread_priority() -> Result<(), windows::core::Error> {
+    unsafe { SetThreadPriority(GetCurrentThread(), THREAD_PRIORITY_ABOVE_NORMAL) }
+}
+
+/// 创建或打开命名互斥量
+///
+/// # 参数
+///
+/// * `name` - 互斥量名称，如 `Local\app_name`（`Local\` 前缀表示仅在当前登录会话内可见）
+///
+/// # 返回
+///
+/// 互斥量句柄，以及同名互斥量在调用前是否已经存在
+pub fn create_named_mutex(name: &str) -> Result<(NamedMutex, bool), windows::core::Error> {
+    let name_wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+
+    unsafe {
+        let handle = CreateMutexW(None, false, PCWSTR(name_wide.as_ptr()))?;
+        // 打开已存在的互斥量时 CreateMutexW 同样成功，需要立即通过 GetLastError 区分
+        let already_exists = GetLastError() == ERROR_ALREADY_EXISTS;
+        Ok((NamedMutex(handle), already_exists))
+    }
+}
+
+/// 前台窗口信息
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ForegroundWindow {
+    /// 窗口标题
+    pub title: String,
+    /// 所属进程的可执行文件名（如 `Photoshop.exe`），无权限查询时为空字符串
+    pub process_name: String,
+}
+
+/// 获取当前前台窗口的标题和所属进程
+///
+/// # 返回
+///
+/// 没有前台窗口（如切换窗口过程中）时返回 None
+pub fn foreground_window() -> Option<ForegroundWindow> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0.is_null() {
+            return None;
+        }
+
+        let mut title = [0u16; 512];
+        let len = GetWindowTextW(hwnd, &mut title).max(0) as usize;
+
+        let mut process_id = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut process_id));
+
+        Some(ForegroundWindow {
+            title: String::from_utf16_lossy(&title[..len]),
+            process_name: process_image_name(process_id).unwrap_or_default(),
+        })
+    }
+}
+
+/// 监听前台窗口切换（`EVENT_SYSTEM_FOREGROUND`）
+///
+/// # 参数
+///
+/// * `event_proc` - 事件回调，在调用线程的消息循环中执行（`WINEVENT_OUTOFCONTEXT`），调用线程必须处理消息
+pub fn set_foreground_event_hook(event_proc: WINEVENTPROC) -> Result<HWINEVENTHOOK, windows::core::Error> {
+    unsafe {
+        let hook = SetWinEventHook(
+            EVENT_SYSTEM_FOREGROUND,
+            EVENT_SYSTEM_FOREGROUND,
+            HMODULE::default(),
+            event_proc,
+            0,
+            0,
+            WINEVENT_OUTOFCONTEXT,
+        );
+        if hook.is_invalid() {
+            return Err(windows::core::Error::from_win32());
+        }
+        Ok(hook)
+    }
+}
+
+/// 停止监听前台窗口切换
+///
+/// # 参数
+///
+/// * `hook` - `set_foreground_event_hook` 返回的句柄
+pub fn unhook_foreground_event_hook(hook: HWINEVENTHOOK) -> bool {
+    unsafe { UnhookWinEvent(hook).as_bool() }
+}
+
+/// 查询进程的可执行文件名（不含目录）
+fn process_image_name(process_id: u32) -> Option<String> {
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id).ok()?;
+
+        let mut path = [0u16; MAX_PATH as usize];
+        let mut size = path.len() as u32;
+        let result = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, PWSTR(path.as_mut_ptr()), &mut size);
+        let _ = CloseHandle(process);
+        result.ok()?;
+
+        let path = String::from_utf16_lossy(&path[..size as usize]);
+        Some(file_name(&path).to_string())
+    }
+}
+
+/// 取 Windows 路径中的文件名部分
+fn file_name(path: &str) -> &str {
+    path.rsplit(['\\', '/']).next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_named_mutex_detects_existing() {
+        let name = format!("Local\\rust_keymacro_test_{}", std::process::id());
+
+        let (first, existed) = create_named_mutex(&name).unwrap();
+        assert!(!existed);
+
+        let (_second, existed) = create_named_mutex(&name).unwrap();
+        assert!(existed);
+
+        drop(first);
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name(r"C:\Program Files\Adobe\Photoshop.exe"), "Photoshop.exe");
+        assert_eq!(file_name("chrome.exe"), "chrome.exe");
+    }
+}
@@ -0,0 +1,49 @@
+//! Windows 系统定时器精度封装
+//!
+//! 默认的定时器精度约为 15.6 毫秒，`thread::sleep` 的短等待会被延长到下一个时钟周期；
+//! 执行宏期间临时提高精度，使毫秒级的按键延迟更准确
+
+use windows::Win32::Media::{timeBeginPeriod, timeEndPeriod, TIMERR_NOERROR};
+
+/// 宏执行期间请求的定时器精度（毫秒）
+pub const MACRO_TIMER_PERIOD_MS: u32 = 1;
+
+/// 提高系统定时器精度的守卫，析构时恢复
+///
+/// # 说明
+///
+/// 定时器精度是系统级设置：持有期间整个系统的时钟中断更频繁，耗电略有增加（笔记本上较明显），
+/// 因此只在执行宏期间持有，不要长期保留
+pub struct TimerResolution {
+    period_ms: u32,
+}
+
+impl TimerResolution {
+    /// 请求指定的定时器精度
+    ///
+    /// # 参数
+    ///
+    /// * `period_ms` - 精度（毫秒），通常为 1
+    ///
+    /// # 返回
+    ///
+    /// 成功返回守卫，系统不支持该精度时返回 None（此时保持默认精度）
+    pub fn raise(period_ms: u32) -> Option<Self> {
+        let result = unsafe { timeBeginPeriod(period_ms) };
+        if result == TIMERR_NOERROR {
+            Some(Self { period_ms })
+        } else {
+            log::debug!("设置定时器精度失败 ({}ms): {}", period_ms, result);
+            None
+        }
+    }
+}
+
+impl Drop for TimerResolution {
+    fn drop(&mut self) {
+        // 必须与 timeBeginPeriod 使用相同的值，否则系统不会恢复精度
+        unsafe {
+            timeEndPeriod(self.period_ms);
+        }
+    }
+}
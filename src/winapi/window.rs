@@ -3,14 +3,64 @@
 //! 提供窗口创建、消息处理、窗口管理等功能
 
 use windows::Win32::{
-    Foundation::{HWND, WPARAM, LPARAM, LRESULT, COLORREF, HINSTANCE, RECT},
+    Foundation::{BOOL, HWND, WPARAM, LPARAM, LRESULT, COLORREF, HINSTANCE, POINT, RECT, TRUE},
     UI::WindowsAndMessaging::*,
+    UI::HiDpi::{GetDpiForWindow, SetProcessDpiAwarenessContext, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2},
     Graphics::Gdi::*,
     System::LibraryLoader::GetModuleHandleW,
 };
 use windows::core::PCWSTR;
 use std::ptr;
 
+/// 由 RGB 分量构造 COLORREF
+///
+/// COLORREF 的内存布局为 `0x00BBGGRR`（低字节为红色），与常见的 `0xRRGGBB` 写法相反，
+/// 手写十六进制时容易把红蓝通道弄反，新增颜色请统一使用此函数
+pub const fn rgb(r: u8, g: u8, b: u8) -> COLORREF {
+    COLORREF((r as u32) | ((g as u32) << 8) | ((b as u32) << 16))
+}
+
+/// 100% 缩放时的 DPI
+pub const DEFAULT_DPI: u32 = 96;
+
+/// 将进程设置为按显示器感知 DPI（Per-Monitor V2）
+///
+/// 必须在创建任何窗口之前调用，否则系统会对窗口做位图拉伸，在高缩放比例下显示模糊
+pub fn set_process_dpi_aware() -> Result<(), windows::core::Error> {
+    unsafe {
+        SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2)
+    }
+}
+
+/// 获取窗口所在显示器的 DPI
+///
+/// # 参数
+///
+/// * `hwnd` - 窗口句柄
+///
+/// # 返回
+///
+/// 获取失败时返回 `DEFAULT_DPI`
+pub fn get_dpi_for_window(hwnd: HWND) -> u32 {
+    match unsafe { GetDpiForWindow(hwnd) } {
+        0 => DEFAULT_DPI,
+        dpi => dpi,
+    }
+}
+
+/// 将 100% 缩放下的尺寸按 DPI 缩放（四舍五入）
+///
+/// # 参数
+///
+/// * `value` - 100% 缩放下的尺寸（像素）
+/// * `dpi` - 目标 DPI
+pub fn scale_for_dpi(value: i32, dpi: u32) -> i32 {
+    let scaled = value as i64 * dpi as i64;
+    let half = DEFAULT_DPI as i64 / 2;
+    let rounded = if scaled >= 0 { scaled + half } else { scaled - half };
+    (rounded / DEFAULT_DPI as i64) as i32
+}
+
 /// 窗口类注册信息
 pub struct WindowClassInfo {
     pub class_name: String,
@@ -142,6 +192,22 @@ pub fn post_close_message(hwnd_value: isize) -> Result<(), windows::core::Error>
     }
 }
 
+/// 投递自定义消息（异步）
+///
+/// # 参数
+///
+/// * `hwnd_value` - 窗口句柄值
+/// * `msg` - 消息
+/// * `wparam` - WPARAM
+/// * `lparam` - LPARAM
+pub fn post_message(hwnd_value: isize, msg: u32, wparam: WPARAM, lparam: LPARAM) -> Result<(), windows::core::Error> {
+    unsafe {
+        let hwnd = HWND(hwnd_value as *mut core::ffi::c_void);
+        PostMessageW(hwnd, msg, wparam, lparam)?;
+        Ok(())
+    }
+}
+
 /// 销毁窗口
 ///
 /// # 参数
@@ -182,6 +248,11 @@ pub fn set_window_position(
 ///
 /// * `hwnd` - 窗口句柄
 /// * `alpha` - 透明度（0-255）
+///
+/// # 说明
+///
+/// 只使用 `LWA_ALPHA`，颜色键参数 `COLORREF(0)` 会被忽略。如果以后加上 `LWA_COLORKEY`，
+/// 颜色键为黑色，而窗口类默认使用黑色背景画刷，整个背景都会变成全透明
 pub fn set_window_alpha(hwnd: HWND, alpha: u8) -> Result<(), windows::core::Error> {
     unsafe {
         SetLayeredWindowAttributes(hwnd, COLORREF(0), alpha, LWA_ALPHA)?;
@@ -225,6 +296,95 @@ pub fn bring_window_to_top(hwnd: HWND) -> Result<(), windows::core::Error> {
     }
 }
 
+/// 设置窗口定时器（同一 ID 重复设置会重新计时）
+///
+/// # 参数
+///
+/// * `hwnd` - 窗口句柄
+/// * `id` - 定时器 ID
+/// * `elapse_ms` - 间隔毫秒数
+pub fn set_timer(hwnd: HWND, id: usize, elapse_ms: u32) -> Result<(), windows::core::Error> {
+    unsafe {
+        if SetTimer(hwnd, id, elapse_ms, None) == 0 {
+            Err(windows::core::Error::from_win32())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// 销毁窗口定时器
+///
+/// # 参数
+///
+/// * `hwnd` - 窗口句柄
+/// * `id` - 定时器 ID
+pub fn kill_timer(hwnd: HWND, id: usize) -> Result<(), windows::core::Error> {
+    unsafe {
+        KillTimer(hwnd, id)?;
+        Ok(())
+    }
+}
+
+/// 使整个窗口客户区失效以触发重绘
+///
+/// # 参数
+///
+/// * `hwnd` - 窗口句柄
+pub fn invalidate_window(hwnd: HWND) -> bool {
+    unsafe {
+        InvalidateRect(hwnd, None, true).as_bool()
+    }
+}
+
+/// 获取消息（阻塞直到有消息）
+///
+/// # 参数
+///
+/// * `msg` - 接收消息的结构
+///
+/// # 返回
+///
+/// 收到 WM_QUIT 或出错时返回 false
+pub fn get_message(msg: &mut MSG) -> bool {
+    unsafe {
+        GetMessageW(msg, None, 0, 0).0 > 0
+    }
+}
+
+/// 确保调用线程已有消息队列
+///
+/// 线程首次调用窗口相关函数时系统才会创建消息队列，在此之前投递给该线程的消息会失败
+pub fn ensure_message_queue() {
+    let mut msg = MSG::default();
+    unsafe {
+        let _ = PeekMessageW(&mut msg, None, 0, 0, PM_NOREMOVE);
+    }
+}
+
+/// 向指定线程投递 WM_QUIT，使其 `get_message` 循环结束
+///
+/// # 参数
+///
+/// * `thread_id` - 运行消息循环的线程 ID
+pub fn post_thread_quit(thread_id: u32) -> Result<(), windows::core::Error> {
+    unsafe {
+        PostThreadMessageW(thread_id, WM_QUIT, WPARAM(0), LPARAM(0))
+    }
+}
+
+/// 翻译并分发消息
+///
+/// # 参数
+///
+/// * `msg` - 要分发的消息
+pub fn dispatch_message(msg: &MSG) {
+    unsafe {
+        let _ = TranslateMessage(msg);
+        DispatchMessageW(msg);
+    }
+}
+
 /// 获取系统度量值
 ///
 /// # 参数
@@ -236,6 +396,50 @@ pub fn get_system_metrics(index: SYSTEM_METRICS_INDEX) -> i32 {
     }
 }
 
+/// 显示器的范围和工作区（虚拟屏幕坐标）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonitorArea {
+    /// 整个显示器的范围
+    pub bounds: RECT,
+    /// 工作区（除去任务栏等停靠区域）
+    pub work_area: RECT,
+    /// 是否为主显示器
+    pub primary: bool,
+}
+
+/// 枚举所有显示器
+///
+/// # 返回
+///
+/// 按系统枚举顺序排列的显示器（与"显示设置"中的编号不一定一致），枚举失败时返回空列表
+pub fn enum_monitors() -> Vec<MonitorArea> {
+    unsafe extern "system" fn collect(monitor: HMONITOR, _hdc: HDC, _rect: *mut RECT, data: LPARAM) -> BOOL {
+        let monitors = &mut *(data.0 as *mut Vec<MonitorArea>);
+        let mut info = MONITORINFO { cbSize: std::mem::size_of::<MONITORINFO>() as u32, ..Default::default() };
+        if GetMonitorInfoW(monitor, &mut info).as_bool() {
+            monitors.push(MonitorArea {
+                bounds: info.rcMonitor,
+                work_area: info.rcWork,
+                primary: info.dwFlags & MONITORINFOF_PRIMARY != 0,
+            });
+        }
+        TRUE
+    }
+
+    let mut monitors: Vec<MonitorArea> = Vec::new();
+    unsafe {
+        // 回调在 EnumDisplayMonitors 返回前同步执行，期间 monitors 一直有效
+        let _ = EnumDisplayMonitors(HDC::default(), None, Some(collect), LPARAM(&mut monitors as *mut Vec<MonitorArea> as isize));
+    }
+    monitors
+}
+
+/// 获取鼠标光标位置（屏幕坐标），失败时返回 None
+pub fn cursor_position() -> Option<(i32, i32)> {
+    let mut point = POINT::default();
+    unsafe { GetCursorPos(&mut point) }.ok().map(|()| (point.x, point.y))
+}
+
 /// 创建字体
 ///
 /// # 参数
@@ -413,3 +617,50 @@ pub fn default_window_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM)
         DefWindowProcW(hwnd, msg, wparam, lparam)
     }
 }
+
+/// 显示模态消息框
+///
+/// # 参数
+///
+/// * `text` - 消息内容
+/// * `caption` - 标题
+/// * `style` - 按钮和图标样式，如 `MB_YESNO | MB_ICONQUESTION`
+///
+/// # 返回
+///
+/// 用户点击的按钮，如 `IDYES`
+pub fn message_box(text: &str, caption: &str, style: MESSAGEBOX_STYLE) -> MESSAGEBOX_RESULT {
+    let text_wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+    let caption_wide: Vec<u16> = caption.encode_utf16().chain(std::iter::once(0)).collect();
+
+    unsafe {
+        MessageBoxW(
+            HWND(ptr::null_mut()),
+            PCWSTR(text_wide.as_ptr()),
+            PCWSTR(caption_wide.as_ptr()),
+            style,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_for_dpi() {
+        assert_eq!(scale_for_dpi(200, 96), 200);
+        assert_eq!(scale_for_dpi(200, 144), 300);
+        assert_eq!(scale_for_dpi(150, 192), 300);
+        assert_eq!(scale_for_dpi(150, 120), 188);
+    }
+
+    #[test]
+    fn test_rgb_channel_order() {
+        assert_eq!(rgb(0, 0, 0), COLORREF(0x00000000));
+        assert_eq!(rgb(255, 0, 0), COLORREF(0x000000FF));
+        assert_eq!(rgb(0, 255, 0), COLORREF(0x0000FF00));
+        assert_eq!(rgb(0, 0, 255), COLORREF(0x00FF0000));
+        assert_eq!(rgb(0x12, 0x34, 0x56), COLORREF(0x00563412));
+    }
+}
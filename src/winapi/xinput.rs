@@ -0,0 +1,86 @@
+//! XInput 手柄 API 安全封装
+//!
+//! 提供读取手柄状态（包括 Guide 键）和电池信息的接口
+
+use once_cell::sync::Lazy;
+use windows::core::{w, PCSTR};
+use windows::Win32::Foundation::ERROR_SUCCESS;
+use windows::Win32::System::LibraryLoader::{GetProcAddress, LoadLibraryW};
+use windows::Win32::UI::Input::XboxController::{
+    XInputGetBatteryInformation, XInputGetState, BATTERY_DEVTYPE_GAMEPAD, XINPUT_BATTERY_INFORMATION, XINPUT_STATE,
+};
+
+/// `XInputGetStateEx` 写入的状态结构（`XINPUT_STATE_EX`）：比 `XINPUT_STATE` 多一个保留的 DWORD
+#[repr(C)]
+#[derive(Default)]
+struct XInputStateEx {
+    state: XINPUT_STATE,
+    _reserved: u32,
+}
+
+/// `XInputGetStateEx` 的函数签名（`wButtons` 包含 Guide 键）
+type XInputGetStateExFn = unsafe extern "system" fn(u32, *mut XInputStateEx) -> u32;
+
+/// `XInputGetStateEx` 在 xinput1_4.dll 中的序号（未公开文档，没有导出名称）
+const XINPUT_GET_STATE_EX_ORDINAL: usize = 100;
+
+/// 动态加载的 `XInputGetStateEx`，加载失败时为 None
+///
+/// xinput1_4.dll 加载后在进程生命周期内不卸载
+static GET_STATE_EX: Lazy<Option<XInputGetStateExFn>> = Lazy::new(|| {
+    let proc = unsafe {
+        let module = LoadLibraryW(w!("xinput1_4.dll")).ok()?;
+        GetProcAddress(module, PCSTR(XINPUT_GET_STATE_EX_ORDINAL as *const u8))?
+    };
+    log::debug!("已加载 XInputGetStateEx，支持读取 Guide 键");
+    // 序号 100 的导出函数写入 XINPUT_STATE_EX，缓冲区必须按该结构分配
+    Some(unsafe { std::mem::transmute::<unsafe extern "system" fn() -> isize, XInputGetStateExFn>(proc) })
+});
+
+/// 是否能读取 Guide 键（系统提供 `XInputGetStateEx`）
+pub fn guide_button_supported() -> bool {
+    GET_STATE_EX.is_some()
+}
+
+/// 读取手柄状态
+///
+/// # 参数
+///
+/// * `controller` - 手柄序号（0 ~ 3）
+///
+/// # 返回
+///
+/// 成功返回手柄状态，手柄未连接或读取失败时返回 None
+///
+/// # 说明
+///
+/// 系统提供 `XInputGetStateEx` 时使用它读取，`wButtons` 中包含 Guide 键（0x0400）；
+/// 否则退回 `XInputGetState`，Guide 键始终为未按下
+pub fn get_state(controller: u32) -> Option<XINPUT_STATE> {
+    let (result, state) = match *GET_STATE_EX {
+        Some(get_state_ex) => {
+            let mut state = XInputStateEx::default();
+            (unsafe { get_state_ex(controller, &mut state) }, state.state)
+        }
+        None => {
+            let mut state = XINPUT_STATE::default();
+            (unsafe { XInputGetState(controller, &mut state) }, state)
+        }
+    };
+    (result == ERROR_SUCCESS.0).then_some(state)
+}
+
+/// 读取手柄的电池信息
+///
+/// # 参数
+///
+/// * `controller` - 手柄序号（0 ~ 3）
+///
+/// # 返回
+///
+/// 成功返回电池类型和电量，手柄未连接或读取失败时返回 None
+pub fn battery_information(controller: u32) -> Option<XINPUT_BATTERY_INFORMATION> {
+    let mut info = XINPUT_BATTERY_INFORMATION::default();
+    let result = unsafe { XInputGetBatteryInformation(controller, BATTERY_DEVTYPE_GAMEPAD, &mut info) };
+    (result == ERROR_SUCCESS.0).then_some(info)
+}
@@ -0,0 +1,32 @@
+//! 无界面模式的冒烟测试：启动后请求退出，确认能卸载钩子并正常返回
+
+use rust_keymacro::{bootstrap, Config};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+#[ignore = "会安装真实的键盘钩子并注册全局热键，需要在 Windows 桌面会话中用 cargo test -- --ignored 手动运行"]
+fn headless_mode_starts_and_stops() {
+    let config = Config::from_str("gamepad: { enabled: false }\noverlay_enabled: false\nhotkeys: []").unwrap();
+    let (done_sender, done) = mpsc::channel();
+    let runner = thread::spawn(move || {
+        let result = bootstrap::run_headless(config);
+        let _ = done_sender.send(());
+        result
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !bootstrap::headless_running() {
+        assert!(Instant::now() < deadline, "无界面模式没有启动");
+        if runner.is_finished() {
+            panic!("无界面模式启动失败: {:?}", runner.join().unwrap());
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    bootstrap::stop_headless();
+    done.recv_timeout(Duration::from_secs(5)).expect("无界面模式没有退出");
+    assert_eq!(runner.join().unwrap(), Ok(()));
+    assert!(!bootstrap::headless_running());
+}
@@ -0,0 +1,59 @@
+//! 在没有真实键盘的情况下执行示例配置中的所有热键，确认配置能够完整运行
+
+use rust_keymacro::macros::{run_config_dry, KeyInjector};
+use rust_keymacro::Config;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 只统计按键事件的注入器，不发送任何输入，等待也立即返回
+#[derive(Default)]
+struct CountingInjector {
+    key_events: Mutex<usize>,
+}
+
+impl CountingInjector {
+    fn count(&self) {
+        *self.key_events.lock().unwrap() += 1;
+    }
+}
+
+impl KeyInjector for CountingInjector {
+    fn key_down(&self, _vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+        self.count();
+        Ok(())
+    }
+
+    fn key_up(&self, _vk: u16) -> Result<(), Box<dyn std::error::Error>> {
+        self.count();
+        Ok(())
+    }
+
+    fn unicode_char(&self, _ch: char) -> Result<(), Box<dyn std::error::Error>> {
+        self.count();
+        Ok(())
+    }
+
+    fn sleep(&self, _duration: Duration) {}
+
+    fn set_clipboard_text(&self, _text: &str) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    fn caps_lock_on(&self) -> bool {
+        false
+    }
+}
+
+#[test]
+fn example_config_runs_without_errors() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/config.yaml");
+    let config = Config::from_file(path).unwrap();
+    let injector = CountingInjector::default();
+
+    let results = run_config_dry(&config, &injector);
+    assert_eq!(results.len(), config.hotkeys.len());
+    for (key, result) in &results {
+        assert!(result.is_ok(), "{}: {}", key, result.as_ref().unwrap_err());
+    }
+    assert!(*injector.key_events.lock().unwrap() > 0);
+}